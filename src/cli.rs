@@ -1,8 +1,19 @@
-use crate::utils::FractalType;
-use clap::{Parser, ValueEnum};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, Parser, Subcommand, ValueEnum};
+use mandelbrot::utils::checksum::{hex, sha256};
+use mandelbrot::utils::lyapunov::Sequence;
+use mandelbrot::utils::metadata::FrameMetadata;
+use mandelbrot::utils::overlay::{Corner, Overlay};
+use mandelbrot::utils::palette::Builtin;
+use mandelbrot::utils::postprocess::{Downscale, Pipeline};
+use mandelbrot::utils::preserve::Playback;
+use mandelbrot::utils::rays::Angle;
+use mandelbrot::utils::shader::Language;
+use mandelbrot::utils::symmetry::Mirror;
+use mandelbrot::utils::{Conjugate, EscapeCondition, FractalType, InteriorColoring};
 use std::path::PathBuf;
 
-#[derive(Clone, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum FractalTypeArg {
     Mandelbrot,
     Julia,
@@ -11,6 +22,12 @@ pub enum FractalTypeArg {
     Nova,
     Sin,
     Cos,
+    Phoenix,
+    Collatz,
+    Lyapunov,
+    Magnet1,
+    Magnet2,
+    Multibrot,
 }
 
 impl From<FractalTypeArg> for FractalType {
@@ -23,16 +40,275 @@ impl From<FractalTypeArg> for FractalType {
             FractalTypeArg::Nova => FractalType::Nova,
             FractalTypeArg::Sin => FractalType::Sin,
             FractalTypeArg::Cos => FractalType::Cos,
+            FractalTypeArg::Phoenix => FractalType::Phoenix,
+            FractalTypeArg::Collatz => FractalType::Collatz,
+            FractalTypeArg::Lyapunov => FractalType::Lyapunov,
+            FractalTypeArg::Magnet1 => FractalType::Magnet1,
+            FractalTypeArg::Magnet2 => FractalType::Magnet2,
+            FractalTypeArg::Multibrot => FractalType::Multibrot,
         }
     }
 }
 
-#[derive(Parser)]
+impl From<FractalType> for FractalTypeArg {
+    fn from(value: FractalType) -> Self {
+        match value {
+            FractalType::Mandelbrot => FractalTypeArg::Mandelbrot,
+            FractalType::Julia => FractalTypeArg::Julia,
+            FractalType::BurningShip => FractalTypeArg::BurningShip,
+            FractalType::Tricorn => FractalTypeArg::Tricorn,
+            FractalType::Nova => FractalTypeArg::Nova,
+            FractalType::Sin => FractalTypeArg::Sin,
+            FractalType::Cos => FractalTypeArg::Cos,
+            FractalType::Phoenix => FractalTypeArg::Phoenix,
+            FractalType::Collatz => FractalTypeArg::Collatz,
+            FractalType::Lyapunov => FractalTypeArg::Lyapunov,
+            FractalType::Magnet1 => FractalTypeArg::Magnet1,
+            FractalType::Magnet2 => FractalTypeArg::Magnet2,
+            FractalType::Multibrot => FractalTypeArg::Multibrot,
+        }
+    }
+}
+
+#[test]
+fn test_fractal_type_arg() {
+    // Every fractal type can be chosen on the command line, by its own name
+    let variants = FractalTypeArg::value_variants();
+    assert_eq!(variants.len(), FractalType::all().len());
+    for (&arg, fractal_type) in variants.iter().zip(FractalType::all()) {
+        assert_eq!(FractalType::from(arg), fractal_type);
+        assert_eq!(FractalTypeArg::from(fractal_type) as usize, arg as usize);
+        let name = arg.to_possible_value().unwrap().get_name().to_string();
+        assert_eq!(name.parse(), Ok(fractal_type));
+    }
+}
+
+/// Zoom pointer used when neither `--pointer` nor `--view-center` is given
+pub const DEFAULT_POINTER: &str = "-1.4002,0.0";
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OverlayArg {
+    Grid,
+    Scalebar,
+    Both,
+}
+
+impl From<OverlayArg> for Overlay {
+    fn from(value: OverlayArg) -> Self {
+        match value {
+            OverlayArg::Grid => Overlay::Grid,
+            OverlayArg::Scalebar => Overlay::ScaleBar,
+            OverlayArg::Both => Overlay::Both,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum CornerArg {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl From<CornerArg> for Corner {
+    fn from(value: CornerArg) -> Self {
+        match value {
+            CornerArg::TopLeft => Corner::TopLeft,
+            CornerArg::TopRight => Corner::TopRight,
+            CornerArg::BottomLeft => Corner::BottomLeft,
+            CornerArg::BottomRight => Corner::BottomRight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ShaderArg {
+    Glsl,
+    Wgsl,
+}
+
+impl From<ShaderArg> for Language {
+    fn from(value: ShaderArg) -> Self {
+        match value {
+            ShaderArg::Glsl => Language::Glsl,
+            ShaderArg::Wgsl => Language::Wgsl,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum PaletteArg {
+    Viridis,
+    Magma,
+    Inferno,
+    Plasma,
+}
+
+impl From<PaletteArg> for Builtin {
+    fn from(value: PaletteArg) -> Self {
+        match value {
+            PaletteArg::Viridis => Builtin::Viridis,
+            PaletteArg::Magma => Builtin::Magma,
+            PaletteArg::Inferno => Builtin::Inferno,
+            PaletteArg::Plasma => Builtin::Plasma,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum PlaybackArg {
+    Loop,
+    Once,
+    Pingpong,
+}
+
+impl From<PlaybackArg> for Playback {
+    fn from(value: PlaybackArg) -> Self {
+        match value {
+            PlaybackArg::Loop => Playback::Loop,
+            PlaybackArg::Once => Playback::Once,
+            PlaybackArg::Pingpong => Playback::PingPong,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum MirrorArg {
+    Horizontal,
+    Vertical,
+}
+
+impl From<MirrorArg> for Mirror {
+    fn from(value: MirrorArg) -> Self {
+        match value {
+            MirrorArg::Horizontal => Mirror::Horizontal,
+            MirrorArg::Vertical => Mirror::Vertical,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum DownscaleArg {
+    Box,
+    Bilinear,
+    Lanczos,
+    Gaussian,
+    Mitchell,
+}
+
+impl From<DownscaleArg> for Downscale {
+    fn from(value: DownscaleArg) -> Self {
+        match value {
+            DownscaleArg::Box => Downscale::Box,
+            DownscaleArg::Bilinear => Downscale::Bilinear,
+            DownscaleArg::Lanczos => Downscale::Lanczos,
+            DownscaleArg::Gaussian => Downscale::Gaussian,
+            DownscaleArg::Mitchell => Downscale::Mitchell,
+        }
+    }
+}
+
+/// How the colors of saved PNG frames are stored
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum PixelFormatArg {
+    /// Gray levels, or RGB colors with a palette
+    Direct,
+    /// One-byte indices into a palette stored in the file, like a GIF
+    Indexed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SamplePatternArg {
+    Rect,
+    Polar,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ConjugateArg {
+    Z,
+    C,
+    Both,
+}
+
+impl From<ConjugateArg> for Conjugate {
+    fn from(value: ConjugateArg) -> Self {
+        match value {
+            ConjugateArg::Z => Conjugate::Z,
+            ConjugateArg::C => Conjugate::C,
+            ConjugateArg::Both => Conjugate::Both,
+        }
+    }
+}
+
+impl From<Conjugate> for ConjugateArg {
+    fn from(value: Conjugate) -> Self {
+        match value {
+            Conjugate::Z => ConjugateArg::Z,
+            Conjugate::C => ConjugateArg::C,
+            Conjugate::Both => ConjugateArg::Both,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum EscapeConditionArg {
+    Norm,
+    Imag,
+    Real,
+}
+
+impl From<EscapeConditionArg> for EscapeCondition {
+    fn from(value: EscapeConditionArg) -> Self {
+        match value {
+            EscapeConditionArg::Norm => EscapeCondition::Norm,
+            EscapeConditionArg::Imag => EscapeCondition::Imag,
+            EscapeConditionArg::Real => EscapeCondition::Real,
+        }
+    }
+}
+
+impl From<EscapeCondition> for EscapeConditionArg {
+    fn from(value: EscapeCondition) -> Self {
+        match value {
+            EscapeCondition::Norm => EscapeConditionArg::Norm,
+            EscapeCondition::Imag => EscapeConditionArg::Imag,
+            EscapeCondition::Real => EscapeConditionArg::Real,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum InteriorColoringArg {
+    Average,
+    Final,
+}
+
+impl From<InteriorColoringArg> for InteriorColoring {
+    fn from(value: InteriorColoringArg) -> Self {
+        match value {
+            InteriorColoringArg::Average => InteriorColoring::Average,
+            InteriorColoringArg::Final => InteriorColoring::Final,
+        }
+    }
+}
+
+/// Which frame --poster saves
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Poster {
+    /// The frame with this number, counting from 1
+    Frame(usize),
+    /// The frame with the most detail
+    Auto,
+}
+
+#[derive(Parser, Debug, Clone)]
 #[command(
     name = "mandelbrot",
     about = "Generate fractal zoom animation",
     version,
-    long_about = None
+    long_about = None,
+    args_conflicts_with_subcommands = true
 )]
 pub struct Cli {
     /// Directory where output images and gif will be saved
@@ -43,25 +319,62 @@ pub struct Cli {
     #[arg(long, default_value = "1024x1024")]
     pub pixels: String,
 
+    /// Render the whole animation at the dimensions multiplied by SCALE, a
+    /// number up to 1, and rounded to even numbers, to check the motion of a
+    /// zoom quickly before rendering it in full
+    #[arg(long, value_name = "SCALE")]
+    pub preview_scale: Option<f64>,
+
     /// Upper left corner coordinates in format REAL,IMAGINARY (e.g., -1.20,0.35)
-    #[arg(short, long, default_value = "-2.0,-2.0")]
+    #[arg(short, long, default_value = "-2.0,2.0")]
     pub upper_left: String,
 
     /// Lower right corner coordinates in format REAL,IMAGINARY (e.g., -1,0.20)
-    #[arg(short, long, default_value = "2.0,2.0")]
+    #[arg(short, long, default_value = "2.0,-2.0")]
     pub lower_right: String,
 
+    /// Frame the whole fractal with a view chosen for its type instead of
+    /// --upper-left and --lower-right, widened to the shape of the image
+    #[arg(long, conflicts_with_all = ["upper_left", "lower_right", "view_center", "view_radius",
+          "continue_from"])]
+    pub bounds_auto: bool,
+
     /// Scaling factor for each frame (e.g., 0.9 for zoom in)
     #[arg(short, long, default_value_t = 0.95)]
     pub scale_factor: f64,
 
+    /// Total zoom over the whole animation (e.g., 1000); derives the per-frame scale factor
+    #[arg(long, conflicts_with = "scale_factor", value_parser = parse_positive)]
+    pub total_zoom: Option<f64>,
+
+    /// Zoom in FACTOR times over the animation so that it loops back to the first
+    /// frame (e.g., 4.6692 at the Feigenbaum point); derives the per-frame scale factor
+    #[arg(long, value_name = "FACTOR", conflicts_with_all = ["scale_factor", "total_zoom"], value_parser = parse_positive)]
+    pub zoom_video_loop: Option<f64>,
+
+    /// Fade the last N frames of a --zoom-video-loop into the start of the loop
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        requires = "zoom_video_loop",
+        conflicts_with = "perturbation"
+    )]
+    pub loop_crossfade: usize,
+
     /// Power of the fractal (e.g., 2 for standard Mandelbrot)
     #[arg(long, default_value_t = 2)]
     pub power: i32,
 
-    /// Escape radius for the fractal set
-    #[arg(short, long, default_value_t = 2.0)]
-    pub escape_radius: f64,
+    /// Escape radius for the fractal set [default: 2.0, or 50.0 on the
+    /// imaginary part for sin and cos]
+    #[arg(short, long, value_parser = parse_positive)]
+    pub escape_radius: Option<f64>,
+
+    /// Part of z compared with the escape radius: the modulus, the imaginary
+    /// part or the real part [default: imag for sin and cos, norm otherwise]
+    #[arg(long, value_enum, conflicts_with = "perturbation")]
+    pub escape_condition: Option<EscapeConditionArg>,
 
     /// Number of frames to generate
     #[arg(short, long, default_value_t = 100)]
@@ -71,18 +384,44 @@ pub struct Cli {
     #[arg(short, long, default_value_t = 15)]
     pub delay: u16,
 
-    /// Number of threads to use for rendering
-    #[arg(long, default_value_t = 8)]
+    /// Frames per second of the GIF, instead of --delay; GIF delays are whole
+    /// hundredths of a second, so this rounds to the delay 100 / FPS (30 gives
+    /// a delay of 3, about 33 frames per second)
+    #[arg(long, value_name = "FPS", value_parser = parse_positive, conflicts_with = "delay")]
+    pub fps: Option<f64>,
+
+    /// How the GIF plays back: loop forever, play once, or zoom in and back out
+    #[arg(long, value_enum, default_value_t = PlaybackArg::Loop)]
+    pub playback: PlaybackArg,
+
+    /// Number of times a looping or ping-pong GIF plays before it stops, or 0
+    /// to play forever
+    #[arg(long, default_value_t = 0)]
+    pub loop_count: u16,
+
+    /// Number of threads to use for rendering, or `auto` (or 0) for one per
+    /// available CPU
+    #[arg(long, value_name = "COUNT", default_value = "auto", value_parser = parse_threads)]
     pub threads: usize,
 
-    /// The scale pointer (0, 0) is the upper left corner and (1, 1) is the lower right corner
-    #[arg(short, long, default_value = "-1.4002,0.0")]
-    pub pointer: String,
+    /// The point zoomed towards, in format REAL,IMAGINARY [default: the view center if
+    /// --view-center is given, otherwise -1.4002,0.0]
+    #[arg(short, long)]
+    pub pointer: Option<String>,
 
     /// Whether to save the frames results
     #[arg(long, default_value_t = false)]
     pub no_frames: bool,
 
+    /// Write the image to standard output instead of a file, for piping into
+    /// other tools: the PNG of a single frame, or the GIF of several, with no
+    /// frames saved; messages go to standard error instead
+    #[arg(long, conflicts_with_all = ["no_frames", "no_gif", "sequence_pattern", "metadata",
+          "pipeline_gif", "multithreaded_gif", "poster", "interactive", "dry_run",
+          "contact_sheet", "palette_preview", "background_image", "domain_coloring",
+          "export_float", "color_cycle_frames"])]
+    pub stdout: bool,
+
     /// The type of fractal to generate
     #[arg(long, value_enum, default_value = "mandelbrot")]
     pub fractal_type: FractalTypeArg,
@@ -90,4 +429,1040 @@ pub struct Cli {
     /// Constant for Julia sets in format REAL,IMAGINARY (e.g., -0.8,0.156)
     #[arg(short, long, default_value = "-0.8,0.156")]
     pub julia_constant: String,
+
+    /// Constant p of the phoenix fractal in format REAL,IMAGINARY; the real
+    /// part is added at each step and the imaginary part weighs the previous z
+    #[arg(long, default_value = "0.56667,-0.5")]
+    pub phoenix_p: String,
+
+    /// Animate the Julia constant in a straight line towards this REAL,IMAGINARY value
+    #[arg(long, conflicts_with = "julia_orbit")]
+    pub julia_constant_end: Option<String>,
+
+    /// Animate the Julia constant around a circle of this radius centered on --julia-constant
+    #[arg(long, value_parser = parse_positive)]
+    pub julia_orbit: Option<f64>,
+
+    /// Morph into the formula of this fractal type over the animation,
+    /// blending each step as (1 - t) * step(z) + t * step_to(z), with t
+    /// running from 0 in the first frame to 1 in the last
+    #[arg(long, value_enum, value_name = "TYPE",
+          conflicts_with_all = ["buddhabrot", "perturbation", "domain_coloring", "dual_view"])]
+    pub morph_to: Option<FractalTypeArg>,
+
+    /// Raise the power of the multibrot set from --power in the first frame
+    /// to END in the last, through the fractional powers in between; with
+    /// --bounds-auto the view frames the set at every power along the way
+    #[arg(long, value_name = "END", value_parser = parse_positive,
+          conflicts_with_all = ["power_complex", "color_cycle_frames"])]
+    pub multibrot_power: Option<f64>,
+
+    /// Invert the brightness mapping so that quickly escaping points are dark
+    #[arg(long, default_value_t = false)]
+    pub invert: bool,
+
+    /// Color of points inside the set, as a grayscale byte (e.g., 0) or R,G,B (e.g., 255,0,0)
+    #[arg(long, default_value = "0")]
+    pub interior_color: String,
+
+    /// Render a Buddhabrot by accumulating the orbits of escaping points
+    #[arg(long, default_value_t = false)]
+    pub buddhabrot: bool,
+
+    /// Number of random points sampled per frame in Buddhabrot mode
+    #[arg(long, default_value_t = 1_000_000)]
+    pub samples: usize,
+
+    /// Gamma correction applied to the final image (1.0 leaves it unchanged)
+    #[arg(long, default_value_t = 1.0, value_parser = parse_positive)]
+    pub gamma: f64,
+
+    /// Sharpen the final image with an unsharp mask, pushing each pixel away
+    /// from the blur of its neighbourhood by AMOUNT times the difference;
+    /// this also amplifies the grain of a Buddhabrot
+    #[arg(long, value_name = "AMOUNT", value_parser = parse_positive)]
+    pub sharpen: Option<f64>,
+
+    /// Post-process the final image with comma-separated steps applied in
+    /// order, after --gamma and --sharpen: gamma=GAMMA, contrast=FACTOR about
+    /// middle gray, sharpen=AMOUNT and blur=SIGMA in pixels, e.g.
+    /// "gamma=2.2,contrast=1.1,sharpen=0.5"
+    #[arg(long, value_name = "STEPS",
+          conflicts_with_all = ["background_image", "domain_coloring"])]
+    pub post: Option<Pipeline>,
+
+    /// Bits per channel of the saved PNG frames (the GIF is always 8-bit)
+    #[arg(long, default_value_t = 8, value_parser = parse_bit_depth)]
+    pub bit_depth: u8,
+
+    /// Store saved PNG frames as gray levels or RGB colors (direct), or as
+    /// 8-bit indices into a palette of the --palette colors, or of grays
+    /// without one (indexed), which makes smaller files
+    #[arg(long, value_enum, default_value_t = PixelFormatArg::Direct)]
+    pub pixel_format: PixelFormatArg,
+
+    /// Center of the view in format REAL,IMAGINARY; overrides --upper-left and --lower-right
+    #[arg(long, requires = "view_radius")]
+    pub view_center: Option<String>,
+
+    /// Distance from the view center to the edge of the shorter image axis
+    #[arg(long, requires = "view_center", value_parser = parse_positive)]
+    pub view_radius: Option<f64>,
+
+    /// Sequence of A and B steps for the Lyapunov fractal (e.g., AABAB)
+    #[arg(long, default_value = "AB")]
+    pub lyapunov_sequence: Sequence,
+
+    /// Draw a reference grid and/or scale bar over each frame
+    #[arg(long, value_enum)]
+    pub overlay: Option<OverlayArg>,
+
+    /// Caption each frame with how many times it is magnified over the first
+    /// frame, like "1.3×10^6×"
+    #[arg(long)]
+    pub log_zoom_label: bool,
+
+    /// Corner of the frame the --log-zoom-label caption is drawn in
+    #[arg(long, value_enum, default_value_t = CornerArg::BottomRight,
+          requires = "log_zoom_label")]
+    pub label_corner: CornerArg,
+
+    /// Size of the --log-zoom-label caption, in image pixels per pixel of
+    /// its 5x7 font [default: one per 256 pixels of the shorter side]
+    #[arg(long, value_name = "SCALE", value_parser = clap::value_parser!(u32).range(1..=64),
+          requires = "log_zoom_label")]
+    pub label_scale: Option<u32>,
+
+    /// Draw the external rays of the Mandelbrot set at comma-separated
+    /// ANGLES, fractions P/Q of a turn like "1/3,2/3,1/7", traced inward
+    /// from far outside the set to within a pixel of where they land, or
+    /// --max-iterations depths
+    #[arg(long, value_name = "ANGLES", value_delimiter = ',',
+          conflicts_with_all = ["rotation_per_frame", "sample_pattern", "exp_map"])]
+    pub external_rays: Option<Vec<Angle>>,
+
+    /// Maximum number of iterations before a point is considered inside the set
+    #[arg(long, default_value_t = 255, value_parser = parse_iterations)]
+    pub max_iterations: usize,
+
+    /// Render with perturbation theory so zooms can go far beyond f64 precision
+    /// (power 2 Mandelbrot only; the view center is read at full precision)
+    #[arg(long, requires_all = ["view_center", "view_radius"], conflicts_with = "buddhabrot")]
+    pub perturbation: bool,
+
+    /// Reuse the reference orbit of the frame before while its reference
+    /// point is still in view, computing a new one only once more than
+    /// PERCENT of the pixels glitch against it [default PERCENT when given
+    /// without a value: 1]
+    #[arg(long, value_name = "PERCENT", num_args = 0..=1, default_missing_value = "1",
+          requires = "perturbation")]
+    pub mandelbrot_reference_orbit_cache: Option<f64>,
+
+    /// Keep the view center and the pointer at the precision the deepest
+    /// frame needs, read digit for digit from the command line, and round
+    /// each frame's corners to f64 only to render it; --verbose shows the
+    /// center with all its digits
+    #[arg(long, requires_all = ["view_center", "view_radius"], conflicts_with = "global_normalize")]
+    pub high_precision: bool,
+
+    /// Iterate in 64-bit fixed point instead of floating point, so that the
+    /// escape counts, and --checksum digests, come out bit-identical on every
+    /// platform; power 2 Mandelbrot and Julia sets shaded by escape counts
+    /// only, with views within 32 of zero and --escape-radius at most 8, and
+    /// no deeper than f64 renders
+    #[arg(long, conflicts_with_all = ["buddhabrot", "perturbation", "high_precision",
+          "dual_view", "mirror", "adaptive", "adaptive_aa", "rotation_per_frame", "exp_map"])]
+    pub fixed_point: bool,
+
+    /// Show a progress bar of the rows of each frame while it renders, for
+    /// very large stills that take long to render
+    #[arg(long, conflicts_with_all = ["buddhabrot", "perturbation", "fixed_point", "dual_view",
+          "mirror", "adaptive", "adaptive_aa", "adaptive_iterations", "global_normalize",
+          "color_cycle_frames"])]
+    pub chunk_progress: bool,
+
+    /// Render a single frame to estimate time, memory and disk usage, then exit
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Zoom towards the most detailed boundary point of the opening view,
+    /// found with a low-resolution probe render
+    #[arg(long, conflicts_with = "pointer")]
+    pub auto_target: bool,
+
+    /// Save RGBA frames where points inside the set are fully transparent
+    #[arg(long)]
+    pub alpha_interior: bool,
+
+    /// Raise the iteration limit as the animation zooms in, to
+    /// `--max-iterations + K * log2(zoom)` [default K when given without a value: 50]
+    #[arg(long, value_name = "K", num_args = 0..=1, default_missing_value = "50")]
+    pub auto_iterations: Option<f64>,
+
+    /// Decomposition coloring: darken every other one of SECTORS sectors by the
+    /// argument of the final z [default SECTORS when given without a value: 2]
+    #[arg(long, value_name = "SECTORS", num_args = 0..=1, default_missing_value = "2",
+          value_parser = clap::value_parser!(u32).range(2..))]
+    pub decomposition: Option<u32>,
+
+    /// Color the frames with a gradient loaded from a GIMP .ggr, JSON or text file
+    #[arg(long, value_name = "FILE")]
+    pub palette_file: Option<PathBuf>,
+
+    /// Color the frames with a built-in perceptually uniform color map
+    #[arg(long, value_enum, conflicts_with = "palette_file")]
+    pub palette: Option<PaletteArg>,
+
+    /// Blend the palette, or grayscale without one, into a second gradient
+    /// loaded from a GIMP .ggr, JSON or text file by escape speed: the fastest
+    /// escapes keep the palette's colors and the slowest take this one's
+    #[arg(long, value_name = "FILE")]
+    pub slow_palette_file: Option<PathBuf>,
+
+    /// Blend the palette into a built-in color map by escape speed, like
+    /// --slow-palette-file
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PALETTE",
+        conflicts_with = "slow_palette_file"
+    )]
+    pub slow_palette: Option<PaletteArg>,
+
+    /// Print a SHA-256 checksum of each frame's pixels and one combined over all frames
+    #[arg(long)]
+    pub checksum: bool,
+
+    /// Render only the rectangle X0,Y0,X1,Y1 of the view at the full --pixels
+    /// resolution; whole numbers are pixels, decimals are fractions of the image
+    #[arg(long, value_name = "X0,Y0,X1,Y1", conflicts_with = "perturbation")]
+    pub crop: Option<String>,
+
+    /// Save a labeled grid previewing every fractal type in the opening view,
+    /// instead of an animation
+    #[arg(long)]
+    pub contact_sheet: bool,
+
+    /// Write a JSON file next to each frame recording the parameters needed to
+    /// render it again
+    #[arg(long, conflicts_with = "no_frames")]
+    pub metadata: bool,
+
+    /// Explore the fractal with a preview in the terminal, then print the
+    /// --fractal-type, --view-center and --view-radius that reproduce the
+    /// chosen view
+    #[arg(long, conflicts_with_all = ["contact_sheet", "dry_run"])]
+    pub interactive: bool,
+
+    /// Sample each pixel at its upper-left corner instead of its center, to
+    /// reproduce renders made by earlier versions
+    #[arg(long, conflicts_with = "perturbation")]
+    pub corner_sampling: bool,
+
+    /// Physical print size in format WIDTHxHEIGHT inches (e.g., 24x36), used
+    /// with --dpi instead of --pixels
+    #[arg(
+        long,
+        value_name = "WIDTHxHEIGHT",
+        conflicts_with = "pixels",
+        requires = "dpi"
+    )]
+    pub print_size: Option<String>,
+
+    /// Print resolution in dots per inch, recorded in the saved PNG files
+    #[arg(long, value_parser = parse_positive)]
+    pub dpi: Option<f64>,
+
+    /// ICC profile embedded in the saved PNG files, which tells print and
+    /// color-managed software what color space their values are in; gray
+    /// and RGB profiles only, and print (CMYK) profiles are refused, since
+    /// PNG has no CMYK channels
+    #[arg(long, value_name = "FILE", value_parser = parse_icc_profile)]
+    pub icc_profile: Option<::std::vec::Vec<u8>>,
+
+    /// Convert the palette's colors from sRGB into the space of the RGB
+    /// --icc-profile, which must be built from a matrix and tone curves
+    #[arg(long, requires = "icc_profile")]
+    pub icc_convert: bool,
+
+    /// Render only half of each frame and mirror it across the real
+    /// (horizontal) or imaginary (vertical) axis, for views centered on it
+    #[arg(long, value_enum, conflicts_with_all = ["buddhabrot", "perturbation"])]
+    pub mirror: Option<MirrorArg>,
+
+    /// Render each frame FACTOR times larger in both directions and reduce it
+    /// to --pixels, for smoother edges
+    #[arg(long, value_name = "FACTOR", default_value_t = 1, value_parser = parse_factor)]
+    pub supersample: usize,
+
+    /// Filter used to reduce supersampled frames to the output size: the box
+    /// averages each pixel's samples, the others weigh samples by their
+    /// distance from the pixel's center
+    #[arg(long, visible_alias = "ss-filter", value_enum, default_value_t = DownscaleArg::Box,
+          requires = "supersample")]
+    pub downscale: DownscaleArg,
+
+    /// Render only frames START to END of the animation, counting from 1 and
+    /// keeping their absolute numbers in the file names; options that make a
+    /// frame depend on the clock or on the other frames rendered are refused,
+    /// and --random-view needs a --random-seed
+    #[arg(long, num_args = 2, value_names = ["START", "END"],
+          conflicts_with_all = ["time_budget", "stop_when_uniform", "global_normalize"])]
+    pub frame_range: Option<Vec<usize>>,
+
+    /// Stop starting new frames once SECONDS have passed since the first
+    /// began, and assemble the GIF from the frames rendered so far
+    #[arg(long, value_name = "SECONDS", value_parser = parse_positive)]
+    pub time_budget: Option<f64>,
+
+    /// Warn about each frame that is at least PERCENT interior or exterior,
+    /// estimated from a grid of sample points, as when a zoom has gone past
+    /// the boundary of the set [default PERCENT when given without a value:
+    /// 99]
+    #[arg(long, value_name = "PERCENT", num_args = 0..=1, default_missing_value = "99",
+          conflicts_with = "perturbation")]
+    pub uniform_threshold: Option<f64>,
+
+    /// Stop the animation after the first frame that is uniform by
+    /// --uniform-threshold, which is 99 if not given, and assemble the GIF
+    /// from the frames rendered so far
+    #[arg(long, conflicts_with = "perturbation")]
+    pub stop_when_uniform: bool,
+
+    /// Adapt the scale factor after every frame so each frame reveals about
+    /// as much new detail as the first, measured as the fraction of a grid
+    /// of sample pixels on edges between escape counts: the zoom slows down
+    /// through busy views and speeds up through empty ones
+    #[arg(long, conflicts_with_all = ["perturbation", "high_precision", "buddhabrot",
+          "frame_range", "zoom_video_loop", "loop_crossfade", "global_normalize"])]
+    pub constant_detail: bool,
+
+    /// Also save one frame of the animation as a still PNG beside the GIF:
+    /// frame FRAME, counting from 1, or with `auto` the frame with the most
+    /// detail, by the variance of the escape counts of a grid of sample
+    /// points
+    #[arg(long, value_name = "FRAME", value_parser = parse_poster,
+          conflicts_with_all = ["dry_run", "interactive", "contact_sheet", "palette_preview",
+          "background_image", "domain_coloring", "export_float", "color_cycle_frames"])]
+    pub poster: Option<Poster>,
+
+    /// Render the --poster frame at FACTOR times the frame size in each
+    /// direction
+    #[arg(long, value_name = "FACTOR", default_value_t = 1, value_parser = parse_factor,
+          requires = "poster")]
+    pub poster_scale: usize,
+
+    /// Render the frames with these numbers, counting from 1, at higher
+    /// quality than the rest, as for thumbnails, like `1,50,100`
+    #[arg(long, value_name = "FRAMES", value_delimiter = ',', value_parser = parse_factor,
+          conflicts_with_all = ["global_normalize", "color_cycle_frames"])]
+    pub hq_frames: Option<Vec<usize>>,
+
+    /// Supersample the --hq-frames by FACTOR instead of --supersample
+    #[arg(long, value_name = "FACTOR", default_value_t = 4, value_parser = parse_factor,
+          requires = "hq_frames")]
+    pub hq_supersample: usize,
+
+    /// Multiply the iteration limit of the --hq-frames by FACTOR
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_positive,
+          requires = "hq_frames")]
+    pub hq_iterations: f64,
+
+    /// Save the frames without assembling them into a GIF
+    #[arg(long, conflicts_with = "no_frames")]
+    pub no_gif: bool,
+
+    /// Name the saved frames by PATTERN, a path in the output folder with one
+    /// %0Nd for the frame number zero-padded to N digits, or %d for as many
+    /// as the frame count needs, like `stills/%05d.png` [default:
+    /// frames/<fractal type>-%d.png]
+    #[arg(long, value_name = "PATTERN", conflicts_with = "no_frames")]
+    pub sequence_pattern: Option<String>,
+
+    /// Append the first 8 hex digits of a hash of every option that changes
+    /// the images to the names of the GIF, the frames and the poster, like
+    /// `mandelbrot-a1b2c3d4.gif`, so renders with different settings do not
+    /// overwrite each other
+    #[arg(long)]
+    pub hash_name: bool,
+
+    /// Shade escaping points by the average of sin(DENSITY * arg(z)) over
+    /// their orbit, for a smooth striped texture; a large --escape-radius
+    /// gives the smoothest result
+    #[arg(long, value_name = "DENSITY", value_parser = parse_positive,
+          conflicts_with_all = ["perturbation", "buddhabrot"])]
+    pub stripe_density: Option<f64>,
+
+    /// Save the selected palette as a horizontal gradient strip of --pixels
+    /// size to FILE, instead of rendering a fractal
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["contact_sheet", "interactive"])]
+    pub palette_preview: Option<PathBuf>,
+
+    /// Print a fragment shader in this language that renders the opening
+    /// view with the chosen formula and colors, instead of rendering it
+    #[arg(long, value_name = "LANGUAGE", conflicts_with_all = ["contact_sheet", "interactive",
+          "palette_preview", "dry_run", "exp_map", "sample_pattern"])]
+    pub emit_shader: Option<ShaderArg>,
+
+    /// Write the shader of --emit-shader to FILE instead of printing it
+    #[arg(long, value_name = "FILE", requires = "emit_shader")]
+    pub shader_file: Option<PathBuf>,
+
+    /// Conjugate z before each step, the value added at each step (c or the
+    /// constant), or both, to explore Tricorn-style variants of any formula
+    #[arg(long, value_enum, conflicts_with_all = ["buddhabrot", "perturbation"])]
+    pub conjugate: Option<ConjugateArg>,
+
+    /// Rotate the colors of escaping points by S of the palette every frame,
+    /// wrapping around, so colors flow through the structure as the zoom
+    /// progresses; palettes whose ends differ show a seam where they wrap
+    #[arg(long, value_name = "S", allow_negative_numbers = true)]
+    pub palette_shift_per_frame: Option<f64>,
+
+    /// Render the opening view once and animate it by turning the palette
+    /// through one whole cycle over N frames, instead of zooming; the escape
+    /// counts are kept, so each frame is only shaded again. Use a cyclic
+    /// palette to hide the seam where the palette wraps around
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..),
+          conflicts_with_all = ["buddhabrot", "perturbation", "stripe_density", "smooth",
+          "potential", "decomposition", "supersample", "mirror", "dual_view", "loop_crossfade",
+          "global_normalize", "palette_shift_per_frame", "dry_run", "interactive",
+          "contact_sheet"])]
+    pub color_cycle_frames: Option<u32>,
+
+    /// Decode and index the saved frames on all threads when assembling the
+    /// GIF; they are still written in order
+    #[arg(long, conflicts_with_all = ["no_frames", "no_gif"])]
+    pub multithreaded_gif: bool,
+
+    /// Encode the GIF on a thread of its own while the frames are rendered,
+    /// instead of reading the saved frames back after the last one
+    #[arg(long, conflicts_with_all = ["no_frames", "no_gif", "multithreaded_gif"])]
+    pub pipeline_gif: bool,
+
+    /// Print how long slower steps, such as encoding the GIF, took, and the
+    /// corners, center and size of every frame's view in the complex plane
+    #[arg(long)]
+    pub verbose: bool,
+
+    /// Start from the view and parameters recorded in a frame's --metadata
+    /// JSON file; flags given explicitly still take precedence
+    #[arg(long, value_name = "FILE",
+          conflicts_with_all = ["upper_left", "lower_right", "view_center", "view_radius"])]
+    pub continue_from: Option<PathBuf>,
+
+    /// Shade escaping points by their continuous escape time, which removes
+    /// the bands between escape counts; POWER is how fast |z| grows per step
+    /// far from the origin [default POWER when given without a value: --power]
+    #[arg(long, value_name = "POWER", num_args = 0..=1, value_parser = parse_positive,
+          conflicts_with_all = ["stripe_density", "perturbation", "buddhabrot"])]
+    pub smooth: Option<Option<f64>>,
+
+    /// Shade escaping points by their potential ln|z| / POWER^n, the Green's
+    /// function of the set, on a log scale; with --band-period its
+    /// equipotentials show as bands, and a large --escape-radius gives the
+    /// smoothest result [default POWER when given without a value: --power]
+    #[arg(long, value_name = "POWER", num_args = 0..=1, value_parser = parse_positive,
+          conflicts_with_all = ["smooth", "stripe_density", "contour", "perturbation",
+          "buddhabrot"])]
+    pub potential: Option<Option<f64>>,
+
+    /// Shade the points where a Nova or magnet orbit converges by the root
+    /// they settled on, one flat shade per basin, rather than by how many
+    /// iterations they took to get there
+    #[arg(long, default_value_t = false)]
+    pub root_coloring: bool,
+
+    /// Render the Mandelbrot set with a dot on the Julia constant beside the
+    /// Julia set of that constant, animated with --julia-constant-end or
+    /// --julia-orbit; a frame twice as wide as it is high suits it best
+    #[arg(long, conflicts_with_all = ["fractal_type", "buddhabrot", "perturbation", "mirror",
+          "contact_sheet", "interactive"])]
+    pub dual_view: bool,
+
+    /// Render every frame's escape counts first and shade all frames against
+    /// the range of counts of the whole animation, which stops the brightness
+    /// from flickering; PERCENT of the escaping points at each end of the
+    /// range are clamped [default PERCENT when given without a value: 0]
+    #[arg(long, value_name = "PERCENT", num_args = 0..=1, default_missing_value = "0",
+          conflicts_with_all = ["buddhabrot", "perturbation", "stripe_density", "smooth",
+          "potential", "decomposition", "supersample", "mirror", "dual_view", "loop_crossfade"])]
+    pub global_normalize: Option<f64>,
+
+    /// Save the opening view with each escaping point colored from the image
+    /// FILE, looked up by the argument of its final z across the image and by
+    /// its smooth escape time within its band down the image, instead of an
+    /// animation
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["buddhabrot", "perturbation",
+          "dual_view", "contact_sheet", "interactive", "palette_preview"])]
+    pub background_image: Option<PathBuf>,
+
+    /// Save the opening view with the fractal's formula applied once to each
+    /// point as z, instead of iterated, and its value domain colored: hue
+    /// from its argument and brightness from its modulus; c in z^power + c is
+    /// zero, while Julia sets add --julia-constant
+    #[arg(long, conflicts_with_all = ["buddhabrot", "perturbation", "dual_view", "contact_sheet",
+          "interactive", "palette_preview", "background_image", "power_complex", "conjugate"])]
+    pub domain_coloring: bool,
+
+    /// Also save the opening view's smooth escape time of every pixel, -1 for
+    /// members, as a 32-bit float OpenEXR image to FILE, for coloring or
+    /// relighting it in other tools
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["buddhabrot", "perturbation",
+          "dual_view", "contact_sheet", "interactive", "palette_preview"])]
+    pub export_float: Option<PathBuf>,
+
+    /// Color painted for points that never escape with --background-image
+    #[arg(long, value_name = "R,G,B", default_value = "0,0,0", value_parser = parse_rgb,
+          requires = "background_image")]
+    pub background_fill: [u8; 3],
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Cycle the smooth shading every ITERATIONS of continuous escape time
+    /// instead of spreading it over the iteration limit, so that colors stay
+    /// fixed to the structure while --auto-iterations raises the limit during
+    /// a zoom; implies --smooth
+    #[arg(long, value_name = "ITERATIONS", value_parser = parse_positive,
+          conflicts_with_all = ["stripe_density", "perturbation", "buddhabrot", "global_normalize"])]
+    pub band_period: Option<f64>,
+
+    /// Complex power of the Mandelbrot and Julia formulas, replacing --power,
+    /// which twists the set into spirals; z^power is taken on the principal
+    /// branch, so a seam runs along the negative real axis of z
+    #[arg(long, value_name = "REAL,IMAG", conflicts_with_all = ["power", "buddhabrot", "perturbation"])]
+    pub power_complex: Option<String>,
+
+    /// Value z starts from in place of the origin, for formulas whose orbits
+    /// all start at the same point, like a Mandelbrot set with a perturbed
+    /// start; Julia, phoenix and Collatz orbits start at the pixel instead
+    #[arg(long, value_name = "REAL,IMAG",
+          conflicts_with_all = ["perturbation", "dual_view", "contact_sheet"])]
+    pub initial_z: Option<String>,
+
+    /// Turn the view counterclockwise by DEGREES about the zoom pointer every
+    /// frame, for a spiral zoom
+    #[arg(long, value_name = "DEGREES", allow_hyphen_values = true,
+          conflicts_with_all = ["buddhabrot", "perturbation", "mirror", "dual_view", "overlay"])]
+    pub rotation_per_frame: Option<f64>,
+
+    /// Lay the pixels out in rows and columns of the view, or in a log-polar
+    /// grid around the zoom pointer: one turn across the image and radii
+    /// shrinking down it, which turns the zoom into a vertical scroll
+    #[arg(long, value_enum, default_value_t = SamplePatternArg::Rect,
+          conflicts_with_all = ["buddhabrot", "perturbation", "mirror", "dual_view", "overlay",
+          "crop", "contact_sheet", "interactive", "background_image"])]
+    pub sample_pattern: SamplePatternArg,
+
+    /// Show the exponential map around CENTER, in format REAL,IMAGINARY: the
+    /// log-polar grid of --sample-pattern polar centered on CENTER, which also
+    /// becomes the zoom pointer, so that zooming only scrolls the strip up
+    #[arg(long, value_name = "CENTER",
+          conflicts_with_all = ["sample_pattern", "pointer", "auto_target", "random_view",
+          "find_minibrot", "buddhabrot", "perturbation", "mirror", "dual_view", "overlay", "crop",
+          "contact_sheet", "interactive", "background_image"])]
+    pub exp_map: Option<String>,
+
+    /// Keep the pixels of each frame, and of its supersampled copy, in
+    /// temporary memory-mapped files in the output folder rather than in
+    /// memory, for stills larger than the free memory (unix only)
+    #[arg(long, conflicts_with_all = ["interactive", "contact_sheet", "palette_preview",
+          "background_image"])]
+    pub memory_map: bool,
+
+    /// Store each GIF frame as only the rectangle that changed since the one
+    /// before, which shrinks animations with large static regions (ignored
+    /// for transparent GIFs)
+    #[arg(long, conflicts_with = "no_gif")]
+    pub gif_optimize: bool,
+
+    /// Spread each frame after the first over FACTOR GIF frames, cross-
+    /// dissolving into it from the frame before; cheaper than rendering more
+    /// frames, but frames of a fast zoom blur into each other. Every GIF frame
+    /// is still shown for --delay
+    #[arg(long, value_name = "FACTOR", value_parser = clap::value_parser!(u32).range(2..=100),
+          conflicts_with = "no_gif")]
+    pub interpolate_frames: Option<u32>,
+
+    /// Quantize each GIF frame to 256 colors of its own with NeuQuant instead
+    /// of mapping it to the palette, at QUALITY from 1, fastest, to 10, best;
+    /// worth it for colors the palette lacks, like interpolated blends
+    /// [default QUALITY when given without a value: 5]
+    #[arg(long, value_name = "QUALITY", num_args = 0..=1, default_missing_value = "5",
+          value_parser = clap::value_parser!(u8).range(1..=10),
+          conflicts_with_all = ["no_gif", "gif_optimize"])]
+    pub gif_quality: Option<u8>,
+
+    /// Shade escaping points by their escape count modulo N, which repeats
+    /// the shades in sharp contour bands N iterations wide; a cyclic palette
+    /// gives the classic banded look
+    #[arg(long, value_name = "N", value_parser = parse_iterations,
+          conflicts_with_all = ["smooth", "band_period", "stripe_density", "global_normalize",
+          "buddhabrot"])]
+    pub contour: Option<usize>,
+
+    /// Render by subdividing tiles, filling each region whose corners and
+    /// center agree to within TOLERANCE 8-bit levels without iterating the
+    /// rest of it; fast on views with large uniform areas, but a filament
+    /// thinner than a filled region can be missed [default TOLERANCE when
+    /// given without a value: 0]
+    #[arg(long, value_name = "TOLERANCE", num_args = 0..=1, default_missing_value = "0",
+          conflicts_with_all = ["buddhabrot", "perturbation", "dual_view", "mirror",
+          "global_normalize"])]
+    pub adaptive: Option<u8>,
+
+    /// Iterate every pixel of regions less than PIXELS wide or high instead
+    /// of subdividing them further; larger sizes miss fewer filaments
+    #[arg(long, value_name = "PIXELS", default_value_t = 4, value_parser = parse_factor,
+          requires = "adaptive")]
+    pub adaptive_min_size: usize,
+
+    /// Anti-alias only the edges: render every pixel once, then supersample
+    /// SAMPLES by SAMPLES points in each pixel whose escape count differs
+    /// from a neighbour's by more than THRESHOLD iterations, or that borders
+    /// the set; most of the quality of --supersample at a fraction of the
+    /// cost
+    #[arg(long, num_args = 2, value_names = ["THRESHOLD", "SAMPLES"],
+          conflicts_with_all = ["supersample", "adaptive", "buddhabrot", "perturbation",
+          "dual_view", "mirror", "global_normalize", "color_cycle_frames"])]
+    pub adaptive_aa: Option<Vec<usize>>,
+
+    /// Iterate every pixel up to LOW iterations, then only those on the
+    /// boundary of the points that escaped, and the parts of the set the
+    /// boundary spreads into, up to HIGH, the iteration limit of the frame;
+    /// points deep in the set cost LOW instead of HIGH
+    #[arg(long, num_args = 2, value_names = ["LOW", "HIGH"],
+          conflicts_with_all = ["max_iterations", "adaptive", "adaptive_aa", "buddhabrot",
+          "perturbation", "fixed_point", "dual_view", "mirror", "global_normalize",
+          "color_cycle_frames", "interior_coloring"])]
+    pub adaptive_iterations: Option<Vec<usize>>,
+
+    /// Rotate the colors of escaping points by FRACTION of the palette, which
+    /// moves the detail to other colors; --palette-shift-per-frame starts
+    /// from this shift
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        default_value_t = 0.0,
+        allow_negative_numbers = true
+    )]
+    pub color_offset: f64,
+
+    /// Multiply the palette position of escaping points by FACTOR, wrapping
+    /// around, so the palette repeats FACTOR times across the escape range;
+    /// factors below 1 spread the detail over a part of it
+    #[arg(long, value_name = "FACTOR", value_parser = parse_positive)]
+    pub color_scale: Option<f64>,
+
+    /// Shade points inside the set by the mean |z| of their orbit (average)
+    /// or by |z| after the last iteration (final), from the interior color up
+    /// to white, instead of painting them all with the interior color
+    #[arg(long, value_enum, conflicts_with = "buddhabrot")]
+    pub interior_coloring: Option<InteriorColoringArg>,
+
+    /// Zoom towards a random point near the boundary of the set in the
+    /// opening view, found by keeping the slowest escaping of many sampled
+    /// points; the seed is printed so the view can be rendered again
+    #[arg(long, conflicts_with_all = ["pointer", "auto_target"])]
+    pub random_view: bool,
+
+    /// Seed of --random-view, which picks the same point for the same seed
+    /// [default: taken from the clock]
+    #[arg(long, value_name = "S", requires = "random_view")]
+    pub random_seed: Option<u64>,
+
+    /// Zoom towards the nucleus of the lowest-period minibrot in the opening
+    /// view, found by period detection and Newton's method; only for the
+    /// power 2 Mandelbrot set
+    #[arg(long, conflicts_with_all = ["pointer", "auto_target", "random_view"])]
+    pub find_minibrot: bool,
+
+    /// Blur the motion of the zoom: render each frame as the average of N
+    /// sub-frames spread evenly over the zoom from it to the next frame
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..=1024),
+          conflicts_with_all = ["perturbation", "global_normalize", "loop_crossfade", "mirror"])]
+    pub motion_blur_samples: Option<u32>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Join folders of frames and GIF files into one GIF, in the order given
+    Concat(ConcatArgs),
+    /// Render one small canonical view of every fractal type and compare it
+    /// pixel by pixel with its reference image, exiting with an error if any
+    /// differs
+    Verify(VerifyArgs),
+    /// Render every job of a batch file, each into its own subfolder of the
+    /// output folder, reporting failed jobs without stopping the rest
+    Batch(BatchArgs),
+    /// Print the text chunks of saved PNG frames, which record how each was
+    /// rendered
+    ReadMetadata(ReadMetadataArgs),
+    /// Write a manifest that splits an animation into runs of frames, one per
+    /// machine, to be rendered with --frame-range
+    Split(SplitArgs),
+    /// Collect the frames the machines of a split rendered into one folder,
+    /// in order, and join them into a GIF
+    Merge(MergeArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConcatArgs {
+    /// Folders of PNG frames, played in name order, and GIF files
+    #[arg(required = true, value_name = "INPUT")]
+    pub inputs: Vec<PathBuf>,
+
+    /// File the joined GIF is written to
+    #[arg(short, long, default_value = "results/concat.gif")]
+    pub output: PathBuf,
+
+    /// Delay between frames in hundredths of a second
+    #[arg(short, long, default_value_t = 15)]
+    pub delay: u16,
+
+    /// How the GIF plays back: loop forever, play once, or play forwards and
+    /// back
+    #[arg(long, value_enum, default_value_t = PlaybackArg::Loop)]
+    pub playback: PlaybackArg,
+
+    /// Number of times a looping or ping-pong GIF plays before it stops, or 0
+    /// to play forever
+    #[arg(long, default_value_t = 0)]
+    pub loop_count: u16,
+
+    /// Insert this many frames fading each input's last frame into the next
+    /// input's first
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub crossfade: usize,
+
+    /// Map the frames to the colors of a gradient loaded from a GIMP .ggr,
+    /// JSON or text file, as rendered with --palette-file
+    #[arg(long, value_name = "FILE")]
+    pub palette_file: Option<PathBuf>,
+
+    /// Map the frames to the colors of a built-in color map, as rendered
+    /// with --palette
+    #[arg(long, value_enum, conflicts_with = "palette_file")]
+    pub palette: Option<PaletteArg>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct VerifyArgs {
+    /// Folder holding one reference PNG per fractal type
+    #[arg(long, value_name = "DIR", default_value = "assets/references")]
+    pub references: PathBuf,
+
+    /// Largest difference between the gray levels of a render and its
+    /// reference that still passes
+    #[arg(long, value_name = "LEVELS", default_value_t = 0)]
+    pub tolerance: u8,
+
+    /// Save the current renders as the reference images instead of comparing
+    /// them, after an intended change to the output
+    #[arg(long, conflicts_with = "tolerance")]
+    pub update: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct BatchArgs {
+    /// JSON array of jobs, each an object of long option names and values,
+    /// like `[{"name": "deep", "view-center": "-0.745,0.11", "smooth": true}]`
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Directory that each job's folder, named by its `name` key or
+    /// `job-NNN`, is created in
+    #[arg(short, long, default_value = "results")]
+    pub output_folder: PathBuf,
+
+    /// Number of threads every job renders on, or `auto` (or 0) for one per
+    /// available CPU; the jobs' own `threads` are ignored
+    #[arg(long, value_name = "COUNT", default_value = "auto", value_parser = parse_threads)]
+    pub threads: usize,
+
+    /// Options given to every job before its own, which override them, after
+    /// `--`, like `-- --pixels 512x512 --n-frames 1`
+    #[arg(last = true, value_name = "OPTIONS")]
+    pub defaults: Vec<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SplitArgs {
+    /// Number of frames of the whole animation, as given to every machine
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub n_frames: u32,
+
+    /// Number of machines the frames are split between
+    #[arg(short, long, value_name = "COUNT", value_parser = clap::value_parser!(u32).range(1..))]
+    pub workers: u32,
+
+    /// File the manifest is written to instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct MergeArgs {
+    /// Manifest written by the split subcommand
+    #[arg(value_name = "MANIFEST")]
+    pub manifest: PathBuf,
+
+    /// Folders of the PNG frames the machines saved, in any order
+    #[arg(required = true, value_name = "FOLDER")]
+    pub inputs: Vec<PathBuf>,
+
+    /// Directory the frames are copied to, under frames/, and the GIF is
+    /// saved in
+    #[arg(short, long, default_value = "results")]
+    pub output_folder: PathBuf,
+
+    /// Delay between frames in hundredths of a second
+    #[arg(short, long, default_value_t = 15)]
+    pub delay: u16,
+
+    /// Map the frames to the colors of a gradient loaded from a GIMP .ggr,
+    /// JSON or text file, as rendered with --palette-file
+    #[arg(long, value_name = "FILE")]
+    pub palette_file: Option<PathBuf>,
+
+    /// Map the frames to the colors of a built-in color map, as rendered
+    /// with --palette
+    #[arg(long, value_enum, conflicts_with = "palette_file")]
+    pub palette: Option<PaletteArg>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReadMetadataArgs {
+    /// PNG files to read
+    #[arg(required = true, value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+}
+
+impl Cli {
+    /// Return the first 8 hex digits of the SHA-256 of every option that
+    /// changes the rendered images, for --hash-name.
+    ///
+    /// The options are hashed in their debug form, with those that only
+    /// choose where the output goes, what is saved besides the images, or
+    /// how fast and verbosely they are made reset first, so the same render
+    /// always gets the same name.
+    pub fn recipe_hash(&self) -> String {
+        let recipe = Cli {
+            output_folder: PathBuf::new(),
+            threads: 1,
+            no_frames: false,
+            no_gif: false,
+            sequence_pattern: None,
+            hash_name: false,
+            dry_run: false,
+            checksum: false,
+            metadata: false,
+            time_budget: None,
+            multithreaded_gif: false,
+            pipeline_gif: false,
+            memory_map: false,
+            verbose: false,
+            continue_from: None,
+            command: None,
+            ..self.clone()
+        };
+        let digest = sha256(format!("{:?}", recipe).as_bytes());
+        hex(&digest)[..8].to_string()
+    }
+
+    /// Return the delay between GIF frames in hundredths of a second: that of
+    /// --fps rounded to at least 1 if given, else --delay.
+    pub fn frame_delay(&self) -> u16 {
+        self.fps.map_or(self.delay, |fps| {
+            (100.0 / fps).round().clamp(1.0, u16::MAX as f64) as u16
+        })
+    }
+
+    /// Start from the view and parameters recorded in `metadata`, keeping
+    /// every value that `matches` shows was given on the command line.
+    ///
+    /// The escape radius and constant are only taken along with the fractal
+    /// type, since they rarely suit another formula. Unless a pointer or
+    /// crop is given, the zoom heads for the center of the recorded view.
+    pub fn continue_from(&mut self, metadata: &FrameMetadata, matches: &ArgMatches) {
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let complex = |z: num::Complex<f64>| format!("{:?},{:?}", z.re, z.im);
+        let params = &metadata.params;
+
+        if !explicit("pixels") && self.print_size.is_none() {
+            self.pixels = format!("{}x{}", metadata.bounds.0, metadata.bounds.1);
+        }
+        self.upper_left = complex(metadata.upper_left);
+        self.lower_right = complex(metadata.lower_right);
+        if self.pointer.is_none() && self.crop.is_none() {
+            self.pointer = Some(complex((metadata.upper_left + metadata.lower_right) / 2.0));
+        }
+
+        if !explicit("fractal_type") {
+            self.fractal_type = params.fractal_type.into();
+            if self.escape_radius.is_none() {
+                self.escape_radius = Some(params.escape_radius);
+            }
+            match (params.fractal_type, params.julia_constant) {
+                (FractalType::Julia, Some(c)) if !explicit("julia_constant") => {
+                    self.julia_constant = complex(c)
+                }
+                (FractalType::Phoenix, Some(p)) if !explicit("phoenix_p") => {
+                    self.phoenix_p = complex(p)
+                }
+                _ => {}
+            }
+        }
+        if !explicit("power") {
+            self.power = params.power;
+            if self.power_complex.is_none() {
+                self.power_complex = params.complex_power.map(complex);
+            }
+        }
+        if !explicit("lyapunov_sequence") {
+            self.lyapunov_sequence = params.lyapunov_sequence;
+        }
+        if !explicit("max_iterations") {
+            self.max_iterations = params.max_iterations;
+        }
+        if !explicit("corner_sampling") {
+            self.corner_sampling = params.corner_sampling;
+        }
+        if self.conjugate.is_none() {
+            self.conjugate = params.conjugate.map(Into::into);
+        }
+        if self.escape_condition.is_none() {
+            self.escape_condition = params.escape_condition.map(Into::into);
+        }
+    }
+}
+
+#[test]
+fn test_recipe_hash() {
+    let hash = |args: &[&str]| Cli::parse_from(["mandelbrot"].iter().chain(args)).recipe_hash();
+    let plain = hash(&[]);
+    assert_eq!(plain.len(), 8);
+    assert!(plain.bytes().all(|b| b.is_ascii_hexdigit()));
+    // Where and how fast the images are made leaves the name alone
+    assert_eq!(
+        hash(&[
+            "-o",
+            "elsewhere",
+            "--threads",
+            "3",
+            "--hash-name",
+            "--verbose"
+        ]),
+        plain
+    );
+    assert_ne!(hash(&["--max-iterations", "101"]), plain);
+    assert_ne!(hash(&["--smooth"]), plain);
+}
+
+#[test]
+fn test_frame_delay() {
+    let delay = |args: &[&str]| Cli::parse_from(["mandelbrot"].iter().chain(args)).frame_delay();
+    assert_eq!(delay(&[]), 15);
+    assert_eq!(delay(&["--delay", "4"]), 4);
+    assert_eq!(delay(&["--fps", "25"]), 4);
+    assert_eq!(delay(&["--fps", "30"]), 3);
+    assert_eq!(delay(&["--fps", "0.5"]), 200);
+    // Faster than a hundredth of a second is as fast as GIF goes
+    assert_eq!(delay(&["--fps", "500"]), 1);
+    assert!(Cli::try_parse_from(["mandelbrot", "--fps", "30", "--delay", "3"]).is_err());
+    assert!(Cli::try_parse_from(["mandelbrot", "--fps", "0"]).is_err());
+}
+
+#[test]
+fn test_frame_range_conflicts() {
+    // A part of an animation must render the same frames as the whole
+    let range = ["mandelbrot", "--n-frames", "10", "--frame-range", "3", "4"];
+    assert!(Cli::try_parse_from(range).is_ok());
+    for option in [
+        &["--time-budget", "5"][..],
+        &["--stop-when-uniform"],
+        &["--global-normalize"],
+    ] {
+        let args = range.iter().chain(option);
+        assert!(Cli::try_parse_from(args).is_err(), "{:?}", option);
+    }
+}
+
+/// Parse a strictly positive floating-point argument.
+fn parse_positive(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if value > 0.0 => Ok(value),
+        Ok(_) => Err(format!("`{}` must be greater than zero", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Read the ICC profile in the file `s`, which PNG files must be able to
+/// embed.
+fn parse_icc_profile(s: &str) -> Result<Vec<u8>, String> {
+    let profile = std::fs::read(s).map_err(|e| format!("cannot read {}: {}", s, e))?;
+    mandelbrot::utils::preserve::check_icc_profile(&profile)?;
+    Ok(profile)
+}
+
+/// Parse an iteration limit, which must be at least one.
+fn parse_iterations(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err(format!("`{}` must be at least 1", s)),
+        Ok(value) => Ok(value),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a whole-number scale factor, which must be at least one.
+fn parse_factor(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err(format!("`{}` must be at least 1", s)),
+        Ok(value) => Ok(value),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a thread count, where `auto` or 0 detects the available parallelism.
+fn parse_threads(s: &str) -> Result<usize, String> {
+    match s {
+        "auto" | "0" => Ok(std::thread::available_parallelism().map_or(1, |n| n.get())),
+        _ => s.parse::<usize>().map_err(|e| e.to_string()),
+    }
+}
+
+/// Parse a poster frame, a frame number counting from 1 or `auto`.
+fn parse_poster(s: &str) -> Result<Poster, String> {
+    match s {
+        "auto" => Ok(Poster::Auto),
+        _ => match s.parse::<usize>() {
+            Ok(0) => Err(format!("`{}` must be at least 1", s)),
+            Ok(frame) => Ok(Poster::Frame(frame)),
+            Err(_) => Err(format!("`{}` is neither a frame number nor `auto`", s)),
+        },
+    }
+}
+
+/// Parse a color given as three comma-separated values from 0 to 255.
+fn parse_rgb(s: &str) -> Result<[u8; 3], String> {
+    let values = s
+        .split(',')
+        .map(|field| field.trim().parse::<u8>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<u8>, String>>()?;
+    values
+        .try_into()
+        .map_err(|_| format!("`{}` is not a color (expected R,G,B)", s))
+}
+
+/// Parse a PNG bit depth, which must be 8 or 16.
+fn parse_bit_depth(s: &str) -> Result<u8, String> {
+    match s {
+        "8" => Ok(8),
+        "16" => Ok(16),
+        _ => Err(format!(
+            "`{}` is not a supported bit depth (expected 8 or 16)",
+            s
+        )),
+    }
 }
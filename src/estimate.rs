@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+/// Measurements taken from rendering a single sample frame
+pub struct Estimate {
+    /// Width and height of each frame in pixels
+    pub bounds: (usize, usize),
+    /// Number of frames the full render would produce
+    pub n_frames: usize,
+    /// Bytes per pixel of the frame buffers
+    pub bytes_per_pixel: usize,
+    /// Size of the sample frame's PNG file
+    pub frame_bytes: u64,
+    /// Time taken to render and save the sample frame
+    pub frame_time: Duration,
+}
+
+impl Estimate {
+    /// Describe the resources the full render is expected to need.
+    pub fn report(&self) -> String {
+        let frame_pixels = self.bounds.0 * self.bounds.1;
+        let total_pixels = frame_pixels as u64 * self.n_frames as u64;
+        let buffer_bytes = (frame_pixels * self.bytes_per_pixel) as u64;
+        let disk_bytes = self.frame_bytes * self.n_frames as u64;
+        let total_time = self.frame_time * self.n_frames as u32;
+        format!(
+            "Dry run: {} frames of {}x{}\n\
+             Total pixels:   {}\n\
+             Frame buffer:   {}\n\
+             Disk usage:     ~{} ({} per frame, before the GIF)\n\
+             Estimated time: ~{} ({} per frame)",
+            self.n_frames,
+            self.bounds.0,
+            self.bounds.1,
+            total_pixels,
+            format_bytes(buffer_bytes),
+            format_bytes(disk_bytes),
+            format_bytes(self.frame_bytes),
+            format_duration(total_time),
+            format_duration(self.frame_time),
+        )
+    }
+}
+
+#[test]
+fn test_report() {
+    let estimate = Estimate {
+        bounds: (200, 100),
+        n_frames: 50,
+        bytes_per_pixel: 2,
+        frame_bytes: 4096,
+        frame_time: Duration::from_millis(300),
+    };
+    let report = estimate.report();
+    assert!(report.contains("50 frames of 200x100"));
+    assert!(report.contains("Total pixels:   1000000"));
+    assert!(report.contains("Frame buffer:   39.1 KiB"));
+    assert!(report.contains("~200.0 KiB (4.0 KiB per frame"));
+    assert!(report.contains("~15.0s (300ms per frame)"));
+}
+
+/// Format a byte count with a binary unit, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[test]
+fn test_format_bytes() {
+    assert_eq!(format_bytes(0), "0 B");
+    assert_eq!(format_bytes(1023), "1023 B");
+    assert_eq!(format_bytes(1536), "1.5 KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    assert_eq!(format_bytes(5 << 40), "5.0 TiB");
+}
+
+/// Format a duration for humans, e.g. `250ms`, `4.2s` or `1h 3m 20s`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds < 1.0 {
+        return format!("{}ms", duration.as_millis());
+    }
+    if seconds < 60.0 {
+        return format!("{:.1}s", seconds);
+    }
+    let total = duration.as_secs();
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else {
+        format!("{}m {}s", minutes, seconds)
+    }
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(Duration::from_millis(250)), "250ms");
+    assert_eq!(format_duration(Duration::from_millis(4200)), "4.2s");
+    assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+    assert_eq!(format_duration(Duration::from_secs(3800)), "1h 3m 20s");
+}
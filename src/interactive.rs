@@ -0,0 +1,288 @@
+use mandelbrot::utils::{self, ColorOptions, FractalParams, FractalType};
+use num::Complex;
+use std::io::{self, BufRead, Write};
+
+/// Width of the preview in pixels, one per terminal column
+const PREVIEW_WIDTH: usize = 80;
+/// Height of the preview in pixels, two per terminal row
+const PREVIEW_HEIGHT: usize = 48;
+
+/// The region of the complex plane being explored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub center: Complex<f64>,
+    /// Distance from the center to the edge of the view along the shorter axis
+    pub radius: f64,
+}
+
+/// One navigation step typed at the prompt
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    /// Move the center by these fractions of the radius, right and up
+    Pan(f64, f64),
+    /// Multiply the radius by this factor
+    Zoom(f64),
+    /// Center the view on this preview pixel
+    Pick(usize, usize),
+    /// Center the view on this point
+    Center(Complex<f64>),
+    /// Switch to another formula, keeping the view
+    Type(FractalType),
+    Quit,
+}
+
+/// Parse a line of input into commands.
+///
+/// A line starting with a word sets one parameter: `center RE,IM`, `zoom
+/// FACTOR`, which magnifies the view by FACTOR, or `type NAME`. A line
+/// holding two numbers, `COLUMN ROW`, picks a new center. Otherwise every
+/// character is a command of its own, so `ww++` pans up twice and then zooms
+/// in twice.
+fn parse_line(line: &str) -> Result<Vec<Command>, String> {
+    if let Some((word, value)) = line.trim().split_once(char::is_whitespace) {
+        let value = value.trim();
+        match word.to_ascii_lowercase().as_str() {
+            "center" => {
+                return utils::parse_complex(value)
+                    .map(|center| vec![Command::Center(center)])
+                    .ok_or_else(|| format!("`{}` is not a point (expected RE,IM)", value));
+            }
+            "zoom" => {
+                return match value.parse::<f64>() {
+                    Ok(factor) if factor > 0.0 && factor.is_finite() => {
+                        Ok(vec![Command::Zoom(1.0 / factor)])
+                    }
+                    _ => Err(format!("`{}` is not a positive zoom factor", value)),
+                };
+            }
+            "type" => return Ok(vec![Command::Type(value.parse()?)]),
+            _ => {}
+        }
+    }
+    let fields: Vec<&str> = line.split([' ', ',']).filter(|f| !f.is_empty()).collect();
+    if let [column, row] = fields[..]
+        && let (Ok(column), Ok(row)) = (column.parse(), row.parse())
+    {
+        return Ok(vec![Command::Pick(column, row)]);
+    }
+    line.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c.to_ascii_lowercase() {
+            'w' => Ok(Command::Pan(0.0, 0.5)),
+            'a' => Ok(Command::Pan(-0.5, 0.0)),
+            's' => Ok(Command::Pan(0.0, -0.5)),
+            'd' => Ok(Command::Pan(0.5, 0.0)),
+            '+' | '=' => Ok(Command::Zoom(0.5)),
+            '-' => Ok(Command::Zoom(2.0)),
+            'q' => Ok(Command::Quit),
+            c => Err(format!("unknown command `{}`", c)),
+        })
+        .collect()
+}
+
+impl View {
+    /// Return the corners of the view for an image of dimensions `bounds`.
+    pub fn corners(&self, bounds: (usize, usize)) -> (Complex<f64>, Complex<f64>) {
+        utils::transform::view_from_center(bounds, self.center, self.radius)
+    }
+
+    fn apply(&mut self, command: Command) {
+        match command {
+            Command::Pan(right, up) => {
+                self.center += Complex {
+                    re: right * self.radius,
+                    im: up * self.radius,
+                };
+            }
+            Command::Zoom(factor) => self.radius *= factor,
+            Command::Pick(column, row) => {
+                let bounds = (PREVIEW_WIDTH, PREVIEW_HEIGHT);
+                let (upper_left, lower_right) = self.corners(bounds);
+                self.center = utils::transform::pixel_to_point(
+                    bounds,
+                    (column.min(PREVIEW_WIDTH - 1), row.min(PREVIEW_HEIGHT - 1)),
+                    upper_left,
+                    lower_right,
+                );
+            }
+            Command::Center(center) => self.center = center,
+            Command::Type(_) | Command::Quit => {}
+        }
+    }
+}
+
+/// Return the command-line options that render `view` of `fractal_type`.
+pub fn flags(view: View, fractal_type: FractalType) -> String {
+    format!(
+        "--fractal-type {} --view-center=\"{},{}\" --view-radius {}",
+        fractal_type.name().replace('_', "-"),
+        view.center.re,
+        view.center.im,
+        view.radius
+    )
+}
+
+#[test]
+fn test_navigation() {
+    let mut view = View {
+        center: Complex { re: -0.5, im: 0.0 },
+        radius: 1.0,
+    };
+    for command in parse_line("w d++").unwrap() {
+        view.apply(command);
+    }
+    assert_eq!(view.center, Complex { re: 0.0, im: 0.5 });
+    assert_eq!(view.radius, 0.25);
+
+    // The preview is 80x48 pixels, so its middle pixel sits at the center
+    let mut picked = view;
+    picked.apply(parse_line("40 24").unwrap()[0]);
+    let pixel = 2.0 * view.radius / PREVIEW_HEIGHT as f64;
+    assert!((picked.center - view.center - Complex::new(pixel, -pixel) / 2.0).norm() < 1e-12);
+
+    assert_eq!(
+        parse_line("-q").unwrap(),
+        vec![Command::Zoom(2.0), Command::Quit]
+    );
+    assert!(parse_line("x").is_err());
+
+    // Words set one parameter each
+    let mut set = view;
+    for command in parse_line("center -0.75,0.1").unwrap() {
+        set.apply(command);
+    }
+    set.apply(parse_line(" Zoom 4 ").unwrap()[0]);
+    assert_eq!(set.center, Complex::new(-0.75, 0.1));
+    assert_eq!(set.radius, view.radius / 4.0);
+    assert_eq!(
+        parse_line("type burning-ship").unwrap(),
+        vec![Command::Type(FractalType::BurningShip)]
+    );
+    assert_eq!(
+        flags(set, FractalType::BurningShip),
+        "--fractal-type burning-ship --view-center=\"-0.75,0.1\" --view-radius 0.0625"
+    );
+    for line in ["center 1", "zoom 0", "zoom x", "type spiral"] {
+        assert!(parse_line(line).is_err(), "{:?}", line);
+    }
+}
+
+/// Draw the preview `pixels` with colored half-block characters, two pixel
+/// rows per line, under a ruler of column numbers.
+fn draw(pixels: &[u8], colors: &ColorOptions) -> String {
+    let palette = colors
+        .palette
+        .unwrap_or_else(utils::palette::Palette::grayscale);
+    let mut out = String::new();
+    for column in (0..PREVIEW_WIDTH).step_by(10) {
+        out.push_str(&format!("{:<10}", column));
+    }
+    out.push('\n');
+    for (line, rows) in pixels.chunks(2 * PREVIEW_WIDTH).enumerate() {
+        let (top, bottom) = rows.split_at(PREVIEW_WIDTH);
+        for (&upper, &lower) in top.iter().zip(bottom) {
+            let [ur, ug, ub] = palette.color(upper);
+            let [lr, lg, lb] = palette.color(lower);
+            out.push_str(&format!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                ur, ug, ub, lr, lg, lb
+            ));
+        }
+        out.push_str(&format!("\x1b[0m {}\n", 2 * line));
+    }
+    out
+}
+
+/// Where the user left the preview of [`run`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// They finished at this view
+    Done(View),
+    /// They asked for another formula at this view, which has to be set up
+    /// with that formula's defaults and checks before exploring on
+    Switch(View, FractalType),
+}
+
+/// Explore the fractal of `params`, starting at `view`, with commands read
+/// from `input` and previews written to `output`, until the user finishes
+/// or switches formula.
+///
+/// Each step redraws a low-resolution preview, prints the options that
+/// render it and reads a line of commands: `w`/`a`/`s`/`d` pan, `+` and `-`
+/// zoom, `COLUMN ROW` recenters on a preview pixel, `center RE,IM` and `zoom
+/// FACTOR` set the view directly, `type NAME` switches formula and `q` (or
+/// the end of input) finishes.
+pub fn run(
+    mut view: View,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<Outcome> {
+    let bounds = (PREVIEW_WIDTH, PREVIEW_HEIGHT);
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    let mut lines = input.lines();
+    loop {
+        let (upper_left, lower_right) = view.corners(bounds);
+        utils::render_parallel(&mut pixels, bounds, upper_left, lower_right, params, colors);
+        write!(output, "{}", draw(&pixels, colors))?;
+        write!(
+            output,
+            "{}\n\
+             [w/a/s/d] pan  [+/-] zoom  [COLUMN ROW] center on a pixel  \
+             [center RE,IM] [zoom FACTOR] [type NAME]  [q] done > ",
+            flags(view, params.fractal_type)
+        )?;
+        output.flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            return Ok(Outcome::Done(view));
+        };
+        match parse_line(&line) {
+            Ok(commands) => {
+                for command in commands {
+                    match command {
+                        Command::Quit => return Ok(Outcome::Done(view)),
+                        Command::Type(fractal_type) if fractal_type != params.fractal_type => {
+                            return Ok(Outcome::Switch(view, fractal_type));
+                        }
+                        command => view.apply(command),
+                    }
+                }
+            }
+            Err(e) => writeln!(output, "Error: {}", e)?,
+        }
+    }
+}
+
+#[test]
+fn test_run() {
+    let start = View {
+        center: Complex::new(-0.5, 0.0),
+        radius: 1.0,
+    };
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let explore = |input: &str| {
+        let (mut input, mut output) = (input.as_bytes(), Vec::new());
+        let outcome = run(start, &params, &colors, &mut input, &mut output).unwrap();
+        let rest = String::from_utf8(input.to_vec()).unwrap();
+        (outcome, String::from_utf8(output).unwrap(), rest)
+    };
+
+    // Switching formula hands the view back at once, leaving the rest of
+    // the input for the new formula
+    let zoomed = View {
+        radius: 0.5,
+        ..start
+    };
+    let (outcome, output, rest) = explore("+\ntype julia\nw\n");
+    assert_eq!(outcome, Outcome::Switch(zoomed, FractalType::Julia));
+    assert_eq!(output.matches("--fractal-type mandelbrot").count(), 2);
+    assert_eq!(rest, "w\n");
+
+    // The formula already shown is no switch, and bad lines are reported
+    let (outcome, output, _) = explore("type mandelbrot\nx\n+");
+    assert_eq!(outcome, Outcome::Done(zoomed));
+    assert!(output.contains("Error: unknown command `x`"), "{}", output);
+}
@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by the signal handler once the user presses Ctrl-C.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Install a Ctrl-C (SIGINT) handler that requests a graceful stop.
+///
+/// The first Ctrl-C only sets a flag that the frame loop polls, so the frames
+/// rendered so far can still be assembled into a GIF. The handler then
+/// restores the default behavior, so a second Ctrl-C terminates immediately.
+#[cfg(unix)]
+pub fn install() {
+    extern "C" fn handle(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        // SAFETY: `signal` is async-signal-safe
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+
+    let handler = handle as extern "C" fn(libc::c_int);
+    // SAFETY: the handler only touches an atomic and calls `signal`
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+/// On platforms without POSIX signals Ctrl-C keeps its default behavior.
+#[cfg(not(unix))]
+pub fn install() {}
+
+/// Return true if the user has asked the render to stop.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,8 @@
+//! Escape-time fractal rendering.
+//!
+//! The [`utils`] module holds the rendering core, which works on plain pixel
+//! buffers; only [`utils::preserve`] touches the filesystem.
+
+pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,141 +1,1723 @@
 mod cli;
-mod utils;
+mod estimate;
+mod interactive;
+mod interrupt;
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use indicatif::{ProgressBar, ProgressStyle};
-use rayon::prelude::*;
+use mandelbrot::utils::error::FractalError;
+use mandelbrot::utils::mapped::RenderBuffers;
+use mandelbrot::utils::{self, Channel, ColorOptions, FractalParams, FractalType};
+use num::Complex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tempfile::TempDir;
-use utils::FractalType;
+
+/// Set while standard output carries the image of `--stdout`
+static STDOUT_IMAGE: AtomicBool = AtomicBool::new(false);
+
+/// Print a line like `println!`, or to standard error while standard output
+/// carries the image of `--stdout`.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if STDOUT_IMAGE.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
 fn main() {
-    let cli = cli::Cli::parse();
+    let matches = cli::Cli::command().get_matches();
+    let mut cli = cli::Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // Join earlier renders instead of rendering
+    match &cli.command {
+        Some(cli::Command::Concat(args)) => {
+            concat(args);
+            return;
+        }
+        // Check the renders against the reference images instead of rendering
+        Some(cli::Command::Verify(args)) => verify(args),
+        // Render every job of a batch file
+        Some(cli::Command::Batch(args)) => batch(args),
+        Some(cli::Command::ReadMetadata(args)) => read_metadata(args),
+        // Share an animation between machines and put it back together
+        Some(cli::Command::Split(args)) => split(args),
+        Some(cli::Command::Merge(args)) => merge(args),
+        None => {}
+    }
+    continue_from(&mut cli, &matches);
+
+    // Render on the requested number of threads
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(cli.threads)
+        .build_global()
+        .expect("Error creating thread pool");
+
+    // Let Ctrl-C stop the render after the current frame instead of killing it
+    interrupt::install();
+
+    render(&cli);
+}
+
+/// Pick up where the saved frame of `--continue-from` left off, unless
+/// overridden on the command line.
+fn continue_from(cli: &mut cli::Cli, matches: &clap::ArgMatches) {
+    if let Some(path) = &cli.continue_from {
+        let metadata = utils::metadata::FrameMetadata::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            fail();
+        });
+        cli.continue_from(&metadata, matches);
+    }
+}
+
+/// Set while failures are contained, see [`Containment`], so that a failing
+/// batch job or formula switched to interactively only ends itself
+static CONTAINED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Give up on the render after its error has been printed: exit with status
+/// 1, or, while failures are contained, unwind to where they are caught.
+fn fail() -> ! {
+    give_up(1)
+}
+
+/// Print the library error `error` met while `doing` something and give up
+/// on the render like [`fail`], exiting with a status that tells the kind of
+/// error apart: 2 for unusable input, 3 for files that could not be read or
+/// written, 4 for images and 5 for GIFs that could not be encoded.
+fn fail_with(doing: &str, error: FractalError) -> ! {
+    eprintln!("Error {}: {}", doing, error);
+    give_up(match error {
+        FractalError::Parse(_) => 2,
+        FractalError::Io(_) => 3,
+        FractalError::Image(_) => 4,
+        FractalError::Gif(_) => 5,
+    })
+}
+
+/// Exit with `status`, or, while failures are contained, unwind to where
+/// they are caught.
+fn give_up(status: i32) -> ! {
+    if CONTAINED.load(std::sync::atomic::Ordering::SeqCst) {
+        std::panic::resume_unwind(Box::new(Failed));
+    }
+    std::process::exit(status);
+}
+
+/// Payload of the unwinding that [`fail`] starts while failures are contained
+struct Failed;
+
+/// Warn about the numbers of the `option` value `value` that are rounded to
+/// fit an `f64`, see [`utils::rounded_parts`].
+fn warn_rounded(option: &str, value: &str) {
+    for (part, rounded) in utils::rounded_parts(value) {
+        eprintln!(
+            "Warning: {} {} has more digits than f64 can hold and is rounded to {:?}",
+            option, part, rounded
+        );
+    }
+}
+
+/// Render the animation, still or other output that `cli` asks for.
+fn render(cli: &cli::Cli) {
+    STDOUT_IMAGE.store(cli.stdout, Ordering::Relaxed);
 
     // Create the output directory if it doesn't exist
-    if !cli.output_folder.exists() {
+    if !cli.dry_run && !cli.stdout && !cli.output_folder.exists() {
         std::fs::create_dir_all(&cli.output_folder).expect("Error creating output directory");
     }
 
     // Determine where to store frames
-    // If no_frames is true, use a temporary directory
+    // If no_frames is true (or no animation is made), use a temporary directory
     // Otherwise, use a subdirectory in the result folder
-    let frames_dir = if cli.no_frames {
-        // Create temporary directory that will be automatically deleted when dropped
-        let dir = TempDir::new().expect("Error creating temporary directory");
-        dir.path().to_path_buf()
-    } else {
-        // Use the frames directory in the result folder
-        let dir = cli.output_folder.join("frames");
-        if !dir.exists() {
-            std::fs::create_dir_all(&dir).expect("Error creating frames directory");
+    let (frames_dir, _temp_dir) =
+        if cli.no_frames || cli.stdout || cli.dry_run || cli.contact_sheet || cli.interactive {
+            // Create temporary directory that will be automatically deleted when dropped,
+            // so keep it alive until the GIF has been assembled
+            let dir = TempDir::new().expect("Error creating temporary directory");
+            (dir.path().to_path_buf(), Some(dir))
+        } else if cli.sequence_pattern.is_some() {
+            // The pattern names the frames within the result folder
+            (cli.output_folder.clone(), None)
+        } else {
+            // Use the frames directory in the result folder
+            let dir = cli.output_folder.join("frames");
+            if !dir.exists() {
+                std::fs::create_dir_all(&dir).expect("Error creating frames directory");
+            }
+            (dir, None)
+        };
+
+    // Parse the arguments from the command line interface
+    let bounds = match (&cli.print_size, cli.dpi) {
+        (Some(size), Some(dpi)) => {
+            let (width, height): (f64, f64) =
+                utils::parse_pair(size, 'x').expect("Error parsing print size");
+            let bounds = (
+                (width * dpi).round() as usize,
+                (height * dpi).round() as usize,
+            );
+            if bounds.0 == 0 || bounds.1 == 0 {
+                eprintln!(
+                    "Error: --print-size {} at {} DPI is less than one pixel",
+                    size, dpi
+                );
+                fail();
+            }
+            bounds
+        }
+        _ => utils::parse_pair(&cli.pixels, 'x').expect("Error parsing image dimensions"),
+    };
+    // A preview keeps the view and shrinks only the pixels it is sampled with
+    let bounds = match cli.preview_scale {
+        Some(scale) if !(scale > 0.0 && scale <= 1.0) => {
+            eprintln!("Error: --preview-scale must be more than 0 and at most 1");
+            fail();
         }
-        dir
+        Some(scale) => utils::transform::preview_bounds(bounds, scale),
+        None => bounds,
     };
+    let view_center = cli
+        .view_center
+        .as_ref()
+        .map(|s| utils::parse_complex(s).expect("Error parsing view center"));
+    // Deep zooms read the center again at full precision
+    if let (Some(center), false) = (&cli.view_center, cli.perturbation || cli.high_precision) {
+        warn_rounded("--view-center", center);
+    }
+
+    // Multibrot powers below 2 have no set to frame, and only the multibrot
+    // formula takes a real power
+    let multibrot = FractalType::from(cli.fractal_type) == FractalType::Multibrot;
+    if multibrot && (cli.power < 2 || cli.multibrot_power.is_some_and(|end| end <= 1.0)) {
+        eprintln!(
+            "Error: multibrot sets need --power of at least 2 and a --multibrot-power above 1"
+        );
+        fail();
+    }
+    if cli.multibrot_power.is_some() && !multibrot {
+        eprintln!("Error: --multibrot-power needs --fractal-type multibrot");
+        fail();
+    }
+
+    // A center and radius take precedence over explicit corners
+    let (mut upper_left, mut lower_right) = match (view_center, cli.view_radius) {
+        (Some(center), Some(radius)) => utils::transform::view_from_center(bounds, center, radius),
+        // The multibrot set changes shape with its power
+        _ if cli.bounds_auto && multibrot => {
+            let start = cli.power as f64;
+            let (upper_left, lower_right) =
+                utils::multibrot_view(start, cli.multibrot_power.unwrap_or(start));
+            utils::transform::fit_view(bounds, upper_left, lower_right)
+        }
+        _ if cli.bounds_auto => {
+            let (upper_left, lower_right) = FractalType::from(cli.fractal_type).default_view();
+            utils::transform::fit_view(bounds, upper_left, lower_right)
+        }
+        _ => {
+            let upper_left = utils::parse_complex(&cli.upper_left)
+                .expect("Error parsing upper left corner point");
+            let lower_right = utils::parse_complex(&cli.lower_right)
+                .expect("Error parsing lower right corner point");
+            warn_rounded("--upper-left", &cli.upper_left);
+            warn_rounded("--lower-right", &cli.lower_right);
+            // Swapped corners would render the view mirrored
+            let (upper_left, lower_right, reordered) =
+                utils::transform::order_corners(upper_left, lower_right).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    fail();
+                });
+            if reordered {
+                eprintln!(
+                    "Warning: --upper-left and --lower-right are not the upper-left and lower-right corners; using {},{} and {},{}",
+                    upper_left.re, upper_left.im, lower_right.re, lower_right.im
+                );
+            }
+            (upper_left, lower_right)
+        }
+    };
+
+    // Narrow the opening view down to the cropped rectangle
+    let crop = cli.crop.as_ref().map(|s| {
+        let crop = utils::transform::Crop::parse(s, bounds).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            fail();
+        });
+        let aspect = crop.aspect(bounds) / (bounds.0 as f64 / bounds.1 as f64);
+        if (aspect - 1.0).abs() > 0.01 {
+            eprintln!(
+                "Warning: the crop's shape differs from --pixels, so the image will be stretched"
+            );
+        }
+        (upper_left, lower_right) = crop.apply(upper_left, lower_right);
+        crop
+    });
 
-    // Parse the arguments from the command line interface
-    let bounds = utils::parse_pair(&cli.pixels, 'x').expect("Error parsing image dimensions");
-    let mut upper_left =
-        utils::parse_complex(&cli.upper_left).expect("Error parsing upper left corner point");
-    let mut lower_right =
-        utils::parse_complex(&cli.lower_right).expect("Error parsing lower right corner point");
-    let scale_factor = cli.scale_factor;
-    let power = cli.power;
-    let escape_radius = cli.escape_radius;
-    let scale_pointer = utils::parse_complex(&cli.pointer).expect("Error parsing scale pointer");
     let n_frames = cli.n_frames;
+    let scale_factor = match (cli.total_zoom, cli.zoom_video_loop) {
+        (Some(total_zoom), _) => utils::transform::scale_factor_for_zoom(total_zoom, n_frames),
+        (None, Some(loop_zoom)) => utils::transform::loop_scale_factor(loop_zoom, n_frames),
+        (None, None) => cli.scale_factor,
+    };
+    if cli.loop_crossfade >= n_frames && cli.loop_crossfade > 0 {
+        eprintln!("Error: --loop-crossfade must be smaller than --n-frames");
+        fail();
+    }
+    // Frame indices to render, counting from zero
+    let frames = match cli.frame_range.as_deref() {
+        Some(&[start, end]) => {
+            if start == 0 || start > end || end > n_frames {
+                eprintln!(
+                    "Error: --frame-range {} {} must satisfy 1 <= START <= END <= --n-frames ({})",
+                    start, end, n_frames
+                );
+                fail();
+            }
+            start - 1..end
+        }
+        _ => 0..n_frames,
+    };
+    if let Some(cli::Poster::Frame(frame)) = cli.poster
+        && !frames.contains(&(frame - 1))
+    {
+        eprintln!(
+            "Error: --poster {} must be one of the frames rendered, {} to {}",
+            frame,
+            frames.start + 1,
+            frames.end
+        );
+        fail();
+    }
+    if let Some(frame) = cli
+        .hq_frames
+        .iter()
+        .flatten()
+        .find(|&&frame| frame > n_frames)
+    {
+        eprintln!(
+            "Error: --hq-frames {} is past the last frame, {}",
+            frame, n_frames
+        );
+        fail();
+    }
+    // The detail of a frame is measured on a grid of points in double precision
+    if cli.poster == Some(cli::Poster::Auto) && (cli.perturbation || cli.buddhabrot) {
+        eprintln!(
+            "Error: --poster auto cannot measure the detail of perturbation or Buddhabrot frames; give a frame number"
+        );
+        fail();
+    }
+    let power = cli.power;
+    let complex_power = cli
+        .power_complex
+        .as_deref()
+        .map(|p| utils::parse_complex(p).expect("Error parsing complex power"));
+    let initial_z = cli
+        .initial_z
+        .as_deref()
+        .map(|z| utils::parse_complex(z).expect("Error parsing initial z"));
+    // How fast |z| grows far from the origin, for smooth coloring
+    let growth = complex_power.map_or(power.max(2) as f64, |p| p.re);
+
+    // Zoom towards the view center unless a pointer is given explicitly; an
+    // exponential map zooms about its own center
+    let exp_map = cli
+        .exp_map
+        .as_ref()
+        .map(|s| utils::parse_complex(s).expect("Error parsing exponential map center"));
+    let mut scale_pointer = match (&cli.pointer, exp_map.or(view_center)) {
+        (Some(pointer), _) => {
+            if !(cli.perturbation || cli.high_precision) {
+                warn_rounded("--pointer", pointer);
+            }
+            utils::parse_complex(pointer).expect("Error parsing scale pointer")
+        }
+        // A cropped view zooms towards the middle of the crop
+        (None, _) if crop.is_some() => (upper_left + lower_right) / 2.0,
+        (None, Some(center)) => center,
+        (None, None) => utils::parse_complex(cli::DEFAULT_POINTER).unwrap(),
+    };
 
     // Get the fractal type from CLI
     let fractal_type: FractalType = cli.fractal_type.into();
+    // Outputs are named after the fractal type, and with --hash-name after
+    // the options as well
+    let output_name = if cli.hash_name {
+        format!("{}-{}", fractal_type.name(), cli.recipe_hash())
+    } else {
+        fractal_type.name().to_string()
+    };
+    let escape_radius = cli
+        .escape_radius
+        .unwrap_or_else(|| fractal_type.default_escape_radius());
 
-    // Parse Julia set constant if needed
-    let julia_constant = match fractal_type {
-        FractalType::Julia => {
+    // Parse the Julia set or phoenix constant if needed
+    // A morph into a Julia set or phoenix fractal takes the target's constant
+    let morph_to = cli.morph_to.map(FractalType::from);
+    let julia_constant = match (fractal_type, morph_to) {
+        (FractalType::Julia, _) | (_, Some(FractalType::Julia)) => {
+            Some(utils::parse_complex(&cli.julia_constant).expect("Error parsing Julia constant"))
+        }
+        // Phoenix fractals carry their constant p in the same place
+        (FractalType::Phoenix, _) | (_, Some(FractalType::Phoenix)) => {
+            Some(utils::parse_complex(&cli.phoenix_p).expect("Error parsing phoenix constant"))
+        }
+        // The dual view pairs the Mandelbrot set with its Julia set
+        _ if cli.dual_view => {
             Some(utils::parse_complex(&cli.julia_constant).expect("Error parsing Julia constant"))
         }
         _ => None,
     };
 
-    // The size of the pixel buffer is width * height
-    let mut pixels = vec![0; bounds.0 * bounds.1];
+    // Determine how the Julia constant moves across the animation
+    let julia_path = if let Some(radius) = cli.julia_orbit {
+        utils::JuliaPath::Orbit { radius }
+    } else if let Some(end) = &cli.julia_constant_end {
+        utils::JuliaPath::Line {
+            end: utils::parse_complex(end).expect("Error parsing Julia constant end"),
+        }
+    } else {
+        utils::JuliaPath::Fixed
+    };
+    if julia_constant.is_none() && !matches!(julia_path, utils::JuliaPath::Fixed) {
+        eprintln!(
+            "Warning: --julia-constant-end and --julia-orbit only apply to Julia sets and phoenix fractals"
+        );
+    }
+
+    let mut params = utils::FractalParams {
+        fractal_type,
+        power,
+        escape_radius,
+        julia_constant,
+        lyapunov_sequence: cli.lyapunov_sequence,
+        max_iterations: cli
+            .adaptive_iterations
+            .as_deref()
+            .map_or(cli.max_iterations, |limits| limits[1]),
+        corner_sampling: cli.corner_sampling,
+        conjugate: cli.conjugate.map(Into::into),
+        complex_power,
+        sample_pattern: utils::transform::SamplePattern::Rect,
+        rotation: None,
+        escape_condition: cli.escape_condition.map(Into::into),
+        morph: None,
+        initial_z,
+    };
+
+    // Rays are traced through the quadratic Mandelbrot formula
+    if cli.external_rays.is_some()
+        && (fractal_type != FractalType::Mandelbrot
+            || power != 2
+            || complex_power.is_some()
+            || cli.conjugate.is_some()
+            || morph_to.is_some()
+            || initial_z.is_some())
+    {
+        eprintln!("Error: --external-rays only applies to the plain power-2 Mandelbrot set");
+        fail();
+    }
+
+    // Edges are found where the escape counts of neighbouring pixels differ
+    if let Some(&[_, samples]) = cli.adaptive_aa.as_deref() {
+        if samples == 0 {
+            eprintln!("Error: --adaptive-aa SAMPLES must be at least 1");
+            fail();
+        }
+        if let FractalType::Lyapunov = fractal_type {
+            eprintln!(
+                "Error: --adaptive-aa finds edges by escape counts, which Lyapunov fractals lack"
+            );
+            fail();
+        }
+    }
+
+    // Only members of the low pass are iterated again
+    if let Some(&[low, high]) = cli.adaptive_iterations.as_deref() {
+        if low == 0 || low >= high {
+            eprintln!("Error: --adaptive-iterations needs 1 <= LOW < HIGH");
+            fail();
+        }
+        if let FractalType::Lyapunov = fractal_type {
+            eprintln!("Error: --adaptive-iterations needs an escape-time fractal, not Lyapunov");
+            fail();
+        }
+    }
+
+    if let Some(to) = morph_to
+        && (to == fractal_type
+            || FractalType::Lyapunov == to
+            || FractalType::Lyapunov == fractal_type)
+    {
+        eprintln!(
+            "Error: --morph-to needs another formula than --fractal-type, and Lyapunov fractals have none to blend"
+        );
+        fail();
+    }
+
+    if cli.constant_detail && matches!(fractal_type, FractalType::Lyapunov) {
+        eprintln!(
+            "Error: --constant-detail measures edges by escape counts, which Lyapunov fractals lack"
+        );
+        fail();
+    }
+
+    // Only the Newton step of Nova and the magnet formulas settle on roots
+    if cli.root_coloring
+        && !matches!(
+            fractal_type,
+            FractalType::Nova | FractalType::Magnet1 | FractalType::Magnet2
+        )
+    {
+        eprintln!("Warning: --root-coloring only applies to nova and magnet fractals");
+    }
+
+    // Only the Mandelbrot and Julia formulas raise z to a free power
+    if complex_power.is_some()
+        && !matches!(fractal_type, FractalType::Mandelbrot | FractalType::Julia)
+    {
+        eprintln!("Error: --power-complex only applies to the Mandelbrot and Julia sets");
+        fail();
+    }
+
+    // Only formulas whose orbits all start at the same point have one to move
+    if let Some(start) = initial_z {
+        if matches!(
+            fractal_type,
+            FractalType::Julia
+                | FractalType::Phoenix
+                | FractalType::Collatz
+                | FractalType::Lyapunov
+        ) {
+            eprintln!(
+                "Error: --initial-z needs a formula whose orbits start at a fixed point, not {}",
+                fractal_type.name()
+            );
+            fail();
+        }
+        if fractal_type == FractalType::Nova && start == Complex::new(0.0, 0.0) {
+            eprintln!("Error: --initial-z 0 divides by zero in the Newton step of nova fractals");
+            fail();
+        }
+    }
+
+    // Lyapunov fractals have exponents rather than escape counts to normalize
+    if let Some(clip) = cli.global_normalize {
+        if matches!(fractal_type, FractalType::Lyapunov) {
+            eprintln!("Error: --global-normalize cannot be combined with the Lyapunov fractal");
+            fail();
+        }
+        if !(0.0..50.0).contains(&clip) {
+            eprintln!("Error: --global-normalize must clamp at least 0 and less than 50 percent");
+            fail();
+        }
+    }
+
+    // The Buddhabrot traces complex orbits, which Lyapunov fractals don't have
+    if cli.buddhabrot && matches!(fractal_type, FractalType::Lyapunov) {
+        eprintln!("Error: --buddhabrot cannot be combined with the Lyapunov fractal");
+        fail();
+    }
+
+    // Lyapunov fractals have no final z to look the background up by
+    if cli.background_image.is_some() && matches!(fractal_type, FractalType::Lyapunov) {
+        eprintln!("Error: --background-image cannot be combined with the Lyapunov fractal");
+        fail();
+    }
+
+    // Lyapunov fractals have no escape time to export
+    if cli.export_float.is_some() && matches!(fractal_type, FractalType::Lyapunov) {
+        eprintln!("Error: --export-float cannot be combined with the Lyapunov fractal");
+        fail();
+    }
+
+    // Lyapunov fractals have no complex formula to color
+    if cli.domain_coloring && matches!(fractal_type, FractalType::Lyapunov) {
+        eprintln!("Error: --domain-coloring cannot be combined with the Lyapunov fractal");
+        fail();
+    }
+
+    // Conjugation changes the complex iteration, which Lyapunov fractals don't have
+    if cli.conjugate.is_some() && matches!(fractal_type, FractalType::Lyapunov) {
+        eprintln!("Error: --conjugate cannot be combined with the Lyapunov fractal");
+        fail();
+    }
+    if cli.escape_condition.is_some() && matches!(fractal_type, FractalType::Lyapunov) {
+        eprintln!("Error: --escape-condition cannot be combined with the Lyapunov fractal");
+        fail();
+    }
+
+    // Zoom towards the busiest part of the boundary in the opening view, a
+    // random point on it or a minibrot in it
+    let auto_target = if cli.auto_target {
+        if matches!(fractal_type, FractalType::Lyapunov) {
+            eprintln!("Error: --auto-target needs an escape-time fractal, not Lyapunov");
+            fail();
+        }
+        let target = utils::target::find(bounds, upper_left, lower_right, &params);
+        status!("Auto-target: zooming towards {},{}", target.re, target.im);
+        scale_pointer = target;
+        Some(target)
+    } else if cli.random_view {
+        if matches!(fractal_type, FractalType::Lyapunov) {
+            eprintln!("Error: --random-view needs an escape-time fractal, not Lyapunov");
+            fail();
+        }
+        // Every part of an animation must zoom towards the same point
+        if cli.frame_range.is_some() && cli.random_seed.is_none() {
+            eprintln!("Error: --random-view with --frame-range needs a --random-seed");
+            fail();
+        }
+        let seed = cli.random_seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        });
+        let target =
+            utils::target::random(upper_left, lower_right, &params, seed).unwrap_or_else(|| {
+                eprintln!("Error: --random-view found no escaping points in the opening view");
+                fail();
+            });
+        status!(
+            "Random view (--random-seed {}): zooming towards {},{}",
+            seed,
+            target.re,
+            target.im
+        );
+        scale_pointer = target;
+        Some(target)
+    } else if cli.find_minibrot {
+        let quadratic = matches!(fractal_type, FractalType::Mandelbrot)
+            && params.power == 2
+            && params.conjugate.is_none()
+            && params.complex_power.is_none()
+            && params.morph.is_none()
+            && params.initial_z.is_none();
+        if !quadratic {
+            eprintln!("Error: --find-minibrot needs the power 2 Mandelbrot set");
+            fail();
+        }
+        let nucleus = utils::target::minibrot(upper_left, lower_right, params.max_iterations)
+            .unwrap_or_else(|| {
+                eprintln!("Error: --find-minibrot found no minibrot in the opening view");
+                fail();
+            });
+        let target = nucleus.center;
+        status!(
+            "Minibrot of period {}: zooming towards {},{} (about {:e} across)",
+            nucleus.period,
+            target.re,
+            target.im,
+            nucleus.size
+        );
+        scale_pointer = target;
+        Some(target)
+    } else {
+        None
+    };
+
+    // A log-polar grid is centered on the fixed point of the zoom
+    if cli.sample_pattern == cli::SamplePatternArg::Polar || exp_map.is_some() {
+        params.sample_pattern = utils::transform::SamplePattern::LogPolar {
+            center: scale_pointer,
+        };
+    }
+
+    // Mirroring is only faithful while every frame stays centered on the axis
+    let mirror = cli.mirror.map(utils::symmetry::Mirror::from);
+    if let Some(mirror) = mirror {
+        let last_zoom = scale_factor.powf(n_frames.saturating_sub(1) as f64);
+        let last_frame = (
+            utils::transform::scale_point(upper_left, scale_pointer, last_zoom),
+            utils::transform::scale_point(lower_right, scale_pointer, last_zoom),
+        );
+        if !mirror.is_symmetric(upper_left, lower_right)
+            || !mirror.is_symmetric(last_frame.0, last_frame.1)
+        {
+            eprintln!(
+                "Warning: the view is not centered on the {} axis, so --mirror will not match a full render",
+                match mirror {
+                    utils::symmetry::Mirror::Horizontal => "real",
+                    utils::symmetry::Mirror::Vertical => "imaginary",
+                }
+            );
+        }
+        if mirror == utils::symmetry::Mirror::Horizontal && initial_z.is_some_and(|z| z.im != 0.0) {
+            eprintln!(
+                "Warning: an --initial-z off the real axis breaks the symmetry of the set, so --mirror will not match a full render"
+            );
+        }
+    }
+
+    // Deep zooms keep the view center at arbitrary precision
+    let mut deep_view = if cli.perturbation || cli.high_precision {
+        if cli.perturbation && !utils::perturbation::supports(&params) {
+            eprintln!("Error: --perturbation only supports the Mandelbrot set with power 2");
+            fail();
+        }
+        // Carry enough digits to separate the pixels of the smallest frame
+        let radius = cli.view_radius.unwrap();
+        let final_radius = radius
+            * scale_factor
+                .min(1.0)
+                .powf(n_frames.saturating_sub(1) as f64);
+        let pixel_size = 2.0 * final_radius / bounds.0.min(bounds.1) as f64;
+        let bits = utils::perturbation::precision_for(pixel_size);
+
+        let center = utils::perturbation::DeepPoint::parse(cli.view_center.as_ref().unwrap(), bits)
+            .expect("Error parsing view center");
+        let pointer = match (&cli.pointer, auto_target) {
+            (Some(pointer), _) => utils::perturbation::DeepPoint::parse(pointer, bits)
+                .expect("Error parsing scale pointer"),
+            (None, Some(target)) => utils::perturbation::DeepPoint::from_complex(target, bits),
+            (None, None) => center.clone(),
+        };
+        Some((
+            utils::perturbation::DeepView::new(center, radius, bounds),
+            pointer,
+        ))
+    } else {
+        None
+    };
+    // Consecutive frames of a deep zoom can share their reference orbit
+    let orbit_cache = cli.mandelbrot_reference_orbit_cache.map(|percent| {
+        std::cell::RefCell::new(utils::perturbation::OrbitCache::new(percent / 100.0))
+    });
+
+    let mut colors = utils::ColorOptions {
+        invert: cli.invert,
+        interior: utils::parse_color(&cli.interior_color).expect("Error parsing interior color"),
+        decomposition: cli.decomposition,
+        stripe_density: cli.stripe_density,
+        smooth: match (cli.smooth, cli.band_period) {
+            (Some(power), _) => Some(power.unwrap_or(growth)),
+            (None, Some(_)) if cli.potential.is_none() => Some(growth),
+            _ => None,
+        },
+        band_period: cli.band_period,
+        contour: cli.contour,
+        count_range: None,
+        palette_shift: cli.color_offset,
+        color_scale: cli.color_scale,
+        palette: load_palette(cli.palette_file.as_deref(), cli.palette),
+        interior_coloring: cli.interior_coloring.map(Into::into),
+        potential: cli.potential.map(|power| power.unwrap_or(growth)),
+        root_coloring: cli.root_coloring,
+    };
+
+    // Fixed point has a loop for escape counts of a few formulas, over a
+    // limited range
+    if cli.fixed_point {
+        if !utils::fixed::supports(&params) || colors.needs_orbit() {
+            eprintln!(
+                "Error: --fixed-point only supports the power 2 Mandelbrot and Julia sets with an --escape-radius of at most {}, shaded by escape counts and sampled at pixel centers",
+                utils::fixed::MAX_ESCAPE_RADIUS
+            );
+            fail();
+        }
+        let last_zoom = scale_factor.powf(n_frames.saturating_sub(1) as f64);
+        let last_frame = (
+            utils::transform::scale_point(upper_left, scale_pointer, last_zoom),
+            utils::transform::scale_point(lower_right, scale_pointer, last_zoom),
+        );
+        if !utils::fixed::in_range(upper_left, lower_right)
+            || !utils::fixed::in_range(last_frame.0, last_frame.1)
+        {
+            eprintln!(
+                "Error: --fixed-point only supports views within {} of zero",
+                utils::fixed::MAX_COORDINATE
+            );
+            fail();
+        }
+    }
+
+    // A second palette takes over from the first as escapes slow down
+    if let Some(slow) = load_palette(cli.slow_palette_file.as_deref(), cli.slow_palette) {
+        let fast = colors
+            .palette
+            .unwrap_or_else(utils::palette::Palette::grayscale);
+        colors.palette = Some(utils::palette::Palette::blend(&fast, &slow));
+    }
+
+    // Palettes color the 8-bit gray levels
+    if cli.bit_depth == 16 && colors.palette.is_some() {
+        eprintln!("Error: palettes only support --bit-depth 8");
+        fail();
+    }
+
+    // 16-bit precision only survives in the saved frames, since GIFs are 8-bit
+    if cli.bit_depth == 16 && cli.no_frames {
+        eprintln!(
+            "Error: --bit-depth 16 requires saving frames, but --no-frames only produces an 8-bit GIF"
+        );
+        fail();
+    }
+
+    // Transparency is only available for 8-bit frames
+    if cli.bit_depth == 16 && cli.alpha_interior {
+        eprintln!("Error: --alpha-interior only supports --bit-depth 8");
+        fail();
+    }
+
+    // Palette indices are one byte
+    if cli.bit_depth == 16 && cli.pixel_format == cli::PixelFormatArg::Indexed {
+        eprintln!("Error: --pixel-format indexed only supports --bit-depth 8");
+        fail();
+    }
+
+    // An embedded profile describes the values of either gray or color
+    // images; palette strips, textures and domain colorings are always color
+    if let Some(profile) = &cli.icc_profile {
+        let colored = colors.palette.is_some()
+            || cli.alpha_interior
+            || cli.pixel_format == cli::PixelFormatArg::Indexed
+            || cli.palette_preview.is_some()
+            || cli.background_image.is_some()
+            || cli.domain_coloring;
+        if (&profile[16..20] == b"GRAY") == colored {
+            eprintln!(
+                "Error: --icc-profile is {} profile, but images are saved in {}",
+                if colored { "a gray" } else { "an RGB" },
+                if colored {
+                    "color"
+                } else {
+                    "grayscale without a palette"
+                }
+            );
+            fail();
+        }
+        // Converting the palette makes its colors look the same through the
+        // profile as they do in sRGB
+        if cli.icc_convert {
+            let Some(palette) = &colors.palette else {
+                eprintln!(
+                    "Error: --icc-convert converts the colors of a palette, but there is none"
+                );
+                fail();
+            };
+            match utils::icc::convert_palette(palette, profile) {
+                Ok(converted) => colors.palette = Some(converted),
+                Err(e) => {
+                    eprintln!(
+                        "Error: cannot convert the palette with --icc-profile: {}",
+                        e
+                    );
+                    fail();
+                }
+            }
+        }
+    }
+
+    // Show the palette on its own, from the darkest level to the brightest
+    if let Some(path) = &cli.palette_preview {
+        let palette = colors
+            .palette
+            .unwrap_or_else(utils::palette::Palette::grayscale);
+        let strip = utils::palette::ramp(bounds);
+        let strip_path = path.display().to_string();
+        utils::preserve::write_palette_image(
+            &strip_path,
+            &strip,
+            bounds,
+            &palette,
+            cli.dpi,
+            cli.icc_profile.as_deref(),
+            &[],
+        )
+        .unwrap_or_else(|e| fail_with("writing PNG file", e));
+        status!("Palette preview saved to: {}", strip_path);
+        return;
+    }
+
+    // Hand the opening view to the GPU instead of rendering it here
+    if let Some(language) = cli.emit_shader {
+        let width = (lower_right.re - upper_left.re).abs() / bounds.0 as f64;
+        let magnitude = [upper_left, lower_right]
+            .iter()
+            .map(|c| c.re.abs().max(c.im.abs()))
+            .fold(0.0, f64::max);
+        if width < magnitude * f32::EPSILON as f64 {
+            eprintln!(
+                "Warning: the pixels of this view are finer than single precision resolves, so the shader will show blocks"
+            );
+        }
+        let source =
+            utils::shader::emit(language.into(), &params, &colors, upper_left, lower_right)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    fail();
+                });
+        match &cli.shader_file {
+            Some(path) => {
+                std::fs::write(path, source)
+                    .unwrap_or_else(|e| fail_with("writing shader", e.into()));
+                status!("Shader saved to: {}", path.display());
+            }
+            None => print!("{}", source),
+        }
+        return;
+    }
+
+    // Explore from the opening view and print the flags that reproduce the result
+    if cli.interactive {
+        let start = interactive::View {
+            center: (upper_left + lower_right) / 2.0,
+            radius: (lower_right.re - upper_left.re)
+                .abs()
+                .min((upper_left.im - lower_right.im).abs())
+                / 2.0,
+        };
+        let mut view = start;
+        loop {
+            let outcome = interactive::run(
+                view,
+                &params,
+                &colors,
+                std::io::stdin().lock(),
+                std::io::stdout(),
+            )
+            .unwrap_or_else(|e| fail_with("reading input", e.into()));
+            match outcome {
+                interactive::Outcome::Done(view) => {
+                    status!("\n{}", interactive::flags(view, params.fractal_type));
+                    return;
+                }
+                // Set the new formula up like the command line would, with its
+                // own defaults and checks, or stay with this one if it refuses
+                interactive::Outcome::Switch(at, fractal_type) => {
+                    let switched = cli::Cli {
+                        fractal_type: fractal_type.into(),
+                        view_center: Some(format!("{},{}", at.center.re, at.center.im)),
+                        view_radius: Some(at.radius),
+                        bounds_auto: false,
+                        ..cli.clone()
+                    };
+                    let _contained = Containment::enter();
+                    let explored = std::panic::catch_unwind(|| render(&switched));
+                    if explored.is_ok() {
+                        return;
+                    }
+                    status!(
+                        "Staying with --fractal-type {}",
+                        params.fractal_type.name().replace('_', "-")
+                    );
+                    view = at;
+                }
+            }
+        }
+    }
+
+    // Preview every fractal type in the opening view instead of animating one
+    if cli.contact_sheet {
+        params.julia_constant =
+            Some(utils::parse_complex(&cli.julia_constant).expect("Error parsing Julia constant"));
+        let (sheet, sheet_bounds) = utils::contact_sheet::render::<u8>(
+            bounds,
+            upper_left,
+            lower_right,
+            &params,
+            cli.escape_radius,
+            utils::parse_complex(&cli.phoenix_p).expect("Error parsing phoenix constant"),
+            &colors,
+        );
+        let sheet_path = format!("{}/contact_sheet.png", cli.output_folder.display());
+        match &colors.palette {
+            Some(palette) => utils::preserve::write_palette_image(
+                &sheet_path,
+                &sheet,
+                sheet_bounds,
+                palette,
+                cli.dpi,
+                cli.icc_profile.as_deref(),
+                &[],
+            ),
+            None => utils::preserve::write_image(
+                &sheet_path,
+                &sheet,
+                sheet_bounds,
+                cli.dpi,
+                cli.icc_profile.as_deref(),
+                &[],
+            ),
+        }
+        .unwrap_or_else(|e| fail_with("writing PNG file", e));
+        status!("Contact sheet saved to: {}", sheet_path);
+        return;
+    }
+
+    // Color the opening view from a background image instead of animating it
+    if let Some(path) = &cli.background_image {
+        let texture = image::open(path)
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "Error: cannot read background image {}: {}",
+                    path.display(),
+                    e
+                );
+                fail();
+            })
+            .to_rgb8();
+        if texture.width() == 0 || texture.height() == 0 {
+            eprintln!("Error: background image {} is empty", path.display());
+            fail();
+        }
+        params.julia_constant = julia_constant.map(|start| julia_path.at(start, 0, n_frames));
+        let mut textured = vec![[0; 3]; bounds.0 * bounds.1];
+        utils::texture::render(
+            &mut textured,
+            bounds,
+            upper_left,
+            lower_right,
+            &params,
+            &texture,
+            cli.background_fill,
+        );
+        if let Some(amount) = cli.sharpen {
+            utils::postprocess::sharpen_rgb(&mut textured, bounds, amount);
+        }
+        let textured_path = format!(
+            "{}/{}_textured.png",
+            cli.output_folder.display(),
+            output_name
+        );
+        utils::preserve::write_rgb_image(
+            &textured_path,
+            &textured,
+            bounds,
+            cli.dpi,
+            cli.icc_profile.as_deref(),
+            &[],
+        )
+        .unwrap_or_else(|e| fail_with("writing PNG file", e));
+        status!("Textured image saved to: {}", textured_path);
+        return;
+    }
+
+    // Color the values of one step of the formula instead of iterating it
+    if cli.domain_coloring {
+        let mut colored = vec![[0; 3]; bounds.0 * bounds.1];
+        utils::domain::render(&mut colored, bounds, upper_left, lower_right, &params);
+        if let Some(amount) = cli.sharpen {
+            utils::postprocess::sharpen_rgb(&mut colored, bounds, amount);
+        }
+        let colored_path = format!("{}/{}_domain.png", cli.output_folder.display(), output_name);
+        utils::preserve::write_rgb_image(
+            &colored_path,
+            &colored,
+            bounds,
+            cli.dpi,
+            cli.icc_profile.as_deref(),
+            &[],
+        )
+        .unwrap_or_else(|e| fail_with("writing PNG file", e));
+        status!("Domain coloring saved to: {}", colored_path);
+        return;
+    }
+
+    // Keep the opening view's smooth escape times as floats for other tools
+    if let (Some(path), false) = (&cli.export_float, cli.dry_run) {
+        let opening = utils::FractalParams {
+            julia_constant: julia_constant.map(|start| julia_path.at(start, 0, n_frames)),
+            ..params
+        };
+        let mut values = vec![0.0; bounds.0 * bounds.1];
+        utils::render_smooth_counts(
+            &mut values,
+            bounds,
+            upper_left,
+            lower_right,
+            &opening,
+            growth,
+        );
+        let path = path.display().to_string();
+        utils::preserve::write_float_image(&path, &values, bounds)
+            .unwrap_or_else(|e| fail_with("writing float image", e));
+        status!("Smooth escape times saved to: {}", path);
+    }
+
+    // The buffers are allocated once and reused by every frame
+    let mut pixels = frame_buffer(cli, bounds);
 
     // Collect frame paths for later GIF creation
-    let mut frame_paths: Vec<String> = Vec::with_capacity(n_frames);
+    let mut frame_paths: Vec<String> = Vec::with_capacity(frames.len());
+    let gif_path = format!("{}/{}.gif", cli.output_folder.display(), output_name);
+    let palette = colors
+        .palette
+        .unwrap_or_else(utils::palette::Palette::grayscale);
+    // Without saved frames, each frame goes straight from memory into the GIF,
+    // which is created along with the first frame
+    let mut gif: Option<utils::preserve::GifWriter> = None;
+    // With saved frames, a pipeline can still encode each one as it is done
+    let mut pipeline = (cli.pipeline_gif && !cli.dry_run).then(|| {
+        let (path, delay, playback) = (gif_path.clone(), cli.frame_delay(), cli.playback.into());
+        let (loop_count, transparent) = (cli.loop_count, pixels.has_alpha());
+        let (optimize, interpolate) = (cli.gif_optimize, cli.interpolate_frames.unwrap_or(1));
+        let quality = cli.gif_quality;
+        utils::preserve::GifPipeline::spawn(palette, move |palette| {
+            let mut writer = utils::preserve::GifWriter::create(
+                &path,
+                bounds,
+                delay,
+                palette,
+                playback,
+                loop_count,
+                transparent,
+            )?;
+            writer.set_optimize(optimize);
+            writer.set_interpolation(interpolate as usize);
+            writer.set_quality(quality);
+            Ok(writer)
+        })
+    });
+    let mut rendered = 0;
+
+    let sequence = match &cli.sequence_pattern {
+        Some(pattern) => utils::preserve::SequencePattern::parse(pattern)
+            .expect("Error parsing sequence pattern"),
+        None => utils::preserve::SequencePattern {
+            prefix: format!("{}-", output_name),
+            width: None,
+            suffix: ".png".to_string(),
+        },
+    };
+    // Create any folders the pattern puts the frames in
+    let sequence_dir = frames_dir
+        .join(sequence.name(1, n_frames))
+        .parent()
+        .map_or_else(|| frames_dir.clone(), std::path::Path::to_path_buf);
+    if !cli.dry_run && !sequence_dir.exists() {
+        std::fs::create_dir_all(&sequence_dir).expect("Error creating frames directory");
+    }
+
+    // Animate the opening view by turning the palette, shading the escape
+    // counts of a single render again for every frame
+    if let Some(cycle_frames) = cli.color_cycle_frames {
+        if matches!(fractal_type, FractalType::Lyapunov) {
+            eprintln!("Error: --color-cycle-frames needs an escape-time fractal, not Lyapunov");
+            fail();
+        }
+        let cycle_frames = cycle_frames as usize;
+        params.julia_constant = julia_constant.map(|start| julia_path.at(start, 0, n_frames));
+        let (grid_upper_left, grid_lower_right) =
+            params.sample_pattern.grid(bounds, upper_left, lower_right);
+        utils::normalize::render_counts(
+            pixels.counts(),
+            bounds,
+            grid_upper_left,
+            grid_lower_right,
+            &params,
+        );
+        let view = FrameView {
+            upper_left,
+            lower_right,
+            deep: None,
+            orbit_cache: None,
+            crossfade: None,
+            motion: None,
+            zoom: 1.0,
+        };
+        let mut writer = (!cli.no_gif).then(|| {
+            let mut writer = utils::preserve::GifWriter::create(
+                &gif_path,
+                bounds,
+                cli.frame_delay(),
+                &palette,
+                cli.playback.into(),
+                cli.loop_count,
+                pixels.has_alpha(),
+            )
+            .unwrap_or_else(|e| fail_with("creating GIF file", e));
+            writer.set_optimize(cli.gif_optimize);
+            writer.set_interpolation(cli.interpolate_frames.unwrap_or(1) as usize);
+            writer.set_quality(cli.gif_quality);
+            writer
+        });
+        for k in 0..cycle_frames {
+            colors.palette_shift =
+                (cli.color_offset + k as f64 / cycle_frames as f64).rem_euclid(1.0);
+            pixels.shade_counts(cli, bounds, &view, &colors, params.max_iterations);
+            if !cli.no_frames {
+                let frame_name = frames_dir
+                    .join(sequence.name(k + 1, cycle_frames))
+                    .display()
+                    .to_string();
+                pixels
+                    .save(&frame_name, bounds, &colors, cli, &[])
+                    .unwrap_or_else(|e| fail_with("writing PNG file", e));
+            }
+            if let Some(writer) = &mut writer {
+                pixels
+                    .add_to_gif(writer)
+                    .unwrap_or_else(|e| fail_with("writing GIF frame", e));
+            }
+        }
+        match writer {
+            Some(writer) => {
+                writer
+                    .finish()
+                    .unwrap_or_else(|e| fail_with("creating GIF file", e));
+                status!(
+                    "Palette cycle of {} frames saved to: {}",
+                    cycle_frames,
+                    gif_path
+                );
+            }
+            None => status!("Frames saved to: {}", sequence_dir.display()),
+        }
+        return;
+    }
+
+    // Time one frame of the opening view and extrapolate to the whole animation
+    if cli.dry_run {
+        params.julia_constant = julia_constant.map(|start| julia_path.at(start, 0, n_frames));
+        let probe_name = frames_dir.join("probe.png").display().to_string();
+        let view = FrameView {
+            upper_left,
+            lower_right,
+            deep: deep_view
+                .as_ref()
+                .filter(|_| cli.perturbation)
+                .map(|(deep, _)| deep),
+            orbit_cache: orbit_cache.as_ref(),
+            crossfade: None,
+            motion: Some((scale_pointer, scale_factor)),
+            zoom: 1.0,
+        };
+
+        let started = std::time::Instant::now();
+        pixels.render(cli, bounds, &view, &params, &colors);
+        pixels
+            .save(&probe_name, bounds, &colors, cli, &[])
+            .unwrap_or_else(|e| fail_with("writing PNG file", e));
+        let estimate = estimate::Estimate {
+            bounds,
+            n_frames,
+            // Supersampling and crossfades hold more buffers alongside each frame
+            bytes_per_pixel: pixels.bytes() / (bounds.0 * bounds.1),
+            frame_bytes: std::fs::metadata(&probe_name)
+                .expect("Error reading PNG file size")
+                .len(),
+            frame_time: started.elapsed(),
+        };
+        status!("{}", estimate.report());
+        return;
+    }
 
-    // Get fractal name for file naming
-    let fractal_name = match fractal_type {
-        FractalType::Mandelbrot => "mandelbrot",
-        FractalType::Julia => "julia",
-        FractalType::BurningShip => "burning_ship",
-        FractalType::Tricorn => "tricorn",
-        FractalType::Nova => "nova",
-        FractalType::Sin => "sin",
-        FractalType::Cos => "cos",
+    // The per-frame changes of the zoom, shared with library consumers; the
+    // pointer is the fixed point of the zoom, so turning about it spirals
+    let animation = utils::animation::AnimationConfig {
+        bounds,
+        upper_left,
+        lower_right,
+        pointer: scale_pointer,
+        scale_factor,
+        n_frames,
+        params,
+        colors,
+        julia_path,
+        auto_iterations: cli.auto_iterations,
+        rotation_per_frame: cli.rotation_per_frame,
+        palette_shift_per_frame: cli.palette_shift_per_frame,
+        morph_to,
+        power_end: cli.multibrot_power,
+    };
+
+    // A partial range starts from the view its first frame has in the full
+    // animation, which the precisely kept view reaches by the same steps
+    (upper_left, lower_right) = animation.view_at(frames.start);
+    for _ in 0..frames.start {
+        if let Some((deep, pointer)) = &mut deep_view {
+            deep.zoom(pointer, scale_factor);
+        }
+    }
+
+    // With global normalization, a first pass stores the escape counts of
+    // every frame on disk and finds the range of counts across all of them
+    let counts_dir = if let Some(clip) = cli.global_normalize {
+        let dir = TempDir::new().expect("Error creating temporary directory");
+        let counting = ProgressBar::new(frames.len() as u64);
+        counting.set_style(
+            ProgressStyle::default_bar()
+                .template("[{pos}/{len}] counts {bar:50.cyan/blue} [{elapsed_precise}] {msg}")
+                .unwrap(),
+        );
+        let mut histogram = utils::normalize::Histogram::default();
+        let counts = pixels.counts();
+        for i in frames.clone() {
+            if interrupt::requested() {
+                // Nothing has been rendered yet, so there is nothing to keep
+                counting.abandon_with_message("Interrupted");
+                return;
+            }
+            let params = animation.params_at(i);
+            let (upper_left, lower_right) = animation.view_at(i);
+            let (grid_upper_left, grid_lower_right) =
+                params.sample_pattern.grid(bounds, upper_left, lower_right);
+            utils::normalize::render_counts(
+                counts,
+                bounds,
+                grid_upper_left,
+                grid_lower_right,
+                &params,
+            );
+            histogram.add(counts);
+            utils::normalize::save_counts(&dir.path().join(format!("{}.bin", i)), counts)
+                .expect("Error writing escape counts");
+            counting.inc(1);
+        }
+        counting.finish_with_message("All frames counted");
+        colors.count_range = histogram.range(clip);
+        Some(dir)
+    } else {
+        None
     };
 
     // Setup progress bar for frame generation
-    let progress_bar = ProgressBar::new(n_frames as u64);
+    let progress_bar = ProgressBar::new(frames.len() as u64);
     progress_bar.set_style(
         ProgressStyle::default_bar()
-            .template("[{pos}/{len}] frames {bar:50.cyan/blue} [{elapsed_precise}] {msg}")
+            .template(
+                "[{pos}/{len}] frames {bar:50.cyan/blue} [{elapsed_precise}, ETA {eta_precise}] {msg}",
+            )
             .unwrap(),
     );
 
-    for i in 0..n_frames {
-        {
-            // Process each row in parallel using rayon
+    // The combined checksum hashes the digests of every frame in order
+    let mut checksum = cli.checksum.then(utils::checksum::Sha256::default);
+
+    // Frames already started are finished even if they overrun the budget
+    let budget = cli.time_budget.map(std::time::Duration::from_secs_f64);
+    let rendering_started = std::time::Instant::now();
+    let mut out_of_time = false;
+    // A short statistics pass after each frame finds a view gone featureless
+    let uniform_threshold = cli
+        .uniform_threshold
+        .or(cli.stop_when_uniform.then_some(99.0));
+    let mut uniform = false;
+    // The frame the poster shows, once it has been rendered
+    let mut poster: Option<PosterFrame> = None;
+    // The speed of a zoom with constant detail, measured after each frame
+    let mut detail_control = cli
+        .constant_detail
+        .then(|| utils::animation::DetailControl::new(scale_factor));
+    let mut frame_scale_factor = scale_factor;
+    // High-quality frames render through their own, larger buffers
+    let hq_cli = cli.hq_frames.is_some().then(|| cli::Cli {
+        supersample: cli.hq_supersample,
+        ..cli.clone()
+    });
+    let mut hq_pixels: Option<FrameBuffer> = None;
+
+    for i in frames.clone() {
+        // Stop early if the user pressed Ctrl-C, keeping the frames rendered so far
+        if interrupt::requested() {
+            progress_bar.set_message("Interrupted");
+            break;
+        }
+        if budget.is_some_and(|budget| rendering_started.elapsed() >= budget) {
+            out_of_time = true;
+            break;
+        }
+
+        // Give deeper frames more iterations, move the Julia constant along
+        // its path, turn the view and cycle the palette for this frame
+        let mut params = animation.params_at(i);
+        colors.palette_shift = animation.palette_shift_at(i);
+        // Smooth shading follows the growth of |z| along a multibrot ramp,
+        // unless its power is given
+        if let Some(power) = animation.power_at(i) {
+            if matches!(cli.smooth, Some(None)) || (cli.smooth.is_none() && colors.smooth.is_some())
+            {
+                colors.smooth = Some(power);
+            }
+            if matches!(cli.potential, Some(None)) {
+                colors.potential = Some(power);
+            }
+        }
+        // The frame's depth comes from the steps taken, not their number
+        if let (Some(control), Some(k)) = (&detail_control, cli.auto_iterations) {
+            params.max_iterations =
+                utils::iterations_for_zoom(animation.params.max_iterations, k, control.zoom());
+        }
+        let (frame_cli, pixels) = match &hq_cli {
+            Some(hq_cli) if cli.hq_frames.as_ref().unwrap().contains(&(i + 1)) => {
+                params.max_iterations =
+                    ((params.max_iterations as f64 * cli.hq_iterations).round() as usize).max(1);
+                let buffer = hq_pixels.get_or_insert_with(|| frame_buffer(hq_cli, bounds));
+                (hq_cli, buffer)
+            }
+            _ => (cli, &mut pixels),
+        };
+
+        // Round the precisely kept view to this frame's corners
+        if let (true, Some((deep, _))) = (cli.high_precision, &deep_view) {
+            (upper_left, lower_right) = deep.corners();
+        }
+
+        // Report where in the plane the frame looks, to help aim a zoom
+        if cli.verbose {
+            let center = match &deep_view {
+                Some((deep, _)) => deep.center.to_decimal(),
+                None => ((upper_left + lower_right) / 2.0).to_string(),
+            };
+            let extent = lower_right - upper_left;
+            progress_bar.suspend(|| {
+                eprintln!(
+                    "Frame {}: upper left {}, lower right {}, center {}, {:e} wide by {:e} high",
+                    i + 1,
+                    upper_left,
+                    lower_right,
+                    center,
+                    extent.re.abs(),
+                    extent.im.abs()
+                )
+            });
+        }
+
+        // Render, post-process and save the frame at the selected bit depth
+        let frame_name = frames_dir
+            .join(sequence.name(i + 1, n_frames))
+            .display()
+            .to_string();
+        // Fade the end of a looping zoom into the same frames one loop earlier
+        let crossfade = utils::transform::loop_crossfade_weight(i, n_frames, cli.loop_crossfade)
+            .zip(cli.zoom_video_loop)
+            .map(|(weight, loop_zoom)| {
+                (
+                    utils::transform::scale_point(upper_left, scale_pointer, loop_zoom),
+                    utils::transform::scale_point(lower_right, scale_pointer, loop_zoom),
+                    weight,
+                )
+            });
+        let view = FrameView {
+            upper_left,
+            lower_right,
+            deep: deep_view
+                .as_ref()
+                .filter(|_| cli.perturbation)
+                .map(|(deep, _)| deep),
+            orbit_cache: orbit_cache.as_ref(),
+            crossfade,
+            motion: Some((scale_pointer, frame_scale_factor)),
+            // The frame's view is the first scaled by the steps taken so far
+            zoom: detail_control
+                .as_ref()
+                .map_or_else(|| scale_factor.powf(-(i as f64)), |control| control.zoom()),
+        };
+        match &counts_dir {
+            Some(dir) => pixels
+                .colorize(
+                    frame_cli,
+                    bounds,
+                    &view,
+                    &dir.path().join(format!("{}.bin", i)),
+                    &params,
+                    &colors,
+                )
+                .expect("Error reading escape counts"),
+            None => pixels.render(frame_cli, bounds, &view, &params, &colors),
+        }
+        if cli.no_frames || (cli.stdout && frames.len() > 1) {
+            let writer = match &mut gif {
+                Some(writer) => writer,
+                None => {
+                    let (delay, playback) = (cli.frame_delay(), cli.playback.into());
+                    let (loop_count, transparent) = (cli.loop_count, pixels.has_alpha());
+                    let mut writer = if cli.stdout {
+                        utils::preserve::GifWriter::to_writer(
+                            std::io::stdout(),
+                            bounds,
+                            delay,
+                            &palette,
+                            playback,
+                            loop_count,
+                            transparent,
+                        )
+                    } else {
+                        utils::preserve::GifWriter::create(
+                            &gif_path,
+                            bounds,
+                            delay,
+                            &palette,
+                            playback,
+                            loop_count,
+                            transparent,
+                        )
+                    }
+                    .unwrap_or_else(|e| fail_with("creating GIF file", e));
+                    writer.set_optimize(cli.gif_optimize);
+                    writer.set_interpolation(cli.interpolate_frames.unwrap_or(1) as usize);
+                    writer.set_quality(cli.gif_quality);
+                    gif.insert(writer)
+                }
+            };
             pixels
-                .par_chunks_mut(bounds.0)
-                .enumerate()
-                .for_each(|(row, row_pixels)| {
-                    let row_bounds = (bounds.0, 1);
-                    let row_upper_left =
-                        utils::transform::pixel_to_point(bounds, (0, row), upper_left, lower_right);
-                    let row_lower_right = utils::transform::pixel_to_point(
-                        bounds,
-                        (bounds.0, row + 1),
-                        upper_left,
-                        lower_right,
-                    );
+                .add_to_gif(writer)
+                .unwrap_or_else(|e| fail_with("writing GIF frame", e));
+        } else {
+            // Record how the frame was rendered in the PNG itself
+            let metadata = utils::metadata::FrameMetadata {
+                index: i + 1,
+                bounds,
+                upper_left,
+                lower_right,
+                params,
+            };
+            if cli.stdout {
+                // The only frame goes down the pipe
+                let mut sink = std::io::BufWriter::new(std::io::stdout().lock());
+                pixels
+                    .write(&mut sink, bounds, &colors, cli, &metadata.text_chunks())
+                    .and_then(|_| Ok(std::io::Write::flush(&mut sink)?))
+                    .unwrap_or_else(|e| fail_with("writing PNG to standard output", e));
+            } else {
+                pixels
+                    .save(&frame_name, bounds, &colors, cli, &metadata.text_chunks())
+                    .unwrap_or_else(|e| fail_with("writing PNG file", e));
+                if cli.metadata {
+                    let json_name = std::path::Path::new(&frame_name).with_extension("json");
+                    std::fs::write(json_name, metadata.to_json())
+                        .expect("Error writing frame metadata");
+                }
+                frame_paths.push(frame_name);
+            }
+            if let Some(pipeline) = &mut pipeline {
+                pixels
+                    .send_to_gif(pipeline)
+                    .unwrap_or_else(|e| fail_with("writing GIF frame", e));
+            }
+        }
+        rendered += 1;
 
-                    // Clone the julia_constant for the current row
-                    let row_julia_constant = julia_constant.clone();
-
-                    utils::render(
-                        row_pixels,
-                        row_bounds,
-                        row_upper_left,
-                        row_lower_right,
-                        power,
-                        escape_radius,
-                        fractal_type,
-                        row_julia_constant,
-                    );
+        // Report the frame's checksum and fold it into the one for the animation
+        if let Some(combined) = &mut checksum {
+            let digest = pixels.digest();
+            // Suspend the bar so the line also shows when it is hidden
+            progress_bar.suspend(|| {
+                status!(
+                    "Frame {:03} sha256: {}",
+                    i + 1,
+                    utils::checksum::hex(&digest)
+                )
+            });
+            combined.update(&digest);
+        }
+
+        // Remember the poster frame, or the most detailed frame so far
+        let detail = match cli.poster {
+            Some(cli::Poster::Frame(frame)) if frame == i + 1 => Some(0.0),
+            Some(cli::Poster::Auto) => {
+                let (grid_upper_left, grid_lower_right) =
+                    params.sample_pattern.grid(bounds, upper_left, lower_right);
+                let detail = utils::detail(bounds, grid_upper_left, grid_lower_right, &params, 64);
+                poster
+                    .as_ref()
+                    .is_none_or(|poster| detail > poster.detail)
+                    .then_some(detail)
+            }
+            _ => None,
+        };
+        if let Some(detail) = detail {
+            poster = Some(PosterFrame {
+                index: i,
+                upper_left,
+                lower_right,
+                deep: view.deep.cloned(),
+                crossfade,
+                zoom: view.zoom,
+                params,
+                palette_shift: colors.palette_shift,
+                detail,
+            });
+        }
+
+        // A first frame in which essentially nothing escapes usually means
+        // the escape radius or iteration limit does not suit the formula
+        if rendered == 1
+            && !cli.perturbation
+            && !matches!(params.fractal_type, FractalType::Lyapunov)
+        {
+            let (grid_upper_left, grid_lower_right) =
+                params.sample_pattern.grid(bounds, upper_left, lower_right);
+            let interior =
+                utils::interior_fraction(bounds, grid_upper_left, grid_lower_right, &params, 64);
+            if interior >= 0.999 {
+                progress_bar.suspend(|| {
+                    eprintln!(
+                        "Warning: no point of frame {} escaped within {} iterations; check that --escape-radius ({}) suits the {} formula and that --max-iterations is high enough",
+                        i + 1,
+                        params.max_iterations,
+                        params.escape_radius,
+                        fractal_type.name()
+                    )
                 });
+            }
         }
 
-        // Write the image to a file in the appropriate directory
-        let frame_name = format!("{}/{}-{:03}.png", frames_dir.display(), fractal_name, i + 1);
-        utils::preserve::write_image(&frame_name, &pixels, bounds).expect("Error writing PNG file");
+        // Warn about a frame that is nearly all interior or all exterior
+        if let Some(threshold) = uniform_threshold {
+            let (grid_upper_left, grid_lower_right) =
+                params.sample_pattern.grid(bounds, upper_left, lower_right);
+            let interior = 100.0
+                * utils::interior_fraction(bounds, grid_upper_left, grid_lower_right, &params, 64);
+            let uniformity = interior.max(100.0 - interior);
+            if uniformity >= threshold {
+                let part = if interior >= 50.0 {
+                    "interior"
+                } else {
+                    "exterior"
+                };
+                progress_bar.suspend(|| {
+                    eprintln!("Warning: frame {} is {:.1}% {}", i + 1, uniformity, part)
+                });
+                if cli.stop_when_uniform {
+                    uniform = true;
+                    progress_bar.inc(1);
+                    break;
+                }
+            }
+        }
 
-        // Add frame path to our collection for GIF creation
-        frame_paths.push(frame_name);
+        // Measure the frame's detail to pick the speed of the next step
+        if let Some(control) = &mut detail_control {
+            let (grid_upper_left, grid_lower_right) =
+                params.sample_pattern.grid(bounds, upper_left, lower_right);
+            let fraction =
+                utils::boundary_fraction(bounds, grid_upper_left, grid_lower_right, &params, 64);
+            frame_scale_factor = control.step(fraction);
+            if cli.verbose {
+                progress_bar.suspend(|| {
+                    eprintln!(
+                        "Frame {}: {:.1}% edges, next scale factor {}",
+                        i + 1,
+                        100.0 * fraction,
+                        frame_scale_factor
+                    )
+                });
+            }
+        }
 
-        // Scale the view
-        (upper_left, lower_right) = (
-            utils::transform::scale_point(upper_left, scale_pointer, scale_factor),
-            utils::transform::scale_point(lower_right, scale_pointer, scale_factor),
-        );
+        // Scale the view, from the first frame's so that no error builds up
+        (upper_left, lower_right) = match &detail_control {
+            Some(control) => animation.view_scaled_by(1.0 / control.zoom()),
+            None => animation.view_at(i + 1),
+        };
+        if let Some((deep, pointer)) = &mut deep_view {
+            deep.zoom(pointer, frame_scale_factor);
+        }
 
-        // Update progress bar
+        // Update progress bar, with the throughput of the frames so far
         progress_bar.inc(1);
-        progress_bar.set_message(format!("Frame {}/{} complete", i + 1, n_frames));
+        let per_second = progress_bar.position() as f64 / rendering_started.elapsed().as_secs_f64();
+        progress_bar.set_message(format!(
+            "Frame {}/{} complete, {:.2} frames/s",
+            i + 1,
+            n_frames,
+            per_second
+        ));
+    }
+
+    if let Some(combined) = checksum {
+        status!(
+            "All frames sha256: {}",
+            utils::checksum::hex(&combined.finalize())
+        );
     }
 
     // Finish progress bar
-    progress_bar.finish_with_message("All frames rendered");
+    if rendered < frames.len() {
+        let reason = if out_of_time {
+            "Out of time"
+        } else if uniform {
+            "Stopped at a uniform frame"
+        } else {
+            "Interrupted"
+        };
+        progress_bar.abandon_with_message(format!(
+            "{} after {} of {} frames",
+            reason,
+            rendered,
+            frames.len()
+        ));
+        if rendered == 0 {
+            return;
+        }
+    } else {
+        progress_bar.finish_with_message("All frames rendered");
+    }
+    if let (Some(cache), true) = (&orbit_cache, cli.verbose) {
+        status!(
+            "Reference orbit reused for {} of {} frames",
+            cache.borrow().reused,
+            rendered
+        );
+    }
+
+    // Render the poster frame again, at its own size
+    if let Some(poster) = poster {
+        let poster_bounds = (bounds.0 * cli.poster_scale, bounds.1 * cli.poster_scale);
+        let mut buffer = frame_buffer(cli, poster_bounds);
+        let view = FrameView {
+            upper_left: poster.upper_left,
+            lower_right: poster.lower_right,
+            deep: poster.deep.as_ref(),
+            orbit_cache: None,
+            crossfade: poster.crossfade,
+            motion: Some((scale_pointer, scale_factor)),
+            zoom: poster.zoom,
+        };
+        let colors = ColorOptions {
+            palette_shift: poster.palette_shift,
+            ..colors
+        };
+        buffer.render(cli, poster_bounds, &view, &poster.params, &colors);
+        let metadata = utils::metadata::FrameMetadata {
+            index: poster.index + 1,
+            bounds: poster_bounds,
+            upper_left: poster.upper_left,
+            lower_right: poster.lower_right,
+            params: poster.params,
+        };
+        let path = format!("{}/{}-poster.png", cli.output_folder.display(), output_name);
+        buffer
+            .save(&path, poster_bounds, &colors, cli, &metadata.text_chunks())
+            .unwrap_or_else(|e| fail_with("writing PNG file", e));
+        status!("Poster of frame {} saved to: {}", poster.index + 1, path);
+    }
+
+    // Frames streamed into the GIF only need the file to be closed
+    if let Some(writer) = gif {
+        writer
+            .finish()
+            .unwrap_or_else(|e| fail_with("creating GIF file", e));
+        match cli.stdout {
+            true => status!("GIF written to standard output"),
+            false => status!("GIF created at: {}", gif_path),
+        }
+        return;
+    }
+    if cli.stdout {
+        status!("Frame written to standard output");
+        return;
+    }
+    if let Some(pipeline) = pipeline {
+        pipeline
+            .finish()
+            .unwrap_or_else(|e| fail_with("creating GIF file", e));
+        status!("GIF created at: {}", gif_path);
+        return;
+    }
+
+    // Leave the frames for external tools when no GIF is wanted
+    if cli.no_gif {
+        status!("Frames saved to: {}", sequence_dir.display());
+        return;
+    }
 
     // After generating all frames, create a GIF animation
-    println!("Creating GIF from {} frames...", frame_paths.len());
-    let gif_path = format!("{}/{}.gif", cli.output_folder.display(), fractal_name);
+    status!("Creating GIF from {} frames...", frame_paths.len());
 
     // Add progress bar for GIF creation
     let gif_progress = ProgressBar::new_spinner();
@@ -147,7 +1729,896 @@ fn main() {
     gif_progress.set_message("Creating GIF animation...");
     gif_progress.enable_steady_tick(std::time::Duration::from_millis(100));
 
-    utils::preserve::make_gif(frame_paths, &gif_path, cli.delay).expect("Error creating GIF file");
+    let make_gif = if cli.multithreaded_gif {
+        utils::preserve::make_gif_parallel
+    } else {
+        utils::preserve::make_gif
+    };
+    let started = std::time::Instant::now();
+    make_gif(
+        frame_paths,
+        &gif_path,
+        cli.frame_delay(),
+        &palette,
+        cli.playback.into(),
+        cli.loop_count,
+        cli.gif_optimize,
+        cli.interpolate_frames.unwrap_or(1) as usize,
+        cli.gif_quality,
+    )
+    .unwrap_or_else(|e| fail_with("creating GIF file", e));
 
     gif_progress.finish_with_message(format!("GIF created at: {}", gif_path));
+    if cli.verbose {
+        status!("GIF encoded in {:.2?}", started.elapsed());
+    }
+}
+
+/// Buffers for one frame, at the bit depth selected on the command line
+enum FrameBuffer {
+    Gray8(RenderBuffers<u8>),
+    Gray16(RenderBuffers<u16>),
+    GrayAlpha8(RenderBuffers<utils::GrayAlpha>),
+}
+
+/// Allocate the buffers of a frame of `bounds` pixels at the selected bit
+/// depth.
+fn frame_buffer(cli: &cli::Cli, bounds: (usize, usize)) -> FrameBuffer {
+    match cli.bit_depth {
+        _ if cli.alpha_interior => FrameBuffer::GrayAlpha8(render_buffers(cli, bounds)),
+        16 => FrameBuffer::Gray16(render_buffers(cli, bounds)),
+        _ => FrameBuffer::Gray8(render_buffers(cli, bounds)),
+    }
+}
+
+/// Allocate the buffers frames of `bounds` pixels are rendered through, with
+/// room for the supersampling, crossfade, global normalization and motion
+/// blur selected,
+/// in memory-mapped files in the output folder with `--memory-map`.
+fn render_buffers<P: Channel>(cli: &cli::Cli, bounds: (usize, usize)) -> RenderBuffers<P> {
+    let folder = cli.memory_map.then_some(cli.output_folder.as_path());
+    RenderBuffers::new(
+        bounds,
+        cli.supersample,
+        cli.loop_crossfade > 0 || cli.motion_blur_samples.is_some(),
+        cli.global_normalize.is_some() || cli.color_cycle_frames.is_some(),
+        cli.motion_blur_samples.is_some(),
+        folder,
+    )
+    .expect("Error mapping pixel buffer")
+}
+
+impl FrameBuffer {
+    /// Return how many bytes the buffers take up in memory.
+    fn bytes(&self) -> usize {
+        match self {
+            FrameBuffer::Gray8(buffers) => buffers.bytes(),
+            FrameBuffer::Gray16(buffers) => buffers.bytes(),
+            FrameBuffer::GrayAlpha8(buffers) => buffers.bytes(),
+        }
+    }
+
+    /// Return the buffer escape counts are loaded into.
+    fn counts(&mut self) -> &mut [u32] {
+        match self {
+            FrameBuffer::Gray8(buffers) => &mut buffers.counts,
+            FrameBuffer::Gray16(buffers) => &mut buffers.counts,
+            FrameBuffer::GrayAlpha8(buffers) => &mut buffers.counts,
+        }
+    }
+
+    /// Return whether the pixels carry transparency.
+    fn has_alpha(&self) -> bool {
+        matches!(self, FrameBuffer::GrayAlpha8(_))
+    }
+
+    /// Render `view` into the buffer and apply post-processing.
+    fn render(
+        &mut self,
+        cli: &cli::Cli,
+        bounds: (usize, usize),
+        view: &FrameView,
+        params: &FractalParams,
+        colors: &ColorOptions,
+    ) {
+        match self {
+            FrameBuffer::Gray8(buffer) => render_frame(cli, buffer, bounds, view, params, colors),
+            FrameBuffer::Gray16(buffer) => render_frame(cli, buffer, bounds, view, params, colors),
+            FrameBuffer::GrayAlpha8(buffer) => {
+                render_frame(cli, buffer, bounds, view, params, colors)
+            }
+        }
+    }
+
+    /// Shade the escape counts stored at `path` into the buffer and apply
+    /// post-processing.
+    fn colorize(
+        &mut self,
+        cli: &cli::Cli,
+        bounds: (usize, usize),
+        view: &FrameView,
+        path: &std::path::Path,
+        params: &FractalParams,
+        colors: &ColorOptions,
+    ) -> std::io::Result<()> {
+        utils::normalize::load_counts(path, self.counts())?;
+        self.shade_counts(cli, bounds, view, colors, params.max_iterations);
+        Ok(())
+    }
+
+    /// Shade the escape counts in the buffer's count buffer, counted up to
+    /// `limit` iterations, and apply post-processing.
+    fn shade_counts(
+        &mut self,
+        cli: &cli::Cli,
+        bounds: (usize, usize),
+        view: &FrameView,
+        colors: &ColorOptions,
+        limit: usize,
+    ) {
+        match self {
+            FrameBuffer::Gray8(buffers) => {
+                colorize_frame(cli, buffers, bounds, view, colors, limit)
+            }
+            FrameBuffer::Gray16(buffers) => {
+                colorize_frame(cli, buffers, bounds, view, colors, limit)
+            }
+            FrameBuffer::GrayAlpha8(buffers) => {
+                colorize_frame(cli, buffers, bounds, view, colors, limit)
+            }
+        }
+    }
+
+    /// Save the buffer as `frame_name`, colored with the palette if there is
+    /// one, in the `--pixel-format` and stamped with the `--dpi` of `cli`.
+    fn save(
+        &self,
+        frame_name: &str,
+        bounds: (usize, usize),
+        colors: &ColorOptions,
+        cli: &cli::Cli,
+        text: &[(String, String)],
+    ) -> Result<(), FractalError> {
+        let file = utils::preserve::create_file(frame_name)?;
+        self.write(file, bounds, colors, cli, text)
+    }
+
+    /// Encode the buffer as a PNG image into `sink`, like [`FrameBuffer::save`].
+    fn write(
+        &self,
+        sink: impl std::io::Write,
+        bounds: (usize, usize),
+        colors: &ColorOptions,
+        cli: &cli::Cli,
+        text: &[(String, String)],
+    ) -> Result<(), FractalError> {
+        let dpi = cli.dpi;
+        let palette = colors
+            .palette
+            .unwrap_or_else(utils::palette::Palette::grayscale);
+        if cli.pixel_format == cli::PixelFormatArg::Indexed {
+            return match self {
+                FrameBuffer::Gray8(RenderBuffers { pixels: buffer, .. }) => {
+                    utils::preserve::write_indexed_image_to(
+                        sink,
+                        buffer,
+                        bounds,
+                        &palette,
+                        dpi,
+                        cli.icc_profile.as_deref(),
+                        text,
+                    )
+                }
+                FrameBuffer::Gray16(RenderBuffers { pixels: buffer, .. }) => {
+                    utils::preserve::write_indexed_image_to(
+                        sink,
+                        buffer,
+                        bounds,
+                        &palette,
+                        dpi,
+                        cli.icc_profile.as_deref(),
+                        text,
+                    )
+                }
+                FrameBuffer::GrayAlpha8(RenderBuffers { pixels: buffer, .. }) => {
+                    utils::preserve::write_indexed_image_to(
+                        sink,
+                        buffer,
+                        bounds,
+                        &palette,
+                        dpi,
+                        cli.icc_profile.as_deref(),
+                        text,
+                    )
+                }
+            };
+        }
+        match self {
+            FrameBuffer::Gray8(RenderBuffers { pixels: buffer, .. }) => match &colors.palette {
+                Some(palette) => utils::preserve::write_palette_image_to(
+                    sink,
+                    buffer,
+                    bounds,
+                    palette,
+                    dpi,
+                    cli.icc_profile.as_deref(),
+                    text,
+                ),
+                None => utils::preserve::write_image_to(
+                    sink,
+                    buffer,
+                    bounds,
+                    dpi,
+                    cli.icc_profile.as_deref(),
+                    text,
+                ),
+            },
+            FrameBuffer::Gray16(RenderBuffers { pixels: buffer, .. }) => {
+                utils::preserve::write_image_to(
+                    sink,
+                    buffer,
+                    bounds,
+                    dpi,
+                    cli.icc_profile.as_deref(),
+                    text,
+                )
+            }
+            FrameBuffer::GrayAlpha8(RenderBuffers { pixels: buffer, .. }) => {
+                utils::preserve::write_palette_image_to(
+                    sink,
+                    buffer,
+                    bounds,
+                    &palette,
+                    dpi,
+                    cli.icc_profile.as_deref(),
+                    text,
+                )
+            }
+        }
+    }
+
+    /// Encode the buffer as the next frame of `gif`.
+    fn add_to_gif(&self, gif: &mut utils::preserve::GifWriter) -> Result<(), FractalError> {
+        match self {
+            FrameBuffer::Gray8(RenderBuffers { pixels: buffer, .. }) => gif.add_frame(buffer),
+            FrameBuffer::Gray16(RenderBuffers { pixels: buffer, .. }) => gif.add_frame(buffer),
+            FrameBuffer::GrayAlpha8(RenderBuffers { pixels: buffer, .. }) => gif.add_frame(buffer),
+        }
+    }
+
+    /// Queue the buffer as the next frame of the GIF `pipeline` encodes.
+    fn send_to_gif(&self, pipeline: &mut utils::preserve::GifPipeline) -> Result<(), FractalError> {
+        match self {
+            FrameBuffer::Gray8(RenderBuffers { pixels: buffer, .. }) => pipeline.send(buffer),
+            FrameBuffer::Gray16(RenderBuffers { pixels: buffer, .. }) => pipeline.send(buffer),
+            FrameBuffer::GrayAlpha8(RenderBuffers { pixels: buffer, .. }) => pipeline.send(buffer),
+        }
+    }
+
+    /// Return the SHA-256 digest of the pixel values.
+    fn digest(&self) -> [u8; 32] {
+        match self {
+            FrameBuffer::Gray8(RenderBuffers { pixels: buffer, .. }) => {
+                utils::checksum::digest_pixels(buffer)
+            }
+            FrameBuffer::Gray16(RenderBuffers { pixels: buffer, .. }) => {
+                utils::checksum::digest_pixels(buffer)
+            }
+            FrameBuffer::GrayAlpha8(RenderBuffers { pixels: buffer, .. }) => {
+                utils::checksum::digest_pixels(buffer)
+            }
+        }
+    }
+}
+
+/// The region of the complex plane shown by one frame
+struct FrameView<'a> {
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    /// The same view at full precision, when rendering with `--perturbation`
+    deep: Option<&'a utils::perturbation::DeepView>,
+    /// The reference orbit of the frame before, with
+    /// `--mandelbrot-reference-orbit-cache`
+    orbit_cache: Option<&'a std::cell::RefCell<utils::perturbation::OrbitCache>>,
+    /// The corners of another view to blend in, and how much of it to use
+    crossfade: Option<(Complex<f64>, Complex<f64>, f64)>,
+    /// The fixed point of the zoom and the factor it scales the view by to
+    /// the next frame, to blur the motion across with `--motion-blur-samples`
+    motion: Option<(Complex<f64>, f64)>,
+    /// How many times the frame is magnified over the first, for
+    /// `--log-zoom-label`
+    zoom: f64,
+}
+
+/// A frame of the animation chosen for `--poster`, kept to render it again
+struct PosterFrame {
+    /// Index of the frame, counting from zero
+    index: usize,
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    deep: Option<utils::perturbation::DeepView>,
+    crossfade: Option<(Complex<f64>, Complex<f64>, f64)>,
+    zoom: f64,
+    params: FractalParams,
+    palette_shift: f64,
+    /// The frame's detail with `--poster auto`, see [`utils::detail`]
+    detail: f64,
+}
+
+/// Render `view` into the pixels of `buffers` and apply post-processing.
+fn render_frame<P: Channel>(
+    cli: &cli::Cli,
+    buffers: &mut RenderBuffers<P>,
+    bounds: (usize, usize),
+    view: &FrameView,
+    params: &FractalParams,
+    colors: &ColorOptions,
+) {
+    let RenderBuffers {
+        pixels,
+        samples,
+        blend,
+        sums,
+        ..
+    } = buffers;
+    render_fractal(cli, pixels, samples, bounds, view, params, colors);
+
+    if let (Some(n), Some((pointer, scale_factor))) = (cli.motion_blur_samples, view.motion) {
+        // Average sub-frames spread evenly over the step to the next frame,
+        // members and escaping points alike; the first is the frame itself
+        sums.fill(0);
+        utils::postprocess::accumulate(sums, pixels);
+        for k in 1..n {
+            let t = k as f64 / n as f64;
+            let factor = scale_factor.powf(t);
+            let sub_view = FrameView {
+                upper_left: utils::transform::scale_point(view.upper_left, pointer, factor),
+                lower_right: utils::transform::scale_point(view.lower_right, pointer, factor),
+                deep: None,
+                orbit_cache: None,
+                crossfade: None,
+                motion: None,
+                zoom: view.zoom,
+            };
+            let mut sub_params = *params;
+            if let (Some(rotation), Some(degrees)) =
+                (&mut sub_params.rotation, cli.rotation_per_frame)
+            {
+                rotation.angle += (t * degrees).to_radians();
+            }
+            render_fractal(cli, blend, samples, bounds, &sub_view, &sub_params, colors);
+            utils::postprocess::accumulate(sums, blend);
+        }
+        utils::postprocess::average(pixels, sums, n);
+    }
+
+    if let Some((upper_left, lower_right, weight)) = view.crossfade {
+        let other_view = FrameView {
+            upper_left,
+            lower_right,
+            deep: None,
+            orbit_cache: None,
+            crossfade: None,
+            motion: None,
+            zoom: view.zoom,
+        };
+        render_fractal(cli, blend, samples, bounds, &other_view, params, colors);
+        utils::postprocess::crossfade(pixels, blend, weight);
+    }
+
+    postprocess_frame(cli, pixels, bounds, view);
+}
+
+/// Shade the escape counts in `buffers` into its pixels and apply
+/// post-processing.
+fn colorize_frame<P: Channel>(
+    cli: &cli::Cli,
+    buffers: &mut RenderBuffers<P>,
+    bounds: (usize, usize),
+    view: &FrameView,
+    colors: &ColorOptions,
+    limit: usize,
+) {
+    utils::normalize::colorize(&mut buffers.pixels, &buffers.counts, colors, limit);
+    postprocess_frame(cli, &mut buffers.pixels, bounds, view);
+}
+
+/// Apply gamma correction, sharpening and any post-processing steps and draw
+/// any external rays, overlay and zoom caption before saving.
+fn postprocess_frame<P: Channel>(
+    cli: &cli::Cli,
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    view: &FrameView,
+) {
+    utils::postprocess::apply_gamma(pixels, cli.gamma);
+    if let Some(amount) = cli.sharpen {
+        utils::postprocess::sharpen(pixels, bounds, amount);
+    }
+    if let Some(pipeline) = &cli.post {
+        pipeline.apply(pixels, bounds);
+    }
+    if let Some(angles) = &cli.external_rays {
+        let precision = (view.lower_right.re - view.upper_left.re).abs() / bounds.0 as f64 / 4.0;
+        let rays: Vec<_> = angles
+            .iter()
+            .map(|&angle| utils::rays::trace(angle, cli.max_iterations, precision))
+            .collect();
+        utils::rays::draw(pixels, bounds, view.upper_left, view.lower_right, &rays);
+    }
+    if let Some(overlay) = cli.overlay {
+        utils::overlay::draw(
+            pixels,
+            bounds,
+            view.upper_left,
+            view.lower_right,
+            overlay.into(),
+        );
+    }
+    if cli.log_zoom_label {
+        let scale = cli
+            .label_scale
+            .map_or((bounds.0.min(bounds.1) / 256).max(1), |scale| {
+                scale as usize
+            });
+        utils::overlay::draw_caption(
+            pixels,
+            bounds,
+            &utils::overlay::format_zoom(view.zoom),
+            cli.label_corner.into(),
+            scale,
+        );
+    }
+}
+
+/// Render the fractal in `view` into `pixels`, supersampling it first into
+/// `samples` if requested.
+#[allow(clippy::too_many_arguments)]
+fn render_fractal<P: Channel>(
+    cli: &cli::Cli,
+    pixels: &mut [P],
+    samples: &mut [P],
+    bounds: (usize, usize),
+    view: &FrameView,
+    params: &FractalParams,
+    colors: &ColorOptions,
+) {
+    let factor = cli.supersample;
+    if factor > 1 {
+        // The corners stay put, so each output pixel covers factor^2 samples
+        let large_bounds = (bounds.0 * factor, bounds.1 * factor);
+        render_samples(cli, samples, large_bounds, view, params, colors);
+        utils::postprocess::downscale(samples, factor, pixels, bounds, cli.downscale.into());
+    } else {
+        render_samples(cli, pixels, bounds, view, params, colors);
+    }
+}
+
+/// Render the fractal in `view` into `pixels` with the selected method.
+fn render_samples<P: Channel>(
+    cli: &cli::Cli,
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    view: &FrameView,
+    params: &FractalParams,
+    colors: &ColorOptions,
+) {
+    let (upper_left, lower_right) =
+        params
+            .sample_pattern
+            .grid(bounds, view.upper_left, view.lower_right);
+    if cli.buddhabrot {
+        // Accumulate escaping orbits over the whole image at once
+        utils::buddhabrot::render(
+            pixels,
+            bounds,
+            upper_left,
+            lower_right,
+            params,
+            colors,
+            cli.samples,
+        );
+    } else if let Some(deep) = view.deep {
+        // Iterate every pixel as an offset from a high-precision reference orbit
+        match view.orbit_cache {
+            Some(cache) => utils::perturbation::render_cached(
+                pixels,
+                bounds,
+                deep,
+                params,
+                colors,
+                &mut cache.borrow_mut(),
+            ),
+            None => utils::perturbation::render(pixels, bounds, deep, params, colors),
+        }
+    } else if cli.fixed_point {
+        // Iterate in integers, for the same counts on every platform
+        utils::fixed::render(pixels, bounds, upper_left, lower_right, params, colors);
+    } else if cli.dual_view {
+        // Composite the Mandelbrot and Julia sets into the two halves
+        utils::dual_view::render(pixels, bounds, upper_left, lower_right, params, colors);
+    } else if let Some(mirror) = cli.mirror {
+        // Render half of the rows or columns and reflect them into the rest
+        utils::symmetry::render(
+            pixels,
+            bounds,
+            upper_left,
+            lower_right,
+            params,
+            colors,
+            mirror.into(),
+        );
+    } else if let Some(&[threshold, samples]) = cli.adaptive_aa.as_deref() {
+        // Supersample only the pixels on the edges of the escape counts
+        utils::antialias::render(
+            pixels,
+            bounds,
+            upper_left,
+            lower_right,
+            params,
+            colors,
+            threshold,
+            samples,
+        );
+    } else if let Some(&[low, high]) = cli.adaptive_iterations.as_deref() {
+        // Iterate the boundary further than the rest, keeping the ratio of
+        // the limits when the frame's limit is scaled
+        let low = (low as f64 * params.max_iterations as f64 / high as f64).round() as usize;
+        utils::boost::render(pixels, bounds, upper_left, lower_right, params, colors, low);
+    } else if let Some(tolerance) = cli.adaptive {
+        // Fill uniform regions from a few samples and subdivide the rest
+        utils::adaptive::render(
+            pixels,
+            bounds,
+            upper_left,
+            lower_right,
+            params,
+            colors,
+            tolerance,
+            cli.adaptive_min_size,
+        );
+    } else if cli.chunk_progress {
+        // Tick a bar of its own as the rows of the frame are done
+        let rows = ProgressBar::new(bounds.1 as u64);
+        rows.set_style(
+            ProgressStyle::default_bar()
+                .template("[{percent:>3}%] rows {bar:50.green/white} [{elapsed_precise}, ETA {eta_precise}]")
+                .unwrap(),
+        );
+        utils::render_parallel_with_progress(
+            pixels,
+            bounds,
+            upper_left,
+            lower_right,
+            params,
+            colors,
+            &|| rows.inc(1),
+        );
+        rows.finish_and_clear();
+    } else {
+        // Process each row in parallel using rayon
+        utils::render_parallel(pixels, bounds, upper_left, lower_right, params, colors);
+    }
+}
+
+/// Load the gradient file or built-in color map chosen on the command line,
+/// exiting with an error if the file cannot be read.
+fn load_palette(
+    file: Option<&std::path::Path>,
+    builtin: Option<cli::PaletteArg>,
+) -> Option<utils::palette::Palette> {
+    match (file, builtin) {
+        (Some(path), _) => {
+            let gradient = utils::palette::Gradient::load(path).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                fail();
+            });
+            Some(utils::palette::Palette::from_gradient(&gradient))
+        }
+        (None, Some(builtin)) => Some(utils::palette::Palette::from_gradient(
+            &utils::palette::Builtin::from(builtin).gradient(),
+        )),
+        (None, None) => None,
+    }
+}
+
+/// Render every job of the `batch` subcommand's file in turn, each into its
+/// own subfolder, and exit with an error if any of them failed.
+fn batch(args: &cli::BatchArgs) -> ! {
+    let jobs = utils::batch::load(&args.file).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
+
+    // Every job renders on the same thread pool
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .expect("Error creating thread pool");
+    interrupt::install();
+
+    let started = std::time::Instant::now();
+    let (finished, failed) = run_jobs(&jobs, args);
+    status!(
+        "{} of {} jobs rendered in {:.2?}",
+        finished,
+        jobs.len(),
+        started.elapsed()
+    );
+    if !failed.is_empty() {
+        eprintln!("Error: failed jobs: {}", failed.join(", "));
+        std::process::exit(1);
+    }
+    std::process::exit(0);
+}
+
+/// Render `jobs` in turn with the options of `args`, returning how many of
+/// them finished and the names of those that failed.
+///
+/// A failing job gives up by unwinding to here, see [`give_up`]; a panic in
+/// one is reported on a single line, without a backtrace, and also only
+/// ends that job.
+fn run_jobs<'a>(jobs: &'a [utils::batch::Job], args: &cli::BatchArgs) -> (usize, Vec<&'a str>) {
+    let _contained = Containment::enter();
+
+    let mut failed = Vec::new();
+    let mut finished = 0;
+    for (i, job) in jobs.iter().enumerate() {
+        if interrupt::requested() {
+            status!(
+                "Interrupted, skipping the remaining {} jobs",
+                jobs.len() - i
+            );
+            break;
+        }
+        status!("Job {}/{}: {}", i + 1, jobs.len(), job.name);
+        let job_started = std::time::Instant::now();
+
+        // The job's own options come last, so they override the defaults
+        let folder = args.output_folder.join(&job.name);
+        let arguments = std::iter::once("mandelbrot".to_string())
+            .chain(args.defaults.iter().cloned())
+            .chain([format!("--output-folder={}", folder.display())])
+            .chain(job.args.iter().cloned());
+        let succeeded = match cli::Cli::command()
+            .args_override_self(true)
+            .try_get_matches_from(arguments)
+        {
+            Ok(matches) => std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let mut cli = cli::Cli::from_arg_matches(&matches).unwrap_or_else(|e| {
+                    let _ = e.print();
+                    fail();
+                });
+                if cli.command.is_some() {
+                    eprintln!("Error: batch jobs cannot run subcommands");
+                    fail();
+                }
+                continue_from(&mut cli, &matches);
+                render(&cli);
+            }))
+            .is_ok(),
+            Err(e) => {
+                let _ = e.print();
+                false
+            }
+        };
+        if succeeded {
+            finished += 1;
+            status!("Job {} done in {:.2?}", job.name, job_started.elapsed());
+        } else {
+            eprintln!("Error: job {} failed", job.name);
+            failed.push(job.name.as_str());
+        }
+    }
+
+    (finished, failed)
+}
+
+/// Contained failures, from [`Containment::enter`] until dropped, even by a
+/// panic: renders that fail unwind instead of exiting, and panics are
+/// reported on one line
+struct Containment {
+    contained: bool,
+    default_hook: Option<PanicHook>,
+}
+
+/// A panic hook, as [`std::panic::take_hook`] returns it
+type PanicHook = Box<dyn Fn(&std::panic::PanicHookInfo) + Sync + Send>;
+
+impl Containment {
+    fn enter() -> Containment {
+        let contained = CONTAINED.swap(true, std::sync::atomic::Ordering::SeqCst);
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|info| {
+            eprintln!(
+                "Error: {}",
+                info.payload_as_str().unwrap_or("the render panicked")
+            );
+        }));
+        Containment {
+            contained,
+            default_hook: Some(default_hook),
+        }
+    }
+}
+
+impl Drop for Containment {
+    fn drop(&mut self) {
+        // An interactive render in a batch job stays contained by the batch
+        CONTAINED.store(self.contained, std::sync::atomic::Ordering::SeqCst);
+        // The hook cannot be changed while panicking, when the process ends
+        // anyway
+        if let (Some(hook), false) = (self.default_hook.take(), std::thread::panicking()) {
+            std::panic::set_hook(hook);
+        }
+    }
+}
+
+/// Print the text chunks of the files of the `read-metadata` subcommand,
+/// exiting with an error if any cannot be read.
+fn read_metadata(args: &cli::ReadMetadataArgs) -> ! {
+    let mut failed = false;
+    for path in &args.files {
+        if args.files.len() > 1 {
+            status!("{}:", path.display());
+        }
+        match utils::preserve::read_text_chunks(path) {
+            Ok(chunks) if chunks.is_empty() => status!("No text chunks"),
+            Ok(chunks) => {
+                for (keyword, text) in chunks {
+                    status!("{}: {}", keyword, text.trim_end());
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                failed = true;
+            }
+        }
+    }
+    std::process::exit(failed as i32);
+}
+
+/// Join the frame folders and GIFs of the `concat` subcommand into one GIF.
+fn concat(args: &cli::ConcatArgs) {
+    let segments: Vec<utils::concat::Segment> = args
+        .inputs
+        .iter()
+        .map(|path| {
+            utils::concat::Segment::open(path).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            })
+        })
+        .collect();
+    if let Some(parent) = args.output.parent() {
+        std::fs::create_dir_all(parent).expect("Error creating output directory");
+    }
+    let palette = load_palette(args.palette_file.as_deref(), args.palette)
+        .unwrap_or_else(utils::palette::Palette::grayscale);
+    let output = args.output.display().to_string();
+    utils::concat::concat(
+        &segments,
+        &output,
+        args.delay,
+        &palette,
+        args.playback.into(),
+        args.loop_count,
+        args.crossfade,
+    )
+    .unwrap_or_else(|e| fail_with("joining GIF", e));
+    status!("Joined GIF saved to: {}", output);
+}
+
+/// Write the manifest of the `split` subcommand and exit.
+fn split(args: &cli::SplitArgs) -> ! {
+    let manifest = utils::split::Manifest::new(args.n_frames as usize, args.workers as usize)
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            fail();
+        });
+    let json = manifest.to_json();
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, json).unwrap_or_else(|e| fail_with("writing manifest", e.into()));
+            status!("Manifest saved to: {}", path.display());
+        }
+        None => print!("{}", json),
+    }
+    std::process::exit(0);
+}
+
+/// Copy the frames of a split render into the output folder in order, join
+/// them into a GIF and exit.
+fn merge(args: &cli::MergeArgs) -> ! {
+    let frames = utils::split::Manifest::load(&args.manifest)
+        .and_then(|manifest| manifest.collect_frames(&args.inputs))
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            fail();
+        });
+    let folder = args.output_folder.join("frames");
+    std::fs::create_dir_all(&folder).unwrap_or_else(|e| fail_with("creating folder", e.into()));
+    let files: Vec<std::path::PathBuf> = frames
+        .iter()
+        .map(|frame| {
+            let copy = folder.join(frame.file_name().unwrap());
+            if copy != *frame {
+                std::fs::copy(frame, &copy)
+                    .unwrap_or_else(|e| fail_with("copying frame", e.into()));
+            }
+            copy
+        })
+        .collect();
+    status!("{} frames collected in: {}", files.len(), folder.display());
+
+    let palette = load_palette(args.palette_file.as_deref(), args.palette)
+        .unwrap_or_else(utils::palette::Palette::grayscale);
+    let output = args.output_folder.join("merged.gif").display().to_string();
+    utils::concat::concat(
+        &[utils::concat::Segment::Frames { folder, files }],
+        &output,
+        args.delay,
+        &palette,
+        utils::preserve::Playback::Loop,
+        0,
+        0,
+    )
+    .unwrap_or_else(|e| fail_with("joining GIF", e));
+    status!("GIF created at: {}", output);
+    std::process::exit(0);
+}
+
+/// Compare the canonical renders of the `verify` subcommand with their
+/// reference images, or save them as the references, and exit.
+fn verify(args: &cli::VerifyArgs) -> ! {
+    use utils::verify;
+
+    let mut failed = 0;
+    for fractal_type in FractalType::all() {
+        let path = verify::reference_path(&args.references, fractal_type);
+        let pixels = verify::render_case(fractal_type);
+        if args.update {
+            utils::preserve::write_image(
+                &path.display().to_string(),
+                &pixels,
+                verify::BOUNDS,
+                None,
+                None,
+                &[],
+            )
+            .unwrap_or_else(|e| fail_with("writing PNG file", e));
+            status!("{:<12} saved to {}", fractal_type.name(), path.display());
+            continue;
+        }
+        match verify::load_reference(&path) {
+            Ok(expected) => {
+                let difference = verify::compare(&expected, &pixels);
+                if difference.max <= args.tolerance {
+                    status!("{:<12} ok", fractal_type.name());
+                } else {
+                    failed += 1;
+                    status!(
+                        "{:<12} FAILED: {} pixels differ, max error {}, mean error {:.3}",
+                        fractal_type.name(),
+                        difference.pixels,
+                        difference.max,
+                        difference.mean
+                    );
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                status!("{:<12} FAILED: {}", fractal_type.name(), e);
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!(
+            "Error: {} of {} renders do not match their references",
+            failed,
+            FractalType::all().len()
+        );
+        std::process::exit(1);
+    }
+    std::process::exit(0);
 }
@@ -1,10 +1,45 @@
+pub mod adaptive;
+pub mod animation;
+pub mod antialias;
+pub mod batch;
+pub mod boost;
+pub mod buddhabrot;
+pub mod checksum;
+pub mod concat;
+pub mod contact_sheet;
+pub mod domain;
+pub mod dual_view;
+pub mod error;
+pub mod expression;
+pub mod fixed;
+pub mod font;
+pub mod icc;
+pub mod lyapunov;
+pub mod mapped;
+pub mod metadata;
+pub mod normalize;
+pub mod overlay;
+pub mod palette;
+pub mod perturbation;
+pub mod postprocess;
 pub mod preserve;
+pub mod random;
+pub mod rays;
+pub mod shader;
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod split;
+pub mod symmetry;
+pub mod target;
+pub mod texture;
 pub mod transform;
+pub mod verify;
 use num::Complex;
+use rayon::prelude::*;
 use std::str::FromStr;
 
 /// Enum representing different types of fractals
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FractalType {
     /// Standard Mandelbrot set: z = z^n + c
     Mandelbrot,
@@ -20,6 +55,1101 @@ pub enum FractalType {
     Sin,
     /// Cos fractal: z = cos(z) + c
     Cos,
+    /// Phoenix fractal: z = z^n + Re(p) + Im(p) * z_prev, where z_prev is the
+    /// iterate before z, starting from the point coordinate like a Julia set
+    Phoenix,
+    /// Collatz fractal: z = (2 + 7z - (2 + 5z) cos(pi z)) / 4, which maps
+    /// even integers n to n/2 and odd ones to 3n + 1, starting from the point
+    /// coordinate like a Julia set
+    Collatz,
+    /// Lyapunov fractal: stability of the logistic map x = r*x*(1-x), with r
+    /// alternating between the real (A) and imaginary (B) coordinates
+    Lyapunov,
+    /// Magnet type 1 fractal: z = ((z^2 + c - 1) / (2z + c - 2))^2, from the
+    /// renormalization of a model of magnetism; orbits stop when they escape
+    /// or converge to the fixed point 1
+    Magnet1,
+    /// Magnet type 2 fractal:
+    /// z = ((z^3 + 3(c - 1)z + (c - 1)(c - 2)) / (3z^2 + 3(c - 2)z + (c - 1)(c - 2) + 1))^2,
+    /// stopping like the type 1 fractal
+    Magnet2,
+    /// Multibrot set: z = z^d + c for a real power d, whole or fractional,
+    /// that can change from frame to frame; fractional powers are taken on
+    /// the principal branch, see [`FractalParams::with_real_power`]
+    Multibrot,
+}
+
+impl FractalType {
+    /// Return every fractal type, in declaration order.
+    ///
+    /// This is the one list of the types that contact sheets, verification
+    /// and name parsing go through.
+    pub fn all() -> [FractalType; 13] {
+        // A new variant fails to compile here until it is listed below too
+        match FractalType::Mandelbrot {
+            FractalType::Mandelbrot
+            | FractalType::Julia
+            | FractalType::BurningShip
+            | FractalType::Tricorn
+            | FractalType::Nova
+            | FractalType::Sin
+            | FractalType::Cos
+            | FractalType::Phoenix
+            | FractalType::Collatz
+            | FractalType::Lyapunov
+            | FractalType::Magnet1
+            | FractalType::Magnet2
+            | FractalType::Multibrot => {}
+        }
+        [
+            FractalType::Mandelbrot,
+            FractalType::Julia,
+            FractalType::BurningShip,
+            FractalType::Tricorn,
+            FractalType::Nova,
+            FractalType::Sin,
+            FractalType::Cos,
+            FractalType::Phoenix,
+            FractalType::Collatz,
+            FractalType::Lyapunov,
+            FractalType::Magnet1,
+            FractalType::Magnet2,
+            FractalType::Multibrot,
+        ]
+    }
+
+    /// Return the escape radius that suits this formula.
+    ///
+    /// Sin and Cos grow like `e^|Im(z)|`, so orbits only diverge for good
+    /// once the imaginary part is large; a radius of 2 would stop points
+    /// that are still bounded. They are tested against `|Im(z)|` instead of
+    /// `|z|`, see [`FractalType::default_escape_condition`].
+    pub fn default_escape_radius(self) -> f64 {
+        match self {
+            FractalType::Sin | FractalType::Cos => 50.0,
+            // Orbits of the Collatz map wander well away from the origin, and
+            // only run off for good once they are far out
+            FractalType::Collatz => 1000.0,
+            // Magnet orbits land on the fixed point 1 from well outside the
+            // unit disk, so only orbits that run far off count as escaped
+            FractalType::Magnet1 | FractalType::Magnet2 => 100.0,
+            _ => 2.0,
+        }
+    }
+
+    /// Return the escape test that suits this formula.
+    ///
+    /// Sin and Cos are periodic along the real axis, so only the imaginary
+    /// part measures how far an orbit has run off: an orbit can stay bounded
+    /// far out along the real axis, where `|z|` would count it as escaped.
+    pub fn default_escape_condition(self) -> EscapeCondition {
+        match self {
+            FractalType::Sin | FractalType::Cos => EscapeCondition::Imag,
+            _ => EscapeCondition::Norm,
+        }
+    }
+
+    /// Return the upper-left and lower-right corners of a view that frames
+    /// the whole fractal at its default parameters, for --bounds-auto.
+    ///
+    /// The Mandelbrot set reaches further left than right, the Burning Ship
+    /// lies mostly below the real axis, Sin and Cos repeat along it and show
+    /// one period, and the ordered Lyapunov region is the square from 2 to 4.
+    /// Multibrot sets change shape with their power, see [`multibrot_view`];
+    /// this is the view of power 2.
+    pub fn default_view(self) -> (Complex<f64>, Complex<f64>) {
+        let (upper_left, lower_right) = match self {
+            FractalType::Multibrot => return multibrot_view(2.0, 2.0),
+            FractalType::Mandelbrot => ((-2.5, 1.25), (1.0, -1.25)),
+            FractalType::Julia => ((-1.8, 1.2), (1.8, -1.2)),
+            FractalType::BurningShip => ((-2.2, 1.0), (1.4, -1.8)),
+            FractalType::Tricorn => ((-1.8, 1.5), (1.2, -1.5)),
+            FractalType::Nova => ((-1.4, 1.2), (0.6, -1.2)),
+            FractalType::Sin => ((-3.2, 2.2), (3.2, -2.2)),
+            FractalType::Cos => ((-3.5, 3.5), (3.5, -3.5)),
+            FractalType::Phoenix => ((-1.0, 1.5), (1.0, -1.5)),
+            FractalType::Collatz => ((-4.0, 1.5), (4.0, -1.5)),
+            FractalType::Lyapunov => ((2.0, 4.0), (4.0, 2.0)),
+            FractalType::Magnet1 => ((-1.5, 2.5), (3.5, -2.5)),
+            FractalType::Magnet2 => ((-1.0, 2.0), (3.0, -2.0)),
+        };
+        (
+            Complex::new(upper_left.0, upper_left.1),
+            Complex::new(lower_right.0, lower_right.1),
+        )
+    }
+
+    /// Return the lowercase name used for output files.
+    pub fn name(self) -> &'static str {
+        match self {
+            FractalType::Mandelbrot => "mandelbrot",
+            FractalType::Julia => "julia",
+            FractalType::BurningShip => "burning_ship",
+            FractalType::Tricorn => "tricorn",
+            FractalType::Nova => "nova",
+            FractalType::Sin => "sin",
+            FractalType::Cos => "cos",
+            FractalType::Phoenix => "phoenix",
+            FractalType::Collatz => "collatz",
+            FractalType::Lyapunov => "lyapunov",
+            FractalType::Magnet1 => "magnet1",
+            FractalType::Magnet2 => "magnet2",
+            FractalType::Multibrot => "multibrot",
+        }
+    }
+}
+
+impl FromStr for FractalType {
+    type Err = String;
+
+    /// Parse the name given by [`FractalType::name`], in any case and with
+    /// hyphens or underscores, like `burning-ship` or `Burning_Ship`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase().replace('-', "_");
+        FractalType::all()
+            .into_iter()
+            .find(|t| t.name() == name)
+            .ok_or_else(|| format!("unknown fractal type `{}`", s))
+    }
+}
+
+impl std::fmt::Display for FractalType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[test]
+fn test_fractal_type_all() {
+    let names: Vec<&str> = FractalType::all().iter().map(|t| t.name()).collect();
+    assert_eq!(names.len(), 13);
+    assert_eq!(names[0], "mandelbrot");
+    assert_eq!(names[9], "lyapunov");
+    assert_eq!(names[11], "magnet2");
+    assert_eq!(names[12], "multibrot");
+    // Every name is distinct
+    assert!(
+        names
+            .iter()
+            .all(|n| names.iter().filter(|m| m == &n).count() == 1)
+    );
+}
+
+#[test]
+fn test_parse_fractal_type() {
+    for fractal_type in FractalType::all() {
+        assert_eq!(fractal_type.to_string().parse(), Ok(fractal_type));
+    }
+    for name in [
+        "burning_ship",
+        "burning-ship",
+        "Burning-Ship",
+        " BURNING_SHIP ",
+    ] {
+        assert_eq!(name.parse(), Ok(FractalType::BurningShip), "{:?}", name);
+    }
+    assert_eq!(FractalType::Magnet2.to_string(), "magnet2");
+    assert_eq!(
+        "burningship".parse::<FractalType>(),
+        Err("unknown fractal type `burningship`".to_string())
+    );
+}
+
+/// What part of `z` is compared with the escape radius to decide that an
+/// orbit has escaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeCondition {
+    /// The modulus `|z|`
+    Norm,
+    /// The size of the imaginary part, `|Im(z)|`
+    Imag,
+    /// The size of the real part, `|Re(z)|`
+    Real,
+}
+
+impl EscapeCondition {
+    /// Return the lowercase name used on the command line and in metadata.
+    pub fn name(self) -> &'static str {
+        match self {
+            EscapeCondition::Norm => "norm",
+            EscapeCondition::Imag => "imag",
+            EscapeCondition::Real => "real",
+        }
+    }
+}
+
+/// Where complex conjugation is applied in each step of the iteration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Conjugate {
+    /// Conjugate `z` before the formula is applied, turning the Mandelbrot
+    /// set into the Tricorn
+    Z,
+    /// Conjugate the value added at each step: `c`, or the constant of Julia
+    /// sets and phoenix fractals
+    C,
+    /// Conjugate both
+    Both,
+}
+
+/// Parameters controlling how the fractal formula is iterated
+#[derive(Debug, Clone, Copy)]
+pub struct FractalParams {
+    /// The fractal formula to iterate
+    pub fractal_type: FractalType,
+    /// Power of the fractal (e.g., 2 for standard Mandelbrot)
+    pub power: i32,
+    /// Escape radius for the fractal set
+    pub escape_radius: f64,
+    /// Constant used by Julia sets, or the constant p of phoenix fractals
+    pub julia_constant: Option<Complex<f64>>,
+    /// Pattern of A and B steps used by Lyapunov fractals
+    pub lyapunov_sequence: lyapunov::Sequence,
+    /// Maximum number of iterations before a point is considered a member
+    pub max_iterations: usize,
+    /// Sample each pixel at its upper-left corner rather than its center, as
+    /// renders did before center sampling
+    pub corner_sampling: bool,
+    /// Apply complex conjugation inside each step of the formula
+    pub conjugate: Option<Conjugate>,
+    /// Complex exponent that replaces `power` in the Mandelbrot and Julia
+    /// formulas, raising `z` with [`complex_pow`]
+    pub complex_power: Option<Complex<f64>>,
+    /// How sampled points are laid out; the corners given to the renderer
+    /// are those of [`transform::SamplePattern::grid`]
+    pub sample_pattern: transform::SamplePattern,
+    /// Rotation applied to every sampled point, which turns the whole
+    /// sampling grid about a fixed center
+    pub rotation: Option<transform::Rotation>,
+    /// Escape test of every orbit, or `None` for the one that suits the
+    /// formula, see [`FractalType::default_escape_condition`]
+    pub escape_condition: Option<EscapeCondition>,
+    /// Blend of each step of the formula with that of another fractal type
+    pub morph: Option<Morph>,
+    /// Value every orbit starts from in place of [`initial_z`], for formulas
+    /// whose orbits all start at the same point rather than at the pixel
+    pub initial_z: Option<Complex<f64>>,
+}
+
+/// A blend of the formula of [`FractalParams::fractal_type`] with that of
+/// another type, `(1 - t) * step(z) + t * step_to(z)`, for animations that
+/// turn one fractal into the other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Morph {
+    /// The fractal type blended towards
+    pub to: FractalType,
+    /// Weight of the step of `to`, from 0 for the own formula alone to 1 for
+    /// that of `to` alone
+    pub t: f64,
+}
+
+impl Default for FractalParams {
+    fn default() -> Self {
+        FractalParams {
+            fractal_type: FractalType::Mandelbrot,
+            power: 2,
+            escape_radius: 2.0,
+            julia_constant: None,
+            lyapunov_sequence: lyapunov::Sequence::default(),
+            max_iterations: u8::MAX as usize,
+            corner_sampling: false,
+            conjugate: None,
+            complex_power: None,
+            sample_pattern: transform::SamplePattern::Rect,
+            rotation: None,
+            escape_condition: None,
+            morph: None,
+            initial_z: None,
+        }
+    }
+}
+
+impl FractalParams {
+    /// Return the escape time of the point `c` under these parameters, like
+    /// [`escape_time`] with the iteration limit `max_iterations`.
+    pub fn escape_time(&self, c: Complex<f64>) -> Option<usize> {
+        match (self.conjugate, self.complex_power) {
+            (None, None)
+                if self.has_default_escape_condition()
+                    && self.morph.is_none()
+                    && self.initial_z.is_none() =>
+            {
+                escape_time(
+                    c,
+                    self.max_iterations,
+                    self.power,
+                    self.escape_radius,
+                    self.fractal_type,
+                    self.julia_constant,
+                )
+            }
+            _ => escape_orbit(c, self, None).iterations,
+        }
+    }
+
+    /// Return these parameters with the real power `d` of a multibrot set: a
+    /// whole `d` as [`power`](FractalParams::power), for the integer loop, and
+    /// a fractional one as a [`complex_power`](FractalParams::complex_power)
+    /// with no imaginary part, with `power` at the nearest whole number.
+    pub fn with_real_power(self, d: f64) -> FractalParams {
+        FractalParams {
+            power: d.round() as i32,
+            complex_power: (d.fract() != 0.0).then_some(Complex { re: d, im: 0.0 }),
+            ..self
+        }
+    }
+
+    /// Return the escape test of every orbit: the one chosen, or the one
+    /// that suits the formula.
+    pub fn escape_condition(&self) -> EscapeCondition {
+        self.escape_condition
+            .unwrap_or(self.fractal_type.default_escape_condition())
+    }
+
+    /// Return whether orbits are tested the way that suits the formula, which
+    /// the fast loops assume.
+    pub fn has_default_escape_condition(&self) -> bool {
+        self.escape_condition() == self.fractal_type.default_escape_condition()
+    }
+
+    /// Return the point sampled for `pixel`, with the same arguments as
+    /// [`transform::pixel_to_point`]: the pixel's center, or its upper-left
+    /// corner with `corner_sampling`, mapped by the sample pattern and turned
+    /// by any rotation.
+    ///
+    /// The rotation has its own center rather than the middle of the view, so
+    /// a part of the view, such as one row, is sampled at the same points as
+    /// the whole view would be.
+    pub fn sample_point(
+        &self,
+        bounds: (usize, usize),
+        pixel: (usize, usize),
+        upper_left: Complex<f64>,
+        lower_right: Complex<f64>,
+    ) -> Complex<f64> {
+        let point = if self.corner_sampling {
+            transform::pixel_corner_to_point(bounds, pixel, upper_left, lower_right)
+        } else {
+            transform::pixel_to_point(bounds, pixel, upper_left, lower_right)
+        };
+        let point = self.sample_pattern.map(point);
+        match self.rotation {
+            Some(rotation) => rotation.apply(point),
+            None => point,
+        }
+    }
+}
+
+/// Return the upper-left and lower-right corners of a view that frames the
+/// multibrot sets of every real power from `from` to `to`, both more than 1,
+/// for --bounds-auto.
+///
+/// The set of power d lies within 2^(1/(d - 1)) of the origin, turns into
+/// itself by a (d - 1)th of a turn and is mirrored in the real axis. The
+/// members of a coarse render at a few powers along the way give the extent
+/// of all of them, which is padded and kept centered on the real axis.
+pub fn multibrot_view(from: f64, to: f64) -> (Complex<f64>, Complex<f64>) {
+    // An odd number of rows puts the middle one on the real axis, where the
+    // antenna of the power 2 set reaches further than anywhere else
+    const GRID: usize = 97;
+    const POWERS: usize = 9;
+    assert!(from > 1.0 && to > 1.0);
+    let (mut left, mut right, mut top) = (f64::INFINITY, f64::NEG_INFINITY, 0.0f64);
+    let mut cell = 0.0f64;
+    for k in 0..POWERS {
+        let d = from + (to - from) * k as f64 / (POWERS - 1) as f64;
+        let radius = 2f64.powf(1.0 / (d - 1.0));
+        let params = FractalParams {
+            fractal_type: FractalType::Multibrot,
+            max_iterations: 64,
+            ..FractalParams::default()
+        }
+        .with_real_power(d);
+        let step = 2.0 * radius / GRID as f64;
+        cell = cell.max(step);
+        for row in 0..GRID {
+            for column in 0..GRID {
+                let c = Complex {
+                    re: -radius + (column as f64 + 0.5) * step,
+                    im: radius - (row as f64 + 0.5) * step,
+                };
+                if params.escape_time(c).is_none() {
+                    (left, right) = (left.min(c.re), right.max(c.re));
+                    top = top.max(c.im.abs());
+                }
+            }
+        }
+    }
+    if left > right {
+        return (Complex::new(-2.0, 2.0), Complex::new(2.0, -2.0));
+    }
+    let pad = cell + 0.05 * (right - left).max(2.0 * top);
+    (
+        Complex::new(left - pad, top + pad),
+        Complex::new(right + pad, -top - pad),
+    )
+}
+
+#[test]
+fn test_multibrot() {
+    // Whole powers match the Mandelbrot formula of the same power
+    for (power, c) in [(2, Complex::new(-0.75, 0.1)), (3, Complex::new(0.1, 0.6))] {
+        let mandelbrot = FractalParams {
+            power,
+            ..FractalParams::default()
+        };
+        let multibrot = FractalParams {
+            fractal_type: FractalType::Multibrot,
+            ..FractalParams::default()
+        }
+        .with_real_power(power as f64);
+        assert_eq!(multibrot.complex_power, None);
+        assert_eq!(multibrot.escape_time(c), mandelbrot.escape_time(c));
+    }
+    let half = FractalParams::default().with_real_power(2.5);
+    assert_eq!(half.complex_power, Some(Complex { re: 2.5, im: 0.0 }));
+
+    // The view of power 2 reaches the tip at -2, and that of power 3, whose
+    // set is symmetric about both axes, is centered on the origin
+    let (upper_left, lower_right) = multibrot_view(2.0, 2.0);
+    assert!(upper_left.re < -2.0 && upper_left.re > -2.3);
+    assert!(lower_right.re > 0.45 && lower_right.re < 0.8);
+    assert_eq!(upper_left.im, -lower_right.im);
+    let (upper_left, lower_right) = multibrot_view(3.0, 3.0);
+    assert!((upper_left.re + lower_right.re).abs() < 0.1);
+    // A ramp frames every power along it
+    let (ramp_upper_left, ramp_lower_right) = multibrot_view(2.0, 3.0);
+    assert!(ramp_upper_left.re <= upper_left.re && ramp_lower_right.re >= lower_right.re);
+    assert!(ramp_upper_left.im >= upper_left.im);
+}
+
+/// Return the iteration limit for a frame magnified `zoom` times relative to
+/// the opening view: `base + k * log2(zoom)`.
+///
+/// Each halving of the view adds `k` iterations, since boundary detail needs
+/// more iterations the closer you look. Zooming out never drops below `base`.
+pub fn iterations_for_zoom(base: usize, k: f64, zoom: f64) -> usize {
+    let extra = k * zoom.log2().max(0.0);
+    base + extra.round() as usize
+}
+
+#[test]
+fn test_iterations_for_zoom() {
+    assert_eq!(iterations_for_zoom(255, 50.0, 1.0), 255);
+    assert_eq!(iterations_for_zoom(255, 50.0, 2.0), 305);
+    assert_eq!(iterations_for_zoom(100, 10.0, 1024.0), 200);
+    assert_eq!(iterations_for_zoom(100, 10.0, 1e-3), 100);
+}
+
+/// How the Julia constant moves through parameter space over an animation
+#[derive(Debug, Clone, Copy)]
+pub enum JuliaPath {
+    /// The constant stays the same for every frame
+    Fixed,
+    /// The constant moves in a straight line from the start value to `end`
+    Line { end: Complex<f64> },
+    /// The constant sweeps once around a circle of `radius` centered on the start value
+    Orbit { radius: f64 },
+}
+
+impl JuliaPath {
+    /// Return the Julia constant for frame `frame` of an `n_frames` animation
+    /// that begins at `start`.
+    pub fn at(&self, start: Complex<f64>, frame: usize, n_frames: usize) -> Complex<f64> {
+        match *self {
+            JuliaPath::Fixed => start,
+            JuliaPath::Line { end } => {
+                // Hit the end value exactly on the last frame
+                let t = if n_frames > 1 {
+                    frame as f64 / (n_frames - 1) as f64
+                } else {
+                    0.0
+                };
+                start * (1.0 - t) + end * t
+            }
+            JuliaPath::Orbit { radius } => {
+                // Stop one step short of a full turn so the animation loops cleanly
+                let angle = std::f64::consts::TAU * frame as f64 / n_frames.max(1) as f64;
+                start + Complex::from_polar(radius, angle)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_julia_path() {
+    let start = Complex {
+        re: -0.8,
+        im: 0.156,
+    };
+    assert_eq!(JuliaPath::Fixed.at(start, 7, 10), start);
+
+    let line = JuliaPath::Line {
+        end: Complex { re: 0.2, im: 0.156 },
+    };
+    assert_eq!(line.at(start, 0, 11), start);
+    assert_eq!(line.at(start, 10, 11), Complex { re: 0.2, im: 0.156 });
+    assert!((line.at(start, 5, 11).re - -0.3).abs() < 1e-12);
+    assert_eq!(line.at(start, 0, 1), start);
+
+    let orbit = JuliaPath::Orbit { radius: 0.5 };
+    let first = orbit.at(start, 0, 4);
+    let quarter = orbit.at(start, 1, 4);
+    assert!((first.re - -0.3).abs() < 1e-12 && (first.im - 0.156).abs() < 1e-12);
+    assert!((quarter.re - -0.8).abs() < 1e-12 && (quarter.im - 0.656).abs() < 1e-12);
+}
+
+/// Options controlling how escape counts are mapped to pixel values
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColorOptions {
+    /// Flip the mapping so that quickly escaping points are dark instead of bright
+    pub invert: bool,
+    /// Value painted for points that never escape
+    pub interior: u8,
+    /// Split escaping points into this many sectors by the argument of their
+    /// final `z`, darkening every other sector
+    pub decomposition: Option<u32>,
+    /// Shade escaping points by the average of `sin(density * arg(z))` over
+    /// their orbit instead of by their escape count
+    pub stripe_density: Option<f64>,
+    /// Shade escaping points by their continuous escape time instead of the
+    /// whole escape count, taking this power as the growth rate of `|z|`
+    pub smooth: Option<f64>,
+    /// Length in iterations of one cycle of smooth shading, which then rises
+    /// and falls with the continuous escape time instead of spanning the
+    /// iteration limit once, so that a point keeps its shade when the limit
+    /// changes from frame to frame
+    pub band_period: Option<f64>,
+    /// Shade escaping points by their escape count modulo this many
+    /// iterations, so that the shades repeat in sharp contour bands of equal
+    /// width instead of spanning the iteration limit once
+    pub contour: Option<usize>,
+    /// Escape counts mapped to the ends of the channel range in place of zero
+    /// and the iteration limit, so that every frame of an animation can share
+    /// one range; counts outside it are clamped
+    pub count_range: Option<(usize, usize)>,
+    /// Fraction of the channel range that escaping points' levels are rotated
+    /// by, wrapping around from the brightest level to the darkest
+    pub palette_shift: f64,
+    /// Factor escaping points' levels are multiplied by before they are
+    /// rotated, wrapping around likewise, so that the palette repeats this
+    /// many times across the escape range
+    pub color_scale: Option<f64>,
+    /// Colors that 8-bit gray levels are mapped to when frames are saved
+    pub palette: Option<palette::Palette>,
+    /// Shade members by a magnitude of their orbit instead of painting them
+    /// all with the interior color
+    pub interior_coloring: Option<InteriorColoring>,
+    /// Shade escaping points by their potential, see
+    /// [`EscapeResult::potential`], taking this power as the growth rate of
+    /// `|z|`; the band period cycles it like smooth shading
+    pub potential: Option<f64>,
+    /// Shade converged points by the argument of the fixed point they settled
+    /// on instead of by how fast they got there, so that every basin takes
+    /// one flat shade
+    pub root_coloring: bool,
+}
+
+/// Magnitude of a member's orbit that its shade is taken from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InteriorColoring {
+    /// The mean of `|z|` over the orbit, see [`EscapeResult::mean_magnitude`]
+    Average,
+    /// `|z|` after the iteration limit
+    Final,
+}
+
+/// Orbit magnitude shown at the brightest interior level; the escape radius
+/// of the Mandelbrot set bounds the orbits of its members
+pub const INTERIOR_MAGNITUDE: f64 = 2.0;
+
+impl ColorOptions {
+    /// Map the result of `escape_time` with the given iteration `limit` to a
+    /// grayscale pixel value.
+    ///
+    /// Escape counts are spread across the full range of the channel type,
+    /// or across each contour band, and the 8-bit interior color is rescaled
+    /// to match it.
+    pub fn shade<P: Channel>(&self, escape: Option<usize>, limit: usize) -> P {
+        let level = match escape {
+            None => self.interior as u64 * P::MAX as u64 / u8::MAX as u64,
+            Some(count) => {
+                let scaled = match self.contour {
+                    Some(period) => (count % period) as u64 * P::MAX as u64 / period as u64,
+                    None => {
+                        let (low, high) = self.count_range.unwrap_or((0, limit));
+                        let count = count.clamp(low, high) - low;
+                        count as u64 * P::MAX as u64 / (high - low).max(1) as u64
+                    }
+                };
+                let level = if self.invert {
+                    scaled
+                } else {
+                    P::MAX as u64 - scaled
+                };
+                self.cycle::<P>(level as u32) as u64
+            }
+        };
+        match escape {
+            None => P::interior(level as u32),
+            Some(_) => P::from_level(level as u32),
+        }
+    }
+
+    /// Map an [`EscapeResult`] to a pixel value like [`ColorOptions::shade`],
+    /// applying stripe average, smooth and decomposition coloring if they are
+    /// enabled.
+    ///
+    /// With a stripe density, the brightness of an escaping point is its
+    /// stripe average; with smoothing, it is mapped from the point's
+    /// [`EscapeResult::smooth_count`] like a count, or cycled through every
+    /// band period if one is given. The argument of the final `z` picks one of the sectors
+    /// around the origin; points in odd sectors are shown at half brightness,
+    /// which outlines the cells of the binary decomposition when there are two.
+    ///
+    /// With a potential, escaping points are shaded by the number of times
+    /// their potential is divided by the power from that of the escape
+    /// radius, `-log_power(G)`, which is spread like the smooth escape time.
+    ///
+    /// With interior coloring, members are shaded from the interior color at
+    /// a magnitude of zero up to the brightest level at
+    /// [`INTERIOR_MAGNITUDE`], without inverting or cycling, so that the
+    /// inside of the set reads as its own ramp next to the escape palette.
+    pub fn shade_result<P: Channel>(&self, result: &EscapeResult, limit: usize) -> P {
+        if let (Some(mode), None) = (self.interior_coloring, result.iterations) {
+            let magnitude = match mode {
+                InteriorColoring::Average => result.mean_magnitude,
+                InteriorColoring::Final => result.final_z.norm(),
+            };
+            let t = (magnitude / INTERIOR_MAGNITUDE).clamp(0.0, 1.0);
+            let low = self.interior as f64 * P::MAX as f64 / u8::MAX as f64;
+            let level = low + t * (P::MAX as f64 - low);
+            return P::interior(level.round() as u32);
+        }
+        if let (true, true, Some(_)) = (self.root_coloring, result.converged, result.iterations) {
+            let turn = (result.final_z.arg() / std::f64::consts::TAU).rem_euclid(1.0);
+            let level = (turn * P::MAX as f64).round() as u32;
+            return P::from_level(self.cycle::<P>(level));
+        }
+        let continuous = match (self.potential, self.stripe_density, self.smooth) {
+            // -log_power(G), without the underflow of G itself
+            (Some(power), _, _) => result.iterations.map(|count| {
+                let u = count as f64 - result.final_z.norm().ln().ln() / power.max(2.0).ln();
+                if u.is_finite() {
+                    u.max(0.0)
+                } else {
+                    count as f64
+                }
+            }),
+            (None, None, Some(power)) => result
+                .iterations
+                .map(|count| result.smooth_count(count, power)),
+            _ => None,
+        };
+        let shade: P = match (continuous, self.stripe_density, result.iterations) {
+            (Some(smooth), _, _) => {
+                let scaled = match self.band_period {
+                    // A triangle wave has no seam between one cycle and the next
+                    Some(period) => {
+                        let phase = (smooth / period).rem_euclid(1.0);
+                        (1.0 - (2.0 * phase - 1.0).abs()) * P::MAX as f64
+                    }
+                    None => smooth * P::MAX as f64 / limit as f64,
+                }
+                .clamp(0.0, P::MAX as f64);
+                let level = scaled.round() as u32;
+                P::from_level(self.cycle::<P>(if self.invert { level } else { P::MAX - level }))
+            }
+            (None, Some(_), Some(_)) => {
+                let level = (result.stripe_average * P::MAX as f64).round() as u32;
+                P::from_level(self.cycle::<P>(if self.invert { P::MAX - level } else { level }))
+            }
+            _ => self.shade(result.iterations, limit),
+        };
+        match (self.decomposition, result.iterations) {
+            (Some(sectors), Some(_)) => {
+                let turn = (result.final_z.arg() / std::f64::consts::TAU).rem_euclid(1.0);
+                let sector = (turn * sectors as f64) as u32 % sectors;
+                if sector % 2 == 1 {
+                    shade.with_level(shade.level() / 2)
+                } else {
+                    shade
+                }
+            }
+            _ => shade,
+        }
+    }
+
+    /// Return whether shading needs more of the orbit than its escape count,
+    /// so that points must be iterated with [`escape_orbit`].
+    pub fn needs_orbit(&self) -> bool {
+        self.decomposition.is_some()
+            || self.stripe_density.is_some()
+            || self.smooth.is_some()
+            || self.interior_coloring.is_some()
+            || self.potential.is_some()
+            || self.root_coloring
+    }
+
+    /// Stretch the level of an escaping point by the color scale and rotate
+    /// it by the palette shift, so that shifting by a whole number of turns
+    /// leaves it unchanged.
+    pub fn cycle<P: Channel>(&self, level: u32) -> u32 {
+        if self.palette_shift == 0.0 && self.color_scale.is_none() {
+            return level;
+        }
+        let steps = P::MAX as f64 + 1.0;
+        let level = match self.color_scale {
+            Some(scale) => (level as f64 * scale).round().rem_euclid(steps) as u64,
+            None => level as u64,
+        };
+        let offset = (self.palette_shift.rem_euclid(1.0) * steps).round() as u64;
+        ((level + offset) % (P::MAX as u64 + 1)) as u32
+    }
+}
+
+#[test]
+fn test_cycle() {
+    let colors = ColorOptions {
+        palette_shift: 0.25,
+        ..ColorOptions::default()
+    };
+    assert_eq!(colors.cycle::<u8>(0), 64);
+    assert_eq!(colors.cycle::<u8>(200), 8);
+    assert_eq!(colors.cycle::<u16>(0), 16384);
+    // Whole turns and negative shifts wrap around
+    let colors = ColorOptions {
+        palette_shift: -0.75,
+        ..colors
+    };
+    assert_eq!(colors.cycle::<u8>(0), 64);
+    // The interior is not cycled
+    assert_eq!(colors.shade::<u8>(None, 255), 0);
+    assert_eq!(colors.shade::<u8>(Some(255), 255), 64);
+}
+
+#[test]
+fn test_color_scale() {
+    let colors = ColorOptions {
+        color_scale: Some(2.0),
+        ..ColorOptions::default()
+    };
+    // Twice the scale runs through the palette twice
+    assert_eq!(colors.cycle::<u8>(64), 128);
+    assert_eq!(colors.cycle::<u8>(128), 0);
+    assert_eq!(colors.cycle::<u8>(200), 144);
+    assert_eq!(colors.cycle::<u16>(40000), 14464);
+    // The scaled level is then shifted
+    let colors = ColorOptions {
+        palette_shift: 0.25,
+        ..colors
+    };
+    assert_eq!(colors.cycle::<u8>(128), 64);
+    // Smaller scales compress the escape range into part of the palette
+    let colors = ColorOptions {
+        color_scale: Some(0.5),
+        palette_shift: 0.0,
+        ..colors
+    };
+    assert_eq!(colors.cycle::<u8>(255), 128);
+}
+
+/// A grayscale channel type that images can be rendered into.
+pub trait Channel: Copy + Default + Send + Sync {
+    /// The brightest representable value
+    const MAX: u32;
+
+    /// Convert a level in `0..=MAX` to the channel type.
+    fn from_level(level: u32) -> Self;
+
+    /// Return the level of this value in `0..=MAX`.
+    fn level(self) -> u32;
+
+    /// Convert a level to the value painted for points inside the set.
+    fn interior(level: u32) -> Self {
+        Self::from_level(level)
+    }
+
+    /// Replace the level of this value, keeping anything else it carries.
+    fn with_level(self, level: u32) -> Self {
+        Self::from_level(level)
+    }
+
+    /// Whether the type carries an alpha channel
+    const HAS_ALPHA: bool = false;
+
+    /// Return the 8-bit opacity of this value.
+    fn alpha(self) -> u8 {
+        u8::MAX
+    }
+
+    /// Replace the opacity of this value, if the type carries one.
+    fn with_alpha(self, _alpha: u8) -> Self {
+        self
+    }
+}
+
+impl Channel for u8 {
+    const MAX: u32 = u8::MAX as u32;
+
+    fn from_level(level: u32) -> Self {
+        level as u8
+    }
+
+    fn level(self) -> u32 {
+        self as u32
+    }
+}
+
+impl Channel for u16 {
+    const MAX: u32 = u16::MAX as u32;
+
+    fn from_level(level: u32) -> Self {
+        level as u16
+    }
+
+    fn level(self) -> u32 {
+        self as u32
+    }
+}
+
+/// An 8-bit gray level with an alpha channel, used to make the interior of
+/// the set transparent.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GrayAlpha {
+    pub gray: u8,
+    pub alpha: u8,
+}
+
+impl Channel for GrayAlpha {
+    const MAX: u32 = u8::MAX as u32;
+
+    fn from_level(level: u32) -> Self {
+        GrayAlpha {
+            gray: level as u8,
+            alpha: u8::MAX,
+        }
+    }
+
+    fn level(self) -> u32 {
+        self.gray as u32
+    }
+
+    fn interior(level: u32) -> Self {
+        GrayAlpha {
+            gray: level as u8,
+            alpha: 0,
+        }
+    }
+
+    fn with_level(self, level: u32) -> Self {
+        GrayAlpha {
+            gray: level as u8,
+            ..self
+        }
+    }
+
+    const HAS_ALPHA: bool = true;
+
+    fn alpha(self) -> u8 {
+        self.alpha
+    }
+
+    fn with_alpha(self, alpha: u8) -> Self {
+        GrayAlpha { alpha, ..self }
+    }
+}
+
+#[test]
+fn test_shade_result() {
+    let colors = ColorOptions {
+        decomposition: Some(2),
+        ..ColorOptions::default()
+    };
+    let escaped = |re, im| EscapeResult {
+        iterations: Some(5),
+        final_z: Complex { re, im },
+        ..EscapeResult::default()
+    };
+    // Binary decomposition splits by the sign of the imaginary part
+    assert_eq!(colors.shade_result::<u8>(&escaped(3.0, 1.0), 255), 250);
+    assert_eq!(colors.shade_result::<u8>(&escaped(3.0, -1.0), 255), 125);
+    assert_eq!(colors.shade_result::<u8>(&escaped(-3.0, -1.0), 255), 125);
+
+    // Four sectors alternate every quarter turn
+    let colors = ColorOptions {
+        decomposition: Some(4),
+        ..colors
+    };
+    assert_eq!(colors.shade_result::<u8>(&escaped(1.0, 1.0), 255), 250);
+    assert_eq!(colors.shade_result::<u8>(&escaped(-1.0, 1.0), 255), 125);
+    assert_eq!(colors.shade_result::<u8>(&escaped(-1.0, -1.0), 255), 250);
+
+    // Members keep the interior color
+    let member = EscapeResult {
+        iterations: None,
+        final_z: Complex { re: 0.1, im: -0.1 },
+        ..EscapeResult::default()
+    };
+    assert_eq!(colors.shade_result::<u8>(&member, 255), 0);
+
+    // Root coloring shades converged points by the turn of their root alone
+    let colors = ColorOptions {
+        root_coloring: true,
+        ..ColorOptions::default()
+    };
+    let converged = |count, re, im| EscapeResult {
+        iterations: Some(count),
+        converged: true,
+        final_z: Complex { re, im },
+        ..EscapeResult::default()
+    };
+    assert_eq!(
+        colors.shade_result::<u8>(&converged(3, -1.0, 0.0), 255),
+        128
+    );
+    assert_eq!(
+        colors.shade_result::<u8>(&converged(9, -1.0, 0.0), 255),
+        128
+    );
+    assert_eq!(
+        colors.shade_result::<u8>(&converged(3, 0.0, -1.0), 255),
+        191
+    );
+    // Escaping points keep their escape count
+    assert_eq!(colors.shade_result::<u8>(&escaped(-1.0, 0.0), 255), 250);
+
+    // Stripes replace the escape count with the orbit's stripe average
+    let colors = ColorOptions {
+        stripe_density: Some(5.0),
+        ..ColorOptions::default()
+    };
+    let striped = EscapeResult {
+        stripe_average: 0.25,
+        ..escaped(3.0, 1.0)
+    };
+    assert_eq!(colors.shade_result::<u8>(&striped, 255), 64);
+    assert_eq!(colors.shade_result::<u16>(&striped, 255), 16384);
+    assert_eq!(colors.shade_result::<u8>(&member, 255), 0);
+
+    // Smooth coloring shades by the fractional escape time, here 4.25
+    let colors = ColorOptions {
+        smooth: Some(2.0),
+        ..ColorOptions::default()
+    };
+    let smoothed = EscapeResult {
+        smoothing_steps: 3,
+        bailout_ratio: 2f64.powf(3.75),
+        ..escaped(3.0, 1.0)
+    };
+    assert_eq!(colors.shade_result::<u8>(&smoothed, 255), 251);
+    assert_eq!(colors.shade_result::<u8>(&member, 255), 0);
+
+    // A band period of 8 puts 4.25 just past the peak of the triangle wave,
+    // whatever the iteration limit
+    let colors = ColorOptions {
+        band_period: Some(8.0),
+        ..colors
+    };
+    assert_eq!(colors.shade_result::<u8>(&smoothed, 255), 16);
+    assert_eq!(colors.shade_result::<u8>(&smoothed, 1000), 16);
+}
+
+#[test]
+fn test_band_period_zoom() {
+    // Consecutive frames of a zoom, the second with a higher limit as
+    // --auto-iterations would give it, share a row of points
+    let colors = ColorOptions {
+        smooth: Some(2.0),
+        band_period: Some(16.0),
+        ..ColorOptions::default()
+    };
+    let row: Vec<Complex<f64>> = (0..=400)
+        .map(|i| Complex {
+            re: -1.5 + i as f64 * 0.005,
+            im: 0.75,
+        })
+        .collect();
+    let shades = |limit: usize| -> Vec<Option<u8>> {
+        let params = FractalParams {
+            max_iterations: limit,
+            ..FractalParams::default()
+        };
+        row.iter()
+            .map(|&c| {
+                let result = escape_orbit(c, &params, None);
+                let shade = colors.shade_result(&result, limit);
+                result.iterations.map(|_| shade)
+            })
+            .collect()
+    };
+    let (first, second) = (shades(200), shades(250));
+    // Every point that escapes in the first frame keeps its shade in the
+    // second, so the bands stay where they were
+    for (i, (a, b)) in first.iter().zip(&second).enumerate() {
+        if a.is_some() {
+            assert_eq!(a, b, "point {}", i);
+        }
+    }
+    assert!(first.iter().flatten().count() > 100);
+
+    // Without a period the shades follow the limit, so the bands move
+    let colors = ColorOptions {
+        band_period: None,
+        ..colors
+    };
+    let result = escape_orbit(row[0], &FractalParams::default(), None);
+    assert_ne!(
+        colors.shade_result::<u8>(&result, 200),
+        colors.shade_result::<u8>(&result, 250)
+    );
+}
+
+#[test]
+fn test_shade() {
+    let colors = ColorOptions {
+        invert: false,
+        interior: 10,
+        ..ColorOptions::default()
+    };
+    assert_eq!(colors.shade::<u8>(None, 255), 10);
+    assert_eq!(colors.shade::<u8>(Some(0), 255), 255);
+    assert_eq!(colors.shade::<u8>(Some(5), 255), 250);
+    assert_eq!(colors.shade::<u16>(None, 255), 2570);
+    assert_eq!(colors.shade::<u16>(Some(5), 255), 65535 - 5 * 257);
+
+    let inverted = ColorOptions {
+        invert: true,
+        interior: 255,
+        ..ColorOptions::default()
+    };
+    assert_eq!(inverted.shade::<u8>(Some(5), 255), 5);
+    assert_eq!(inverted.shade::<u16>(None, 255), 65535);
+
+    // A shared count range stretches its counts over the full channel
+    let ranged = ColorOptions {
+        count_range: Some((5, 10)),
+        ..colors
+    };
+    assert_eq!(ranged.shade::<u8>(Some(5), 255), 255);
+    assert_eq!(ranged.shade::<u8>(Some(8), 255), 102);
+    assert_eq!(ranged.shade::<u8>(Some(40), 255), 0);
+    assert_eq!(ranged.shade::<u8>(None, 255), 10);
+
+    // Contours restart the shades every period, whatever the limit
+    let contour = ColorOptions {
+        contour: Some(4),
+        ..colors
+    };
+    assert_eq!(contour.shade::<u8>(Some(0), 255), 255);
+    assert_eq!(contour.shade::<u8>(Some(1), 255), 192);
+    assert_eq!(contour.shade::<u8>(Some(7), 1000), 64);
+    assert_eq!(contour.shade::<u8>(Some(8), 1000), 255);
+    assert_eq!(contour.shade::<u16>(Some(2), 255), 65535 - 32767);
+
+    // With an alpha channel only the interior is transparent
+    let interior = GrayAlpha { gray: 10, alpha: 0 };
+    assert_eq!(colors.shade::<GrayAlpha>(None, 255), interior);
+    assert_eq!(colors.shade::<GrayAlpha>(Some(5), 255).alpha, 255);
 }
 
 /// Parse the string `s` as a coordinate pair, like `"400x600"` or `"1.0,0.5"`.
@@ -53,112 +1183,2097 @@ fn test_parse_pair() {
 
 /// Given the row and column of a pixel in the output image, return the
 /// Parse a pair of floating-point numbers separated by a comma as a complex number.
+///
+/// Either part may instead be an arithmetic expression of numbers and
+/// constants, like `-0.75,pi/4`, which is evaluated by
+/// [`expression::evaluate`]; plain numbers are parsed directly.
 pub fn parse_complex(s: &str) -> Option<Complex<f64>> {
-    parse_pair(s, ',').map(|(re, im)| Complex { re, im })
+    parse_pair(s, ',')
+        .or_else(|| {
+            let (re, im) = s.split_once(',')?;
+            Some((expression::evaluate(re)?, expression::evaluate(im)?))
+        })
+        .map(|(re, im)| Complex { re, im })
 }
 
 #[test]
 fn test_parse_complex() {
     assert_eq!(parse_complex("0.5,1.5"), Some(Complex { re: 0.5, im: 1.5 }));
     assert_eq!(parse_complex("0.5,1.5x"), None);
+    assert_eq!(
+        parse_complex("1e-15,-2E3"),
+        Some(Complex {
+            re: 1e-15,
+            im: -2e3
+        })
+    );
+    assert_eq!(
+        parse_complex("-0.75,pi/4"),
+        Some(Complex {
+            re: -0.75,
+            im: std::f64::consts::FRAC_PI_4
+        })
+    );
+    assert_eq!(
+        parse_complex("-(1+2)/4, 2^-2"),
+        Some(Complex {
+            re: -0.75,
+            im: 0.25
+        })
+    );
+    assert_eq!(parse_complex("pi"), None);
+    assert_eq!(parse_complex("pi,(1"), None);
+    assert_eq!(parse_complex("1,2,3"), None);
 }
 
-/// Try to determine if a point is in the fractal set, using at most `limit`
-/// iterations to decide.
+/// Return the plain numbers among the comma-separated parts of `s` that an
+/// `f64` cannot hold to every digit given, each with the value it is rounded
+/// to.
 ///
-/// If the point is not a member, return `Some(i)`, where `i` is the number of
-/// iterations it took for the calculation to exceed the escape radius.
-/// If the point seems to be a member (more precisely, if we reached the
-/// iteration limit without being able to prove that it's not a member),
-/// return `None`.
-pub fn escape_time(
-    c: Complex<f64>,
-    limit: usize,
-    power: i32,
-    escape_radius: f64,
-    fractal_type: FractalType,
-    julia_constant: Option<Complex<f64>>,
-) -> Option<usize> {
-    assert!(limit > 0);
-    assert!(escape_radius > 0.0);
-    
-    // Initial z value depends on the fractal type
-    let mut z = match fractal_type {
-        FractalType::Julia => c,                     // For Julia sets, z starts at the point coordinate
-        _ => Complex { re: 0.0, im: 0.0 },           // For others, start at origin
+/// A number loses precision when the shortest decimal that parses back to
+/// its `f64` has other significant digits than the number itself, so `0.1`,
+/// which reads back the same, passes while `0.74364388703715870475` does not.
+/// Expressions are left out, since their value is computed in `f64` anyway.
+pub fn rounded_parts(s: &str) -> Vec<(&str, f64)> {
+    s.split(',')
+        .filter_map(|part| {
+            let digits = significant_digits(part)?;
+            let value: f64 = part.trim().parse().ok()?;
+            let kept =
+                value.is_finite() && significant_digits(&format!("{:e}", value)) == Some(digits);
+            (!kept).then_some((part.trim(), value))
+        })
+        .collect()
+}
+
+/// Return the sign, the significant digits and the position of the decimal
+/// point relative to them of a plain decimal number, or `None` for anything
+/// else. Zero has no digits and no sign.
+fn significant_digits(s: &str) -> Option<(bool, String, i64)> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
     };
-    
-    for i in 0..limit {
-        if z.norm_sqr() > escape_radius.powi(2) {
-            return Some(i);
-        }
-        
-        // Apply the appropriate formula based on the fractal type
-        z = match fractal_type {
-            FractalType::Mandelbrot => z.powi(power) + c,
-            
-            FractalType::Julia => {
-                // Julia sets use a constant value k instead of c for the iteration
-                let k = julia_constant.unwrap_or(Complex { re: -0.8, im: 0.156 });
-                z.powi(power) + k
-            },
-            
-            FractalType::BurningShip => {
-                // Take absolute values of real and imaginary parts before squaring
-                let re_abs = z.re.abs();
-                let im_abs = z.im.abs();
-                Complex { re: re_abs, im: im_abs }.powi(2) + c
-            },
-            
-            FractalType::Tricorn => {
-                // Take the complex conjugate before applying the power
-                let z_conj = Complex { re: z.re, im: -z.im };
-                z_conj.powi(power) + c
-            },
-            
-            FractalType::Nova => {
-                // Nova fractal: z = z - (z^n - 1)/(n*z^(n-1)) + c
-                let p = power as f64;
-                let numerator = z.powi(power) - Complex::new(1.0, 0.0);
-                let denominator = p * z.powi(power - 1);
-                z - (numerator / denominator) + c
-            },
-            
-            FractalType::Sin => Complex::new(z.sin().re, z.sin().im) + c,
-            
-            FractalType::Cos => Complex::new(z.cos().re, z.cos().im) + c,
-        };
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (s, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let plain = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() && fraction.is_empty() || !plain(whole) || !plain(fraction) {
+        return None;
     }
-    
-    None
+    let digits = format!("{}{}", whole, fraction);
+    let leading = digits.len() - digits.trim_start_matches('0').len();
+    let digits = digits.trim_matches('0');
+    if digits.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    let point = whole.len() as i64 - leading as i64 + exponent;
+    Some((negative, digits.to_string(), point))
 }
 
-/// Render a rectangle of the fractal set into a buffer of pixels.
+#[test]
+fn test_rounded_parts() {
+    // Every digit survives the round trip
+    for s in [
+        "-0.75,0.1",
+        "1e-15,-2E3",
+        "0.000,+12.50",
+        "-1.4002,0.0",
+        "0.1,pi/4",
+    ] {
+        assert!(rounded_parts(s).is_empty(), "{:?}", s);
+    }
+    assert_eq!(
+        rounded_parts("-0.74364388703715870475,0.131825904205311970493"),
+        [
+            ("-0.74364388703715870475", -0.7436438870371587),
+            ("0.131825904205311970493", 0.13182590420531198),
+        ]
+    );
+    // Exactly representable numbers count only their shortest digits
+    assert!(rounded_parts("0.5000000000000000000000001,1").len() == 1);
+    assert!(rounded_parts("0.50000000000000000000000,1").is_empty());
+    // Past the range of f64, numbers overflow or flush to zero
+    assert_eq!(rounded_parts("1e400,1e-400").len(), 2);
+    assert_eq!(
+        significant_digits("-012.340e1"),
+        Some((true, "1234".to_string(), 3))
+    );
+    assert_eq!(significant_digits("."), None);
+}
+
+/// Parse a color given either as a single grayscale byte, like `"128"`, or as
+/// an `R,G,B` triple, like `"255,0,64"`.
 ///
-/// The `bounds` argument gives the width and height of the buffer `pixels`,
-/// which holds one grayscale pixel per byte. The `upper_left` and `lower_right`
-/// arguments specify points on the complex plane corresponding to the upper-
-/// left and lower-right corners of the pixel buffer.
-pub fn render(
-    pixels: &mut [u8],
-    bounds: (usize, usize),
-    upper_left: Complex<f64>,
-    lower_right: Complex<f64>,
+/// Since the output images are grayscale, an RGB triple is reduced to its
+/// luma using the Rec. 601 weights.
+pub fn parse_color(s: &str) -> Option<u8> {
+    let channels: Vec<u8> = s
+        .split(',')
+        .map(|part| u8::from_str(part.trim()).ok())
+        .collect::<Option<_>>()?;
+    match channels[..] {
+        [gray] => Some(gray),
+        [r, g, b] => Some((0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_parse_color() {
+    assert_eq!(parse_color("0"), Some(0));
+    assert_eq!(parse_color("200"), Some(200));
+    assert_eq!(parse_color("255,255,255"), Some(255));
+    assert_eq!(parse_color("255,0,0"), Some(76));
+    assert_eq!(parse_color("256"), None);
+    assert_eq!(parse_color("1,2"), None);
+    assert_eq!(parse_color(""), None);
+}
+
+/// Return the value `z` starts from when iterating the point `c`, unless
+/// [`FractalParams::initial_z`] gives another.
+pub fn initial_z(c: Complex<f64>, fractal_type: FractalType) -> Complex<f64> {
+    match fractal_type {
+        // For Julia sets, phoenix and Collatz fractals, z starts at the point coordinate
+        FractalType::Julia | FractalType::Phoenix | FractalType::Collatz => c,
+        // Nova starts at the root 1 of z^n - 1, since the Newton step divides by z^(n-1)
+        FractalType::Nova => Complex { re: 1.0, im: 0.0 },
+        // For others, start at origin
+        _ => Complex { re: 0.0, im: 0.0 },
+    }
+}
+
+/// Constant k of Julia sets when none is given
+pub const DEFAULT_JULIA_CONSTANT: Complex<f64> = Complex {
+    re: -0.8,
+    im: 0.156,
+};
+
+/// Constant p of the phoenix fractal when none is given, from Ushiki's
+/// original flame-shaped example
+pub const DEFAULT_PHOENIX_CONSTANT: Complex<f64> = Complex {
+    re: 0.56667,
+    im: -0.5,
+};
+
+/// Apply one iteration of the fractal formula to `z` for the point `c`.
+///
+/// `previous` is the iterate before `z`, which only the phoenix fractal
+/// depends on; it is zero at the start of an orbit.
+pub fn step(
+    z: Complex<f64>,
+    previous: Complex<f64>,
+    c: Complex<f64>,
+    power: i32,
+    fractal_type: FractalType,
+    julia_constant: Option<Complex<f64>>,
+) -> Complex<f64> {
+    match fractal_type {
+        FractalType::Mandelbrot | FractalType::Multibrot => z.powi(power) + c,
+
+        FractalType::Julia => {
+            // Julia sets use a constant value k instead of c for the iteration
+            let k = julia_constant.unwrap_or(DEFAULT_JULIA_CONSTANT);
+            z.powi(power) + k
+        }
+
+        FractalType::BurningShip => {
+            // Take absolute values of real and imaginary parts before squaring
+            let re_abs = z.re.abs();
+            let im_abs = z.im.abs();
+            Complex {
+                re: re_abs,
+                im: im_abs,
+            }
+            .powi(2)
+                + c
+        }
+
+        FractalType::Tricorn => {
+            // Take the complex conjugate before applying the power
+            let z_conj = Complex {
+                re: z.re,
+                im: -z.im,
+            };
+            z_conj.powi(power) + c
+        }
+
+        FractalType::Nova => {
+            // Nova fractal: z = z - (z^n - 1)/(n*z^(n-1)) + c
+            let p = power as f64;
+            let numerator = z.powi(power) - Complex::new(1.0, 0.0);
+            let denominator = p * z.powi(power - 1);
+            z - (numerator / denominator) + c
+        }
+
+        FractalType::Sin => Complex::new(z.sin().re, z.sin().im) + c,
+
+        FractalType::Cos => Complex::new(z.cos().re, z.cos().im) + c,
+
+        FractalType::Phoenix => {
+            // The real part of p shifts z, the imaginary part weighs the memory
+            let p = julia_constant.unwrap_or(DEFAULT_PHOENIX_CONSTANT);
+            z.powi(power) + p.re + p.im * previous
+        }
+
+        FractalType::Collatz => {
+            // cos(pi z) is 1 on even integers and -1 on odd ones
+            let cos = (std::f64::consts::PI * z).cos();
+            (2.0 + 7.0 * z - (2.0 + 5.0 * z) * cos) / 4.0
+        }
+
+        FractalType::Magnet1 => {
+            let one = Complex { re: 1.0, im: 0.0 };
+            ((z * z + c - one) / (2.0 * z + c - 2.0 * one)).powi(2)
+        }
+
+        FractalType::Magnet2 => {
+            let one = Complex { re: 1.0, im: 0.0 };
+            let (a, b) = (c - one, c - 2.0 * one);
+            let numerator = z.powi(3) + 3.0 * a * z + a * b;
+            let denominator = 3.0 * z * z + 3.0 * b * z + a * b + one;
+            (numerator / denominator).powi(2)
+        }
+
+        FractalType::Lyapunov => {
+            unreachable!("Lyapunov fractals are not an iteration on the complex plane")
+        }
+    }
+}
+
+/// Distance from the fixed point 1 within which a magnet orbit counts as
+/// converged, and the step within which a Nova orbit does
+pub const CONVERGENCE_RADIUS: f64 = 1e-6;
+
+/// Return whether an orbit at `z`, whose previous value was `previous`, has
+/// settled on a fixed point that stops the iteration like escaping does: the
+/// point 1 of the magnet fractals, or any root Nova's Newton step stalls on.
+/// Every other formula lacks one.
+pub fn has_converged(z: Complex<f64>, previous: Complex<f64>, fractal_type: FractalType) -> bool {
+    let radius_sqr = CONVERGENCE_RADIUS * CONVERGENCE_RADIUS;
+    match fractal_type {
+        FractalType::Magnet1 | FractalType::Magnet2 => {
+            (z - Complex { re: 1.0, im: 0.0 }).norm_sqr() < radius_sqr
+        }
+        FractalType::Nova => (z - previous).norm_sqr() < radius_sqr,
+        _ => false,
+    }
+}
+
+/// What iterating a single point revealed about it, for coloring modes that
+/// need more than the escape count
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EscapeResult {
+    /// Iterations taken to exceed the escape radius or to converge, or
+    /// `None` for members
+    pub iterations: Option<usize>,
+    /// Whether the orbit stopped by converging to a fixed point, see
+    /// [`has_converged`], rather than by escaping
+    pub converged: bool,
+    /// The value of `z` when the iteration stopped
+    pub final_z: Complex<f64>,
+    /// The derivative of the final `z`, with respect to `c` for parameter-plane
+    /// fractals and to the starting point for Julia sets. Zero for formulas
+    /// that have no complex derivative (Burning Ship, Tricorn, Nova), for the
+    /// phoenix fractal, whose orbit depends on two earlier iterates, and for
+    /// the Collatz fractal
+    pub final_dz: Complex<f64>,
+    /// The smallest distance between the origin and any `z` after the start
+    /// of the orbit, for orbit-trap coloring
+    pub min_trap_distance: f64,
+    /// The mean of `(sin(density * arg(z)) + 1) / 2` over the orbit after the
+    /// start, between 0 and 1, when a stripe density was given. It is blended
+    /// between the means with and without the last `z` by the fractional part
+    /// of the smooth escape time, so it changes continuously across the
+    /// bands of equal escape count
+    pub stripe_average: f64,
+    /// The mean of `|z|` over the orbit after the start for members, for
+    /// interior coloring; zero for escaping and converged orbits
+    pub mean_magnitude: f64,
+    /// Iterations run past the escape radius until `|z|` exceeded
+    /// [`SMOOTHING_RADIUS`], for smooth coloring
+    pub smoothing_steps: usize,
+    /// `ln|z| / ln(escape radius)` for the `z` reached after the smoothing
+    /// steps, zero for members, and one for converged orbits, whose smooth
+    /// escape time is their count
+    pub bailout_ratio: f64,
+}
+
+/// Radius that orbits are followed out to past the escape radius before
+/// `|z|` is measured for smooth coloring. Out there the added constant no
+/// longer changes how fast `|z|` grows, so the smooth escape time doesn't
+/// jump where the escape count does, even with a small escape radius.
+pub const SMOOTHING_RADIUS: f64 = 1e6;
+
+/// Most iterations run past the escape radius for smooth coloring, for
+/// orbits that stop growing
+const MAX_SMOOTHING_STEPS: usize = 64;
+
+impl EscapeResult {
+    /// Return the potential of an orbit that escaped after `count`
+    /// iterations, `ln|z| / power^count` for its final `z`, when `|z|` grows
+    /// like `|z|^power` far from the origin.
+    ///
+    /// This estimates the Green's function of the set, which falls
+    /// continuously to zero towards the set and whose level curves are the
+    /// equipotentials of a charged set. The estimate jumps a little where
+    /// the escape count does, less the larger the escape radius. Deep counts
+    /// underflow to zero.
+    pub fn potential(&self, count: usize, power: f64) -> f64 {
+        self.final_z.norm().ln() / power.max(2.0).powf(count as f64)
+    }
+
+    /// Return the continuous escape time of an orbit that escaped after
+    /// `count` iterations, when `|z|` grows like `|z|^power` far from the
+    /// origin: `count + smoothing_steps - log_power(bailout_ratio)`.
+    ///
+    /// It is close to the range from `count - 1` to `count`, and falls back
+    /// to `count` for orbits that overflowed on the way out.
+    pub fn smooth_count(&self, count: usize, power: f64) -> f64 {
+        let smooth =
+            (count + self.smoothing_steps) as f64 - self.bailout_ratio.ln() / power.max(2.0).ln();
+        if smooth.is_finite() {
+            smooth.max(0.0)
+        } else {
+            count as f64
+        }
+    }
+}
+
+#[test]
+fn test_smooth_count() {
+    // Along the real axis outside the set the escape count drops in steps,
+    // but the smooth escape time falls continuously through each of them
+    let params = FractalParams::default();
+    let smooth: Vec<(usize, f64)> = (0..=3000)
+        .map(|i| {
+            let c = Complex {
+                re: 0.5 + i as f64 / 2000.0,
+                im: 0.1,
+            };
+            let result = escape_orbit(c, &params, None);
+            let count = result.iterations.unwrap();
+            (count, result.smooth_count(count, 2.0))
+        })
+        .collect();
+    let mut jumps = 0;
+    for pair in smooth.windows(2) {
+        let ((count, a), (next_count, b)) = (pair[0], pair[1]);
+        jumps += (count != next_count) as usize;
+        assert!((a - b).abs() < 0.02, "{} then {}", a, b);
+        assert!(a >= b);
+    }
+    assert!(jumps >= 2, "only {} jumps in the escape count", jumps);
+
+    // Orbits that overflow keep their whole count
+    let overflowed = EscapeResult {
+        bailout_ratio: f64::INFINITY,
+        ..EscapeResult::default()
+    };
+    assert_eq!(overflowed.smooth_count(7, 2.0), 7.0);
+}
+
+#[test]
+fn test_potential() {
+    let params = FractalParams::default();
+    // c = 3 escapes at once with z = 3
+    let result = escape_orbit(Complex { re: 3.0, im: 0.0 }, &params, None);
+    assert_eq!(result.iterations, Some(1));
+    assert_eq!(result.potential(1, 2.0), 3f64.ln() / 2.0);
+
+    // The potential falls towards the set along the real axis
+    let potentials: Vec<f64> = (0..50)
+        .map(|i| {
+            let c = Complex {
+                re: 0.26 + i as f64 / 20.0,
+                im: 0.0,
+            };
+            let result = escape_orbit(c, &params, None);
+            result.potential(result.iterations.unwrap(), 2.0)
+        })
+        .collect();
+    assert!(potentials[0] < 1e-6);
+    assert!(potentials.windows(10).all(|window| window[0] < window[9]));
+
+    // A large escape radius brings the estimate close to the true potential,
+    // which rises smoothly across the escape counts
+    let params = FractalParams {
+        escape_radius: 1e10,
+        ..params
+    };
+    let potentials: Vec<f64> = (0..50)
+        .map(|i| {
+            let c = Complex {
+                re: 0.26 + i as f64 / 20.0,
+                im: 0.0,
+            };
+            let result = escape_orbit(c, &params, None);
+            result.potential(result.iterations.unwrap(), 2.0)
+        })
+        .collect();
+    assert!(potentials.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // Shading takes -log2(G) = 1 - log2(ln 3) across the limit of 100
+    let colors = ColorOptions {
+        potential: Some(2.0),
+        ..ColorOptions::default()
+    };
+    assert!(colors.needs_orbit());
+    assert_eq!(colors.shade_result::<u8>(&result, 100), 253);
+    assert_eq!(colors.shade_result::<u8>(&EscapeResult::default(), 100), 0);
+}
+
+/// Raise `z` to the complex power `p` on the principal branch of the
+/// logarithm, as `exp(p * ln(z))` with the argument of `z` taken in `(-pi, pi]`.
+///
+/// Unless `p` is a whole number the result jumps across the branch cut along
+/// the negative real axis, which shows as a seam in the fractal. Zero raised
+/// to a power with a positive real part is zero; to any other power it is
+/// infinite, so an orbit that lands on the origin escapes.
+pub fn complex_pow(z: Complex<f64>, p: Complex<f64>) -> Complex<f64> {
+    if z.re == 0.0 && z.im == 0.0 {
+        let value = if p.re > 0.0 { 0.0 } else { f64::INFINITY };
+        return Complex { re: value, im: 0.0 };
+    }
+    (p * z.ln()).exp()
+}
+
+#[test]
+fn test_complex_pow() {
+    let z = Complex { re: 0.6, im: -1.3 };
+    let close = |a: Complex<f64>, b: Complex<f64>| (a - b).norm() < 1e-12;
+    // Real whole powers agree with powi
+    assert!(close(
+        complex_pow(z, Complex { re: 3.0, im: 0.0 }),
+        z.powi(3)
+    ));
+    // i^i is real on the principal branch
+    let i = Complex { re: 0.0, im: 1.0 };
+    let expected = (-std::f64::consts::FRAC_PI_2).exp();
+    assert!(close(
+        complex_pow(i, i),
+        Complex {
+            re: expected,
+            im: 0.0
+        }
+    ));
+    // The square root of -1 is +i, with the argument of -1 taken as +pi
+    let minus_one = Complex { re: -1.0, im: 0.0 };
+    assert!(close(
+        complex_pow(minus_one, Complex { re: 0.5, im: 0.0 }),
+        i
+    ));
+    // The origin stays put for powers with a positive real part
+    let origin = Complex { re: 0.0, im: 0.0 };
+    assert_eq!(complex_pow(origin, Complex { re: 2.0, im: 1.0 }), origin);
+    assert!(
+        complex_pow(origin, Complex { re: -1.0, im: 1.0 })
+            .re
+            .is_infinite()
+    );
+
+    // A real exponent of 2 iterates the ordinary Mandelbrot set
+    let params = FractalParams {
+        complex_power: Some(Complex { re: 2.0, im: 0.0 }),
+        ..FractalParams::default()
+    };
+    for c in [
+        Complex { re: 1.0, im: 0.0 },
+        Complex { re: -0.75, im: 0.2 },
+        Complex { re: -0.1, im: 0.1 },
+    ] {
+        assert_eq!(
+            params.escape_time(c),
+            FractalParams::default().escape_time(c)
+        );
+    }
+    // An imaginary part twists the set, moving its escape counts
+    let twisted = FractalParams {
+        complex_power: Some(Complex { re: 2.0, im: 0.5 }),
+        ..params
+    };
+    let c = Complex { re: -0.75, im: 0.2 };
+    assert_ne!(twisted.escape_time(c), params.escape_time(c));
+}
+
+/// Apply one iteration of the derivative `dz` of `z` alongside [`step`].
+pub fn derivative_step(
+    z: Complex<f64>,
+    dz: Complex<f64>,
+    power: i32,
+    fractal_type: FractalType,
+) -> Complex<f64> {
+    let one = Complex { re: 1.0, im: 0.0 };
+    match fractal_type {
+        FractalType::Mandelbrot | FractalType::Multibrot => {
+            power as f64 * z.powi(power - 1) * dz + one
+        }
+        FractalType::Julia => power as f64 * z.powi(power - 1) * dz,
+        FractalType::Sin => z.cos() * dz + one,
+        FractalType::Cos => -z.sin() * dz + one,
+        _ => Complex { re: 0.0, im: 0.0 },
+    }
+}
+
+/// Return whether an orbit at `z` is known to diverge, because the part of
+/// `z` that `condition` measures is beyond the escape radius.
+///
+/// An orbit that is no longer finite has escaped too. Transcendental steps
+/// can overflow to infinity long before passing a large radius, Nova divides
+/// by zero when `z` lands on the origin, and the NaN that follows compares
+/// false with any radius, which would otherwise make the point a member.
+pub fn has_escaped(z: Complex<f64>, escape_radius: f64, condition: EscapeCondition) -> bool {
+    if !z.re.is_finite() || !z.im.is_finite() {
+        return true;
+    }
+    match condition {
+        EscapeCondition::Norm => z.norm_sqr() > escape_radius * escape_radius,
+        EscapeCondition::Imag => z.im.abs() > escape_radius,
+        EscapeCondition::Real => z.re.abs() > escape_radius,
+    }
+}
+
+#[test]
+fn test_has_escaped() {
+    use EscapeCondition::{Imag, Norm, Real};
+    let far_along_real = Complex { re: 100.0, im: 1.0 };
+    assert!(has_escaped(far_along_real, 2.0, Norm));
+    assert!(!has_escaped(far_along_real, 50.0, Imag));
+    assert!(has_escaped(far_along_real, 50.0, Real));
+    assert!(has_escaped(Complex { re: 0.0, im: -51.0 }, 50.0, Imag));
+    assert!(!has_escaped(Complex { re: 0.0, im: -51.0 }, 50.0, Real));
+    // Orbits that overflowed have escaped, even though NaN compares false
+    let overflowed = Complex {
+        re: f64::NAN,
+        im: f64::INFINITY,
+    };
+    assert!(has_escaped(overflowed, 1000.0, Norm));
+    assert!(has_escaped(overflowed, 50.0, Imag));
+    let nan = Complex {
+        re: f64::NAN,
+        im: 0.0,
+    };
+    assert!(has_escaped(nan, f64::MAX, Norm));
+    assert!(!has_escaped(far_along_real, 1000.0, Norm));
+}
+
+#[test]
+fn test_escape_time_non_finite() {
+    // Nova's first step from z = 1 lands on the origin for c = -1, and the
+    // next divides by zero
+    let c = Complex { re: -1.0, im: 0.0 };
+    assert_eq!(
+        escape_time(c, 255, 2, 2.0, FractalType::Nova, None),
+        Some(2)
+    );
+    // With the largest radius, z^3 + 2 overflows to infinity on the eighth
+    // step before |z| ever exceeds it
+    let c = Complex { re: 2.0, im: 0.0 };
+    assert_eq!(
+        escape_time(c, 255, 3, f64::MAX, FractalType::Mandelbrot, None),
+        Some(8)
+    );
+    // The quadratic fast path guards against the same overflow
+    assert_eq!(
+        escape_time(c, 255, 2, f64::MAX, FractalType::Mandelbrot, None),
+        Some(10)
+    );
+}
+
+#[test]
+fn test_sin_default_escape_radius() {
+    // sin(z) + c for c = 2.5 stays on the real axis between 1.9 and 3.1,
+    // so it never diverges even though it leaves the disk of radius 2
+    let c = Complex { re: 2.5, im: 0.0 };
+    let radius = FractalType::Sin.default_escape_radius();
+    assert_eq!(escape_time(c, 255, 2, radius, FractalType::Sin, None), None);
+    // Far up the imaginary axis the orbit escapes after the first step
+    let c = Complex { re: 0.0, im: 60.0 };
+    assert_eq!(
+        escape_time(c, 255, 2, radius, FractalType::Sin, None),
+        Some(1)
+    );
+}
+
+#[test]
+fn test_escape_condition() {
+    let sin = |escape_condition| FractalParams {
+        fractal_type: FractalType::Sin,
+        escape_radius: FractalType::Sin.default_escape_radius(),
+        escape_condition,
+        ..FractalParams::default()
+    };
+    // sin(z) + 60 stays on the real axis between 59 and 61, so the norm test
+    // stops an orbit that the imaginary one rightly keeps
+    let c = Complex { re: 60.0, im: 0.0 };
+    assert_eq!(sin(None).escape_condition(), EscapeCondition::Imag);
+    assert_eq!(sin(None).escape_time(c), None);
+    assert_eq!(sin(Some(EscapeCondition::Imag)).escape_time(c), None);
+    assert_eq!(sin(Some(EscapeCondition::Norm)).escape_time(c), Some(1));
+    assert_eq!(sin(Some(EscapeCondition::Real)).escape_time(c), Some(1));
+    // Up the imaginary axis the orbit escapes at once under the imaginary
+    // test, and only once it overflows under the real one
+    let c = Complex { re: 0.0, im: 60.0 };
+    assert_eq!(sin(None).escape_time(c), Some(1));
+    assert!(sin(Some(EscapeCondition::Real)).escape_time(c) > Some(1));
+
+    // Choosing the norm for the Mandelbrot set changes nothing
+    let mandelbrot = FractalParams {
+        escape_condition: Some(EscapeCondition::Norm),
+        ..FractalParams::default()
+    };
+    let c = Complex { re: -0.75, im: 0.1 };
+    assert_eq!(
+        mandelbrot.escape_time(c),
+        FractalParams::default().escape_time(c)
+    );
+}
+
+#[test]
+fn test_initial_z() {
+    let seeded = |fractal_type, initial_z| FractalParams {
+        fractal_type,
+        initial_z,
+        ..FractalParams::default()
+    };
+    // Starting from the value each formula starts from anyway changes nothing
+    for (fractal_type, start) in [
+        (FractalType::Mandelbrot, Complex::new(0.0, 0.0)),
+        (FractalType::BurningShip, Complex::new(0.0, 0.0)),
+        (FractalType::Nova, Complex::new(1.0, 0.0)),
+    ] {
+        for c in [Complex::new(-0.75, 0.1), Complex::new(0.3, 0.5)] {
+            assert_eq!(
+                seeded(fractal_type, Some(start)).escape_time(c),
+                seeded(fractal_type, None).escape_time(c),
+                "{:?}",
+                fractal_type
+            );
+        }
+    }
+    // At c = 0 the orbit of 1 stays put, and one outside the radius escapes at once
+    let mandelbrot = |start| seeded(FractalType::Mandelbrot, Some(start));
+    let origin = Complex::new(0.0, 0.0);
+    assert_eq!(mandelbrot(Complex::new(1.0, 0.0)).escape_time(origin), None);
+    assert_eq!(
+        mandelbrot(Complex::new(2.5, 0.0)).escape_time(origin),
+        Some(0)
+    );
+    // The cardioid shortcut no longer applies: -0.1 is a member from the
+    // origin, but from 1.9 the first step lands at 3.51
+    let c = Complex::new(-0.1, 0.0);
+    assert_eq!(mandelbrot(Complex::new(1.9, 0.0)).escape_time(c), Some(1));
+}
+
+/// Iterate the point `c` like [`escape_time`], also reporting the final `z`,
+/// its derivative and how close the orbit came to the origin.
+pub fn escape_details(
+    c: Complex<f64>,
+    limit: usize,
+    power: i32,
+    escape_radius: f64,
+    fractal_type: FractalType,
+    julia_constant: Option<Complex<f64>>,
+) -> EscapeResult {
+    let params = FractalParams {
+        fractal_type,
+        power,
+        escape_radius,
+        julia_constant,
+        max_iterations: limit,
+        ..FractalParams::default()
+    };
+    escape_orbit(c, &params, None)
+}
+
+/// Iterate the point `c` under `params` like [`escape_details`], applying
+/// any conjugation or morph and accumulating the stripe average of the orbit
+/// for `stripe_density` if it is given.
+///
+/// Conjugating `z` makes the formula anti-holomorphic, so the reported
+/// derivative is left at zero, as it is for complex powers and morphs. A
+/// morph starts `z` from the same blend of the two types' starting values as
+/// its steps, and stops converging orbits by the test of the type weighted
+/// more. A given [`FractalParams::initial_z`] replaces the starting value of
+/// either.
+pub fn escape_orbit(
+    c: Complex<f64>,
+    params: &FractalParams,
+    stripe_density: Option<f64>,
+) -> EscapeResult {
+    let FractalParams {
+        fractal_type,
+        power,
+        escape_radius,
+        max_iterations: limit,
+        ..
+    } = *params;
+    assert!(limit > 0);
+    assert!(escape_radius > 0.0);
+    let condition = params.escape_condition();
+
+    let mut z = match (params.initial_z, params.morph) {
+        (Some(start), _) => start,
+        (None, Some(Morph { to, t })) => {
+            (1.0 - t) * initial_z(c, fractal_type) + t * initial_z(c, to)
+        }
+        (None, None) => initial_z(c, fractal_type),
+    };
+    let converging_type = match params.morph {
+        Some(Morph { to, t }) if t > 0.5 => to,
+        _ => fractal_type,
+    };
+    // Julia sets differentiate with respect to the starting point
+    let mut dz = match fractal_type {
+        FractalType::Julia => Complex { re: 1.0, im: 0.0 },
+        _ => Complex { re: 0.0, im: 0.0 },
+    };
+    let mut min_trap_distance = f64::INFINITY;
+    let mut magnitude_sum = 0.0;
+    let mut previous = Complex { re: 0.0, im: 0.0 };
+    // The sum of the stripe terms so far, and the latest one
+    let (mut stripe_sum, mut stripe_last) = (0.0, 0.0);
+    let conjugate_z = matches!(params.conjugate, Some(Conjugate::Z | Conjugate::Both));
+    // The value added at each step, after any conjugation
+    let (added, julia_constant) = match params.conjugate {
+        Some(Conjugate::C | Conjugate::Both) => (
+            c.conj(),
+            Some(params.julia_constant.unwrap_or(match fractal_type {
+                FractalType::Phoenix => DEFAULT_PHOENIX_CONSTANT,
+                _ => DEFAULT_JULIA_CONSTANT,
+            }))
+            .map(|k| k.conj()),
+        ),
+        _ => (c, params.julia_constant),
+    };
+
+    // Apply the step of one formula to z, leaving out the derivative
+    let formula = |current: Complex<f64>, previous: Complex<f64>, fractal_type| {
+        match (params.complex_power, fractal_type) {
+            (Some(p), FractalType::Mandelbrot | FractalType::Multibrot) => {
+                return complex_pow(current, p) + added;
+            }
+            (Some(p), FractalType::Julia) => {
+                let k = julia_constant.unwrap_or(DEFAULT_JULIA_CONSTANT);
+                return complex_pow(current, p) + k;
+            }
+            _ => {}
+        }
+        step(
+            current,
+            previous,
+            added,
+            power,
+            fractal_type,
+            julia_constant,
+        )
+    };
+    let advance = |z: Complex<f64>, previous: Complex<f64>| {
+        let current = if conjugate_z { z.conj() } else { z };
+        match params.morph {
+            Some(Morph { to, t }) => {
+                (1.0 - t) * formula(current, previous, fractal_type)
+                    + t * formula(current, previous, to)
+            }
+            None => formula(current, previous, fractal_type),
+        }
+    };
+
+    for i in 0..limit {
+        // A converged orbit never grows, so it has no smooth escape time
+        if has_converged(z, previous, converging_type) {
+            return EscapeResult {
+                iterations: Some(i),
+                converged: true,
+                final_z: z,
+                final_dz: dz,
+                min_trap_distance,
+                stripe_average: 0.0,
+                mean_magnitude: 0.0,
+                smoothing_steps: 0,
+                bailout_ratio: 1.0,
+            };
+        }
+        if has_escaped(z, escape_radius, condition) {
+            // Run on past the bailout to measure how fast z is escaping
+            let (mut beyond, mut before) = (z, previous);
+            let mut smoothing_steps = 0;
+            while beyond.norm_sqr() <= SMOOTHING_RADIUS * SMOOTHING_RADIUS
+                && smoothing_steps < MAX_SMOOTHING_STEPS
+            {
+                (beyond, before) = (advance(beyond, before), beyond);
+                smoothing_steps += 1;
+            }
+            let bailout_ratio = beyond.norm().ln() / escape_radius.ln();
+            let stripe_average = match stripe_density {
+                Some(_) if i > 0 => {
+                    let average = stripe_sum / i as f64;
+                    let before = if i > 1 {
+                        (stripe_sum - stripe_last) / (i - 1) as f64
+                    } else {
+                        average
+                    };
+                    // 1 when z only just escaped, 0 when it overshot by a full step
+                    let smooth = EscapeResult {
+                        smoothing_steps,
+                        bailout_ratio,
+                        ..EscapeResult::default()
+                    }
+                    .smooth_count(i, power as f64);
+                    let fraction = (1.0 + smooth - i as f64).clamp(0.0, 1.0);
+                    fraction * average + (1.0 - fraction) * before
+                }
+                _ => 0.0,
+            };
+            return EscapeResult {
+                iterations: Some(i),
+                converged: false,
+                final_z: z,
+                final_dz: dz,
+                min_trap_distance,
+                stripe_average,
+                mean_magnitude: 0.0,
+                smoothing_steps,
+                bailout_ratio,
+            };
+        }
+
+        // Apply the appropriate formula based on the fractal type
+        if !conjugate_z && params.complex_power.is_none() && params.morph.is_none() {
+            dz = derivative_step(z, dz, power, fractal_type);
+        }
+        (z, previous) = (advance(z, previous), z);
+        let magnitude = z.norm();
+        min_trap_distance = min_trap_distance.min(magnitude);
+        magnitude_sum += magnitude;
+        if let Some(density) = stripe_density {
+            stripe_last = 0.5 * (density * z.arg()).sin() + 0.5;
+            stripe_sum += stripe_last;
+        }
+    }
+
+    EscapeResult {
+        iterations: None,
+        converged: false,
+        final_z: z,
+        final_dz: dz,
+        min_trap_distance,
+        stripe_average: 0.0,
+        mean_magnitude: magnitude_sum / limit as f64,
+        smoothing_steps: 0,
+        bailout_ratio: 0.0,
+    }
+}
+
+#[test]
+fn test_escape_details() {
+    // c = 1 gives 0, 1, 2, 5
+    let c = Complex { re: 1.0, im: 0.0 };
+    let result = escape_details(c, 255, 2, 2.0, FractalType::Mandelbrot, None);
+    assert_eq!(result.iterations, Some(3));
+    assert_eq!(result.final_z, Complex { re: 5.0, im: 0.0 });
+    // dz = 2 z dz + 1 gives 0, 1, 3, 13
+    assert_eq!(result.final_dz, Complex { re: 13.0, im: 0.0 });
+    assert_eq!(result.min_trap_distance, 1.0);
+
+    // c = -1 cycles between 0 and -1, ending on 0 after an even count
+    let c = Complex { re: -1.0, im: 0.0 };
+    let result = escape_details(c, 10, 2, 2.0, FractalType::Mandelbrot, None);
+    assert_eq!(result.iterations, None);
+    assert_eq!(result.final_z, Complex { re: 0.0, im: 0.0 });
+    assert_eq!(result.min_trap_distance, 0.0);
+
+    // For Julia sets z^2 + k the derivative doubles z at every step
+    let k = Some(Complex { re: 0.0, im: 0.0 });
+    let c = Complex { re: 1.5, im: 0.0 };
+    let result = escape_details(c, 255, 2, 2.0, FractalType::Julia, k);
+    assert_eq!(result.iterations, Some(1));
+    assert_eq!(result.final_dz, Complex { re: 3.0, im: 0.0 });
+    assert_eq!(result.min_trap_distance, 2.25);
+
+    // c = 1 lands on the positive real axis, where every stripe term is 1/2
+    let c = Complex { re: 1.0, im: 0.0 };
+    let result = escape_orbit(c, &FractalParams::default(), Some(3.0));
+    assert_eq!(result.iterations, Some(3));
+    assert_eq!(result.stripe_average, 0.5);
+    // Without a density the average is left at zero
+    assert_eq!(
+        escape_details(c, 255, 2, 2.0, FractalType::Mandelbrot, None).stripe_average,
+        0.0
+    );
+
+    // escape_time is a thin wrapper around the full result
+    for re in [-2.0, -0.5, 0.2, 0.3, 1.0] {
+        let c = Complex { re, im: 0.4 };
+        assert_eq!(
+            escape_time(c, 100, 2, 2.0, FractalType::Tricorn, None),
+            escape_details(c, 100, 2, 2.0, FractalType::Tricorn, None).iterations
+        );
+    }
+}
+
+#[test]
+fn test_morph() {
+    let morph = |to, t| FractalParams {
+        morph: Some(Morph { to, t }),
+        ..FractalParams::default()
+    };
+    let ship = FractalParams {
+        fractal_type: FractalType::BurningShip,
+        ..FractalParams::default()
+    };
+    for re in [-1.9, -0.8, 0.1, 0.3] {
+        for im in [-0.9, -0.2, 0.4] {
+            let c = Complex { re, im };
+            // The ends of the morph are the two formulas
+            let start = morph(FractalType::BurningShip, 0.0).escape_time(c);
+            assert_eq!(start, FractalParams::default().escape_time(c), "{}", c);
+            let end = morph(FractalType::BurningShip, 1.0).escape_time(c);
+            assert_eq!(end, ship.escape_time(c), "{}", c);
+        }
+        // On the real axis the Tricorn's step is the Mandelbrot set's, so
+        // any blend of the two is too
+        let c = Complex { re, im: 0.0 };
+        assert_eq!(
+            morph(FractalType::Tricorn, 0.5).escape_time(c),
+            FractalParams::default().escape_time(c)
+        );
+    }
+    // Julia sets start from the point itself, so halfway z starts from c / 2
+    let result = escape_orbit(
+        Complex { re: 6.0, im: 0.0 },
+        &morph(FractalType::Julia, 0.5),
+        None,
+    );
+    assert_eq!(result.iterations, Some(0));
+    assert_eq!(result.final_z, Complex { re: 3.0, im: 0.0 });
+}
+
+#[test]
+fn test_interior_coloring() {
+    // c = -1 cycles through -1, 0, -1, 0, ...
+    let c = Complex { re: -1.0, im: 0.0 };
+    let result = escape_details(c, 100, 2, 2.0, FractalType::Mandelbrot, None);
+    assert_eq!(result.iterations, None);
+    assert_eq!(result.mean_magnitude, 0.5);
+    assert_eq!(result.final_z.norm(), 0.0);
+    let escaped = escape_details(
+        Complex { re: 1.0, im: 0.0 },
+        100,
+        2,
+        2.0,
+        FractalType::Mandelbrot,
+        None,
+    );
+    assert_eq!(escaped.mean_magnitude, 0.0);
+
+    let colors = ColorOptions {
+        interior: 20,
+        interior_coloring: Some(InteriorColoring::Average),
+        ..ColorOptions::default()
+    };
+    // A quarter of the way from the interior color to white
+    assert_eq!(colors.shade_result::<u8>(&result, 100), 79);
+    assert_eq!(colors.shade_result::<u16>(&result, 100), 20239);
+    let colors = ColorOptions {
+        interior_coloring: Some(InteriorColoring::Final),
+        ..colors
+    };
+    assert_eq!(colors.shade_result::<u8>(&result, 100), 20);
+    // Escaping points are shaded as before
+    assert_eq!(
+        colors.shade_result::<u8>(&escaped, 100),
+        ColorOptions::default().shade_result::<u8>(&escaped, 100)
+    );
+    // Transparent frames keep the inside of the set transparent
+    let pixel = colors.shade_result::<GrayAlpha>(&result, 100);
+    assert_eq!((pixel.gray, pixel.alpha), (20, 0));
+}
+
+#[test]
+fn test_escape_time_phoenix() {
+    // With p = 0.56667 - 0.5i, z = 1 runs 1, 1.56667, 2.52 and escapes
+    let c = Complex { re: 1.0, im: 0.0 };
+    let p = Some(DEFAULT_PHOENIX_CONSTANT);
+    assert_eq!(
+        escape_time(c, 255, 2, 2.0, FractalType::Phoenix, p),
+        Some(2)
+    );
+    assert_eq!(
+        escape_time(c, 255, 2, 2.0, FractalType::Phoenix, None),
+        Some(2)
+    );
+
+    // Without the memory term the phoenix is the Julia set of Re(p)
+    let p = Complex { re: -0.75, im: 0.0 };
+    for re in [-1.5, -0.9, -0.2, 0.0, 0.4, 1.1] {
+        let c = Complex { re, im: 0.3 };
+        assert_eq!(
+            escape_time(c, 100, 2, 2.0, FractalType::Phoenix, Some(p)),
+            escape_time(c, 100, 2, 2.0, FractalType::Julia, Some(p))
+        );
+    }
+}
+
+#[test]
+fn test_escape_time_collatz() {
+    // On the integers the map is the Collatz map: 2 -> 1 -> 4 -> 2 forever
+    let step_collatz = |z| step(z, z, z, 2, FractalType::Collatz, None);
+    let two = Complex { re: 2.0, im: 0.0 };
+    let one = step_collatz(two);
+    assert!((one - Complex { re: 1.0, im: 0.0 }).norm() < 1e-12);
+    assert!((step_collatz(one) - Complex { re: 4.0, im: 0.0 }).norm() < 1e-12);
+    let radius = FractalType::Collatz.default_escape_radius();
+    assert_eq!(
+        escape_time(two, 255, 2, radius, FractalType::Collatz, None),
+        None
+    );
+    // Off the real axis the cosine overflows quickly and the point escapes
+    let c = Complex { re: 0.5, im: 3.0 };
+    assert!(escape_time(c, 255, 2, radius, FractalType::Collatz, None).is_some());
+}
+
+#[test]
+fn test_escape_time_magnet() {
+    let radius = FractalType::Magnet1.default_escape_radius();
+    let params = |fractal_type| FractalParams {
+        fractal_type,
+        escape_radius: radius,
+        ..FractalParams::default()
+    };
+    // 1 is a fixed point of both formulas for every c
+    let one = Complex { re: 1.0, im: 0.0 };
+    let c = Complex { re: 0.3, im: -0.8 };
+    for fractal_type in [FractalType::Magnet1, FractalType::Magnet2] {
+        assert!((step(one, one, c, 2, fractal_type, None) - one).norm() < 1e-12);
+    }
+
+    // For c = 1.5 the first step of type 1 lands on 1 exactly
+    let result = escape_orbit(
+        Complex { re: 1.5, im: 0.0 },
+        &params(FractalType::Magnet1),
+        None,
+    );
+    assert_eq!((result.iterations, result.converged), (Some(1), true));
+    assert_eq!(result.smooth_count(1, 2.0), 1.0);
+    // For c = 2.5 the orbit runs off instead
+    let result = escape_orbit(
+        Complex { re: 2.5, im: 0.0 },
+        &params(FractalType::Magnet1),
+        None,
+    );
+    assert_eq!((result.iterations, result.converged), (Some(4), false));
+    // For c = 3 it settles on the other fixed point, 4, and never stops
+    let c = Complex { re: 3.0, im: 0.0 };
+    assert_eq!(
+        escape_time(c, 255, 2, radius, FractalType::Magnet1, None),
+        None
+    );
+
+    let c = Complex { re: 4.0, im: 0.0 };
+    assert_eq!(
+        escape_time(c, 255, 2, radius, FractalType::Magnet2, None),
+        Some(3)
+    );
+    assert!(escape_orbit(c, &params(FractalType::Magnet2), None).converged);
+    // Other formulas never converge
+    assert!(!has_converged(one, one, FractalType::Mandelbrot));
+}
+
+#[test]
+fn test_conjugate() {
+    let tricorn = FractalParams {
+        fractal_type: FractalType::Tricorn,
+        ..FractalParams::default()
+    };
+    let conjugate = |conjugate| FractalParams {
+        conjugate: Some(conjugate),
+        ..FractalParams::default()
+    };
+    for (re, im) in [
+        (-1.2, 0.3),
+        (-0.2, 0.8),
+        (0.3, -0.5),
+        (0.4, 0.1),
+        (-0.1, -0.7),
+    ] {
+        let c = Complex { re, im };
+        // Conjugating z turns the Mandelbrot set into the Tricorn
+        assert_eq!(
+            conjugate(Conjugate::Z).escape_time(c),
+            tricorn.escape_time(c)
+        );
+        // Conjugating c mirrors it across the real axis
+        assert_eq!(
+            conjugate(Conjugate::C).escape_time(c),
+            FractalParams::default().escape_time(c.conj())
+        );
+    }
+}
+
+/// Try to determine if a point is in the fractal set, using at most `limit`
+/// iterations to decide.
+///
+/// If the point is not a member, return `Some(i)`, where `i` is the number of
+/// iterations it took for the calculation to exceed the escape radius, or for
+/// a magnet orbit to converge to 1; [`EscapeResult::converged`] tells the two
+/// apart.
+/// If the point seems to be a member (more precisely, if we reached the
+/// iteration limit without being able to prove that it's not a member),
+/// return `None`.
+///
+/// Quadratic Mandelbrot points in the main cardioid or the period-2 bulb are
+/// returned as members without iterating, see [`in_main_cardioid_or_bulb`],
+/// as long as the escape radius is at least 2, past which no member's orbit
+/// ever goes.
+pub fn escape_time(
+    c: Complex<f64>,
+    limit: usize,
+    power: i32,
+    escape_radius: f64,
+    fractal_type: FractalType,
+    julia_constant: Option<Complex<f64>>,
+) -> Option<usize> {
+    if power == 2 && matches!(fractal_type, FractalType::Mandelbrot | FractalType::Julia) {
+        if fractal_type == FractalType::Mandelbrot
+            && escape_radius >= 2.0
+            && in_main_cardioid_or_bulb(c)
+        {
+            return None;
+        }
+        return escape_time_quadratic(c, limit, escape_radius, fractal_type, julia_constant);
+    }
+    escape_details(c, limit, power, escape_radius, fractal_type, julia_constant).iterations
+}
+
+/// Return whether `c` lies in the main cardioid or the period-2 bulb of the
+/// Mandelbrot set, whose points are members without iterating them.
+///
+/// Both regions have closed forms: the cardioid is where
+/// `q (q + x - 1/4) <= y^2 / 4` with `q = (x - 1/4)^2 + y^2`, and the bulb is
+/// the disk of radius 1/4 about -1.
+pub fn in_main_cardioid_or_bulb(c: Complex<f64>) -> bool {
+    let x = c.re - 0.25;
+    let y2 = c.im * c.im;
+    let q = x * x + y2;
+    q * (q + x) <= 0.25 * y2 || (c.re + 1.0) * (c.re + 1.0) + y2 <= 0.0625
+}
+
+/// Return whether the point `c` seems to be a member of the fractal set,
+/// with the same arguments as [`escape_time`].
+///
+/// For the quadratic Mandelbrot set, points in the main cardioid or the
+/// period-2 bulb are accepted at once, like [`escape_time`] does; those
+/// regions cover much of a typical view and would otherwise each take all
+/// `limit` iterations. Other points are iterated.
+pub fn in_set(
+    c: Complex<f64>,
+    limit: usize,
     power: i32,
     escape_radius: f64,
     fractal_type: FractalType,
     julia_constant: Option<Complex<f64>>,
+) -> bool {
+    escape_time(c, limit, power, escape_radius, fractal_type, julia_constant).is_none()
+}
+
+#[test]
+fn test_in_set() {
+    let mandelbrot = FractalType::Mandelbrot;
+    // The cardioid's center and cusp side, and the bulb's center
+    for (re, im) in [
+        (0.0, 0.0),
+        (-0.5, 0.5),
+        (0.2, 0.0),
+        (-1.0, 0.0),
+        (-1.2, 0.1),
+    ] {
+        let c = Complex { re, im };
+        assert!(in_main_cardioid_or_bulb(c), "{}", c);
+        assert!(in_set(c, 10, 2, 2.0, mandelbrot, None));
+    }
+    // Members outside both regions, like the center of a period-3 bulb,
+    // still take iterating
+    let c = Complex {
+        re: -0.1226,
+        im: 0.7449,
+    };
+    assert!(!in_main_cardioid_or_bulb(c));
+    assert!(in_set(c, 1000, 2, 2.0, mandelbrot, None));
+    assert!(!in_set(
+        Complex { re: 0.3, im: 0.0 },
+        1000,
+        2,
+        2.0,
+        mandelbrot,
+        None
+    ));
+    // With a smaller escape radius, members' orbits can still leave it
+    let c = Complex { re: -0.5, im: 0.5 };
+    assert!(!in_set(c, 10, 2, 0.5, mandelbrot, None));
+
+    // Everywhere else the answer is the escape time's
+    for row in 0..30 {
+        for column in 0..40 {
+            let c = Complex {
+                re: -2.2 + column as f64 * 0.08,
+                im: -1.2 + row as f64 * 0.08,
+            };
+            for (power, fractal_type) in [(2, mandelbrot), (3, mandelbrot), (2, FractalType::Julia)]
+            {
+                for radius in [0.5, 2.0] {
+                    assert_eq!(
+                        in_set(c, 500, power, radius, fractal_type, None),
+                        escape_time(c, 500, power, radius, fractal_type, None).is_none(),
+                        "{:?} {} at {} with radius {}",
+                        fractal_type,
+                        power,
+                        c,
+                        radius
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Compute the escape time of `z^2 + c` for the Mandelbrot set, or of
+/// `z^2 + k` for Julia sets, on plain `f64` parts.
+///
+/// The squares of the real and imaginary parts serve both the escape test
+/// and the next step, so each iteration costs three multiplications. The
+/// results are identical to the generic `Complex` loop.
+fn escape_time_quadratic(
+    c: Complex<f64>,
+    limit: usize,
+    escape_radius: f64,
+    fractal_type: FractalType,
+    julia_constant: Option<Complex<f64>>,
+) -> Option<usize> {
+    assert!(limit > 0);
+    assert!(escape_radius > 0.0);
+    // Overflowed squares then count as escaped before the next step can
+    // turn them into NaN, even for radii whose square is infinite
+    let r2 = (escape_radius * escape_radius).min(f64::MAX);
+
+    let (mut re, mut im, add) = match fractal_type {
+        FractalType::Julia => {
+            let k = julia_constant.unwrap_or(DEFAULT_JULIA_CONSTANT);
+            (c.re, c.im, k)
+        }
+        _ => (0.0, 0.0, c),
+    };
+    let (mut re2, mut im2) = (re * re, im * im);
+
+    for i in 0..limit {
+        if re2 + im2 > r2 {
+            return Some(i);
+        }
+        // Doubling is exact, so this matches re * im + im * re bit for bit
+        im = 2.0 * re * im + add.im;
+        re = re2 - im2 + add.re;
+        re2 = re * re;
+        im2 = im * im;
+    }
+    None
+}
+
+#[test]
+fn test_escape_time_skips_cardioid_and_bulb() {
+    let mandelbrot = FractalType::Mandelbrot;
+    // Deep inside, a limit of one iteration is still enough
+    for (re, im) in [
+        (0.0, 0.0),
+        (-0.5, 0.5),
+        (0.24, 0.0),
+        (-1.0, 0.0),
+        (-1.2, 0.1),
+    ] {
+        let c = Complex { re, im };
+        assert_eq!(escape_time(c, 1, 2, 2.0, mandelbrot, None), None, "{}", c);
+    }
+
+    // Around the edges of both regions nothing changes
+    let edge = |t: f64| {
+        let cardioid = Complex::from_polar(0.5, t) * (1.0 - Complex::from_polar(0.5, t) * 0.5);
+        let bulb = Complex { re: -1.0, im: 0.0 } + Complex::from_polar(0.25, t);
+        [cardioid, bulb]
+    };
+    for i in 0..400 {
+        for point in edge(i as f64 * std::f64::consts::TAU / 400.0) {
+            for offset in [-1e-3, -1e-9, 0.0, 1e-9, 1e-3] {
+                let c = point * (1.0 + offset);
+                for radius in [0.5, 2.0, 10.0] {
+                    assert_eq!(
+                        escape_time(c, 300, 2, radius, mandelbrot, None),
+                        escape_time_quadratic(c, 300, radius, mandelbrot, None),
+                        "{} with radius {}",
+                        c,
+                        radius
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_escape_time_quadratic_matches_generic() {
+    let k = Complex { re: -0.4, im: 0.6 };
+    for (fractal_type, julia_constant) in [
+        (FractalType::Mandelbrot, None),
+        (FractalType::Julia, None),
+        (FractalType::Julia, Some(k)),
+    ] {
+        for radius in [2.0, 10.0] {
+            for row in 0..60 {
+                for column in 0..80 {
+                    let c = Complex {
+                        re: -2.2 + column as f64 * 0.04,
+                        im: -1.2 + row as f64 * 0.04,
+                    };
+                    assert_eq!(
+                        escape_time_quadratic(c, 300, radius, fractal_type, julia_constant),
+                        escape_details(c, 300, 2, radius, fractal_type, julia_constant).iterations,
+                        "{:?} at {}",
+                        fractal_type,
+                        c
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Compare the plain `f64` loop with the generic `Complex` loop.
+///
+/// Run with `cargo test --release -- --ignored --nocapture bench_escape_time_quadratic`.
+#[test]
+#[ignore]
+fn bench_escape_time_quadratic() {
+    use std::time::Instant;
+
+    let limit = 1000;
+    let points: Vec<Complex<f64>> = (0..512 * 512)
+        .map(|i| Complex {
+            re: -2.0 + (i % 512) as f64 * 3.0 / 512.0,
+            im: -1.5 + (i / 512) as f64 * 3.0 / 512.0,
+        })
+        .collect();
+    let mandelbrot = FractalType::Mandelbrot;
+
+    let start = Instant::now();
+    let expected: Vec<Option<usize>> = points
+        .iter()
+        .map(|&c| escape_details(c, limit, 2, 2.0, mandelbrot, None).iterations)
+        .collect();
+    let generic = start.elapsed();
+
+    let start = Instant::now();
+    let counts: Vec<Option<usize>> = points
+        .iter()
+        .map(|&c| escape_time_quadratic(c, limit, 2.0, mandelbrot, None))
+        .collect();
+    let quadratic = start.elapsed();
+
+    assert_eq!(counts, expected);
+    println!(
+        "generic: {:?}, quadratic: {:?}, speedup: {:.2}x",
+        generic,
+        quadratic,
+        generic.as_secs_f64() / quadratic.as_secs_f64()
+    );
+}
+
+#[cfg(test)]
+fn escape(re: f64, im: f64, power: i32, fractal_type: FractalType) -> Option<usize> {
+    escape_time(
+        Complex { re, im },
+        u8::MAX as usize,
+        power,
+        2.0,
+        fractal_type,
+        None,
+    )
+}
+
+#[test]
+fn test_escape_time_mandelbrot() {
+    use FractalType::Mandelbrot;
+    // The origin and the period-2 point c = -1 never escape
+    assert_eq!(escape(0.0, 0.0, 2, Mandelbrot), None);
+    assert_eq!(escape(-1.0, 0.0, 2, Mandelbrot), None);
+    // c = i is a preperiodic boundary point: 0, i, -1+i, -i, -1+i, ...
+    assert_eq!(escape(0.0, 1.0, 2, Mandelbrot), None);
+    // c = 1 gives 0, 1, 2, 5; |2| is not strictly outside the radius
+    assert_eq!(escape(1.0, 0.0, 2, Mandelbrot), Some(3));
+    // Points far away escape right after the first step
+    assert_eq!(escape(3.0, 0.0, 2, Mandelbrot), Some(1));
+    assert_eq!(escape(-10.0, 10.0, 2, Mandelbrot), Some(1));
+    // The cubic Multibrot keeps 0.3 bounded but lets 1 escape: 0, 1, 2, 9
+    assert_eq!(escape(0.3, 0.0, 3, Mandelbrot), None);
+    assert_eq!(escape(1.0, 0.0, 3, Mandelbrot), Some(3));
+}
+
+#[test]
+fn test_escape_time_julia() {
+    // With k = 0 the filled Julia set is the closed unit disk
+    let k = Some(Complex { re: 0.0, im: 0.0 });
+    let julia = |re, im| escape_time(Complex { re, im }, 255, 2, 2.0, FractalType::Julia, k);
+    assert_eq!(julia(0.5, 0.5), None);
+    assert_eq!(julia(0.0, -1.0), None);
+    // 1.5 -> 2.25 -> 5.06
+    assert_eq!(julia(1.5, 0.0), Some(1));
+    // The starting point itself is tested, so far points escape at once
+    assert_eq!(julia(3.0, 0.0), Some(0));
+
+    // Without an explicit constant, the default k = -0.8 + 0.156i is used
+    let k = Some(Complex {
+        re: -0.8,
+        im: 0.156,
+    });
+    let point = Complex { re: 0.1, im: 0.1 };
+    assert_eq!(
+        escape(0.1, 0.1, 2, FractalType::Julia),
+        escape_time(point, 255, 2, 2.0, FractalType::Julia, k)
+    );
+}
+
+#[test]
+fn test_escape_time_burning_ship() {
+    use FractalType::BurningShip;
+    assert_eq!(escape(0.0, 0.0, 2, BurningShip), None);
+    assert_eq!(escape(-1.0, 0.0, 2, BurningShip), None);
+    assert_eq!(escape(1.0, 0.0, 2, BurningShip), Some(3));
+    // The absolute values break the conjugate symmetry of the Mandelbrot set:
+    // c = -i gives 0, -i, -1-i, i, -1-i, ... while c = i gives 0, i, -1+i, 3i
+    assert_eq!(escape(0.0, -1.0, 2, BurningShip), None);
+    assert_eq!(escape(0.0, 1.0, 2, BurningShip), Some(3));
+}
+
+#[test]
+fn test_escape_time_tricorn() {
+    use FractalType::Tricorn;
+    assert_eq!(escape(0.0, 0.0, 2, Tricorn), None);
+    assert_eq!(escape(-1.0, 0.0, 2, Tricorn), None);
+    // Unlike the Mandelbrot set, c = i escapes: 0, i, -1+i, 3i
+    assert_eq!(escape(0.0, 1.0, 2, Tricorn), Some(3));
+    assert_eq!(escape(3.0, 0.0, 2, Tricorn), Some(1));
+}
+
+#[test]
+fn test_escape_time_nova() {
+    use FractalType::Nova;
+    // z starts on the root 1, so with c = 0 it has converged after one step
+    assert_eq!(escape(0.0, 0.0, 3, Nova), Some(1));
+    let params = FractalParams {
+        fractal_type: Nova,
+        power: 3,
+        ..FractalParams::default()
+    };
+    let result = escape_orbit(Complex { re: 0.0, im: 0.0 }, &params, None);
+    assert_eq!((result.iterations, result.converged), (Some(1), true));
+    // A large c throws z outside the radius on the first step: 1 -> 4
+    assert_eq!(escape(3.0, 0.0, 3, Nova), Some(1));
+    assert!(!escape_orbit(Complex { re: 3.0, im: 0.0 }, &params, None).converged);
+    // A small c moves the root, which the orbit still settles on
+    let result = escape_orbit(Complex { re: 0.1, im: 0.05 }, &params, None);
+    assert!(result.converged);
+    let next = step(
+        result.final_z,
+        result.final_z,
+        Complex { re: 0.1, im: 0.05 },
+        3,
+        Nova,
+        None,
+    );
+    assert!((next - result.final_z).norm() < 1e-5);
+}
+
+#[test]
+fn test_escape_time_transcendental() {
+    use FractalType::{Cos, Sin};
+    // sin(0) = 0 is a fixed point
+    assert_eq!(escape(0.0, 0.0, 2, Sin), None);
+    // Orbits along the real axis stay bounded, so they never escape
+    assert_eq!(escape(3.0, 0.0, 2, Sin), None);
+    // 0 -> sin(0) + 3i = 3i, whose imaginary part is past the radius
+    assert_eq!(escape(0.0, 3.0, 2, Sin), Some(1));
+    // cos iterates from 0 towards the Dottie number 0.739...
+    assert_eq!(escape(0.0, 0.0, 2, Cos), None);
+    // 0 -> cos(0) + 3i = 1 + 3i
+    assert_eq!(escape(0.0, 3.0, 2, Cos), Some(1));
+}
+
+/// Render a rectangle of the fractal set into a buffer of pixels.
+///
+/// The `bounds` argument gives the width and height of the buffer `pixels`,
+/// which holds one grayscale value per pixel. The `upper_left` and `lower_right`
+/// arguments specify points on the complex plane corresponding to the upper-
+/// left and lower-right corners of the pixel buffer. `params` selects the
+/// fractal formula and `colors` how escape counts become pixel values.
+pub fn render<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
 ) {
     assert!(pixels.len() == bounds.0 * bounds.1);
 
+    // Use the vectorized loop for the formulas it supports, which only
+    // tracks escape counts
+    #[cfg(feature = "simd")]
+    if simd::supports(params) && !colors.needs_orbit() {
+        simd::render(pixels, bounds, upper_left, lower_right, params, colors);
+        return;
+    }
+
+    if let FractalType::Lyapunov = params.fractal_type {
+        // The two axes are the A and B parameters of the logistic map
+        for row in 0..bounds.1 {
+            for column in 0..bounds.0 {
+                let point = params.sample_point(bounds, (column, row), upper_left, lower_right);
+                let exponent = lyapunov::exponent(
+                    point.re,
+                    point.im,
+                    params.lyapunov_sequence,
+                    params.max_iterations,
+                );
+                pixels[row * bounds.0 + column] = lyapunov::shade(exponent, colors);
+            }
+        }
+        return;
+    }
+
     for row in 0..bounds.1 {
         for column in 0..bounds.0 {
-            let point = transform::pixel_to_point(bounds, (column, row), upper_left, lower_right);
-            pixels[row * bounds.0 + column] =
-                match escape_time(point, u8::MAX as usize, power, escape_radius, fractal_type, julia_constant) {
-                    None => 0,
-                    Some(count) => u8::MAX - count as u8,
-                };
+            let point = params.sample_point(bounds, (column, row), upper_left, lower_right);
+            // Escape counts are enough unless the orbit picks the shade
+            let result = if !colors.needs_orbit() {
+                EscapeResult {
+                    iterations: params.escape_time(point),
+                    ..EscapeResult::default()
+                }
+            } else {
+                escape_orbit(point, params, colors.stripe_density)
+            };
+            pixels[row * bounds.0 + column] = colors.shade_result(&result, params.max_iterations);
+        }
+    }
+}
+
+/// Everything needed to render a single frame
+#[derive(Debug, Clone, Copy)]
+pub struct RenderConfig {
+    /// Width and height of the frame in pixels
+    pub bounds: (usize, usize),
+    /// The point on the complex plane at the upper-left corner
+    pub upper_left: Complex<f64>,
+    /// The point on the complex plane at the lower-right corner
+    pub lower_right: Complex<f64>,
+    pub params: FractalParams,
+    pub colors: ColorOptions,
+    /// Give points inside the set an alpha of zero
+    pub transparent_interior: bool,
+}
+
+/// Render one frame with [`render`] and return it as an image, without
+/// touching files or threads.
+///
+/// The image is 8-bit grayscale, or RGB with the palette in `config.colors`
+/// if there is one, with an alpha channel only if `config` asks for a
+/// transparent interior.
+///
+/// ```
+/// use mandelbrot::utils::{ColorOptions, FractalParams, RenderConfig, render_single_frame};
+/// use num::Complex;
+///
+/// let image = render_single_frame(&RenderConfig {
+///     bounds: (64, 48),
+///     upper_left: Complex::new(-2.0, 1.2),
+///     lower_right: Complex::new(1.0, -1.2),
+///     params: FractalParams::default(),
+///     colors: ColorOptions::default(),
+///     transparent_interior: false,
+/// });
+/// assert_eq!((image.width(), image.height()), (64, 48));
+/// assert_eq!(image.color(), image::ColorType::L8);
+/// ```
+pub fn render_single_frame(config: &RenderConfig) -> image::DynamicImage {
+    use image::{DynamicImage, ImageBuffer};
+    let (width, height) = config.bounds;
+    let mut pixels = vec![GrayAlpha::default(); width * height];
+    render(
+        &mut pixels,
+        config.bounds,
+        config.upper_left,
+        config.lower_right,
+        &config.params,
+        &config.colors,
+    );
+    let (width, height) = (width as u32, height as u32);
+    let at = |x: u32, y: u32| pixels[y as usize * config.bounds.0 + x as usize];
+    match (config.colors.palette, config.transparent_interior) {
+        (None, false) => DynamicImage::ImageLuma8(ImageBuffer::from_fn(width, height, |x, y| {
+            image::Luma([at(x, y).gray])
+        })),
+        (None, true) => DynamicImage::ImageLumaA8(ImageBuffer::from_fn(width, height, |x, y| {
+            let p = at(x, y);
+            image::LumaA([p.gray, p.alpha])
+        })),
+        (Some(palette), false) => {
+            DynamicImage::ImageRgb8(ImageBuffer::from_fn(width, height, |x, y| {
+                image::Rgb(palette.color(at(x, y).gray))
+            }))
+        }
+        (Some(palette), true) => {
+            DynamicImage::ImageRgba8(ImageBuffer::from_fn(width, height, |x, y| {
+                let p = at(x, y);
+                let [r, g, b] = palette.color(p.gray);
+                image::Rgba([r, g, b, p.alpha])
+            }))
         }
     }
 }
+
+#[test]
+fn test_render_single_frame() {
+    let mut config = RenderConfig {
+        bounds: (8, 6),
+        upper_left: Complex { re: -2.0, im: 1.5 },
+        lower_right: Complex { re: 1.0, im: -1.5 },
+        params: FractalParams::default(),
+        colors: ColorOptions::default(),
+        transparent_interior: false,
+    };
+    let gray = render_single_frame(&config).into_luma8();
+    let mut expected = vec![0u8; 8 * 6];
+    render(
+        &mut expected,
+        config.bounds,
+        config.upper_left,
+        config.lower_right,
+        &config.params,
+        &config.colors,
+    );
+    assert_eq!(gray.into_raw(), expected);
+
+    // A palette colors the levels, and the origin turns transparent
+    config.colors.palette = Some(palette::Palette::grayscale());
+    config.transparent_interior = true;
+    let image = render_single_frame(&config);
+    assert_eq!(image.color(), image::ColorType::Rgba8);
+    let rgba = image.into_rgba8();
+    assert!(rgba.pixels().any(|p| p.0[3] == 0));
+    assert!(rgba.pixels().all(|p| p.0[0] == p.0[1] && p.0[1] == p.0[2]));
+}
+
+/// Render like [`render`], splitting the image into rows that are rendered in
+/// parallel on the current rayon thread pool.
+///
+/// Every row is rendered from its own corners, so the output doesn't depend on
+/// how many threads share the work.
+pub fn render_parallel<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+) {
+    render_parallel_with_progress(
+        pixels,
+        bounds,
+        upper_left,
+        lower_right,
+        params,
+        colors,
+        &|| {},
+    );
+}
+
+/// Render like [`render_parallel`], calling `on_row` each time a row is done,
+/// to report the progress of large images.
+///
+/// `on_row` is called from the threads rendering the rows, in no particular
+/// order, so it must be safe to share between them.
+pub fn render_parallel_with_progress<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    on_row: &(dyn Fn() + Sync),
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    // Rows are cut from the grid before it is mapped or rotated
+    let unrotated = FractalParams {
+        sample_pattern: transform::SamplePattern::Rect,
+        rotation: None,
+        ..*params
+    };
+
+    pixels
+        .par_chunks_mut(bounds.0)
+        .enumerate()
+        .for_each(|(row, row_pixels)| {
+            let row_bounds = (bounds.0, 1);
+            // A view of no height samples its row at exactly the imaginary part
+            // the whole image would, keeping the output identical to `render`
+            let im = unrotated
+                .sample_point(bounds, (0, row), upper_left, lower_right)
+                .im;
+            let row_upper_left = Complex {
+                re: upper_left.re,
+                im,
+            };
+            let row_lower_right = Complex {
+                re: lower_right.re,
+                im,
+            };
+
+            render(
+                row_pixels,
+                row_bounds,
+                row_upper_left,
+                row_lower_right,
+                params,
+                colors,
+            );
+            on_row();
+        });
+}
+
+/// Render the smooth escape time of every pixel of the view between
+/// `upper_left` and `lower_right` into `values`, as
+/// [`EscapeResult::smooth_count`] for orbits whose `|z|` grows like
+/// `|z|^power`, and -1 for members.
+///
+/// The values are kept as floats rather than mapped to levels, for tools
+/// that color or light the fractal themselves. Rows are rendered in
+/// parallel on the current rayon thread pool.
+pub fn render_smooth_counts(
+    values: &mut [f32],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    power: f64,
+) {
+    assert!(values.len() == bounds.0 * bounds.1);
+    values
+        .par_chunks_mut(bounds.0)
+        .enumerate()
+        .for_each(|(row, row_values)| {
+            for (column, value) in row_values.iter_mut().enumerate() {
+                let point = params.sample_point(bounds, (column, row), upper_left, lower_right);
+                let result = escape_orbit(point, params, None);
+                *value = result
+                    .iterations
+                    .map_or(-1.0, |count| result.smooth_count(count, power) as f32);
+            }
+        });
+}
+
+#[test]
+fn test_render_smooth_counts() {
+    let params = FractalParams::default();
+    let bounds = (6, 4);
+    let upper_left = Complex { re: -2.0, im: 1.0 };
+    let lower_right = Complex { re: 1.0, im: -1.0 };
+    let mut values = vec![0.0; bounds.0 * bounds.1];
+    render_smooth_counts(&mut values, bounds, upper_left, lower_right, &params, 2.0);
+    for (i, value) in values.iter().enumerate() {
+        let point = params.sample_point(bounds, (i % 6, i / 6), upper_left, lower_right);
+        let result = escape_orbit(point, &params, None);
+        match result.iterations {
+            Some(count) => assert_eq!(*value, result.smooth_count(count, 2.0) as f32),
+            None => assert_eq!(*value, -1.0),
+        }
+    }
+    assert!(values.contains(&-1.0));
+    assert!(
+        values
+            .iter()
+            .any(|&value| value > 0.0 && value.fract() != 0.0)
+    );
+}
+
+/// Return the fraction of the view between `upper_left` and `lower_right`
+/// that lies in the set, estimated from the pixels of a grid of up to
+/// `grid` by `grid` evenly spaced pixels of an image of `bounds` pixels.
+///
+/// The estimate iterates far fewer points than a render, so it can be run
+/// after every frame of an animation to tell when the view has become all
+/// interior or all exterior. Lyapunov points count as members where they
+/// are chaotic.
+pub fn interior_fraction(
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    grid: usize,
+) -> f64 {
+    let members = sample_grid(bounds, grid, |pixel| {
+        let point = params.sample_point(bounds, pixel, upper_left, lower_right);
+        match params.fractal_type {
+            FractalType::Lyapunov => {
+                let exponent = lyapunov::exponent(
+                    point.re,
+                    point.im,
+                    params.lyapunov_sequence,
+                    params.max_iterations,
+                );
+                exponent >= 0.0
+            }
+            _ => params.escape_time(point).is_none(),
+        }
+    });
+    members.iter().filter(|&&member| member).count() as f64 / members.len() as f64
+}
+
+/// Return how much detail the view between `upper_left` and `lower_right`
+/// holds, as the variance of the escape counts of the same grid of pixels
+/// as [`interior_fraction`], with members counted at the iteration limit.
+///
+/// A view all of one count has none, and one crossing the boundary of the
+/// set, where counts change quickly, has the most. For Lyapunov fractals the
+/// variance is that of the finite exponents.
+pub fn detail(
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    grid: usize,
+) -> f64 {
+    let values = sample_grid(bounds, grid, |pixel| {
+        let point = params.sample_point(bounds, pixel, upper_left, lower_right);
+        match params.fractal_type {
+            FractalType::Lyapunov => lyapunov::exponent(
+                point.re,
+                point.im,
+                params.lyapunov_sequence,
+                params.max_iterations,
+            ),
+            _ => params.escape_time(point).unwrap_or(params.max_iterations) as f64,
+        }
+    });
+    let values: Vec<f64> = values.into_iter().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64
+}
+
+/// Return the fraction of the pixels of the same grid as
+/// [`interior_fraction`] that lie on an edge: whose escape count differs
+/// from that of the pixel beside or below it, or that are members where
+/// that pixel is not.
+///
+/// Smooth bands far from the set have few edge pixels and filaments close
+/// to it have many, so the fraction tells how busy a frame looks. Lyapunov
+/// fractals have no escape counts and always give 0.
+pub fn boundary_fraction(
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    grid: usize,
+) -> f64 {
+    if matches!(params.fractal_type, FractalType::Lyapunov) {
+        return 0.0;
+    }
+    let count =
+        |pixel| params.escape_time(params.sample_point(bounds, pixel, upper_left, lower_right));
+    let edges = sample_grid(bounds, grid, |(column, row)| {
+        let own = count((column, row));
+        (column + 1 < bounds.0 && count((column + 1, row)) != own)
+            || (row + 1 < bounds.1 && count((column, row + 1)) != own)
+    });
+    edges.iter().filter(|&&edge| edge).count() as f64 / edges.len() as f64
+}
+
+/// Apply `measure` to up to `grid` by `grid` evenly spaced pixels of an
+/// image of `bounds` pixels, in parallel rows, returning the results row by
+/// row.
+fn sample_grid<T: Send>(
+    bounds: (usize, usize),
+    grid: usize,
+    measure: impl Fn((usize, usize)) -> T + Sync,
+) -> Vec<T> {
+    let (columns, rows) = (grid.min(bounds.0).max(1), grid.min(bounds.1).max(1));
+    (0..rows)
+        .into_par_iter()
+        .flat_map_iter(|i| {
+            let row = (2 * i + 1) * bounds.1 / (2 * rows);
+            let measure = &measure;
+            (0..columns).map(move |j| {
+                let column = (2 * j + 1) * bounds.0 / (2 * columns);
+                measure((column, row))
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_interior_fraction() {
+    let params = FractalParams::default();
+    let fraction = |upper_left, lower_right| {
+        interior_fraction((200, 100), upper_left, lower_right, &params, 32)
+    };
+    // Inside the main cardioid, far outside the set and across it
+    let inside = fraction(Complex { re: -0.3, im: 0.2 }, Complex { re: -0.1, im: 0.0 });
+    assert_eq!(inside, 1.0);
+    let outside = fraction(Complex { re: 1.0, im: 2.0 }, Complex { re: 2.0, im: 1.5 });
+    assert_eq!(outside, 0.0);
+    let whole = fraction(Complex { re: -2.0, im: 1.2 }, Complex { re: 0.6, im: -1.2 });
+    assert!(whole > 0.1 && whole < 0.5, "{}", whole);
+}
+
+#[test]
+fn test_detail() {
+    let params = FractalParams::default();
+    let detail = |upper_left, lower_right| detail((200, 100), upper_left, lower_right, &params, 32);
+    // A view inside the main cardioid is all one count
+    let inside = detail(Complex { re: -0.3, im: 0.2 }, Complex { re: -0.1, im: 0.0 });
+    assert_eq!(inside, 0.0);
+    // One across the boundary has more than one far outside the set
+    let outside = detail(Complex { re: 1.0, im: 2.0 }, Complex { re: 2.0, im: 1.5 });
+    let whole = detail(Complex { re: -2.0, im: 1.2 }, Complex { re: 0.6, im: -1.2 });
+    assert!(whole > 10.0 * outside, "{} {}", whole, outside);
+}
+
+#[test]
+fn test_boundary_fraction() {
+    let params = FractalParams::default();
+    let fraction = |upper_left, lower_right| {
+        boundary_fraction((200, 100), upper_left, lower_right, &params, 32)
+    };
+    // Inside the main cardioid and far outside the set there are no edges
+    let inside = fraction(Complex { re: -0.3, im: 0.2 }, Complex { re: -0.1, im: 0.0 });
+    assert_eq!(inside, 0.0);
+    let outside = fraction(Complex { re: 1.5, im: 2.0 }, Complex { re: 2.0, im: 1.8 });
+    assert_eq!(outside, 0.0);
+    // Filaments near the boundary are busier than the whole set
+    let whole = fraction(Complex { re: -2.0, im: 1.2 }, Complex { re: 0.6, im: -1.2 });
+    let seahorse = fraction(
+        Complex {
+            re: -0.76,
+            im: 0.12,
+        },
+        Complex { re: -0.74, im: 0.1 },
+    );
+    assert!(whole > 0.0 && seahorse > whole, "{} {}", whole, seahorse);
+}
+
+#[test]
+fn test_render_parallel_thread_counts() {
+    let colors = ColorOptions::default();
+    let params = FractalParams::default();
+    // A height that none of the thread counts divide evenly
+    let bounds = (41, 29);
+    let upper_left = Complex { re: -2.0, im: 1.2 };
+    let lower_right = Complex { re: 0.6, im: -1.2 };
+
+    let mut expected = vec![0u8; bounds.0 * bounds.1];
+    render(
+        &mut expected,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+    );
+
+    for threads in [1, 3, 7, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let mut pixels = vec![0u8; bounds.0 * bounds.1];
+        pool.install(|| {
+            render_parallel(
+                &mut pixels,
+                bounds,
+                upper_left,
+                lower_right,
+                &params,
+                &colors,
+            )
+        });
+        assert!(pixels == expected, "{} threads differ", threads);
+    }
+
+    // Every row is reported once, and reporting changes nothing
+    let rows = std::sync::atomic::AtomicUsize::new(0);
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    render_parallel_with_progress(
+        &mut pixels,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+        &|| {
+            rows.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        },
+    );
+    assert_eq!(rows.into_inner(), bounds.1);
+    assert!(pixels == expected);
+}
+
+#[test]
+fn test_render_rotated() {
+    let colors = ColorOptions::default();
+    let bounds = (30, 20);
+    let upper_left = Complex { re: -2.0, im: 1.0 };
+    let lower_right = Complex { re: 1.0, im: -1.0 };
+    let mut straight = vec![0u8; bounds.0 * bounds.1];
+    render(
+        &mut straight,
+        bounds,
+        upper_left,
+        lower_right,
+        &FractalParams::default(),
+        &colors,
+    );
+
+    // A half turn about the middle of the view turns the image upside down
+    let params = FractalParams {
+        rotation: Some(transform::Rotation {
+            center: (upper_left + lower_right) / 2.0,
+            angle: std::f64::consts::PI,
+        }),
+        ..FractalParams::default()
+    };
+    let mut rotated = vec![0u8; bounds.0 * bounds.1];
+    render(
+        &mut rotated,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+    );
+    let flipped: Vec<u8> = straight.iter().rev().copied().collect();
+    assert_eq!(rotated, flipped);
+
+    // Rendering row by row rotates every row about the same center
+    let mut parallel = vec![0u8; bounds.0 * bounds.1];
+    render_parallel(
+        &mut parallel,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+    );
+    assert_eq!(parallel, rotated);
+}
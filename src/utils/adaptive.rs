@@ -0,0 +1,243 @@
+use super::{
+    Channel, ColorOptions, EscapeResult, FractalParams, FractalType, escape_orbit, lyapunov,
+};
+use num::Complex;
+use rayon::prelude::*;
+
+/// Side of the square tiles the image is cut into before subdividing, which
+/// is also the largest region that is ever filled without iterating it
+pub const TILE: usize = 32;
+
+/// Render like [`render_parallel`](super::render_parallel), filling uniform
+/// regions without iterating every pixel in them.
+///
+/// The image is cut into tiles [`TILE`] pixels wide. For each region, starting
+/// with a tile, the four corner pixels and the center pixel are iterated; if
+/// they are all members or all escaping, and their levels are no more than
+/// `tolerance` 8-bit levels apart, the whole region is filled with the
+/// center's value. Otherwise the region is split into four and each quarter is
+/// tested likewise, down to regions less than `min_size` pixels wide or high,
+/// which are iterated pixel by pixel.
+///
+/// Filled regions are not checked inside, so a filament thinner than the
+/// region that passes between the five samples is lost; a larger `min_size`
+/// iterates more pixels and loses fewer. Bands of tiles are rendered in
+/// parallel on the current rayon thread pool. Returns the number of points
+/// iterated.
+#[allow(clippy::too_many_arguments)]
+pub fn render<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    tolerance: u8,
+    min_size: usize,
+) -> usize {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    let view = View {
+        bounds,
+        upper_left,
+        lower_right,
+        params,
+        colors,
+        tolerance: (tolerance as u64 * P::MAX as u64 / u8::MAX as u64) as u32,
+        min_size: min_size.max(1),
+    };
+    pixels
+        .par_chunks_mut(bounds.0 * TILE)
+        .enumerate()
+        .map(|(band, band_pixels)| {
+            let top = band * TILE;
+            let height = band_pixels.len() / bounds.0;
+            (0..bounds.0)
+                .step_by(TILE)
+                .map(|left| {
+                    let region = Region {
+                        left,
+                        top,
+                        width: TILE.min(bounds.0 - left),
+                        height,
+                    };
+                    view.fill(band_pixels, top, region)
+                })
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+/// What every region of one render shares
+struct View<'a> {
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &'a FractalParams,
+    colors: &'a ColorOptions,
+    /// The tolerance in levels of the channel type
+    tolerance: u32,
+    min_size: usize,
+}
+
+/// A rectangle of pixels, in image coordinates
+#[derive(Clone, Copy)]
+struct Region {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+impl View<'_> {
+    /// Return the value of the pixel at `(column, row)` and whether it is a
+    /// member of the set.
+    fn sample<P: Channel>(&self, column: usize, row: usize) -> (P, bool) {
+        let params = self.params;
+        let point = params.sample_point(
+            self.bounds,
+            (column, row),
+            self.upper_left,
+            self.lower_right,
+        );
+        if let FractalType::Lyapunov = params.fractal_type {
+            let exponent = lyapunov::exponent(
+                point.re,
+                point.im,
+                params.lyapunov_sequence,
+                params.max_iterations,
+            );
+            // Chaotic points take the interior color, like members
+            return (lyapunov::shade(exponent, self.colors), exponent >= 0.0);
+        }
+        let result = if self.colors.needs_orbit() {
+            escape_orbit(point, params, self.colors.stripe_density)
+        } else {
+            EscapeResult {
+                iterations: params.escape_time(point),
+                ..EscapeResult::default()
+            }
+        };
+        (
+            self.colors.shade_result(&result, params.max_iterations),
+            result.iterations.is_none(),
+        )
+    }
+
+    /// Render `region` into `pixels`, the rows of the image from `top` on,
+    /// and return the number of points iterated.
+    fn fill<P: Channel>(&self, pixels: &mut [P], top: usize, region: Region) -> usize {
+        let width = self.bounds.0;
+        let index = |column: usize, row: usize| (row - top) * width + column;
+        let Region {
+            left,
+            top: region_top,
+            width: w,
+            height: h,
+        } = region;
+
+        if w < self.min_size || h < self.min_size {
+            for row in region_top..region_top + h {
+                for column in left..left + w {
+                    pixels[index(column, row)] = self.sample::<P>(column, row).0;
+                }
+            }
+            return w * h;
+        }
+
+        let (right, bottom) = (left + w - 1, region_top + h - 1);
+        let center = self.sample::<P>(left + w / 2, region_top + h / 2);
+        let corners = [
+            (left, region_top),
+            (right, region_top),
+            (left, bottom),
+            (right, bottom),
+        ]
+        .map(|(column, row)| self.sample::<P>(column, row));
+        let (low, high) = corners
+            .iter()
+            .fold((center.0.level(), center.0.level()), |(low, high), p| {
+                (low.min(p.0.level()), high.max(p.0.level()))
+            });
+        let uniform = corners.iter().all(|p| p.1 == center.1) && high - low <= self.tolerance;
+        if uniform {
+            for row in region_top..region_top + h {
+                pixels[index(left, row)..=index(right, row)].fill(center.0);
+            }
+            return 5;
+        }
+
+        // Split into quarters, the first ones taking any odd middle pixel
+        let (w1, h1) = (w.div_ceil(2), h.div_ceil(2));
+        let quarters = [
+            (left, region_top, w1, h1),
+            (left + w1, region_top, w - w1, h1),
+            (left, region_top + h1, w1, h - h1),
+            (left + w1, region_top + h1, w - w1, h - h1),
+        ];
+        5 + quarters
+            .into_iter()
+            .filter(|&(_, _, w, h)| w > 0 && h > 0)
+            .map(|(left, region_top, width, height)| {
+                let region = Region {
+                    left,
+                    top: region_top,
+                    width,
+                    height,
+                };
+                self.fill(pixels, top, region)
+            })
+            .sum::<usize>()
+    }
+}
+
+#[test]
+fn test_adaptive_render() {
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let render_both = |bounds: (usize, usize), upper_left, lower_right, min_size| {
+        let mut expected = vec![0u8; bounds.0 * bounds.1];
+        super::render_parallel(
+            &mut expected,
+            bounds,
+            upper_left,
+            lower_right,
+            &params,
+            &colors,
+        );
+        let mut pixels = vec![0u8; bounds.0 * bounds.1];
+        let iterated = render(
+            &mut pixels,
+            bounds,
+            upper_left,
+            lower_right,
+            &params,
+            &colors,
+            0,
+            min_size,
+        );
+        (expected, pixels, iterated)
+    };
+
+    // Regions never smaller than the tiles are iterated pixel by pixel
+    let upper_left = Complex { re: -2.0, im: 1.2 };
+    let lower_right = Complex { re: 1.0, im: -1.2 };
+    let (expected, pixels, iterated) = render_both((70, 45), upper_left, lower_right, TILE + 1);
+    assert_eq!(pixels, expected);
+    assert_eq!(iterated, 70 * 45);
+
+    // Inside the main cardioid every tile is filled after five samples
+    let (expected, pixels, iterated) = render_both(
+        (64, 64),
+        Complex { re: -0.3, im: 0.2 },
+        Complex { re: -0.1, im: 0.0 },
+        4,
+    );
+    assert_eq!(pixels, expected);
+    assert_eq!(iterated, 4 * 5);
+
+    // On the whole set only a few pixels near the boundary are missed
+    let (expected, pixels, iterated) = render_both((160, 128), upper_left, lower_right, 4);
+    let wrong = pixels.iter().zip(&expected).filter(|(a, b)| a != b).count();
+    assert!(wrong < pixels.len() / 50, "{} pixels differ", wrong);
+    assert!(iterated < pixels.len());
+}
@@ -0,0 +1,376 @@
+use super::transform::{Rotation, scale_point};
+use super::{
+    ColorOptions, FractalParams, FractalType, JuliaPath, Morph, iterations_for_zoom,
+    render_parallel,
+};
+use num::Complex;
+
+/// Everything that decides the frames of a zoom animation
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationConfig {
+    /// Dimensions of every frame in pixels
+    pub bounds: (usize, usize),
+    /// Upper-left corner of the first frame's view
+    pub upper_left: Complex<f64>,
+    /// Lower-right corner of the first frame's view
+    pub lower_right: Complex<f64>,
+    /// The fixed point of the zoom
+    pub pointer: Complex<f64>,
+    /// Factor each frame's view is scaled by about the pointer to give the next
+    pub scale_factor: f64,
+    /// Number of frames in the animation
+    pub n_frames: usize,
+    /// Parameters of the first frame; its Julia constant is where the Julia
+    /// path starts and its iteration limit the base of `auto_iterations`
+    pub params: FractalParams,
+    /// Shading of the first frame
+    pub colors: ColorOptions,
+    /// How the Julia constant moves from frame to frame
+    pub julia_path: JuliaPath,
+    /// Iterations added each time the view halves, see [`iterations_for_zoom`]
+    pub auto_iterations: Option<f64>,
+    /// Degrees the view turns counterclockwise about the pointer every frame
+    pub rotation_per_frame: Option<f64>,
+    /// Fraction of the channel range the palette cycles by every frame
+    pub palette_shift_per_frame: Option<f64>,
+    /// Fractal type the formula is blended into over the animation, from
+    /// none of it in the first frame to all of it in the last
+    pub morph_to: Option<FractalType>,
+    /// Real power the multibrot formula reaches in the last frame, from the
+    /// power of the first on a straight line through the fractional powers
+    pub power_end: Option<f64>,
+}
+
+impl AnimationConfig {
+    /// Return the parameters of frame `i`, counting from zero: the first
+    /// frame's, with the iteration limit, Julia constant, rotation, morph and
+    /// power the frame has reached.
+    pub fn params_at(&self, i: usize) -> FractalParams {
+        let mut params = self.params;
+        if let Some(k) = self.auto_iterations {
+            let zoom = self.scale_factor.powi(-(i as i32));
+            params.max_iterations = iterations_for_zoom(self.params.max_iterations, k, zoom);
+        }
+        params.julia_constant = self
+            .params
+            .julia_constant
+            .map(|start| self.julia_path.at(start, i, self.n_frames));
+        if let Some(degrees) = self.rotation_per_frame {
+            params.rotation = Some(Rotation {
+                center: self.pointer,
+                angle: (i as f64 * degrees).to_radians(),
+            });
+        }
+        params.morph = self.morph_to.map(|to| Morph {
+            to,
+            t: i as f64 / self.n_frames.saturating_sub(1).max(1) as f64,
+        });
+        if let Some(power) = self.power_at(i) {
+            params = params.with_real_power(power);
+        }
+        params
+    }
+
+    /// Return the real power of frame `i`, counting from zero, along the
+    /// ramp to [`power_end`](AnimationConfig::power_end), or `None` without
+    /// one.
+    pub fn power_at(&self, i: usize) -> Option<f64> {
+        let t = i as f64 / self.n_frames.saturating_sub(1).max(1) as f64;
+        let start = self.params.power as f64;
+        self.power_end.map(|end| start + (end - start) * t)
+    }
+
+    /// Return the palette shift of frame `i`, counting from zero: the first
+    /// frame's, advanced by the shift per frame.
+    pub fn palette_shift_at(&self, i: usize) -> f64 {
+        self.palette_shift_per_frame
+            .map_or(self.colors.palette_shift, |shift| {
+                (self.colors.palette_shift + i as f64 * shift).rem_euclid(1.0)
+            })
+    }
+
+    /// Return the view of frame `i`, counting from zero: the first frame's
+    /// scaled by the scale factor to the power `i` about the pointer.
+    ///
+    /// Every frame's corners come straight from the first frame's, rather
+    /// than from scaling the view of the frame before, so rounding errors do
+    /// not pile up over a long zoom and every consumer of the animation gets
+    /// the same corners for a frame, however it got there.
+    pub fn view_at(&self, i: usize) -> (Complex<f64>, Complex<f64>) {
+        self.view_scaled_by(self.scale_factor.powf(i as f64))
+    }
+
+    /// Return the first frame's view scaled by `scale` about the pointer, for
+    /// zooms whose speed changes from frame to frame.
+    pub fn view_scaled_by(&self, scale: f64) -> (Complex<f64>, Complex<f64>) {
+        (
+            scale_point(self.upper_left, self.pointer, scale),
+            scale_point(self.lower_right, self.pointer, scale),
+        )
+    }
+}
+
+#[test]
+fn test_view_drift() {
+    // A long, slow zoom towards -0.75, where the upper left corner's real
+    // part is -0.75 - 1.25 s^i, with s^i kept to about 106 bits by
+    // double-double products as a reference
+    let config = AnimationConfig {
+        bounds: (4, 4),
+        upper_left: Complex { re: -2.0, im: 1.25 },
+        lower_right: Complex { re: 0.5, im: -1.25 },
+        pointer: Complex { re: -0.75, im: 0.0 },
+        scale_factor: 0.9999,
+        n_frames: 100_000,
+        params: FractalParams::default(),
+        colors: ColorOptions::default(),
+        julia_path: JuliaPath::Fixed,
+        auto_iterations: None,
+        rotation_per_frame: None,
+        palette_shift_per_frame: None,
+        morph_to: None,
+        power_end: None,
+    };
+    let s = config.scale_factor;
+    let (mut high, mut low) = (1.0f64, 0.0f64);
+    let mut stepped = config.upper_left;
+    let (mut stepped_error, mut direct_error) = (0.0f64, 0.0f64);
+    // Distance from the reference in units of the spacing of floats near the
+    // pointer, the least any corner can be off by
+    let error = |re: f64, high: f64, low: f64| {
+        ((re + 0.75) + 1.25 * high + 1.25 * low).abs() / f64::EPSILON
+    };
+    for i in 1..config.n_frames {
+        let product = high * s;
+        let rounding = high.mul_add(s, -product) + low * s;
+        high = product + rounding;
+        low = rounding - (high - product);
+        // Scaling each frame's view to give the next, as the frames once were
+        stepped = scale_point(stepped, config.pointer, s);
+        stepped_error = stepped_error.max(error(stepped.re, high, low));
+        direct_error = direct_error.max(error(config.view_at(i).0.re, high, low));
+    }
+    // Stepping piles up the rounding of every step, which a zoom in shrinks
+    // only by s each frame; computing each frame from the first rounds once
+    assert!(direct_error <= 1.0, "{}", direct_error);
+    assert!(stepped_error > 10.0, "{}", stepped_error);
+
+    assert_eq!(config.view_at(0), (config.upper_left, config.lower_right));
+    assert_eq!(config.view_scaled_by(s.powf(7.0)), config.view_at(7));
+}
+
+/// Most that [`DetailControl`] speeds up or slows down the zoom, as a factor
+/// of the logarithm of the scale factor it starts from
+const MAX_SPEED_CHANGE: f64 = 4.0;
+
+/// Feedback control of the speed of a zoom, so that every frame reveals
+/// about as much new detail as the first.
+///
+/// The detail a step reveals is taken to be the frame's
+/// [`boundary_fraction`](super::boundary_fraction) times how far the step
+/// zooms, the logarithm of its scale factor. After each frame the step is
+/// moved halfway towards the one that would reveal as much as the first
+/// frame's did, so busy stretches of the zoom slow down and featureless ones
+/// speed up, but never by more than [`MAX_SPEED_CHANGE`] times.
+#[derive(Debug, Clone, Copy)]
+pub struct DetailControl {
+    /// Logarithm of the scale factor the zoom starts with
+    base: f64,
+    /// Logarithm of the scale factor of the last step
+    step: f64,
+    /// Boundary fraction of the first frame
+    target: Option<f64>,
+    /// Product of the scale factors of the steps so far
+    scale: f64,
+}
+
+impl DetailControl {
+    /// Start controlling a zoom whose first step is by `scale_factor`.
+    pub fn new(scale_factor: f64) -> Self {
+        DetailControl {
+            base: scale_factor.ln(),
+            step: scale_factor.ln(),
+            target: None,
+            scale: 1.0,
+        }
+    }
+
+    /// Return how many times the frames are magnified so far relative to the
+    /// first.
+    pub fn zoom(&self) -> f64 {
+        1.0 / self.scale
+    }
+
+    /// Take the boundary fraction of the frame just rendered and return the
+    /// scale factor of the step to the next frame.
+    pub fn step(&mut self, fraction: f64) -> f64 {
+        let target = *self.target.get_or_insert(fraction);
+        // A view without edges reveals nothing at any speed
+        let ratio = if target == 0.0 {
+            1.0
+        } else if fraction == 0.0 {
+            MAX_SPEED_CHANGE
+        } else {
+            (target / fraction).clamp(1.0 / MAX_SPEED_CHANGE, MAX_SPEED_CHANGE)
+        };
+        self.step = (self.step + self.base * ratio) / 2.0;
+        let scale_factor = self.step.exp();
+        self.scale *= scale_factor;
+        scale_factor
+    }
+}
+
+#[test]
+fn test_detail_control() {
+    let mut control = DetailControl::new(0.5);
+    // The first frame sets the target, so the zoom goes on at its speed
+    assert_eq!(control.step(0.2), 0.5);
+    // Twice the detail halves the logarithm of the step, over a few frames
+    let mut factor = control.step(0.4);
+    assert!(factor > 0.5 && factor < 0.5f64.sqrt(), "{}", factor);
+    for _ in 0..20 {
+        factor = control.step(0.4);
+    }
+    assert!((factor - 0.5f64.sqrt()).abs() < 1e-6, "{}", factor);
+    // A featureless view speeds up, but only so far
+    for _ in 0..40 {
+        factor = control.step(0.0);
+    }
+    assert!((factor - 0.5f64.powi(4)).abs() < 1e-6, "{}", factor);
+    assert!(control.zoom() > 2f64.powi(40));
+}
+
+/// Render the frames of `config` one after another, passing each to
+/// `on_frame` with its index, counting from zero, its 8-bit gray levels and
+/// its dimensions.
+///
+/// Nothing is written to disk, so what happens to the frames is up to the
+/// caller. Each frame is rendered in parallel rows like
+/// [`render_parallel`], into one buffer that is reused for the next frame.
+///
+/// The command line has a frame loop of its own, for its deep zooms,
+/// supersampling and other options, and only shares
+/// [`AnimationConfig::view_at`] and [`AnimationConfig::params_at`] with this
+/// one.
+pub fn render_animation(
+    config: &AnimationConfig,
+    mut on_frame: impl FnMut(usize, &[u8], (usize, usize)),
+) {
+    let bounds = config.bounds;
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    for i in 0..config.n_frames {
+        let (upper_left, lower_right) = config.view_at(i);
+        let params = config.params_at(i);
+        let colors = ColorOptions {
+            palette_shift: config.palette_shift_at(i),
+            ..config.colors
+        };
+        let (grid_upper_left, grid_lower_right) =
+            params.sample_pattern.grid(bounds, upper_left, lower_right);
+        render_parallel(
+            &mut pixels,
+            bounds,
+            grid_upper_left,
+            grid_lower_right,
+            &params,
+            &colors,
+        );
+        on_frame(i, &pixels, bounds);
+    }
+}
+
+#[test]
+fn test_render_animation() {
+    let config = AnimationConfig {
+        bounds: (12, 8),
+        upper_left: Complex { re: -2.0, im: 1.0 },
+        lower_right: Complex { re: 1.0, im: -1.0 },
+        pointer: Complex { re: -0.75, im: 0.1 },
+        scale_factor: 0.5,
+        n_frames: 3,
+        params: FractalParams {
+            max_iterations: 100,
+            ..FractalParams::default()
+        },
+        colors: ColorOptions::default(),
+        julia_path: JuliaPath::Fixed,
+        auto_iterations: Some(10.0),
+        rotation_per_frame: None,
+        palette_shift_per_frame: Some(0.25),
+        morph_to: None,
+        power_end: None,
+    };
+    let mut frames = Vec::new();
+    render_animation(&config, |i, pixels, bounds| {
+        assert_eq!(bounds, (12, 8));
+        frames.push((i, pixels.to_vec()));
+    });
+    assert_eq!(frames.len(), 3);
+
+    // The last frame shows the view a quarter of the size, with 20 more
+    // iterations and the palette turned by half
+    let (upper_left, lower_right) = config.view_at(2);
+    assert!((lower_right - upper_left - Complex { re: 0.75, im: -0.5 }).norm() < 1e-12);
+    let params = config.params_at(2);
+    assert_eq!(params.max_iterations, 120);
+    let colors = ColorOptions {
+        palette_shift: 0.5,
+        ..ColorOptions::default()
+    };
+    let mut expected = vec![0u8; 12 * 8];
+    render_parallel(
+        &mut expected,
+        (12, 8),
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+    );
+    assert_eq!(frames[2], (2, expected));
+
+    // The Julia constant and rotation follow the frame
+    let config = AnimationConfig {
+        params: FractalParams {
+            julia_constant: Some(Complex { re: 0.0, im: 0.0 }),
+            ..config.params
+        },
+        julia_path: JuliaPath::Line {
+            end: Complex { re: 1.0, im: 0.0 },
+        },
+        rotation_per_frame: Some(90.0),
+        ..config
+    };
+    let params = config.params_at(1);
+    assert_eq!(params.julia_constant, Some(Complex { re: 0.5, im: 0.0 }));
+    let rotation = params.rotation.unwrap();
+    assert_eq!(rotation.center, config.pointer);
+    assert!((rotation.angle - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+
+    // A morph runs from the own formula in the first frame to the other's in
+    // the last
+    let config = AnimationConfig {
+        morph_to: Some(FractalType::BurningShip),
+        ..config
+    };
+    let t = |i| config.params_at(i).morph.map(|morph| morph.t);
+    assert_eq!((t(0), t(1), t(2)), (Some(0.0), Some(0.5), Some(1.0)));
+
+    // A multibrot ramp passes through a fractional power on its way from
+    // the first frame's power to the last's
+    let config = AnimationConfig {
+        params: FractalParams {
+            fractal_type: FractalType::Multibrot,
+            ..config.params
+        },
+        morph_to: None,
+        power_end: Some(5.0),
+        ..config
+    };
+    let power = |i| {
+        let params = config.params_at(i);
+        (params.power, params.complex_power.map(|p| p.re))
+    };
+    assert_eq!(power(0), (2, None));
+    assert_eq!(power(1), (4, Some(3.5)));
+    assert_eq!(power(2), (5, None));
+}
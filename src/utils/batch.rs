@@ -0,0 +1,290 @@
+/// One render of a batch file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    /// Name of the job's subfolder of the batch output folder
+    pub name: String,
+    /// Command-line options the job's render is run with, like
+    /// `--view-radius=0.01`
+    pub args: Vec<String>,
+}
+
+/// Parse a batch file: a JSON array of objects, one per job, whose keys are
+/// the long names of command-line options.
+///
+/// A string or number value becomes `--key=value`, `true` turns a flag on,
+/// and `false` or `null` leaves the option out. Underscores in keys stand
+/// for hyphens. The key `name` instead names the job's folder, which
+/// defaults to `job-001`, `job-002` and so on; names must be unique. Only
+/// this flat layout is understood, not arbitrary JSON.
+pub fn parse_jobs(text: &str) -> Result<Vec<Job>, String> {
+    let mut reader = Reader {
+        bytes: text.as_bytes(),
+        position: 0,
+    };
+    let mut jobs: Vec<Job> = Vec::new();
+    reader.expect(b'[')?;
+    if !reader.next_is(b']') {
+        loop {
+            let job = reader.job(jobs.len())?;
+            if jobs.iter().any(|other| other.name == job.name) {
+                return Err(format!("two jobs are named `{}`", job.name));
+            }
+            jobs.push(job);
+            if !reader.next_is(b',') {
+                break;
+            }
+        }
+        reader.expect(b']')?;
+    }
+    reader.skip_whitespace();
+    if reader.position != reader.bytes.len() {
+        return Err(reader.error("nothing but whitespace after the array"));
+    }
+    Ok(jobs)
+}
+
+/// Read the jobs of the batch file `path`, see [`parse_jobs`].
+pub fn load(path: &std::path::Path) -> Result<Vec<Job>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    parse_jobs(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// A value of a job option
+enum Value {
+    Text(String),
+    Flag(bool),
+    Null,
+}
+
+/// A cursor over the bytes of a batch file
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, expected: &str) -> String {
+        let line = 1 + self.bytes[..self.position]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+        format!("expected {} on line {}", expected, line)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Skip whitespace and consume `byte` if it comes next.
+    fn next_is(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.bytes.get(self.position) == Some(&byte);
+        self.position += found as usize;
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.next_is(byte) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{}`", byte as char)))
+        }
+    }
+
+    /// Parse the object of the job at `index` in the array.
+    fn job(&mut self, index: usize) -> Result<Job, String> {
+        self.expect(b'{')?;
+        let mut name = None;
+        let mut args = Vec::new();
+        if !self.next_is(b'}') {
+            loop {
+                self.skip_whitespace();
+                let key = self.string()?;
+                self.expect(b':')?;
+                let value = self.value()?;
+                if key == "name" {
+                    match value {
+                        Value::Text(text) if valid_name(&text) => name = Some(text),
+                        _ => return Err(format!("invalid job name for job {}", index + 1)),
+                    }
+                } else {
+                    let valid = key.starts_with(|c: char| c.is_ascii_alphanumeric())
+                        && key
+                            .bytes()
+                            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+                    if !valid {
+                        return Err(format!("invalid option `{}` in job {}", key, index + 1));
+                    }
+                    let option = format!("--{}", key.replace('_', "-"));
+                    match value {
+                        Value::Text(text) => args.push(format!("{}={}", option, text)),
+                        Value::Flag(true) => args.push(option),
+                        Value::Flag(false) | Value::Null => {}
+                    }
+                }
+                if !self.next_is(b',') {
+                    break;
+                }
+            }
+            self.expect(b'}')?;
+        }
+        Ok(Job {
+            name: name.unwrap_or_else(|| format!("job-{:03}", index + 1)),
+            args,
+        })
+    }
+
+    /// Parse a string, a number, `true`, `false` or `null`.
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        let rest = &self.bytes[self.position..];
+        for (word, value) in [
+            ("true", Value::Flag(true)),
+            ("false", Value::Flag(false)),
+            ("null", Value::Null),
+        ] {
+            if rest.starts_with(word.as_bytes()) {
+                self.position += word.len();
+                return Ok(value);
+            }
+        }
+        match rest.first() {
+            Some(b'"') => Ok(Value::Text(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.position;
+                while self
+                    .bytes
+                    .get(self.position)
+                    .is_some_and(|&b| b.is_ascii_alphanumeric() || b"+-.".contains(&b))
+                {
+                    self.position += 1;
+                }
+                let number = std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+                match number.parse::<f64>() {
+                    Ok(_) => Ok(Value::Text(number.to_string())),
+                    Err(_) => Err(format!("invalid number `{}`", number)),
+                }
+            }
+            _ => Err(self.error("a string, number, true, false or null")),
+        }
+    }
+
+    /// Parse a string in double quotes, with its escapes.
+    fn string(&mut self) -> Result<String, String> {
+        if self.bytes.get(self.position) != Some(&b'"') {
+            return Err(self.error("a string"));
+        }
+        self.position += 1;
+        let mut text = Vec::new();
+        loop {
+            let Some(&byte) = self.bytes.get(self.position) else {
+                return Err(self.error("the end of the string"));
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = match self.bytes.get(self.position) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'n') => '\n',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let digits = self
+                                .bytes
+                                .get(self.position + 1..self.position + 5)
+                                .and_then(|d| std::str::from_utf8(d).ok())
+                                .and_then(|d| u32::from_str_radix(d, 16).ok())
+                                .and_then(char::from_u32);
+                            self.position += 4;
+                            digits.ok_or_else(|| self.error("four hex digits after \\u"))?
+                        }
+                        _ => return Err(self.error("a valid escape")),
+                    };
+                    self.position += 1;
+                    text.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                _ => text.push(byte),
+            }
+        }
+        // The bytes came from a `str` and escapes add whole characters
+        Ok(String::from_utf8(text).unwrap())
+    }
+}
+
+/// Return whether `name` can be used as a folder name inside the output
+/// folder without escaping it.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+#[test]
+fn test_parse_jobs() {
+    let text = r#"[
+        {"name": "seahorse", "view-center": "-0.745,0.11", "view_radius": 0.01,
+         "smooth": true, "invert": false, "palette": null},
+        {"fractal-type": "julia", "julia-constant": "-0.8,1.5\"", "n-frames": 1},
+        {}
+    ]"#;
+    let jobs = parse_jobs(text).unwrap();
+    assert_eq!(
+        jobs,
+        [
+            Job {
+                name: "seahorse".to_string(),
+                args: vec![
+                    "--view-center=-0.745,0.11".to_string(),
+                    "--view-radius=0.01".to_string(),
+                    "--smooth".to_string(),
+                ],
+            },
+            Job {
+                name: "job-002".to_string(),
+                args: vec![
+                    "--fractal-type=julia".to_string(),
+                    "--julia-constant=-0.8,1.5\"".to_string(),
+                    "--n-frames=1".to_string(),
+                ],
+            },
+            Job {
+                name: "job-003".to_string(),
+                args: Vec::new(),
+            },
+        ]
+    );
+    assert_eq!(parse_jobs(" [ ] "), Ok(Vec::new()));
+
+    for (text, error) in [
+        ("", "expected `[` on line 1"),
+        ("[{\"n\": 1},\n{\"n\": 2", "expected `}` on line 2"),
+        (
+            "[{\"n\": [1]}]",
+            "expected a string, number, true, false or null on line 1",
+        ),
+        ("[{\"n\": 1x}]", "invalid number `1x`"),
+        ("[{\"name\": \"../up\"}]", "invalid job name for job 1"),
+        ("[{\"--n\": 1}]", "invalid option `--n` in job 1"),
+        (
+            "[{\"name\": \"a\"}, {\"name\": \"a\"}]",
+            "two jobs are named `a`",
+        ),
+        (
+            "[] []",
+            "expected nothing but whitespace after the array on line 1",
+        ),
+        (
+            "[{\"n\": \"open",
+            "expected the end of the string on line 1",
+        ),
+    ] {
+        assert_eq!(parse_jobs(text), Err(error.to_string()), "{:?}", text);
+    }
+}
@@ -0,0 +1,170 @@
+use super::{Channel, ColorOptions, EscapeResult, FractalParams, escape_orbit};
+use num::Complex;
+use rayon::prelude::*;
+
+/// Render like [`render_parallel`](super::render_parallel) with the
+/// iteration limit of `params`, but iterate most points only up to `low`.
+///
+/// Every pixel is first iterated up to `low` iterations. A point that
+/// escapes by then escapes at the same count under the full limit, so only
+/// the pixels left as members can change. Those beside a pixel that escaped,
+/// on the boundary where the detail is, are iterated again up to the full
+/// limit, and wherever one of them escapes after all, the members beside it
+/// follow, so the second pass spreads into the filaments the low limit filled
+/// in and stops at points that stay members. The interior of the set, where
+/// every point costs the full limit, is left at `low`. Returns the number of
+/// pixels iterated twice.
+///
+/// Points that only escape under the full limit but are cut off from every
+/// escaping pixel by members are missed. Lyapunov fractals have no escape
+/// counts to compare, and the interior coloring of members left at `low`
+/// would differ from a full render.
+pub fn render<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    low: usize,
+) -> usize {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    let (width, height) = bounds;
+    let limit = params.max_iterations;
+    let first = FractalParams {
+        max_iterations: low.clamp(1, limit),
+        ..*params
+    };
+
+    // The escape count of every pixel under the low limit, with members at
+    // None
+    let mut counts = vec![None; pixels.len()];
+    pixels
+        .par_chunks_mut(width)
+        .zip(counts.par_chunks_mut(width))
+        .enumerate()
+        .for_each(|(row, (row_pixels, row_counts))| {
+            for column in 0..width {
+                let point = params.sample_point(bounds, (column, row), upper_left, lower_right);
+                let (pixel, count) = sample(point, &first, colors, limit);
+                row_pixels[column] = pixel;
+                row_counts[column] = count;
+            }
+        });
+    if first.max_iterations == limit {
+        return 0;
+    }
+
+    // The pixels around a pixel, diagonals included
+    let neighbours = |index: usize| {
+        let (column, row) = (index % width, index / width);
+        (row.saturating_sub(1)..(row + 2).min(height))
+            .flat_map(move |r| {
+                (column.saturating_sub(1)..(column + 2).min(width)).map(move |c| r * width + c)
+            })
+            .filter(move |&n| n != index)
+    };
+    let mut queued: Vec<bool> = (0..pixels.len())
+        .into_par_iter()
+        .map(|index| counts[index].is_none() && neighbours(index).any(|n| counts[n].is_some()))
+        .collect();
+    let mut frontier: Vec<usize> = (0..pixels.len()).filter(|&index| queued[index]).collect();
+    let mut resampled = 0;
+    while !frontier.is_empty() {
+        resampled += frontier.len();
+        let results: Vec<(usize, P, Option<usize>)> = frontier
+            .par_iter()
+            .map(|&index| {
+                let point = params.sample_point(
+                    bounds,
+                    (index % width, index / width),
+                    upper_left,
+                    lower_right,
+                );
+                let (pixel, count) = sample(point, params, colors, limit);
+                (index, pixel, count)
+            })
+            .collect();
+        frontier.clear();
+        for (index, pixel, count) in results {
+            pixels[index] = pixel;
+            if count.is_some() {
+                counts[index] = count;
+                for n in neighbours(index) {
+                    if counts[n].is_none() && !queued[n] {
+                        queued[n] = true;
+                        frontier.push(n);
+                    }
+                }
+            }
+        }
+    }
+    resampled
+}
+
+/// Return the shade of `point` iterated up to the limit of `params`, shaded
+/// as if counted up to `limit`, and its escape count, or `None` for members.
+fn sample<P: Channel>(
+    point: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    limit: usize,
+) -> (P, Option<usize>) {
+    // Escape counts are enough unless the orbit picks the shade
+    let result = if colors.needs_orbit() {
+        escape_orbit(point, params, colors.stripe_density)
+    } else {
+        EscapeResult {
+            iterations: params.escape_time(point),
+            ..EscapeResult::default()
+        }
+    };
+    let pixel = colors.shade_result(&result, limit);
+    (pixel, result.iterations)
+}
+
+#[test]
+fn test_boost_render() {
+    let params = FractalParams {
+        max_iterations: 1000,
+        ..FractalParams::default()
+    };
+    let colors = ColorOptions::default();
+    let bounds = (64, 48);
+    let upper_left = Complex { re: -2.0, im: 1.2 };
+    let lower_right = Complex { re: 0.6, im: -1.2 };
+    let mut full = vec![0u8; bounds.0 * bounds.1];
+    super::render_parallel(&mut full, bounds, upper_left, lower_right, &params, &colors);
+
+    // Only the boundary is iterated twice, and the image is the same
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    let resampled = render(
+        &mut pixels,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+        20,
+    );
+    assert_eq!(pixels, full);
+    let members = full.iter().filter(|&&p| p == 0).count();
+    assert!(
+        resampled > 0 && resampled < members / 2,
+        "{} of {}",
+        resampled,
+        members
+    );
+
+    // A low limit as high as the full one is a plain render
+    let resampled = render(
+        &mut pixels,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+        1000,
+    );
+    assert_eq!((resampled, pixels), (0, full));
+}
@@ -0,0 +1,198 @@
+use super::random::Rng;
+use super::{Channel, ColorOptions, FractalParams, has_escaped, initial_z, step, transform};
+use num::Complex;
+use rayon::prelude::*;
+
+/// Number of samples drawn by each parallel task.
+const SAMPLES_PER_CHUNK: usize = 10_000;
+
+/// Render a Buddhabrot image of the fractal into a buffer of pixels.
+///
+/// Rather than coloring each pixel by its own escape time, this draws
+/// `samples` random points `c` from the square enclosed by the escape
+/// radius, iterates each one, and for every point that escapes increments
+/// the density of each pixel its orbit passed through. The density buffer is
+/// then normalized to the full range of the channel type, using a square
+/// root to bring out the faint outer orbits.
+///
+/// The samples are drawn in chunks spread over the current rayon thread
+/// pool, sized by `--threads`. Each task counts the densities of its chunks
+/// in a buffer of its own, and the buffers are summed once at the end, so
+/// threads never contend for a pixel. The sampling is seeded
+/// deterministically per chunk and the sums don't depend on their order, so
+/// the same arguments always produce the same image on any number of
+/// threads.
+pub fn render<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    samples: usize,
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+
+    let chunks = samples.div_ceil(SAMPLES_PER_CHUNK);
+    let density = (0..chunks)
+        .into_par_iter()
+        .fold(
+            || vec![0u32; pixels.len()],
+            |mut density, chunk| {
+                let count = SAMPLES_PER_CHUNK.min(samples - chunk * SAMPLES_PER_CHUNK);
+                accumulate(
+                    &mut density,
+                    bounds,
+                    upper_left,
+                    lower_right,
+                    params,
+                    chunk,
+                    count,
+                );
+                density
+            },
+        )
+        .reduce_with(|mut total, density| {
+            for (total, d) in total.iter_mut().zip(density) {
+                *total += d;
+            }
+            total
+        })
+        .unwrap_or_else(|| vec![0; pixels.len()]);
+
+    normalize(pixels, &density, colors);
+}
+
+/// Draw the `count` samples of chunk number `chunk` and add the orbits of
+/// those that escape to `density`.
+fn accumulate(
+    density: &mut [u32],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    chunk: usize,
+    count: usize,
+) {
+    let limit = params.max_iterations;
+    let radius = params.escape_radius;
+    let mut rng = Rng::new(chunk as u64);
+    let mut orbit = Vec::with_capacity(limit);
+
+    for _ in 0..count {
+        let c = Complex {
+            re: rng.range(-radius, radius),
+            im: rng.range(-radius, radius),
+        };
+
+        // Record the orbit until it escapes or reaches the iteration limit
+        orbit.clear();
+        let mut z = params
+            .initial_z
+            .unwrap_or_else(|| initial_z(c, params.fractal_type));
+        let mut previous = Complex { re: 0.0, im: 0.0 };
+        let mut escaped = false;
+        for _ in 0..limit {
+            if has_escaped(z, radius, params.escape_condition()) {
+                escaped = true;
+                break;
+            }
+            orbit.push(z);
+            (z, previous) = (
+                step(
+                    z,
+                    previous,
+                    c,
+                    params.power,
+                    params.fractal_type,
+                    params.julia_constant,
+                ),
+                z,
+            );
+        }
+
+        // Only orbits of points outside the set contribute to the image
+        if !escaped {
+            continue;
+        }
+        for &point in &orbit {
+            if let Some((column, row)) =
+                transform::point_to_pixel(bounds, point, upper_left, lower_right)
+            {
+                density[row * bounds.0 + column] += 1;
+            }
+        }
+    }
+}
+
+/// Map the accumulated orbit densities onto grayscale pixel values.
+fn normalize<P: Channel>(pixels: &mut [P], density: &[u32], colors: &ColorOptions) {
+    let max = density.iter().copied().max().unwrap_or(0);
+
+    for (pixel, &d) in pixels.iter_mut().zip(density) {
+        *pixel = if d == 0 {
+            colors.shade(None, 1)
+        } else {
+            let value = (P::MAX as f64 * (d as f64 / max as f64).sqrt()).round() as u32;
+            P::from_level(colors.cycle::<P>(if colors.invert { P::MAX - value } else { value }))
+        };
+    }
+}
+
+#[test]
+fn test_buddhabrot_render() {
+    let params = FractalParams {
+        fractal_type: super::FractalType::Mandelbrot,
+        power: 2,
+        escape_radius: 2.0,
+        julia_constant: None,
+        ..FractalParams::default()
+    };
+    let colors = ColorOptions::default();
+    let bounds = (32, 32);
+    let upper_left = Complex { re: -2.0, im: 2.0 };
+    let lower_right = Complex { re: 2.0, im: -2.0 };
+
+    let mut first = vec![0u8; bounds.0 * bounds.1];
+    render(
+        &mut first,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+        20_000,
+    );
+    let mut second = vec![0u8; bounds.0 * bounds.1];
+    render(
+        &mut second,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+        20_000,
+    );
+
+    // Sampling is deterministic and the densest pixel is full brightness
+    assert_eq!(first, second);
+    assert_eq!(first.iter().max(), Some(&u8::MAX));
+
+    // The per-thread densities add up the same on one thread
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    pool.install(|| {
+        render(
+            &mut second,
+            bounds,
+            upper_left,
+            lower_right,
+            &params,
+            &colors,
+            20_000,
+        )
+    });
+    assert_eq!(first, second);
+}
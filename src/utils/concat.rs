@@ -0,0 +1,284 @@
+use super::error::FractalError;
+use super::palette::Palette;
+use super::preserve::{GifWriter, Playback};
+use image::{AnimationDecoder, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// One part of a joined animation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// The PNG frames of `folder`, in the order they play
+    Frames {
+        folder: PathBuf,
+        files: Vec<PathBuf>,
+    },
+    /// The frames of an animated GIF
+    Gif(PathBuf),
+}
+
+impl Segment {
+    /// Open `path` as a folder of PNG frames or as a GIF file.
+    ///
+    /// The PNG files of a folder are ordered by name, except that the numbers
+    /// at the end of names are compared by value, so that `mandelbrot-1000.png`
+    /// follows `mandelbrot-999.png`.
+    pub fn open(path: &Path) -> Result<Segment, std::io::Error> {
+        if !path.is_dir() {
+            return match path.extension().and_then(|e| e.to_str()) {
+                Some(e) if e.eq_ignore_ascii_case("gif") => Ok(Segment::Gif(path.to_path_buf())),
+                _ => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{} is neither a folder nor a GIF file", path.display()),
+                )),
+            };
+        }
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let file = entry?.path();
+            let png = file
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+            if png && file.is_file() {
+                files.push(file);
+            }
+        }
+        files.sort_by_cached_key(|file| frame_key(file));
+        Ok(Segment::Frames {
+            folder: path.to_path_buf(),
+            files,
+        })
+    }
+
+    /// The folder or GIF file the segment was opened from
+    pub fn source(&self) -> &Path {
+        match self {
+            Segment::Frames { folder, .. } => folder,
+            Segment::Gif(path) => path,
+        }
+    }
+
+    /// Decode the frames one at a time, passing each to `f` in order.
+    fn for_each_frame(
+        &self,
+        mut f: impl FnMut(RgbaImage) -> Result<(), FractalError>,
+    ) -> Result<(), FractalError> {
+        let invalid = |path: &Path, e: image::ImageError| match e {
+            image::ImageError::IoError(e) => FractalError::Io(e),
+            e => FractalError::Image(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        match self {
+            Segment::Frames { files, .. } => {
+                for file in files {
+                    f(image::open(file).map_err(|e| invalid(file, e))?.to_rgba8())?;
+                }
+            }
+            Segment::Gif(path) => {
+                let file = std::io::BufReader::new(std::fs::File::open(path)?);
+                let decoder =
+                    image::codecs::gif::GifDecoder::new(file).map_err(|e| invalid(path, e))?;
+                for frame in decoder.into_frames() {
+                    f(frame.map_err(|e| invalid(path, e))?.into_buffer())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split the name of a frame file, without its extension, into the part
+/// before the number at its end and that number, if there is one.
+pub fn frame_number(file: &Path) -> (String, Option<u64>) {
+    let stem = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (prefix, number) = stem.split_at(stem.len() - digits);
+    (prefix.to_string(), number.parse().ok())
+}
+
+/// Sort key of a frame file: its name without the number at its end, then
+/// that number, then the whole name.
+fn frame_key(file: &Path) -> (String, u64, String) {
+    let (prefix, number) = frame_number(file);
+    (prefix, number.unwrap_or(0), file.display().to_string())
+}
+
+#[test]
+fn test_frame_key() {
+    let mut files: Vec<PathBuf> = ["b-2.png", "a-1000.png", "a-999.png", "a-01.png", "a.png"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    files.sort_by_cached_key(|file| frame_key(file));
+    let names: Vec<_> = files.iter().map(|f| f.display().to_string()).collect();
+    assert_eq!(
+        names,
+        ["a.png", "a-01.png", "a-999.png", "a-1000.png", "b-2.png"]
+    );
+}
+
+/// Blend `to` over `from` with weight `t` between 0 and 1.
+fn blend(from: &RgbaImage, to: &RgbaImage, t: f64) -> RgbaImage {
+    let mut mixed = from.clone();
+    for (mixed, (a, b)) in mixed.iter_mut().zip(from.iter().zip(to.iter())) {
+        *mixed = (*a as f64 + (*b as f64 - *a as f64) * t).round() as u8;
+    }
+    mixed
+}
+
+/// Join the frames of `segments` into the GIF `output`, in order.
+///
+/// Every frame must have the dimensions of the first. With a `crossfade`,
+/// that many frames blending the last frame of each segment into the first
+/// frame of the next are inserted between them. The frames are mapped to the
+/// closest colors of `palette`, and the GIF is transparent if the first frame
+/// has pixels with an alpha below one half. See
+/// [`make_gif`](super::preserve::make_gif) for the other arguments.
+pub fn concat(
+    segments: &[Segment],
+    output: &str,
+    delay: u16,
+    palette: &Palette,
+    playback: Playback,
+    loop_count: u16,
+    crossfade: usize,
+) -> Result<(), FractalError> {
+    let mut writer: Option<GifWriter> = None;
+    let mut last: Option<RgbaImage> = None;
+    for segment in segments {
+        let mut first = true;
+        segment.for_each_frame(|frame| {
+            let writer = match &mut writer {
+                Some(writer) => writer,
+                None => {
+                    let bounds = (frame.width() as usize, frame.height() as usize);
+                    let transparent = frame.pixels().any(|p| p.0[3] < 128);
+                    writer.insert(GifWriter::create(
+                        output,
+                        bounds,
+                        delay,
+                        palette,
+                        playback,
+                        loop_count,
+                        transparent,
+                    )?)
+                }
+            };
+            if let Some(previous) = &last {
+                if previous.dimensions() != frame.dimensions() {
+                    return Err(FractalError::Parse(format!(
+                        "{} has {}x{} frames, but the animation so far is {}x{}",
+                        segment.source().display(),
+                        frame.width(),
+                        frame.height(),
+                        previous.width(),
+                        previous.height()
+                    )));
+                }
+                if first {
+                    for step in 1..=crossfade {
+                        let t = step as f64 / (crossfade + 1) as f64;
+                        writer.add_image(&blend(previous, &frame, t))?;
+                    }
+                }
+            }
+            first = false;
+            writer.add_image(&frame)?;
+            last = Some(frame);
+            Ok(())
+        })?;
+        if first {
+            return Err(FractalError::Parse(format!(
+                "{} has no frames",
+                segment.source().display()
+            )));
+        }
+    }
+    match writer {
+        Some(writer) => writer.finish(),
+        None => Err(FractalError::Parse(
+            "No frames provided for GIF creation".to_string(),
+        )),
+    }
+}
+
+#[test]
+fn test_concat() {
+    use super::preserve::{make_gif, write_image};
+    let dir = tempfile::TempDir::new().unwrap();
+    let palette = Palette::grayscale();
+    // One folder of three frames, written out of order, and a GIF of two
+    let folder = dir.path().join("first");
+    for i in [10u8, 9, 8] {
+        let name = folder
+            .join(format!("frame-{}.png", i))
+            .display()
+            .to_string();
+        write_image(&name, &[i * 10; 6], (3, 2), None, None, &[]).unwrap();
+    }
+    let frames: Vec<String> = [200u8, 250]
+        .iter()
+        .map(|&level| {
+            let name = dir.path().join(format!("{}.png", level));
+            let name = name.display().to_string();
+            write_image(&name, &[level; 6], (3, 2), None, None, &[]).unwrap();
+            name
+        })
+        .collect();
+    let gif = dir.path().join("second.gif");
+    make_gif(
+        frames,
+        gif.to_str().unwrap(),
+        5,
+        &palette,
+        Playback::Loop,
+        0,
+        false,
+        1,
+        None,
+    )
+    .unwrap();
+
+    let segments = [
+        Segment::open(&folder).unwrap(),
+        Segment::open(&gif).unwrap(),
+    ];
+    let output = dir.path().join("joined.gif");
+    concat(
+        &segments,
+        output.to_str().unwrap(),
+        5,
+        &palette,
+        Playback::Once,
+        0,
+        1,
+    )
+    .unwrap();
+    let mut levels = Vec::new();
+    Segment::Gif(output)
+        .for_each_frame(|frame| {
+            levels.push(frame.get_pixel(0, 0).0[0]);
+            Ok(())
+        })
+        .unwrap();
+    // The crossfade lands halfway between 100 and 200
+    assert_eq!(levels, [80, 90, 100, 150, 200, 250]);
+
+    // Frames of another size are refused
+    let small = dir.path().join("small");
+    write_image(
+        small.join("0.png").to_str().unwrap(),
+        &[0u8; 4],
+        (2, 2),
+        None,
+        None,
+        &[],
+    )
+    .unwrap();
+    let segments = [segments[0].clone(), Segment::open(&small).unwrap()];
+    let output = dir.path().join("mixed.gif").display().to_string();
+    assert!(concat(&segments, &output, 5, &palette, Playback::Loop, 0, 0).is_err());
+    assert!(Segment::open(&dir.path().join("80.txt")).is_err());
+}
@@ -0,0 +1,113 @@
+use super::{Channel, ColorOptions, FractalParams, FractalType, font, render_parallel};
+use num::Complex;
+
+/// Number of tiles in each row of the sheet
+pub const COLUMNS: usize = 4;
+
+/// Render every fractal type in the same view and lay the results out in a
+/// labeled grid, `COLUMNS` tiles wide.
+///
+/// The sheet is as wide as `bounds`, and each tile keeps the aspect ratio of
+/// `bounds` so the view is not distorted. Apart from the fractal type, every
+/// tile uses `params`, with `escape_radius` if given or else the default
+/// radius of each type. The phoenix tile takes `phoenix_constant` in place of
+/// the Julia constant. Returns the pixels of the sheet and its dimensions.
+pub fn render<P: Channel>(
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    escape_radius: Option<f64>,
+    phoenix_constant: Complex<f64>,
+    colors: &ColorOptions,
+) -> (Vec<P>, (usize, usize)) {
+    let types = FractalType::all();
+    let rows = types.len().div_ceil(COLUMNS);
+    let tile_width = (bounds.0 / COLUMNS).max(1);
+    let tile = (tile_width, (tile_width * bounds.1 / bounds.0).max(1));
+    let sheet_bounds = (tile.0 * COLUMNS, tile.1 * rows);
+    let mut sheet = vec![P::from_level(0); sheet_bounds.0 * sheet_bounds.1];
+
+    let mut pixels = vec![P::from_level(0); tile.0 * tile.1];
+    for (i, &fractal_type) in types.iter().enumerate() {
+        let params = FractalParams {
+            fractal_type,
+            escape_radius: escape_radius.unwrap_or_else(|| fractal_type.default_escape_radius()),
+            julia_constant: match fractal_type {
+                FractalType::Phoenix => Some(phoenix_constant),
+                _ => params.julia_constant,
+            },
+            ..*params
+        };
+        render_parallel(&mut pixels, tile, upper_left, lower_right, &params, colors);
+        label(&mut pixels, tile, fractal_type.name());
+
+        // Copy the tile into its cell, row by row
+        let origin = ((i % COLUMNS) * tile.0, (i / COLUMNS) * tile.1);
+        for (row, tile_row) in pixels.chunks(tile.0).enumerate() {
+            let start = (origin.1 + row) * sheet_bounds.0 + origin.0;
+            sheet[start..start + tile.0].copy_from_slice(tile_row);
+        }
+    }
+    (sheet, sheet_bounds)
+}
+
+/// Write `text` in white on a dark box in the upper-left corner of a tile.
+fn label<P: Channel>(pixels: &mut [P], bounds: (usize, usize), text: &str) {
+    let scale = (bounds.0 / 256).max(1);
+    let (width, height) = font::text_size(text, scale);
+    font::fill_rect(
+        pixels,
+        bounds,
+        (0, 0),
+        (width + 4 * scale, height + 4 * scale),
+        P::from_level(0),
+    );
+    font::draw_text(
+        pixels,
+        bounds,
+        (2 * scale, 2 * scale),
+        text,
+        scale,
+        P::from_level(P::MAX),
+    );
+}
+
+#[test]
+fn test_render_contact_sheet() {
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let upper_left = Complex { re: -2.0, im: 1.5 };
+    let lower_right = Complex { re: 1.0, im: -1.5 };
+    let (sheet, sheet_bounds) = render::<u8>(
+        (200, 100),
+        upper_left,
+        lower_right,
+        &params,
+        None,
+        super::DEFAULT_PHOENIX_CONSTANT,
+        &colors,
+    );
+    assert_eq!(sheet_bounds, (200, 100));
+    assert_eq!(sheet.len(), 200 * 100);
+
+    // The first tile matches a plain render of the Mandelbrot set, apart from
+    // its label in the corner
+    let mut expected = vec![0u8; 50 * 25];
+    render_parallel(
+        &mut expected,
+        (50, 25),
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+    );
+    for row in 12..25 {
+        assert_eq!(
+            sheet[row * 200..row * 200 + 50],
+            expected[row * 50..(row + 1) * 50]
+        );
+    }
+    // The label is drawn in white
+    assert!(sheet[..200 * 11].contains(&255));
+}
@@ -0,0 +1,95 @@
+use super::{FractalParams, step};
+use num::Complex;
+use rayon::prelude::*;
+
+/// Return the domain color of `value`: its argument picks the hue, once
+/// around the color wheel from red on the positive real axis, and its
+/// modulus the brightness, from black at zero towards full brightness as it
+/// grows, at half brightness on the unit circle.
+///
+/// Infinite values are white and undefined ones black.
+pub fn color(value: Complex<f64>) -> [u8; 3] {
+    let modulus = value.norm();
+    if modulus.is_nan() {
+        return [0; 3];
+    }
+    if modulus.is_infinite() {
+        return [u8::MAX; 3];
+    }
+    let hue = (value.arg() / std::f64::consts::TAU).rem_euclid(1.0) * 6.0;
+    let brightness = modulus / (1.0 + modulus);
+    // Full saturation: one channel at the brightness, one at zero and the
+    // third ramping between them across each sixth of the wheel
+    let ramp = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, ramp, 0.0),
+        1 => (ramp, 1.0, 0.0),
+        2 => (0.0, 1.0, ramp),
+        3 => (0.0, ramp, 1.0),
+        4 => (ramp, 0.0, 1.0),
+        _ => (1.0, 0.0, ramp),
+    };
+    [r, g, b].map(|channel: f64| (channel * brightness * u8::MAX as f64).round() as u8)
+}
+
+/// Render the view between `upper_left` and `lower_right` into `pixels` by
+/// applying the formula of `params` once to the point of each pixel, as
+/// `z`, and domain coloring the result with [`color`].
+///
+/// The point `c` that parameter-plane formulas like `z^power + c` add is
+/// zero, so every pixel shows the same map, while Julia sets add their
+/// constant as usual. Rows are rendered in parallel on the current rayon
+/// thread pool.
+pub fn render(
+    pixels: &mut [[u8; 3]],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    pixels
+        .par_chunks_mut(bounds.0)
+        .enumerate()
+        .for_each(|(row, row_pixels)| {
+            for (column, pixel) in row_pixels.iter_mut().enumerate() {
+                let z = params.sample_point(bounds, (column, row), upper_left, lower_right);
+                let value = step(
+                    z,
+                    Complex::default(),
+                    Complex::default(),
+                    params.power,
+                    params.fractal_type,
+                    params.julia_constant,
+                );
+                *pixel = color(value);
+            }
+        });
+}
+
+#[test]
+fn test_domain_coloring() {
+    let at = |re, im| color(Complex { re, im });
+    assert_eq!(at(1.0, 0.0), [128, 0, 0]);
+    assert_eq!(at(0.0, 3.0), [96, 191, 0]);
+    assert_eq!(at(-1.0, 0.0), [0, 128, 128]);
+    assert_eq!(at(0.0, 0.0), [0, 0, 0]);
+    assert_eq!(at(f64::INFINITY, 0.0), [255, 255, 255]);
+    assert_eq!(at(f64::NAN, 0.0), [0, 0, 0]);
+
+    // z^2 turns the plane twice around, so opposite points share a color
+    let params = FractalParams::default();
+    let bounds = (4, 4);
+    let mut pixels = vec![[0; 3]; bounds.0 * bounds.1];
+    render(
+        &mut pixels,
+        bounds,
+        Complex { re: -2.0, im: 2.0 },
+        Complex { re: 2.0, im: -2.0 },
+        &params,
+    );
+    for (i, pixel) in pixels.iter().enumerate() {
+        assert_eq!(*pixel, pixels[pixels.len() - 1 - i]);
+    }
+    assert_eq!(pixels[0], color(Complex { re: -1.5, im: 1.5 }.powi(2)));
+}
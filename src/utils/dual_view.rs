@@ -0,0 +1,146 @@
+use super::{Channel, ColorOptions, DEFAULT_JULIA_CONSTANT, FractalParams, FractalType};
+use super::{render_parallel, transform};
+use num::Complex;
+
+/// Render the Mandelbrot set and the Julia set of `params.julia_constant`
+/// side by side, with a dot on the Mandelbrot set where the constant lies.
+///
+/// Both halves keep the pixel size of the view between `upper_left` and
+/// `lower_right`, so neither is distorted. The Mandelbrot half shows the
+/// middle of that view, and the Julia half the same extent centered on the
+/// origin, where Julia sets are centered. Apart from the fractal type, both
+/// halves use `params`.
+pub fn render<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    let constant = params.julia_constant.unwrap_or(DEFAULT_JULIA_CONSTANT);
+    let center = (upper_left + lower_right) / 2.0;
+    let origin = Complex { re: 0.0, im: 0.0 };
+    let halves = [
+        (bounds.0 / 2, center, FractalType::Mandelbrot),
+        (bounds.0 - bounds.0 / 2, origin, FractalType::Julia),
+    ];
+
+    let pixel_size = (lower_right.re - upper_left.re) / bounds.0 as f64;
+    let half_height = (upper_left.im - lower_right.im) / 2.0;
+    let mut offset = 0;
+    for (width, center, fractal_type) in halves {
+        let half_bounds = (width, bounds.1);
+        let half_width = width as f64 * pixel_size / 2.0;
+        let half_upper_left = center
+            + Complex {
+                re: -half_width,
+                im: half_height,
+            };
+        let half_lower_right = center
+            + Complex {
+                re: half_width,
+                im: -half_height,
+            };
+        let params = FractalParams {
+            fractal_type,
+            julia_constant: Some(constant),
+            ..*params
+        };
+        let mut half = vec![P::default(); width * bounds.1];
+        render_parallel(
+            &mut half,
+            half_bounds,
+            half_upper_left,
+            half_lower_right,
+            &params,
+            colors,
+        );
+        if fractal_type == FractalType::Mandelbrot {
+            let pixel =
+                transform::point_to_pixel(half_bounds, constant, half_upper_left, half_lower_right);
+            if let Some(pixel) = pixel {
+                marker(&mut half, half_bounds, pixel);
+            }
+        }
+
+        // Copy the half into its side of the frame, row by row
+        for (row, half_row) in half.chunks(width.max(1)).enumerate() {
+            let start = row * bounds.0 + offset;
+            pixels[start..start + width].copy_from_slice(half_row);
+        }
+        offset += width;
+    }
+}
+
+/// Draw a white dot ringed in black, so it shows on any background, centered
+/// on `pixel` and sized to the image.
+fn marker<P: Channel>(pixels: &mut [P], bounds: (usize, usize), pixel: (usize, usize)) {
+    let radius = (bounds.1 / 100).max(2) as isize;
+    let (column, row) = (pixel.0 as isize, pixel.1 as isize);
+    for y in row - radius - 1..=row + radius + 1 {
+        for x in column - radius - 1..=column + radius + 1 {
+            if x < 0 || y < 0 || x >= bounds.0 as isize || y >= bounds.1 as isize {
+                continue;
+            }
+            let distance = (x - column).pow(2) + (y - row).pow(2);
+            let value = if distance <= radius * radius {
+                P::from_level(P::MAX)
+            } else if distance <= (radius + 1) * (radius + 1) {
+                P::from_level(0)
+            } else {
+                continue;
+            };
+            let index = y as usize * bounds.0 + x as usize;
+            pixels[index] = value.with_alpha(u8::MAX);
+        }
+    }
+}
+
+#[test]
+fn test_render_dual_view() {
+    let constant = Complex { re: -0.5, im: 0.5 };
+    let params = FractalParams {
+        julia_constant: Some(constant),
+        ..FractalParams::default()
+    };
+    let colors = ColorOptions::default();
+    let bounds = (120, 60);
+    let upper_left = Complex { re: -3.0, im: 1.5 };
+    let lower_right = Complex { re: 3.0, im: -1.5 };
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    render(
+        &mut pixels,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+    );
+
+    // The right half is the Julia set, centered on the origin at the same scale
+    let mut julia = vec![0u8; 60 * 60];
+    let julia_params = FractalParams {
+        fractal_type: FractalType::Julia,
+        ..params
+    };
+    render_parallel(
+        &mut julia,
+        (60, 60),
+        Complex { re: -1.5, im: 1.5 },
+        Complex { re: 1.5, im: -1.5 },
+        &julia_params,
+        &colors,
+    );
+    for (row, julia_row) in pixels.chunks(bounds.0).zip(julia.chunks(60)) {
+        assert_eq!(&row[60..], julia_row);
+    }
+
+    // The left half shows the Mandelbrot set from -1.5 to 1.5, with a white
+    // dot ringed in black on the constant at column 20 and row 20
+    assert_eq!(pixels[20 * bounds.0 + 20], 255);
+    assert_eq!(pixels[20 * bounds.0 + 22], 255);
+    assert_eq!(pixels[20 * bounds.0 + 23], 0);
+    assert!(pixels[..60].iter().all(|&p| p > 0));
+}
@@ -0,0 +1,61 @@
+/// What went wrong saving frames and animations, told apart so that callers
+/// can match on the kind of failure rather than on its message
+#[derive(Debug, thiserror::Error)]
+pub enum FractalError {
+    /// Input that cannot be used as given, like an empty list of frames or
+    /// frames of different sizes
+    #[error("{0}")]
+    Parse(String),
+    /// A file or folder could not be read or written
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// An image could not be encoded or decoded
+    #[error("{0}")]
+    Image(String),
+    /// A GIF could not be encoded
+    #[error("{0}")]
+    Gif(String),
+}
+
+impl From<png::EncodingError> for FractalError {
+    fn from(error: png::EncodingError) -> Self {
+        match error {
+            png::EncodingError::IoError(error) => FractalError::Io(error),
+            error => FractalError::Image(error.to_string()),
+        }
+    }
+}
+
+impl From<image::ImageError> for FractalError {
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(error) => FractalError::Io(error),
+            error => FractalError::Image(error.to_string()),
+        }
+    }
+}
+
+impl From<gif::EncodingError> for FractalError {
+    fn from(error: gif::EncodingError) -> Self {
+        match error {
+            gif::EncodingError::Io(error) => FractalError::Io(error),
+            error => FractalError::Gif(error.to_string()),
+        }
+    }
+}
+
+#[test]
+fn test_error_sources() {
+    let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+    let error = FractalError::from(png::EncodingError::IoError(missing));
+    assert!(matches!(error, FractalError::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+
+    let error = FractalError::from(gif::EncodingError::from(
+        gif::EncodingFormatError::MissingColorPalette,
+    ));
+    assert!(matches!(error, FractalError::Gif(_)));
+    assert_eq!(
+        FractalError::Parse("no frames".to_string()).to_string(),
+        "no frames"
+    );
+}
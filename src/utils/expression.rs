@@ -0,0 +1,196 @@
+/// Deepest nesting of parentheses and signs an expression may have, which
+/// keeps hostile input from overflowing the stack
+const MAX_DEPTH: usize = 64;
+
+/// Evaluate a small arithmetic expression, like `"pi/4"` or `"-(1 + 2e-3)^2"`,
+/// returning `None` if it is malformed or its value is not finite.
+///
+/// Expressions are made of numbers in any form `f64` parses, the constants
+/// `pi`, `tau` and `e`, parentheses, the binary operators `+`, `-`, `*`, `/`
+/// and `^`, and signs. Powers bind tightest and group from the right, then
+/// signs, so `-2^2` is -4, then products and sums from the left.
+pub fn evaluate(s: &str) -> Option<f64> {
+    let mut parser = Parser {
+        bytes: s.as_bytes(),
+        position: 0,
+        depth: 0,
+    };
+    let value = parser.sum()?;
+    parser.skip_whitespace();
+    if parser.position != parser.bytes.len() || !value.is_finite() {
+        return None;
+    }
+    Some(value)
+}
+
+/// A recursive descent parser over the bytes of an expression
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Skip whitespace and consume the next byte if it is one of `operators`.
+    fn operator(&mut self, operators: &[u8]) -> Option<u8> {
+        self.skip_whitespace();
+        let byte = *self.bytes.get(self.position)?;
+        if operators.contains(&byte) {
+            self.position += 1;
+            Some(byte)
+        } else {
+            None
+        }
+    }
+
+    /// Parse terms joined by `+` and `-`.
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+        while let Some(operator) = self.operator(b"+-") {
+            let term = self.product()?;
+            value = if operator == b'+' {
+                value + term
+            } else {
+                value - term
+            };
+        }
+        Some(value)
+    }
+
+    /// Parse factors joined by `*` and `/`.
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.signed()?;
+        while let Some(operator) = self.operator(b"*/") {
+            let factor = self.signed()?;
+            value = if operator == b'*' {
+                value * factor
+            } else {
+                value / factor
+            };
+        }
+        Some(value)
+    }
+
+    /// Parse a power with any number of leading signs.
+    fn signed(&mut self) -> Option<f64> {
+        match self.operator(b"+-") {
+            Some(sign) => {
+                let value = self.nested(Parser::signed)?;
+                Some(if sign == b'-' { -value } else { value })
+            }
+            None => self.power(),
+        }
+    }
+
+    /// Parse an atom, raised to a signed power if `^` follows.
+    fn power(&mut self) -> Option<f64> {
+        let base = self.atom()?;
+        match self.operator(b"^") {
+            Some(_) => Some(base.powf(self.nested(Parser::signed)?)),
+            None => Some(base),
+        }
+    }
+
+    /// Parse a number, a constant or an expression in parentheses.
+    fn atom(&mut self) -> Option<f64> {
+        if self.operator(b"(").is_some() {
+            let value = self.nested(Parser::sum)?;
+            self.operator(b")")?;
+            return Some(value);
+        }
+        let start = self.position;
+        let byte = *self.bytes.get(start)?;
+        if byte.is_ascii_alphabetic() {
+            while self
+                .bytes
+                .get(self.position)
+                .is_some_and(u8::is_ascii_alphabetic)
+            {
+                self.position += 1;
+            }
+            let name = std::str::from_utf8(&self.bytes[start..self.position]).ok()?;
+            return match name.to_ascii_lowercase().as_str() {
+                "pi" => Some(std::f64::consts::PI),
+                "tau" => Some(std::f64::consts::TAU),
+                "e" => Some(std::f64::consts::E),
+                _ => None,
+            };
+        }
+        let digits = |parser: &mut Parser| {
+            while parser
+                .bytes
+                .get(parser.position)
+                .is_some_and(|&b| b.is_ascii_digit() || b == b'.')
+            {
+                parser.position += 1;
+            }
+        };
+        digits(self);
+        // An exponent only follows a mantissa, and only if digits follow it
+        if self.position > start && matches!(self.bytes.get(self.position), Some(b'e' | b'E')) {
+            let mut end = self.position + 1;
+            if matches!(self.bytes.get(end), Some(b'+' | b'-')) {
+                end += 1;
+            }
+            if self.bytes.get(end).is_some_and(u8::is_ascii_digit) {
+                self.position = end;
+                digits(self);
+            }
+        }
+        let number = std::str::from_utf8(&self.bytes[start..self.position]).ok()?;
+        number.parse().ok()
+    }
+
+    /// Run `parse` one level deeper, failing past [`MAX_DEPTH`].
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<f64>) -> Option<f64> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+}
+
+#[test]
+fn test_evaluate() {
+    use std::f64::consts::PI;
+    assert_eq!(evaluate("0.25"), Some(0.25));
+    assert_eq!(evaluate("1e-15"), Some(1e-15));
+    assert_eq!(evaluate("2.5E+3"), Some(2500.0));
+    assert_eq!(evaluate("pi/4"), Some(PI / 4.0));
+    assert_eq!(evaluate(" -PI * 2 "), Some(-2.0 * PI));
+    assert_eq!(evaluate("tau - 2*pi"), Some(0.0));
+    assert_eq!(evaluate("e"), Some(std::f64::consts::E));
+    assert_eq!(evaluate("1 - 2 - 3"), Some(-4.0));
+    assert_eq!(evaluate("8 / 4 / 2"), Some(1.0));
+    assert_eq!(evaluate("1 + 2 * 3"), Some(7.0));
+    assert_eq!(evaluate("(1 + 2) * 3"), Some(9.0));
+    assert_eq!(evaluate("2^3^2"), Some(512.0));
+    assert_eq!(evaluate("-2^2"), Some(-4.0));
+    assert_eq!(evaluate("2^-1"), Some(0.5));
+    assert_eq!(evaluate("--1"), Some(1.0));
+
+    // Malformed or infinite expressions are refused
+    for s in [
+        "", " ", "1 +", "* 2", "(1", "1)", "2e", "2pi", "1..5", "foo", "1/0", "1 2", "é",
+    ] {
+        assert_eq!(evaluate(s), None, "{:?}", s);
+    }
+    let deep = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+    assert_eq!(evaluate(&deep), None);
+    assert_eq!(evaluate(&"-".repeat(10_000)), None);
+    let shallow = format!("{}1{}", "(".repeat(10), ")".repeat(10));
+    assert_eq!(evaluate(&shallow), Some(1.0));
+}
@@ -0,0 +1,290 @@
+use super::palette::Palette;
+
+/// The sRGB primaries adapted to the D50 white of the ICC connection space,
+/// as the columns of the matrix from linear sRGB to XYZ
+const SRGB_TO_XYZ: [[f64; 3]; 3] = [
+    [0.4360747, 0.3850649, 0.1430804],
+    [0.2225045, 0.7168786, 0.0606169],
+    [0.0139322, 0.0971045, 0.7141733],
+];
+
+/// A tone curve of an ICC profile, from encoded values to linear light, both
+/// in `0.0..=1.0`
+#[derive(Debug, Clone, PartialEq)]
+enum Curve {
+    Gamma(f64),
+    /// Values at evenly spaced inputs, interpolated linearly
+    Table(Vec<f64>),
+    /// One of the five parametric functions of the `para` type, with its
+    /// parameters g, a, b, c, d, e and f, the missing ones zero
+    Parametric(u16, [f64; 7]),
+}
+
+impl Curve {
+    fn apply(&self, x: f64) -> f64 {
+        match self {
+            Curve::Gamma(gamma) => x.powf(*gamma),
+            Curve::Table(table) => {
+                let position = x * (table.len() - 1) as f64;
+                let i = (position as usize).min(table.len() - 2);
+                let f = position - i as f64;
+                table[i] + (table[i + 1] - table[i]) * f
+            }
+            Curve::Parametric(kind, [g, a, b, c, d, e, f]) => match kind {
+                0 => x.powf(*g),
+                1 if x >= -b / a => (a * x + b).max(0.0).powf(*g),
+                1 => 0.0,
+                2 if x >= -b / a => (a * x + b).max(0.0).powf(*g) + c,
+                2 => *c,
+                3 if x >= *d => (a * x + b).max(0.0).powf(*g),
+                3 => c * x,
+                _ if x >= *d => (a * x + b).max(0.0).powf(*g) + e,
+                _ => c * x + f,
+            },
+        }
+    }
+
+    /// Return the encoded value whose linear light is `y`, for a curve that
+    /// rises from 0 to 1.
+    fn invert(&self, y: f64) -> f64 {
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..40 {
+            let middle = (low + high) / 2.0;
+            if self.apply(middle) < y {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        (low + high) / 2.0
+    }
+}
+
+/// An RGB profile built from a matrix and three tone curves, the kind most
+/// display and working-space profiles are
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// Matrix from the profile's linear RGB to XYZ, whose columns are its
+    /// red, green and blue colorants
+    to_xyz: [[f64; 3]; 3],
+    curves: [Curve; 3],
+}
+
+impl Profile {
+    /// Read the matrix and curves of the ICC profile `profile`, which must be
+    /// an RGB one; profiles that describe their colors by lookup tables
+    /// alone are refused.
+    pub fn parse(profile: &[u8]) -> Result<Profile, String> {
+        super::preserve::check_icc_profile(profile)?;
+        if &profile[16..20] != b"RGB " {
+            return Err("only RGB profiles can convert palette colors".to_string());
+        }
+        let colorant = |signature: &[u8; 4]| -> Result<[f64; 3], String> {
+            let data = tag(profile, signature)?;
+            if data.len() < 20 || &data[..4] != b"XYZ " {
+                return Err(format!("invalid {} tag", name(signature)));
+            }
+            Ok([
+                s15_fixed16(&data[8..]),
+                s15_fixed16(&data[12..]),
+                s15_fixed16(&data[16..]),
+            ])
+        };
+        let columns = [colorant(b"rXYZ")?, colorant(b"gXYZ")?, colorant(b"bXYZ")?];
+        let to_xyz = std::array::from_fn(|row| std::array::from_fn(|column| columns[column][row]));
+        let curves = [
+            curve(profile, b"rTRC")?,
+            curve(profile, b"gTRC")?,
+            curve(profile, b"bTRC")?,
+        ];
+        Ok(Profile { to_xyz, curves })
+    }
+
+    /// Return the values in this profile's space of the sRGB color `color`,
+    /// with the colors it cannot show clipped to its gamut.
+    pub fn from_srgb(&self, color: [u8; 3]) -> [u8; 3] {
+        let linear = color.map(|value| {
+            let x = value as f64 / 255.0;
+            if x <= 0.04045 {
+                x / 12.92
+            } else {
+                ((x + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        let xyz = multiply(&SRGB_TO_XYZ, linear);
+        let rgb = multiply(&invert(&self.to_xyz), xyz);
+        std::array::from_fn(|i| {
+            let encoded = self.curves[i].invert(rgb[i].clamp(0.0, 1.0));
+            (encoded * 255.0).round() as u8
+        })
+    }
+}
+
+/// Return `palette` with every color converted from sRGB into the space of
+/// the ICC profile `profile`, see [`Profile::from_srgb`].
+pub fn convert_palette(palette: &Palette, profile: &[u8]) -> Result<Palette, String> {
+    let profile = Profile::parse(profile)?;
+    Ok(Palette(palette.0.map(|color| profile.from_srgb(color))))
+}
+
+/// Return the data of the tag `signature` of `profile`.
+fn tag<'a>(profile: &'a [u8], signature: &[u8; 4]) -> Result<&'a [u8], String> {
+    let count = u32_at(profile, 128).ok_or("truncated tag table")? as usize;
+    for i in 0..count {
+        let entry = 132 + i * 12;
+        let (Some(offset), Some(size)) = (u32_at(profile, entry + 4), u32_at(profile, entry + 8))
+        else {
+            return Err("truncated tag table".to_string());
+        };
+        if profile.get(entry..entry + 4) == Some(signature) {
+            let (offset, size) = (offset as usize, size as usize);
+            return profile
+                .get(offset..offset + size)
+                .ok_or(format!("the {} tag runs past the end", name(signature)));
+        }
+    }
+    Err(format!(
+        "the profile has no {} tag, as profiles built from lookup tables alone do not",
+        name(signature)
+    ))
+}
+
+/// Read the tone curve tag `signature`, of the `curv` or `para` type.
+fn curve(profile: &[u8], signature: &[u8; 4]) -> Result<Curve, String> {
+    let data = tag(profile, signature)?;
+    let invalid = || format!("invalid {} tag", name(signature));
+    match data.get(..4) {
+        Some(b"curv") => {
+            let count = u32_at(data, 8).ok_or_else(invalid)? as usize;
+            let values = data.get(12..12 + 2 * count).ok_or_else(invalid)?;
+            let value = |i: usize| u16::from_be_bytes([values[2 * i], values[2 * i + 1]]);
+            Ok(match count {
+                0 => Curve::Gamma(1.0),
+                1 => Curve::Gamma(value(0) as f64 / 256.0),
+                _ => Curve::Table((0..count).map(|i| value(i) as f64 / 65535.0).collect()),
+            })
+        }
+        Some(b"para") => {
+            let kind = data
+                .get(8..10)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or_else(invalid)?;
+            let n = [1, 3, 4, 5, 7].get(kind as usize).ok_or_else(invalid)?;
+            let fields = data.get(12..12 + 4 * n).ok_or_else(invalid)?;
+            let mut parameters = [0.0; 7];
+            for (i, parameter) in parameters.iter_mut().take(*n).enumerate() {
+                *parameter = s15_fixed16(&fields[4 * i..]);
+            }
+            Ok(Curve::Parametric(kind, parameters))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Read a signed 15.16 fixed-point number from the start of `data`.
+fn s15_fixed16(data: &[u8]) -> f64 {
+    i32::from_be_bytes(data[..4].try_into().unwrap()) as f64 / 65536.0
+}
+
+fn name(signature: &[u8; 4]) -> String {
+    String::from_utf8_lossy(signature).into_owned()
+}
+
+fn multiply(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+fn invert(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let cofactor = |r: usize, c: usize| {
+        let (r1, r2) = ((r + 1) % 3, (r + 2) % 3);
+        let (c1, c2) = ((c + 1) % 3, (c + 2) % 3);
+        m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]
+    };
+    let determinant: f64 = (0..3).map(|c| m[0][c] * cofactor(0, c)).sum();
+    std::array::from_fn(|r| std::array::from_fn(|c| cofactor(c, r) / determinant))
+}
+
+/// Build an RGB profile with the sRGB primaries and the given tone curve
+/// tag for every channel.
+#[cfg(test)]
+fn test_profile(curve: &[u8]) -> Vec<u8> {
+    let mut tags: Vec<(&[u8; 4], Vec<u8>)> = Vec::new();
+    for (signature, column) in [(b"rXYZ", 0), (b"gXYZ", 1), (b"bXYZ", 2)] {
+        let mut data = b"XYZ \0\0\0\0".to_vec();
+        for row in SRGB_TO_XYZ {
+            data.extend(((row[column] * 65536.0).round() as i32).to_be_bytes());
+        }
+        tags.push((signature, data));
+    }
+    for signature in [b"rTRC", b"gTRC", b"bTRC"] {
+        tags.push((signature, curve.to_vec()));
+    }
+    let mut profile = vec![0u8; 128];
+    profile[16..20].copy_from_slice(b"RGB ");
+    profile[20..24].copy_from_slice(b"XYZ ");
+    profile[36..40].copy_from_slice(b"acsp");
+    profile.extend((tags.len() as u32).to_be_bytes());
+    let mut offset = 132 + 12 * tags.len();
+    let mut data: Vec<u8> = Vec::new();
+    for (signature, tag) in &tags {
+        profile.extend(*signature);
+        profile.extend((offset as u32).to_be_bytes());
+        profile.extend((tag.len() as u32).to_be_bytes());
+        offset += tag.len();
+        data.extend(tag);
+    }
+    profile.extend(data);
+    let size = (profile.len() as u32).to_be_bytes();
+    profile[..4].copy_from_slice(&size);
+    profile
+}
+
+#[test]
+fn test_convert_palette() {
+    // The sRGB curve as a parametric function of type 3
+    let mut srgb = b"para\0\0\0\0\0\x03\0\0".to_vec();
+    for parameter in [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045] {
+        srgb.extend(((parameter * 65536.0f64).round() as i32).to_be_bytes());
+    }
+    let palette = Palette::from_gradient(
+        &super::palette::Gradient::parse("0 0 0 255\n0.5 30 200 90\n1 255 128 0").unwrap(),
+    );
+    let converted = convert_palette(&palette, &test_profile(&srgb)).unwrap();
+    for (a, b) in palette.0.iter().zip(converted.0.iter()) {
+        assert!((0..3).all(|i| a[i].abs_diff(b[i]) <= 1), "{:?} {:?}", a, b);
+    }
+
+    // A linear profile stores linear light, and a gamma of 2.2 comes close
+    // to sRGB; as a table, the same curve gives the same colors
+    let linear = test_profile(b"curv\0\0\0\0\0\0\0\0");
+    let profile = Profile::parse(&linear).unwrap();
+    assert_eq!(profile.from_srgb([128, 0, 255]), [55, 0, 255]);
+    let gamma = test_profile(b"curv\0\0\0\0\0\0\0\x01\x02\x33");
+    let color = Profile::parse(&gamma).unwrap().from_srgb([128, 64, 200]);
+    assert!(
+        color
+            .iter()
+            .zip([128, 64, 200])
+            .all(|(&a, b)| a.abs_diff(b) <= 3)
+    );
+    let mut table = b"curv\0\0\0\0\0\0\x01\0".to_vec();
+    for i in 0..256 {
+        table.extend((((i as f64 / 255.0).powf(2.2) * 65535.0).round() as u16).to_be_bytes());
+    }
+    let table = Profile::parse(&test_profile(&table)).unwrap();
+    assert!((table.from_srgb([128, 64, 200]).iter().zip(color)).all(|(&a, b)| a.abs_diff(b) <= 1));
+
+    // Gray profiles, and RGB ones without a matrix, cannot convert colors
+    let mut gray = linear.clone();
+    gray[16..20].copy_from_slice(b"GRAY");
+    assert!(Profile::parse(&gray).is_err());
+    let mut lookup = linear;
+    lookup[132..136].copy_from_slice(b"A2B0");
+    assert!(Profile::parse(&lookup).unwrap_err().contains("no rXYZ tag"));
+}
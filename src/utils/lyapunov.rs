@@ -0,0 +1,122 @@
+use super::{Channel, ColorOptions};
+use std::str::FromStr;
+
+/// The pattern of "A" and "B" steps that drives a Lyapunov fractal.
+///
+/// Stored as a bitmask (a set bit means "B") so that it stays `Copy` like the
+/// rest of the fractal parameters; sequences may be up to 64 steps long.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sequence {
+    bits: u64,
+    len: u32,
+}
+
+impl Sequence {
+    /// Return true if step `n` of the repeating sequence uses the B parameter.
+    pub fn uses_b(&self, n: usize) -> bool {
+        self.bits >> (n % self.len as usize) & 1 == 1
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        "AB".parse().unwrap()
+    }
+}
+
+impl FromStr for Sequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > 64 {
+            return Err(format!("`{}` must contain between 1 and 64 steps", s));
+        }
+        let mut bits = 0;
+        for (i, step) in s.chars().enumerate() {
+            match step.to_ascii_uppercase() {
+                'A' => {}
+                'B' => bits |= 1 << i,
+                _ => return Err(format!("`{}` may only contain the letters A and B", s)),
+            }
+        }
+        Ok(Sequence {
+            bits,
+            len: s.len() as u32,
+        })
+    }
+}
+
+impl std::fmt::Display for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for n in 0..self.len as usize {
+            f.write_str(if self.uses_b(n) { "B" } else { "A" })?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_sequence() {
+    let sequence: Sequence = "AAB".parse().unwrap();
+    assert!(!sequence.uses_b(0));
+    assert!(!sequence.uses_b(1));
+    assert!(sequence.uses_b(2));
+    assert!(!sequence.uses_b(3));
+    assert!(sequence.uses_b(5));
+    assert_eq!("ab".parse::<Sequence>(), Ok(Sequence::default()));
+    assert!("".parse::<Sequence>().is_err());
+    assert!("ABC".parse::<Sequence>().is_err());
+    assert_eq!(sequence.to_string(), "AAB");
+}
+
+/// Compute the Lyapunov exponent of the logistic map `x = r * x * (1 - x)`,
+/// where `r` alternates between `a` and `b` following `sequence`.
+///
+/// The first quarter of the `limit` iterations lets the orbit settle and is
+/// not counted. A negative exponent means the orbit is stable, a positive one
+/// that it is chaotic.
+pub fn exponent(a: f64, b: f64, sequence: Sequence, limit: usize) -> f64 {
+    assert!(limit > 0);
+
+    let warmup = limit / 4;
+    let mut x = 0.5;
+    let mut sum = 0.0;
+    for n in 0..warmup + limit {
+        let r = if sequence.uses_b(n) { b } else { a };
+        x = r * x * (1.0 - x);
+        if n >= warmup {
+            sum += (r * (1.0 - 2.0 * x)).abs().ln();
+        }
+    }
+
+    let exponent = sum / limit as f64;
+    // A superstable orbit hits ln(0); treat it as maximally stable
+    if exponent.is_nan() {
+        f64::NEG_INFINITY
+    } else {
+        exponent
+    }
+}
+
+#[test]
+fn test_exponent() {
+    let sequence = Sequence::default();
+    // r = 3.2 settles into a stable 2-cycle
+    assert!(exponent(3.2, 3.2, sequence, 1000) < 0.0);
+    // r = 4 is fully chaotic with exponent ln(2)
+    assert!((exponent(3.999_999, 3.999_999, sequence, 5000) - 2f64.ln()).abs() < 0.1);
+}
+
+/// Map a Lyapunov exponent to a grayscale pixel value.
+///
+/// Stable regions (negative exponents) get brighter the more stable they are,
+/// while chaotic regions are painted with the interior color.
+pub fn shade<P: Channel>(exponent: f64, colors: &ColorOptions) -> P {
+    if exponent >= 0.0 {
+        return colors.shade(None, 1);
+    }
+
+    let stability = -exponent.tanh();
+    let level = (P::MAX as f64 * stability).round() as u32;
+    P::from_level(colors.cycle::<P>(if colors.invert { P::MAX - level } else { level }))
+}
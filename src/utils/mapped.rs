@@ -0,0 +1,252 @@
+use super::Channel;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+/// A pixel buffer backed by a memory-mapped temporary file.
+///
+/// The operating system pages the pixels in and out of the file as they are
+/// used, so a still far larger than the free memory can be rendered. The file
+/// is unlinked from its folder as soon as it is created and disappears with
+/// the buffer.
+pub struct MappedBuffer<P> {
+    pixels: *mut P,
+    len: usize,
+    /// Keeps the mapped file open for as long as the mapping lives
+    _file: std::fs::File,
+}
+
+// SAFETY: the buffer owns its mapping exclusively, like a `Vec<P>`
+unsafe impl<P: Send> Send for MappedBuffer<P> {}
+unsafe impl<P: Sync> Sync for MappedBuffer<P> {}
+
+impl<P: Channel> MappedBuffer<P> {
+    /// Map `len` pixels, all set to `P::default()`, onto a new temporary file
+    /// in `folder`, creating the folder if needed.
+    ///
+    /// Put the file on a disk with room for the whole image: a folder on a
+    /// memory-backed file system would only move the pixels back into memory.
+    #[cfg(unix)]
+    pub fn new(len: usize, folder: &Path) -> Result<MappedBuffer<P>, std::io::Error> {
+        use std::os::fd::AsRawFd;
+
+        std::fs::create_dir_all(folder)?;
+        let file = tempfile::tempfile_in(folder)?;
+        let bytes = len.checked_mul(size_of::<P>()).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("A buffer of {} pixels is too large to map", len),
+            )
+        })?;
+        if bytes == 0 {
+            // Empty mappings are refused, and a dangling pointer reads as empty
+            return Ok(MappedBuffer {
+                pixels: std::ptr::NonNull::dangling().as_ptr(),
+                len: 0,
+                _file: file,
+            });
+        }
+        file.set_len(bytes as u64)?;
+        // SAFETY: the file is open for reading and writing and `bytes` long,
+        // and nothing else maps it
+        let address = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                bytes,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        // Mappings are page aligned, which suits any pixel type
+        let pixels = address as *mut P;
+        for i in 0..len {
+            // SAFETY: `i` is within the mapping, which is not yet read as `P`
+            unsafe { pixels.add(i).write(P::default()) };
+        }
+        Ok(MappedBuffer {
+            pixels,
+            len,
+            _file: file,
+        })
+    }
+
+    /// On platforms without `mmap` the buffer cannot be created.
+    #[cfg(not(unix))]
+    pub fn new(_len: usize, _folder: &Path) -> Result<MappedBuffer<P>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Memory-mapped pixel buffers are only available on unix",
+        ))
+    }
+}
+
+impl<P> Deref for MappedBuffer<P> {
+    type Target = [P];
+
+    fn deref(&self) -> &[P] {
+        // SAFETY: the mapping holds `len` initialized pixels
+        unsafe { std::slice::from_raw_parts(self.pixels, self.len) }
+    }
+}
+
+impl<P> DerefMut for MappedBuffer<P> {
+    fn deref_mut(&mut self) -> &mut [P] {
+        // SAFETY: the mapping holds `len` initialized pixels, borrowed
+        // through `&mut self` only once at a time
+        unsafe { std::slice::from_raw_parts_mut(self.pixels, self.len) }
+    }
+}
+
+impl<P> Drop for MappedBuffer<P> {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if self.len > 0 {
+            // SAFETY: the mapping was created in `new` with this size
+            unsafe {
+                libc::munmap(self.pixels as *mut libc::c_void, self.len * size_of::<P>());
+            }
+        }
+    }
+}
+
+/// A pixel buffer in memory or in a memory-mapped file
+pub enum PixelBuffer<P> {
+    Heap(Vec<P>),
+    Mapped(MappedBuffer<P>),
+}
+
+impl<P: Channel> PixelBuffer<P> {
+    /// Allocate `len` pixels set to `P::default()`, in memory, or in a
+    /// [`MappedBuffer`] in `mapped_in` if it is given.
+    pub fn new(len: usize, mapped_in: Option<&Path>) -> Result<PixelBuffer<P>, std::io::Error> {
+        Ok(match mapped_in {
+            Some(folder) => PixelBuffer::Mapped(MappedBuffer::new(len, folder)?),
+            None => PixelBuffer::Heap(vec![P::default(); len]),
+        })
+    }
+}
+
+impl<P> Deref for PixelBuffer<P> {
+    type Target = [P];
+
+    fn deref(&self) -> &[P] {
+        match self {
+            PixelBuffer::Heap(pixels) => pixels,
+            PixelBuffer::Mapped(pixels) => pixels,
+        }
+    }
+}
+
+impl<P> DerefMut for PixelBuffer<P> {
+    fn deref_mut(&mut self) -> &mut [P] {
+        match self {
+            PixelBuffer::Heap(pixels) => pixels,
+            PixelBuffer::Mapped(pixels) => pixels,
+        }
+    }
+}
+
+/// Every buffer a frame is rendered through, allocated once so that each
+/// frame of an animation reuses them instead of allocating its own
+pub struct RenderBuffers<P> {
+    /// The finished frame
+    pub pixels: PixelBuffer<P>,
+    /// The samples of a supersampled frame before they are downscaled, empty
+    /// without supersampling
+    pub samples: PixelBuffer<P>,
+    /// A second frame to blend into the first, empty if none is blended
+    pub blend: PixelBuffer<P>,
+    /// Escape counts to shade the frame from, empty if it is rendered directly
+    pub counts: Vec<u32>,
+    /// Sums of the levels of sub-frames to average, empty if none are
+    pub sums: Vec<u32>,
+}
+
+impl<P: Channel> RenderBuffers<P> {
+    /// Allocate the buffers for frames of `bounds` pixels, with room for
+    /// `supersample`² samples per pixel if `supersample` is above 1, for a
+    /// frame to blend in if `blend` is set, for escape counts if `counts` is
+    /// set and for sums of sub-frames if `sums` is set.
+    ///
+    /// The pixel buffers are memory-mapped in `mapped_in` if it is given;
+    /// empty ones never are.
+    pub fn new(
+        bounds: (usize, usize),
+        supersample: usize,
+        blend: bool,
+        counts: bool,
+        sums: bool,
+        mapped_in: Option<&Path>,
+    ) -> Result<RenderBuffers<P>, std::io::Error> {
+        let len = bounds.0 * bounds.1;
+        let buffer = |len: usize| match len {
+            0 => Ok(PixelBuffer::Heap(Vec::new())),
+            _ => PixelBuffer::new(len, mapped_in),
+        };
+        let samples = if supersample > 1 {
+            len * supersample * supersample
+        } else {
+            0
+        };
+        Ok(RenderBuffers {
+            pixels: buffer(len)?,
+            samples: buffer(samples)?,
+            blend: buffer(if blend { len } else { 0 })?,
+            counts: vec![0; if counts { len } else { 0 }],
+            sums: vec![0; if sums { len } else { 0 }],
+        })
+    }
+
+    /// Return how many bytes the buffers take up together.
+    pub fn bytes(&self) -> usize {
+        let pixels = self.pixels.len() + self.samples.len() + self.blend.len();
+        let sums = self.counts.len() + self.sums.len();
+        pixels * size_of::<P>() + sums * size_of::<u32>()
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_mapped_buffer() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = dir.path().join("scratch");
+    let mut mapped = PixelBuffer::<u16>::new(6, Some(&folder)).unwrap();
+    assert!(matches!(mapped, PixelBuffer::Mapped(_)));
+    assert_eq!(*mapped, [0; 6]);
+    mapped.copy_from_slice(&[1, 2, 3, 1000, 65535, 7]);
+
+    // The temporary file is already gone from the folder
+    assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 0);
+
+    // A mapped buffer saves exactly like one in memory
+    let heap = PixelBuffer::Heap(mapped.to_vec());
+    let save = |name: &str, pixels: &[u16]| {
+        let filename = dir.path().join(name).display().to_string();
+        super::preserve::write_image(&filename, pixels, (3, 2), None, None, &[]).unwrap();
+        std::fs::read(filename).unwrap()
+    };
+    assert_eq!(save("mapped.png", &mapped), save("heap.png", &heap));
+
+    let empty = PixelBuffer::<u8>::new(0, Some(&folder)).unwrap();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_render_buffers() {
+    let buffers = RenderBuffers::<u8>::new((4, 3), 2, false, true, false, None).unwrap();
+    assert_eq!(buffers.pixels.len(), 12);
+    assert_eq!(buffers.samples.len(), 48);
+    assert!(buffers.blend.is_empty());
+    assert_eq!(buffers.counts.len(), 12);
+    assert_eq!(buffers.bytes(), 12 + 48 + 12 * 4);
+
+    let buffers = RenderBuffers::<u16>::new((4, 3), 1, true, false, true, None).unwrap();
+    assert!(buffers.samples.is_empty());
+    assert_eq!(buffers.blend.len(), 12);
+    assert!(buffers.counts.is_empty());
+    assert_eq!(buffers.sums.len(), 12);
+}
@@ -0,0 +1,492 @@
+use super::transform::{Rotation, SamplePattern};
+use super::{Conjugate, EscapeCondition, FractalParams, FractalType, Morph};
+use num::Complex;
+
+/// Keyword of the PNG text chunk that holds a frame's metadata as JSON
+pub const JSON_KEYWORD: &str = "Frame metadata";
+
+/// Everything needed to render one frame of an animation again on its own
+#[derive(Debug, Clone, Copy)]
+pub struct FrameMetadata {
+    /// Position of the frame in the animation, counting from 1
+    pub index: usize,
+    /// Width and height of the frame in pixels
+    pub bounds: (usize, usize),
+    /// The point on the complex plane at the upper-left corner
+    pub upper_left: Complex<f64>,
+    /// The point on the complex plane at the lower-right corner
+    pub lower_right: Complex<f64>,
+    pub params: FractalParams,
+}
+
+impl FrameMetadata {
+    /// Serialize the metadata as a JSON object.
+    ///
+    /// Numbers are written with the shortest representation that parses back
+    /// to the same `f64`, so the frame can be reproduced exactly.
+    pub fn to_json(&self) -> String {
+        let complex = |z: Complex<f64>| format!("{{\"re\": {:?}, \"im\": {:?}}}", z.re, z.im);
+        let params = &self.params;
+        let julia_constant = params.julia_constant.map_or("null".to_string(), complex);
+        format!(
+            "{{\n  \"frame\": {},\n  \"width\": {},\n  \"height\": {},\n  \
+             \"upper_left\": {},\n  \"lower_right\": {},\n  \
+             \"fractal_type\": \"{}\",\n  \"power\": {},\n  \"escape_radius\": {:?},\n  \
+             \"julia_constant\": {},\n  \"lyapunov_sequence\": \"{}\",\n  \
+             \"max_iterations\": {},\n  \"sampling\": \"{}\",\n  \"conjugate\": {},\n  \
+             \"complex_power\": {},\n  \"log_polar_center\": {},\n  \"rotation\": {},\n  \
+             \"escape_condition\": {},\n  \"morph\": {},\n  \"initial_z\": {}\n}}\n",
+            self.index,
+            self.bounds.0,
+            self.bounds.1,
+            complex(self.upper_left),
+            complex(self.lower_right),
+            params.fractal_type.name(),
+            params.power,
+            params.escape_radius,
+            julia_constant,
+            params.lyapunov_sequence,
+            params.max_iterations,
+            if params.corner_sampling {
+                "corner"
+            } else {
+                "center"
+            },
+            match params.conjugate {
+                None => "null",
+                Some(Conjugate::Z) => "\"z\"",
+                Some(Conjugate::C) => "\"c\"",
+                Some(Conjugate::Both) => "\"both\"",
+            },
+            params.complex_power.map_or("null".to_string(), complex),
+            match params.sample_pattern {
+                SamplePattern::Rect => "null".to_string(),
+                SamplePattern::LogPolar { center } => complex(center),
+            },
+            params
+                .rotation
+                .map_or("null".to_string(), |rotation| format!(
+                    "{{\"re\": {:?}, \"im\": {:?}, \"angle\": {:?}}}",
+                    rotation.center.re, rotation.center.im, rotation.angle
+                )),
+            params
+                .escape_condition
+                .map_or("null".to_string(), |condition| format!(
+                    "\"{}\"",
+                    condition.name()
+                )),
+            params.morph.map_or("null".to_string(), |morph| format!(
+                "{{\"to\": \"{}\", \"t\": {:?}}}",
+                morph.to, morph.t
+            )),
+            params.initial_z.map_or("null".to_string(), complex),
+        )
+    }
+
+    /// Return the PNG text chunks that record the frame: the main parameters
+    /// in readable form, then the whole of [`FrameMetadata::to_json`] under
+    /// [`JSON_KEYWORD`].
+    pub fn text_chunks(&self) -> Vec<(String, String)> {
+        let complex = |z: Complex<f64>| format!("{:?},{:?}", z.re, z.im);
+        let params = &self.params;
+        let mut chunks = vec![
+            (
+                "Software".to_string(),
+                format!("mandelbrot {}", env!("CARGO_PKG_VERSION")),
+            ),
+            (
+                "Fractal type".to_string(),
+                params.fractal_type.name().to_string(),
+            ),
+            ("Upper left".to_string(), complex(self.upper_left)),
+            ("Lower right".to_string(), complex(self.lower_right)),
+            ("Power".to_string(), params.power.to_string()),
+            (
+                "Max iterations".to_string(),
+                params.max_iterations.to_string(),
+            ),
+        ];
+        if let Some(k) = params.julia_constant {
+            chunks.push(("Julia constant".to_string(), complex(k)));
+        }
+        chunks.push((JSON_KEYWORD.to_string(), self.to_json()));
+        chunks
+    }
+
+    /// Parse metadata written by [`FrameMetadata::to_json`].
+    ///
+    /// Only the flat layout of that method is understood, not arbitrary JSON.
+    pub fn from_json(text: &str) -> Result<FrameMetadata, String> {
+        let number = |key: &str| -> Result<f64, String> {
+            let value = field(text, key)?;
+            value
+                .parse()
+                .map_err(|_| format!("invalid number `{}` for \"{}\"", value, key))
+        };
+        let count = |key: &str| -> Result<usize, String> {
+            let value = field(text, key)?;
+            value
+                .parse()
+                .map_err(|_| format!("invalid count `{}` for \"{}\"", value, key))
+        };
+        let complex = |key: &str| -> Result<Option<Complex<f64>>, String> {
+            let value = field(text, key)?;
+            if value == "null" {
+                return Ok(None);
+            }
+            Ok(Some(Complex {
+                re: number_in(value, "re", key)?,
+                im: number_in(value, "im", key)?,
+            }))
+        };
+        let optional = |key: &str| {
+            if text.contains(&format!("\"{}\"", key)) {
+                complex(key)
+            } else {
+                Ok(None)
+            }
+        };
+        let corner = |key: &str| complex(key)?.ok_or(format!("\"{}\" must not be null", key));
+
+        let index = count("frame")?;
+        let bounds = (count("width")?, count("height")?);
+        let upper_left = corner("upper_left")?;
+        let lower_right = corner("lower_right")?;
+
+        let fractal_type: FractalType = string(text, "fractal_type")?.parse()?;
+        let params = FractalParams {
+            fractal_type,
+            power: number("power")? as i32,
+            escape_radius: number("escape_radius")?,
+            julia_constant: complex("julia_constant")?,
+            lyapunov_sequence: string(text, "lyapunov_sequence")?.parse()?,
+            max_iterations: count("max_iterations")?,
+            corner_sampling: match string(text, "sampling")? {
+                "corner" => true,
+                "center" => false,
+                other => return Err(format!("unknown sampling `{}`", other)),
+            },
+            conjugate: match field(text, "conjugate")? {
+                "null" => None,
+                "\"z\"" => Some(Conjugate::Z),
+                "\"c\"" => Some(Conjugate::C),
+                "\"both\"" => Some(Conjugate::Both),
+                other => return Err(format!("unknown conjugate {}", other)),
+            },
+            // Files from before complex powers, sample patterns, rotation,
+            // escape conditions, morphs and initial values leave them out
+            complex_power: optional("complex_power")?,
+            sample_pattern: match optional("log_polar_center")? {
+                Some(center) => SamplePattern::LogPolar { center },
+                None => SamplePattern::Rect,
+            },
+            rotation: match optional("rotation")? {
+                Some(center) => Some(Rotation {
+                    center,
+                    angle: number_in(field(text, "rotation")?, "angle", "rotation")?,
+                }),
+                None => None,
+            },
+            escape_condition: match text.contains("\"escape_condition\"") {
+                false => None,
+                true => match field(text, "escape_condition")? {
+                    "null" => None,
+                    "\"norm\"" => Some(EscapeCondition::Norm),
+                    "\"imag\"" => Some(EscapeCondition::Imag),
+                    "\"real\"" => Some(EscapeCondition::Real),
+                    other => return Err(format!("unknown escape condition {}", other)),
+                },
+            },
+            morph: match text.contains("\"morph\"") {
+                false => None,
+                true => match field(text, "morph")? {
+                    "null" => None,
+                    morph => Some(Morph {
+                        to: string(morph, "to")?.parse()?,
+                        t: number_in(morph, "t", "morph")?,
+                    }),
+                },
+            },
+            initial_z: optional("initial_z")?,
+        };
+        Ok(FrameMetadata {
+            index,
+            bounds,
+            upper_left,
+            lower_right,
+            params,
+        })
+    }
+
+    /// Read metadata from a JSON file written with `--metadata`, or from the
+    /// text chunks of a PNG frame.
+    pub fn load(path: &std::path::Path) -> Result<FrameMetadata, String> {
+        let text = if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"))
+        {
+            super::preserve::read_text_chunks(path)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .find(|(keyword, _)| keyword == JSON_KEYWORD)
+                .map(|(_, text)| text)
+                .ok_or(format!("{} holds no frame metadata", path.display()))?
+        } else {
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e))?
+        };
+        FrameMetadata::from_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[test]
+fn test_frame_metadata_to_json() {
+    let metadata = FrameMetadata {
+        index: 3,
+        bounds: (640, 480),
+        upper_left: Complex { re: -2.0, im: 1.5 },
+        lower_right: Complex {
+            re: 0.1 + 0.2,
+            im: -1e-30,
+        },
+        params: FractalParams {
+            julia_constant: Some(Complex {
+                re: -0.8,
+                im: 0.156,
+            }),
+            ..FractalParams::default()
+        },
+    };
+    let json = metadata.to_json();
+    assert!(json.starts_with("{\n  \"frame\": 3,\n  \"width\": 640,\n  \"height\": 480,\n"));
+    assert!(json.contains("\"upper_left\": {\"re\": -2.0, \"im\": 1.5}"));
+    // Values keep every digit needed to round-trip
+    assert!(json.contains("\"lower_right\": {\"re\": 0.30000000000000004, \"im\": -1e-30}"));
+    assert!(json.contains("\"fractal_type\": \"mandelbrot\""));
+    assert!(json.contains("\"escape_radius\": 2.0"));
+    assert!(json.contains("\"julia_constant\": {\"re\": -0.8, \"im\": 0.156}"));
+    assert!(json.contains("\"lyapunov_sequence\": \"AB\""));
+    assert!(json.contains("\"max_iterations\": 255,\n"));
+    assert!(json.contains("\"sampling\": \"center\",\n"));
+    assert!(json.contains("\"conjugate\": null,\n"));
+    assert!(json.contains("\"complex_power\": null,\n"));
+    assert!(json.contains("\"rotation\": null,\n"));
+    assert!(json.ends_with("\"morph\": null,\n  \"initial_z\": null\n}\n"));
+
+    let metadata = FrameMetadata {
+        params: FractalParams {
+            conjugate: Some(Conjugate::Z),
+            ..FractalParams::default()
+        },
+        ..metadata
+    };
+    let json = metadata.to_json();
+    assert!(json.contains("\"julia_constant\": null"));
+    assert!(json.contains("\"conjugate\": \"z\""));
+}
+
+/// Return the raw text of the value of `key` in the flat JSON object `text`:
+/// a nested object up to its closing brace, a quoted string with its quotes,
+/// or anything else up to the next comma or brace.
+fn field<'a>(text: &'a str, key: &str) -> Result<&'a str, String> {
+    let quoted = format!("\"{}\"", key);
+    let start = text.find(&quoted).ok_or(format!("missing \"{}\"", key))? + quoted.len();
+    let rest = text[start..].trim_start();
+    let rest = rest
+        .strip_prefix(':')
+        .ok_or(format!("expected `:` after \"{}\"", key))?
+        .trim_start();
+    let end = match rest.chars().next() {
+        Some('{') => rest.find('}').map(|end| end + 1),
+        Some('"') => rest[1..].find('"').map(|end| end + 2),
+        _ => rest.find([',', '}', '\n']),
+    }
+    .ok_or(format!("unterminated value for \"{}\"", key))?;
+    Ok(rest[..end].trim())
+}
+
+/// Return the value of `key` in `text` with the quotes of a string removed.
+fn string<'a>(text: &'a str, key: &str) -> Result<&'a str, String> {
+    let value = field(text, key)?;
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or(format!("\"{}\" must be a string", key))
+}
+
+/// Parse the number `key` of the nested object `object`, which is the value
+/// of `outer`.
+fn number_in(object: &str, key: &str, outer: &str) -> Result<f64, String> {
+    let value = field(object, key)?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid number `{}` in \"{}\"", value, outer))
+}
+
+#[test]
+fn test_frame_metadata_from_json() {
+    let metadata = FrameMetadata {
+        index: 7,
+        bounds: (320, 200),
+        upper_left: Complex {
+            re: -0.1 - 0.2,
+            im: 1e-17,
+        },
+        lower_right: Complex {
+            re: 0.25,
+            im: -1.0 / 3.0,
+        },
+        params: FractalParams {
+            fractal_type: FractalType::BurningShip,
+            power: 3,
+            escape_radius: 4.5,
+            lyapunov_sequence: "AABB".parse().unwrap(),
+            max_iterations: 1000,
+            corner_sampling: true,
+            conjugate: Some(Conjugate::Both),
+            complex_power: Some(Complex { re: 2.5, im: -0.1 }),
+            sample_pattern: SamplePattern::LogPolar {
+                center: Complex {
+                    re: 0.25,
+                    im: -1e-9,
+                },
+            },
+            rotation: Some(Rotation {
+                center: Complex { re: -0.75, im: 0.1 },
+                angle: 0.1 + 0.2,
+            }),
+            escape_condition: Some(EscapeCondition::Real),
+            morph: Some(Morph {
+                to: FractalType::Tricorn,
+                t: 1.0 / 3.0,
+            }),
+            initial_z: Some(Complex { re: 0.1, im: -0.3 }),
+            ..FractalParams::default()
+        },
+    };
+    let parsed = FrameMetadata::from_json(&metadata.to_json()).unwrap();
+    // Every value survives the round trip exactly
+    assert_eq!(parsed.index, 7);
+    assert_eq!(parsed.bounds, (320, 200));
+    assert_eq!(parsed.upper_left, metadata.upper_left);
+    assert_eq!(parsed.lower_right, metadata.lower_right);
+    assert_eq!(parsed.params.fractal_type, FractalType::BurningShip);
+    assert_eq!(parsed.params.power, 3);
+    assert_eq!(parsed.params.escape_radius, 4.5);
+    assert_eq!(parsed.params.julia_constant, None);
+    assert_eq!(parsed.params.lyapunov_sequence.to_string(), "AABB");
+    assert_eq!(parsed.params.max_iterations, 1000);
+    assert!(parsed.params.corner_sampling);
+    assert_eq!(parsed.params.conjugate, Some(Conjugate::Both));
+    assert_eq!(parsed.params.complex_power, metadata.params.complex_power);
+    assert_eq!(parsed.params.sample_pattern, metadata.params.sample_pattern);
+    assert_eq!(parsed.params.rotation, metadata.params.rotation);
+    assert_eq!(parsed.params.escape_condition, Some(EscapeCondition::Real));
+    assert_eq!(parsed.params.morph, metadata.params.morph);
+    assert_eq!(parsed.params.initial_z, metadata.params.initial_z);
+
+    let constant = Complex {
+        re: -0.8,
+        im: 0.156,
+    };
+    let julia = FrameMetadata {
+        params: FractalParams {
+            fractal_type: FractalType::Julia,
+            julia_constant: Some(constant),
+            ..FractalParams::default()
+        },
+        ..metadata
+    };
+    let parsed = FrameMetadata::from_json(&julia.to_json()).unwrap();
+    assert_eq!(parsed.params.julia_constant, Some(constant));
+    assert_eq!(parsed.params.conjugate, None);
+    // Files written before complex powers were recorded still load
+    let legacy = julia
+        .to_json()
+        .replace(",\n  \"complex_power\": null", "")
+        .replace(",\n  \"log_polar_center\": null", "")
+        .replace(",\n  \"rotation\": null", "")
+        .replace(",\n  \"escape_condition\": null", "")
+        .replace(",\n  \"morph\": null", "")
+        .replace(",\n  \"initial_z\": null", "");
+    assert!(legacy.ends_with("\"conjugate\": null\n}\n"));
+    let parsed = FrameMetadata::from_json(&legacy).unwrap();
+    assert_eq!(parsed.params.complex_power, None);
+    assert_eq!(parsed.params.sample_pattern, SamplePattern::Rect);
+    assert_eq!(parsed.params.rotation, None);
+    assert_eq!(parsed.params.escape_condition, None);
+    assert_eq!(parsed.params.morph, None);
+    assert_eq!(parsed.params.initial_z, None);
+
+    let error = FrameMetadata::from_json("{\"frame\": 1}").unwrap_err();
+    assert_eq!(error, "missing \"width\"");
+}
+
+#[test]
+fn test_frame_metadata_in_png() {
+    let metadata = FrameMetadata {
+        index: 2,
+        bounds: (2, 2),
+        upper_left: Complex { re: -2.0, im: 1.5 },
+        lower_right: Complex {
+            re: 0.1 + 0.2,
+            im: -1.5,
+        },
+        params: FractalParams {
+            fractal_type: FractalType::Julia,
+            julia_constant: Some(Complex {
+                re: -0.8,
+                im: 0.156,
+            }),
+            max_iterations: 500,
+            ..FractalParams::default()
+        },
+    };
+    let chunks = metadata.text_chunks();
+    let keywords: Vec<&str> = chunks.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(
+        keywords,
+        [
+            "Software",
+            "Fractal type",
+            "Upper left",
+            "Lower right",
+            "Power",
+            "Max iterations",
+            "Julia constant",
+            JSON_KEYWORD
+        ]
+    );
+    assert_eq!(chunks[1].1, "julia");
+    assert_eq!(chunks[3].1, "0.30000000000000004,-1.5");
+    assert_eq!(chunks[6].1, "-0.8,0.156");
+
+    // A frame saved with the chunks can be continued from like a JSON file
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("frame.png");
+    super::preserve::write_image(
+        &path.display().to_string(),
+        &[0u8; 4],
+        (2, 2),
+        None,
+        None,
+        &chunks,
+    )
+    .unwrap();
+    let loaded = FrameMetadata::load(&path).unwrap();
+    assert_eq!(loaded.index, 2);
+    assert_eq!(loaded.lower_right, metadata.lower_right);
+    assert_eq!(loaded.params.julia_constant, metadata.params.julia_constant);
+
+    super::preserve::write_image(
+        &path.display().to_string(),
+        &[0u8; 4],
+        (2, 2),
+        None,
+        None,
+        &[],
+    )
+    .unwrap();
+    let error = FrameMetadata::load(&path).unwrap_err();
+    assert!(error.ends_with("holds no frame metadata"), "{}", error);
+}
@@ -0,0 +1,161 @@
+use super::{Channel, ColorOptions, FractalParams};
+use num::Complex;
+use rayon::prelude::*;
+
+/// Raw count stored for points that never escape
+pub const MEMBER: u32 = u32::MAX;
+
+/// Compute the escape count of every pixel of the view between `upper_left`
+/// and `lower_right`, without shading it, storing [`MEMBER`] for points
+/// that never escape.
+///
+/// Rows are computed in parallel on the current rayon thread pool.
+pub fn render_counts(
+    counts: &mut [u32],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+) {
+    assert!(counts.len() == bounds.0 * bounds.1);
+    counts
+        .par_chunks_mut(bounds.0)
+        .enumerate()
+        .for_each(|(row, row_counts)| {
+            for (column, count) in row_counts.iter_mut().enumerate() {
+                let point = params.sample_point(bounds, (column, row), upper_left, lower_right);
+                *count = params.escape_time(point).map_or(MEMBER, |i| i as u32);
+            }
+        });
+}
+
+/// Shade raw counts from [`render_counts`] into `pixels` with `colors`, like
+/// rendering with the iteration limit `limit` would.
+pub fn colorize<P: Channel>(pixels: &mut [P], counts: &[u32], colors: &ColorOptions, limit: usize) {
+    assert!(pixels.len() == counts.len());
+    pixels
+        .par_iter_mut()
+        .zip(counts)
+        .for_each(|(pixel, &count)| {
+            let escape = (count != MEMBER).then_some(count as usize);
+            *pixel = colors.shade(escape, limit);
+        });
+}
+
+/// How often each escape count occurs across any number of frames
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    occurrences: Vec<u64>,
+}
+
+impl Histogram {
+    /// Count the escape counts of one frame, skipping members.
+    pub fn add(&mut self, counts: &[u32]) {
+        for &count in counts.iter().filter(|&&count| count != MEMBER) {
+            let count = count as usize;
+            if count >= self.occurrences.len() {
+                self.occurrences.resize(count + 1, 0);
+            }
+            self.occurrences[count] += 1;
+        }
+    }
+
+    /// Return the lowest and highest escape count once `clip` percent of the
+    /// escaping points have been left out at each end, or `None` if no point
+    /// escaped.
+    pub fn range(&self, clip: f64) -> Option<(usize, usize)> {
+        let total: u64 = self.occurrences.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let skip = ((total as f64 * clip / 100.0).floor() as u64).min(total - 1);
+        // The first count reached after passing `skip` points from one end
+        fn first_past<'a>(mut counts: impl Iterator<Item = (usize, &'a u64)>, skip: u64) -> usize {
+            let mut seen = 0;
+            counts
+                .find(|&(_, &n)| {
+                    seen += n;
+                    seen > skip
+                })
+                .map_or(0, |(count, _)| count)
+        }
+        let low = first_past(self.occurrences.iter().enumerate(), skip);
+        let high = first_past(self.occurrences.iter().enumerate().rev(), skip);
+        Some((low, high.max(low)))
+    }
+}
+
+#[test]
+fn test_histogram_range() {
+    let mut histogram = Histogram::default();
+    assert_eq!(histogram.range(0.0), None);
+    histogram.add(&[MEMBER, 3, 4, 4, 5]);
+    histogram.add(&[6, 7, 8, 9, 10, 90, MEMBER]);
+    assert_eq!(histogram.range(0.0), Some((3, 90)));
+    // Leaving out one of the ten points at each end drops the outliers
+    assert_eq!(histogram.range(10.0), Some((4, 10)));
+    // Clipping everything still leaves a valid range
+    assert_eq!(histogram.range(100.0), Some((90, 90)));
+}
+
+/// Write raw counts to `path` as little-endian 32-bit integers.
+pub fn save_counts(path: &std::path::Path, counts: &[u32]) -> std::io::Result<()> {
+    let bytes: Vec<u8> = counts
+        .iter()
+        .flat_map(|count| count.to_le_bytes())
+        .collect();
+    std::fs::write(path, bytes)
+}
+
+/// Read raw counts written by [`save_counts`] into `counts`.
+pub fn load_counts(path: &std::path::Path, counts: &mut [u32]) -> std::io::Result<()> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() != counts.len() * 4 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "{} holds {} bytes instead of {} counts",
+                path.display(),
+                bytes.len(),
+                counts.len()
+            ),
+        ));
+    }
+    for (count, chunk) in counts.iter_mut().zip(bytes.chunks_exact(4)) {
+        *count = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_counts_round_trip() {
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let bounds = (30, 20);
+    let upper_left = Complex { re: -2.0, im: 1.0 };
+    let lower_right = Complex { re: 1.0, im: -1.0 };
+    let mut counts = vec![0; bounds.0 * bounds.1];
+    render_counts(&mut counts, bounds, upper_left, lower_right, &params);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("counts.bin");
+    save_counts(&path, &counts).unwrap();
+    let mut loaded = vec![0; counts.len()];
+    load_counts(&path, &mut loaded).unwrap();
+    assert_eq!(loaded, counts);
+    assert!(load_counts(&path, &mut [0; 3]).is_err());
+
+    // Shading the stored counts gives the same image as rendering directly
+    let mut expected = vec![0u8; counts.len()];
+    super::render_parallel(
+        &mut expected,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+    );
+    let mut pixels = vec![0u8; counts.len()];
+    colorize(&mut pixels, &loaded, &colors, params.max_iterations);
+    assert_eq!(pixels, expected);
+}
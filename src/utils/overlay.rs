@@ -0,0 +1,294 @@
+use super::Channel;
+use super::font;
+use num::Complex;
+
+/// Reference markings that can be drawn over a rendered frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlay {
+    /// Lines at round complex coordinates, labeled with their values
+    Grid,
+    /// A bar of round length in the lower-left corner, labeled with its length
+    ScaleBar,
+    /// Both the grid and the scale bar
+    Both,
+}
+
+/// Return a round spacing, 1, 2 or 5 times a power of ten, that divides
+/// `extent` into roughly `target` intervals.
+pub fn nice_step(extent: f64, target: usize) -> f64 {
+    let raw = extent.abs() / target as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+#[test]
+fn test_nice_step() {
+    assert_eq!(nice_step(4.0, 8), 0.5);
+    assert_eq!(nice_step(3.0, 8), 0.5);
+    assert_eq!(nice_step(0.01, 6), 0.002);
+    assert_eq!(nice_step(-10.0, 10), 1.0);
+    assert_eq!(nice_step(70.0, 10), 10.0);
+}
+
+/// Format `value` with just enough decimals to distinguish multiples of `step`.
+pub fn format_coordinate(value: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    // Avoid printing "-0.00" for values that are zero up to rounding
+    let value = if value.abs() < step / 2.0 { 0.0 } else { value };
+    format!("{:.*}", decimals, value)
+}
+
+#[test]
+fn test_format_coordinate() {
+    assert_eq!(format_coordinate(-1.5, 0.5), "-1.5");
+    assert_eq!(format_coordinate(2.0, 1.0), "2");
+    assert_eq!(format_coordinate(0.004, 0.002), "0.004");
+    assert_eq!(format_coordinate(-1e-17, 0.1), "0.0");
+}
+
+/// A corner of the frame to draw a caption in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Format a magnification with three significant digits, like "250×", or
+/// from ten thousand on in powers of ten with one decimal, like "1.3×10^6×".
+pub fn format_zoom(zoom: f64) -> String {
+    if (0.01..1e4).contains(&zoom) {
+        let decimals = (2.0 - zoom.log10().floor()).max(0.0) as usize;
+        return format!("{:.*}×", decimals, zoom);
+    }
+    let mut exponent = zoom.log10().floor() as i32;
+    let mut mantissa = (zoom / 10f64.powi(exponent) * 10.0).round() / 10.0;
+    // Rounding can carry the mantissa up to the next power of ten
+    if mantissa >= 10.0 {
+        mantissa /= 10.0;
+        exponent += 1;
+    }
+    format!("{:.1}×10^{}×", mantissa, exponent)
+}
+
+#[test]
+fn test_format_zoom() {
+    assert_eq!(format_zoom(1.0), "1.00×");
+    assert_eq!(format_zoom(12.34), "12.3×");
+    assert_eq!(format_zoom(250.2), "250×");
+    assert_eq!(format_zoom(0.05), "0.0500×");
+    assert_eq!(format_zoom(1.3e6), "1.3×10^6×");
+    assert_eq!(format_zoom(9.96e7), "1.0×10^8×");
+    assert_eq!(format_zoom(2e-3), "2.0×10^-3×");
+}
+
+/// Draw `text` in white on a dark box in `corner` of the `pixels` buffer,
+/// whose dimensions are given by `bounds`, with every font pixel a `scale`
+/// x `scale` block.
+pub fn draw_caption<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    text: &str,
+    corner: Corner,
+    scale: usize,
+) {
+    let (width, height) = font::text_size(text, scale);
+    let (box_width, box_height) = (width + 2 * scale, height + 2 * scale);
+    let margin = 4 * scale;
+    let left = match corner {
+        Corner::TopLeft | Corner::BottomLeft => margin,
+        Corner::TopRight | Corner::BottomRight => bounds.0.saturating_sub(box_width + margin),
+    };
+    let top = match corner {
+        Corner::TopLeft | Corner::TopRight => margin,
+        Corner::BottomLeft | Corner::BottomRight => bounds.1.saturating_sub(box_height + margin),
+    };
+    label(pixels, bounds, (left, top), text, scale);
+}
+
+#[test]
+fn test_draw_caption() {
+    let bounds = (80, 40);
+    let mut pixels = vec![128u8; bounds.0 * bounds.1];
+    draw_caption(&mut pixels, bounds, "1×", Corner::BottomRight, 1);
+    // The box of two glyphs fills 13 by 9 pixels, 4 in from the corner
+    let boxed = |column: usize, row: usize| (63..76).contains(&column) && (27..36).contains(&row);
+    for (i, &pixel) in pixels.iter().enumerate() {
+        let (column, row) = (i % bounds.0, i / bounds.0);
+        assert_eq!(
+            pixel != 128,
+            boxed(column, row),
+            "pixel ({}, {})",
+            column,
+            row
+        );
+    }
+    assert!(pixels.contains(&255));
+}
+
+/// Draw `overlay` onto the `pixels` buffer, whose dimensions are given by
+/// `bounds` and whose corners map to `upper_left` and `lower_right`.
+///
+/// The grid spacing and scale bar length are chosen from the current extent
+/// of the view, so they stay readable at every zoom level.
+pub fn draw<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    overlay: Overlay,
+) {
+    let scale = (bounds.0.min(bounds.1) / 512).max(1);
+    if matches!(overlay, Overlay::Grid | Overlay::Both) {
+        draw_grid(pixels, bounds, upper_left, lower_right, scale);
+    }
+    if matches!(overlay, Overlay::ScaleBar | Overlay::Both) {
+        draw_scale_bar(pixels, bounds, upper_left, lower_right, scale);
+    }
+}
+
+/// Draw lines at multiples of a round spacing along both axes.
+fn draw_grid<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    scale: usize,
+) {
+    let (width, height) = (
+        lower_right.re - upper_left.re,
+        upper_left.im - lower_right.im,
+    );
+    let step = nice_step(width, 6);
+
+    // Vertical lines at round real coordinates, labeled along the top
+    let (low, high) = (
+        upper_left.re.min(lower_right.re),
+        upper_left.re.max(lower_right.re),
+    );
+    let mut k = (low / step).ceil();
+    while k * step <= high {
+        let re = k * step;
+        let column = ((re - upper_left.re) / width * bounds.0 as f64) as usize;
+        if column < bounds.0 {
+            for row in 0..bounds.1 {
+                invert(&mut pixels[row * bounds.0 + column]);
+            }
+            let text = format_coordinate(re, step);
+            label(pixels, bounds, (column + 2 * scale, scale), &text, scale);
+        }
+        k += 1.0;
+    }
+
+    // Horizontal lines at round imaginary coordinates, labeled along the left
+    let (low, high) = (
+        upper_left.im.min(lower_right.im),
+        upper_left.im.max(lower_right.im),
+    );
+    let mut k = (low / step).ceil();
+    while k * step <= high {
+        let im = k * step;
+        let row = ((upper_left.im - im) / height * bounds.1 as f64) as usize;
+        if row < bounds.1 {
+            for pixel in &mut pixels[row * bounds.0..(row + 1) * bounds.0] {
+                invert(pixel);
+            }
+            let text = format!("{}i", format_coordinate(im, step));
+            label(pixels, bounds, (scale, row + 2 * scale), &text, scale);
+        }
+        k += 1.0;
+    }
+}
+
+/// Draw a bar of round length in the lower-left corner.
+fn draw_scale_bar<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    scale: usize,
+) {
+    let width = (lower_right.re - upper_left.re).abs();
+    let length = nice_step(width, 4);
+    let length_px = (length / width * bounds.0 as f64).round() as usize;
+
+    let text = format_coordinate(length, length);
+    let (text_width, text_height) = font::text_size(&text, scale);
+    let margin = 4 * scale;
+    let thickness = 3 * scale;
+
+    // A dark panel behind the bar and its label keeps both readable
+    let panel = (
+        length_px.max(text_width) + 2 * margin,
+        text_height + thickness + 3 * margin,
+    );
+    let origin = (margin, bounds.1.saturating_sub(panel.1 + margin));
+    font::fill_rect(pixels, bounds, origin, panel, P::from_level(0));
+    font::draw_text(
+        pixels,
+        bounds,
+        (origin.0 + margin, origin.1 + margin),
+        &text,
+        scale,
+        P::from_level(P::MAX),
+    );
+    font::fill_rect(
+        pixels,
+        bounds,
+        (origin.0 + margin, origin.1 + text_height + 2 * margin),
+        (length_px, thickness),
+        P::from_level(P::MAX),
+    );
+}
+
+/// Draw `text` in white on a dark box.
+fn label<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    origin: (usize, usize),
+    text: &str,
+    scale: usize,
+) {
+    let (width, height) = font::text_size(text, scale);
+    font::fill_rect(
+        pixels,
+        bounds,
+        origin,
+        (width + 2 * scale, height + 2 * scale),
+        P::from_level(0),
+    );
+    font::draw_text(
+        pixels,
+        bounds,
+        (origin.0 + scale, origin.1 + scale),
+        text,
+        scale,
+        P::from_level(P::MAX),
+    );
+}
+
+/// Invert a pixel so lines stay visible on both dark and bright regions.
+fn invert<P: Channel>(pixel: &mut P) {
+    *pixel = P::from_level(P::MAX - pixel.level());
+}
+
+#[test]
+fn test_draw_grid() {
+    let bounds = (100, 100);
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    let upper_left = Complex { re: -2.0, im: 2.0 };
+    let lower_right = Complex { re: 2.0, im: -2.0 };
+    draw(&mut pixels, bounds, upper_left, lower_right, Overlay::Grid);
+
+    // With a spacing of 1, the line re = 0 runs down the middle column
+    assert!((30..45).all(|row| pixels[row * bounds.0 + 50] == 255));
+    // A column between two lines is untouched
+    assert!((30..45).all(|row| pixels[row * bounds.0 + 60] == 0));
+    // The line im = 1 runs along row 25
+    assert!((30..45).all(|column| pixels[25 * bounds.0 + column] == 255));
+}
@@ -0,0 +1,443 @@
+/// A point of a color gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    /// Where the stop sits along the gradient, in `0.0..=1.0`
+    pub position: f64,
+    pub color: [u8; 3],
+}
+
+/// A color gradient interpolated linearly between sorted stops
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<Stop>,
+}
+
+impl Gradient {
+    /// Build a gradient from at least two stops, sorting them by position.
+    pub fn new(mut stops: Vec<Stop>) -> Result<Gradient, String> {
+        if stops.len() < 2 {
+            return Err(format!(
+                "a gradient needs at least two stops, but {} were given",
+                stops.len()
+            ));
+        }
+        if let Some(stop) = stops
+            .iter()
+            .find(|stop| !(0.0..=1.0).contains(&stop.position))
+        {
+            return Err(format!(
+                "stop position {} is outside the range 0 to 1",
+                stop.position
+            ));
+        }
+        // A stable sort keeps stops at the same position in order, for hard edges
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Ok(Gradient { stops })
+    }
+
+    /// Return the color at position `t`, clamped to `0.0..=1.0`.
+    pub fn color_for(&self, t: f64) -> [u8; 3] {
+        let t = t.clamp(0.0, 1.0);
+        let first = self.stops[0];
+        if t <= first.position {
+            return first.color;
+        }
+        for pair in self.stops.windows(2) {
+            let (low, high) = (pair[0], pair[1]);
+            if t <= high.position {
+                let span = high.position - low.position;
+                let f = if span > 0.0 {
+                    (t - low.position) / span
+                } else {
+                    1.0
+                };
+                let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+                return [
+                    mix(low.color[0], high.color[0]),
+                    mix(low.color[1], high.color[1]),
+                    mix(low.color[2], high.color[2]),
+                ];
+            }
+        }
+        self.stops[self.stops.len() - 1].color
+    }
+
+    /// Parse a gradient from the contents of a palette file.
+    ///
+    /// Three formats are understood:
+    /// * GIMP gradients (`.ggr`), recognized by their `GIMP Gradient` header.
+    ///   Each segment contributes its two end colors; blending and coloring
+    ///   types are approximated as linear RGB.
+    /// * JSON: an array of `[position, r, g, b]` arrays, optionally wrapped
+    ///   in an object as `{"stops": [...]}`.
+    /// * Plain text: one `position r g b` stop per line, separated by spaces
+    ///   or commas. Blank lines and lines starting with `#` are ignored.
+    ///
+    /// Positions range from 0 to 1 and colors from 0 to 255, except in GIMP
+    /// gradients, whose colors range from 0 to 1.
+    pub fn parse(text: &str) -> Result<Gradient, String> {
+        let trimmed = text.trim_start();
+        let stops = if trimmed.starts_with("GIMP Gradient") {
+            parse_ggr(trimmed)?
+        } else if trimmed.starts_with('[') || trimmed.starts_with('{') {
+            parse_json(trimmed)?
+        } else {
+            parse_text(trimmed)?
+        };
+        Gradient::new(stops)
+    }
+
+    /// Load a gradient from the palette file at `path`.
+    pub fn load(path: &std::path::Path) -> Result<Gradient, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read palette file {}: {}", path.display(), e))?;
+        Gradient::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Turn a position and three 0-255 channel values into a stop.
+fn stop_from_values(values: &[f64]) -> Result<Stop, String> {
+    let [position, r, g, b] = values else {
+        return Err(format!(
+            "a stop needs a position and three color values, but got {:?}",
+            values
+        ));
+    };
+    let channel = |v: f64| {
+        if (0.0..=255.0).contains(&v) {
+            Ok(v.round() as u8)
+        } else {
+            Err(format!("color value {} is outside the range 0 to 255", v))
+        }
+    };
+    Ok(Stop {
+        position: *position,
+        color: [channel(*r)?, channel(*g)?, channel(*b)?],
+    })
+}
+
+fn parse_text(text: &str) -> Result<Vec<Stop>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let values = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty())
+                .map(|field| {
+                    field
+                        .parse::<f64>()
+                        .map_err(|_| format!("`{}` is not a number", field))
+                })
+                .collect::<Result<Vec<f64>, String>>()?;
+            stop_from_values(&values)
+        })
+        .collect()
+}
+
+fn parse_json(text: &str) -> Result<Vec<Stop>, String> {
+    // Unwrap {"stops": [...]} down to the array
+    let array = if text.starts_with('{') {
+        let key = text
+            .find("\"stops\"")
+            .ok_or("JSON palettes must have a \"stops\" array")?;
+        let rest = &text[key + "\"stops\"".len()..];
+        rest.trim_start()
+            .strip_prefix(':')
+            .ok_or("expected `:` after \"stops\"")?
+            .trim_start()
+    } else {
+        text
+    };
+
+    // Read nested arrays of numbers: [[p, r, g, b], ...]
+    let mut stops = Vec::new();
+    let mut depth = 0;
+    let mut current: Vec<f64> = Vec::new();
+    let mut number = String::new();
+    for c in array.chars() {
+        if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+            number.push(c);
+            continue;
+        }
+        if !number.is_empty() {
+            current.push(
+                number
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a number", number))?,
+            );
+            number.clear();
+        }
+        match c {
+            '[' => {
+                depth += 1;
+                if depth > 2 {
+                    return Err("palette arrays may only be nested two deep".to_string());
+                }
+            }
+            ']' => {
+                if depth == 2 {
+                    stops.push(stop_from_values(&current)?);
+                    current.clear();
+                }
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(stops);
+                }
+            }
+            ',' => {}
+            c if c.is_whitespace() => {}
+            c => return Err(format!("unexpected `{}` in JSON palette", c)),
+        }
+    }
+    Err("unterminated JSON array".to_string())
+}
+
+fn parse_ggr(text: &str) -> Result<Vec<Stop>, String> {
+    let mut lines = text.lines().skip(1).map(str::trim);
+    let mut count_line = lines.next().ok_or("missing segment count")?;
+    if count_line.starts_with("Name:") {
+        count_line = lines.next().ok_or("missing segment count")?;
+    }
+    let count: usize = count_line
+        .parse()
+        .map_err(|_| format!("`{}` is not a segment count", count_line))?;
+
+    let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let mut stops = Vec::with_capacity(count * 2);
+    for _ in 0..count {
+        let line = lines.next().ok_or("fewer segments than announced")?;
+        let values = line
+            .split_whitespace()
+            .map(|field| {
+                field
+                    .parse::<f64>()
+                    .map_err(|_| format!("`{}` is not a number", field))
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+        // left middle right, then the RGBA colors at the left and right ends
+        if values.len() < 11 {
+            return Err(format!("incomplete gradient segment `{}`", line));
+        }
+        stops.push(Stop {
+            position: values[0],
+            color: [channel(values[3]), channel(values[4]), channel(values[5])],
+        });
+        stops.push(Stop {
+            position: values[2],
+            color: [channel(values[7]), channel(values[8]), channel(values[9])],
+        });
+    }
+    Ok(stops)
+}
+
+#[test]
+fn test_gradient_color_for() {
+    let gradient = Gradient::parse("0 0 0 0\n0.5 255 0 100\n1 255 255 255").unwrap();
+    assert_eq!(gradient.color_for(0.0), [0, 0, 0]);
+    assert_eq!(gradient.color_for(0.25), [128, 0, 50]);
+    assert_eq!(gradient.color_for(0.5), [255, 0, 100]);
+    assert_eq!(gradient.color_for(1.0), [255, 255, 255]);
+    // Out-of-range positions are clamped
+    assert_eq!(gradient.color_for(-1.0), [0, 0, 0]);
+    assert_eq!(gradient.color_for(2.0), [255, 255, 255]);
+}
+
+#[test]
+fn test_gradient_parse_formats() {
+    let expected = Gradient::new(vec![
+        Stop {
+            position: 0.0,
+            color: [0, 0, 0],
+        },
+        Stop {
+            position: 1.0,
+            color: [255, 128, 0],
+        },
+    ])
+    .unwrap();
+
+    let text = "# black to orange\n0.0, 0, 0, 0\n\n1.0 255 128 0\n";
+    assert_eq!(Gradient::parse(text).unwrap(), expected);
+
+    let json = "[[0.0, 0, 0, 0], [1.0, 255, 128, 0]]";
+    assert_eq!(Gradient::parse(json).unwrap(), expected);
+    let json = "{\"stops\": [[1, 255, 128, 0], [0, 0, 0, 0]]}";
+    assert_eq!(Gradient::parse(json).unwrap(), expected);
+
+    let ggr = "GIMP Gradient\nName: Orange\n1\n\
+               0.0 0.5 1.0 0 0 0 1 1 0.502 0 1 0 0\n";
+    assert_eq!(Gradient::parse(ggr).unwrap(), expected);
+}
+
+#[test]
+fn test_gradient_errors() {
+    // Too few stops
+    assert!(Gradient::parse("0 0 0 0").is_err());
+    assert!(Gradient::parse("[]").is_err());
+    // Positions outside [0, 1]
+    assert!(Gradient::parse("0 0 0 0\n1.5 1 1 1").is_err());
+    assert!(Gradient::parse("-0.1 0 0 0\n1 1 1 1").is_err());
+    // Malformed stops and colors
+    assert!(Gradient::parse("0 0 0\n1 1 1 1").is_err());
+    assert!(Gradient::parse("0 0 0 0\n1 256 0 0").is_err());
+    assert!(Gradient::parse("[[0, 0, 0, 0], [1, 1, 1, 1]").is_err());
+    assert!(Gradient::parse("{\"colors\": []}").is_err());
+
+    // Unsorted stops are sorted
+    let gradient = Gradient::parse("1 255 255 255\n0 0 0 0").unwrap();
+    assert_eq!(gradient.color_for(0.0), [0, 0, 0]);
+}
+
+/// Perceptually uniform color maps from matplotlib, which also read well for
+/// viewers with color vision deficiencies and in grayscale print
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    /// Dark blue through green to yellow
+    Viridis,
+    /// Black through purple and pink to pale yellow
+    Magma,
+    /// Black through purple and orange to pale yellow
+    Inferno,
+    /// Blue through magenta and orange to yellow
+    Plasma,
+}
+
+/// Each map sampled at nine evenly spaced points, from 0 to 1
+const VIRIDIS: [u32; 9] = [
+    0x440154, 0x472d7b, 0x3b528b, 0x2c728e, 0x21918c, 0x28ae80, 0x5ec962, 0xaddc30, 0xfde725,
+];
+const MAGMA: [u32; 9] = [
+    0x000004, 0x1c1044, 0x4f127b, 0x812581, 0xb5367a, 0xe55064, 0xfb8761, 0xfec287, 0xfcfdbf,
+];
+const INFERNO: [u32; 9] = [
+    0x000004, 0x1f0c48, 0x550f6d, 0x88226a, 0xba3655, 0xe35933, 0xf98e09, 0xf9cb35, 0xfcffa4,
+];
+const PLASMA: [u32; 9] = [
+    0x0d0887, 0x4c02a1, 0x7e03a8, 0xa92395, 0xcc4778, 0xe56b5d, 0xf89441, 0xfdc328, 0xf0f921,
+];
+
+impl Builtin {
+    /// Return the color map as a gradient with evenly spaced stops.
+    pub fn gradient(self) -> Gradient {
+        let colors = match self {
+            Builtin::Viridis => &VIRIDIS,
+            Builtin::Magma => &MAGMA,
+            Builtin::Inferno => &INFERNO,
+            Builtin::Plasma => &PLASMA,
+        };
+        let last = (colors.len() - 1) as f64;
+        let stops = colors
+            .iter()
+            .enumerate()
+            .map(|(i, &rgb)| Stop {
+                position: i as f64 / last,
+                color: [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8],
+            })
+            .collect();
+        Gradient::new(stops).unwrap()
+    }
+}
+
+#[test]
+fn test_builtin_gradients() {
+    let viridis = Builtin::Viridis.gradient();
+    assert_eq!(viridis.color_for(0.0), [0x44, 0x01, 0x54]);
+    assert_eq!(viridis.color_for(0.5), [0x21, 0x91, 0x8c]);
+    assert_eq!(viridis.color_for(1.0), [0xfd, 0xe7, 0x25]);
+
+    // Each map grows steadily brighter, so it keeps its order in grayscale
+    for builtin in [
+        Builtin::Viridis,
+        Builtin::Magma,
+        Builtin::Inferno,
+        Builtin::Plasma,
+    ] {
+        let gradient = builtin.gradient();
+        let luma = |t: f64| {
+            let [r, g, b] = gradient.color_for(t);
+            0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+        };
+        assert!(
+            (0..16).all(|i| luma(i as f64 / 16.0) < luma((i + 1) as f64 / 16.0)),
+            "{:?} is not monotonic",
+            builtin
+        );
+    }
+}
+
+/// The color of every 8-bit gray level, used to colorize rendered frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette(pub [[u8; 3]; 256]);
+
+impl Palette {
+    /// Return the identity palette that keeps frames grayscale.
+    pub fn grayscale() -> Palette {
+        let mut colors = [[0; 3]; 256];
+        for (level, color) in colors.iter_mut().enumerate() {
+            *color = [level as u8; 3];
+        }
+        Palette(colors)
+    }
+
+    /// Sample `gradient` at every gray level.
+    pub fn from_gradient(gradient: &Gradient) -> Palette {
+        let mut colors = [[0; 3]; 256];
+        for (level, color) in colors.iter_mut().enumerate() {
+            *color = gradient.color_for(level as f64 / 255.0);
+        }
+        Palette(colors)
+    }
+
+    /// Blend two palettes by escape speed: level 0, where points escape
+    /// fastest, takes the color of `fast`, the brightest level the color of
+    /// `slow`, and the levels between mix the two in proportion.
+    pub fn blend(fast: &Palette, slow: &Palette) -> Palette {
+        let mut colors = [[0; 3]; 256];
+        for (level, color) in colors.iter_mut().enumerate() {
+            let f = level as f64 / 255.0;
+            let (a, b) = (fast.0[level], slow.0[level]);
+            let mix = |i: usize| (a[i] as f64 + (b[i] as f64 - a[i] as f64) * f).round() as u8;
+            *color = [mix(0), mix(1), mix(2)];
+        }
+        Palette(colors)
+    }
+
+    /// Return the color for a gray level.
+    pub fn color(&self, level: u8) -> [u8; 3] {
+        self.0[level as usize]
+    }
+}
+
+#[test]
+fn test_palette() {
+    let gradient = Gradient::parse("0 0 0 255\n1 255 0 0").unwrap();
+    let palette = Palette::from_gradient(&gradient);
+    assert_eq!(palette.color(0), [0, 0, 255]);
+    assert_eq!(palette.color(255), [255, 0, 0]);
+    assert_eq!(Palette::grayscale().color(77), [77, 77, 77]);
+
+    let blended = Palette::blend(&palette, &Palette::grayscale());
+    assert_eq!(blended.color(0), [0, 0, 255]);
+    assert_eq!(blended.color(255), [255, 255, 255]);
+    assert_eq!(palette.color(51), [51, 0, 204]);
+    assert_eq!(blended.color(51), [51, 10, 173]);
+}
+
+/// Return gray levels for an image of dimensions `bounds` that run from 0 at
+/// the left edge to 255 at the right, the same in every row. Saved with a
+/// palette, they show its colors as a horizontal strip.
+pub fn ramp(bounds: (usize, usize)) -> Vec<u8> {
+    let last = bounds.0.saturating_sub(1).max(1);
+    let row: Vec<u8> = (0..bounds.0)
+        .map(|column| (column * u8::MAX as usize / last) as u8)
+        .collect();
+    row.repeat(bounds.1)
+}
+
+#[test]
+fn test_ramp() {
+    let levels = ramp((4, 2));
+    assert_eq!(levels, vec![0, 85, 170, 255, 0, 85, 170, 255]);
+    assert_eq!(ramp((1, 1)), vec![0]);
+}
@@ -0,0 +1,714 @@
+use super::{Channel, ColorOptions, EscapeResult, FractalParams};
+use num::{BigInt, Complex, One, ToPrimitive, Zero};
+use rayon::prelude::*;
+
+/// Number of references tried before giving up on the remaining glitches
+const MAX_REFERENCES: usize = 8;
+
+/// Pauldelbrot's glitch tolerance: a pixel whose orbit gets this much closer
+/// to zero than the reference has lost the precision of its delta.
+const GLITCH_TOLERANCE: f64 = 1e-6;
+
+/// A signed fixed-point number with `bits` fractional binary digits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixed {
+    value: BigInt,
+    bits: u32,
+}
+
+impl Fixed {
+    /// Return zero at the given precision.
+    pub fn zero(bits: u32) -> Fixed {
+        Fixed {
+            value: BigInt::zero(),
+            bits,
+        }
+    }
+
+    /// Convert a float exactly, then round it to `bits` fractional digits.
+    pub fn from_f64(x: f64, bits: u32) -> Fixed {
+        if x == 0.0 || !x.is_finite() {
+            return Fixed::zero(bits);
+        }
+        // Split x into an integer mantissa and a power of two
+        let raw = x.abs().to_bits();
+        let exponent = ((raw >> 52) & 0x7ff) as i64;
+        let (mantissa, exponent) = if exponent == 0 {
+            (raw & 0xf_ffff_ffff_ffff, -1074)
+        } else {
+            ((raw & 0xf_ffff_ffff_ffff) | (1 << 52), exponent - 1075)
+        };
+        let mut value = BigInt::from(mantissa);
+        let shift = exponent + bits as i64;
+        if shift >= 0 {
+            value <<= shift as usize;
+        } else {
+            value >>= (-shift) as usize;
+        }
+        if x < 0.0 {
+            value = -value;
+        }
+        Fixed { value, bits }
+    }
+
+    /// Parse a decimal number such as `-0.75`, `1e-3` or `2.5E10` without
+    /// going through `f64`, so every digit given is kept.
+    pub fn parse(s: &str, bits: u32) -> Option<Fixed> {
+        let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(index) => (&s[..index], s[index + 1..].parse::<i64>().ok()?),
+            None => (s, 0),
+        };
+        let (whole, fraction) = match mantissa.find('.') {
+            Some(index) => (&mantissa[..index], &mantissa[index + 1..]),
+            None => (mantissa, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        let digits = format!("{}{}", whole, fraction);
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        // value = digits * 10^exponent * 2^bits
+        let mut value: BigInt = digits.parse().ok()?;
+        value <<= bits as usize;
+        let exponent = exponent - fraction.len() as i64;
+        let power = num::pow(BigInt::from(10), exponent.unsigned_abs() as usize);
+        if exponent >= 0 {
+            value *= power;
+        } else {
+            value /= power;
+        }
+        if negative {
+            value = -value;
+        }
+        Some(Fixed { value, bits })
+    }
+
+    /// Round to the nearest `f64`, keeping full relative precision even for
+    /// values far below `f64::EPSILON`.
+    pub fn to_f64(&self) -> f64 {
+        let length = self.value.bits();
+        if length == 0 {
+            return 0.0;
+        }
+        // Keep only the top 64 bits so the conversion can't overflow
+        let shift = length.saturating_sub(64);
+        let top = (&self.value >> shift as usize).to_f64().unwrap_or(0.0);
+        let exponent = shift as i64 - self.bits as i64;
+        // Apply the power of two in two halves so neither step underflows early
+        let half = (exponent / 2) as i32;
+        top * 2f64.powi(half) * 2f64.powi(exponent as i32 - half)
+    }
+
+    fn add(&self, other: &Fixed) -> Fixed {
+        Fixed {
+            value: &self.value + &other.value,
+            bits: self.bits,
+        }
+    }
+
+    fn sub(&self, other: &Fixed) -> Fixed {
+        Fixed {
+            value: &self.value - &other.value,
+            bits: self.bits,
+        }
+    }
+
+    fn mul(&self, other: &Fixed) -> Fixed {
+        Fixed {
+            value: (&self.value * &other.value) >> self.bits as usize,
+            bits: self.bits,
+        }
+    }
+}
+
+/// Write the number in decimal with as many places as its binary digits
+/// take to tell its values apart, rounding the magnitude up so that parsing
+/// the text at the same precision, which rounds it down, gives the number
+/// back. Trailing zeros are left out.
+impl std::fmt::Display for Fixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let places = (self.bits as f64 * std::f64::consts::LOG10_2).ceil() as usize;
+        // Round the magnitude times 10^places up to a whole number
+        let mut scaled =
+            BigInt::from(self.value.magnitude().clone()) * num::pow(BigInt::from(10), places);
+        if self.bits > 0 {
+            scaled += (BigInt::one() << self.bits as usize) - 1;
+        }
+        let digits = format!(
+            "{:0>width$}",
+            scaled >> self.bits as usize,
+            width = places + 1
+        );
+        let (whole, fraction) = digits.split_at(digits.len() - places);
+        let fraction = fraction.trim_end_matches('0');
+        let sign =
+            if self.value.sign() == num::bigint::Sign::Minus && digits.bytes().any(|b| b != b'0') {
+                "-"
+            } else {
+                ""
+            };
+        match fraction {
+            "" => write!(f, "{}{}", sign, whole),
+            _ => write!(f, "{}{}.{}", sign, whole, fraction),
+        }
+    }
+}
+
+#[test]
+fn test_fixed_parse() {
+    let bits = 256;
+    assert_eq!(Fixed::parse("0.5", bits).unwrap().to_f64(), 0.5);
+    assert_eq!(Fixed::parse("-1.25", bits).unwrap().to_f64(), -1.25);
+    assert_eq!(Fixed::parse("+3", bits).unwrap().to_f64(), 3.0);
+    assert_eq!(Fixed::parse("2.5e2", bits).unwrap().to_f64(), 250.0);
+    assert_eq!(
+        Fixed::parse("1e-30", bits).unwrap().to_f64(),
+        1e-30_f64,
+        "tiny values keep their relative precision"
+    );
+    assert_eq!(Fixed::parse("", bits), None);
+    assert_eq!(Fixed::parse("1.2.3", bits), None);
+    assert_eq!(Fixed::parse("abc", bits), None);
+
+    // Digits beyond f64 precision survive and show up in differences
+    let a = Fixed::parse("0.10000000000000000000000000001", bits).unwrap();
+    let b = Fixed::parse("0.1", bits).unwrap();
+    let difference = a.sub(&b).to_f64();
+    assert!((difference - 1e-29).abs() < 1e-35);
+}
+
+#[test]
+fn test_fixed_arithmetic() {
+    let bits = 96;
+    for x in [0.0, 1.0, -0.75, std::f64::consts::PI, 1e-12, -2.5e7] {
+        assert_eq!(Fixed::from_f64(x, bits).to_f64(), x);
+    }
+    let a = Fixed::from_f64(1.5, bits);
+    let b = Fixed::from_f64(-0.25, bits);
+    assert_eq!(a.add(&b).to_f64(), 1.25);
+    assert_eq!(a.sub(&b).to_f64(), 1.75);
+    assert_eq!(a.mul(&b).to_f64(), -0.375);
+    assert_eq!(b.mul(&b).to_f64(), 0.0625);
+}
+
+#[test]
+fn test_fixed_display() {
+    assert_eq!(Fixed::from_f64(-1.25, 8).to_string(), "-1.25");
+    assert_eq!(Fixed::from_f64(3.0, 8).to_string(), "3");
+    assert_eq!(Fixed::zero(0).to_string(), "0");
+    // A third at 8 bits is 85/256, shown to the 3 places 8 bits resolve
+    assert_eq!(Fixed::parse("0.33333", 8).unwrap().to_string(), "0.333");
+
+    // Digits past f64's survive the round trip
+    let text = "-0.7436438870371587047522212450443";
+    let x = Fixed::parse(text, 128).unwrap();
+    assert!(x.to_string().starts_with(&text[..30]), "{}", x);
+    assert_eq!(Fixed::parse(&x.to_string(), 128), Some(x));
+}
+
+/// A point on the complex plane held at fixed-point precision.
+#[derive(Debug, Clone)]
+pub struct DeepPoint {
+    pub re: Fixed,
+    pub im: Fixed,
+}
+
+impl DeepPoint {
+    /// Parse a pair like `-0.75,0.1` at `bits` fractional binary digits.
+    pub fn parse(s: &str, bits: u32) -> Option<DeepPoint> {
+        let (re, im) = s.split_once(',')?;
+        Some(DeepPoint {
+            re: Fixed::parse(re, bits)?,
+            im: Fixed::parse(im, bits)?,
+        })
+    }
+
+    /// Return the point in the `REAL,IMAGINARY` format [`DeepPoint::parse`]
+    /// reads, with every digit its precision holds.
+    pub fn to_decimal(&self) -> String {
+        format!("{},{}", self.re, self.im)
+    }
+
+    /// Convert a float point exactly.
+    pub fn from_complex(c: Complex<f64>, bits: u32) -> DeepPoint {
+        DeepPoint {
+            re: Fixed::from_f64(c.re, bits),
+            im: Fixed::from_f64(c.im, bits),
+        }
+    }
+
+    /// Return this point moved by a small `f64` offset.
+    fn offset(&self, delta: Complex<f64>) -> DeepPoint {
+        let bits = self.re.bits;
+        DeepPoint {
+            re: self.re.add(&Fixed::from_f64(delta.re, bits)),
+            im: self.im.add(&Fixed::from_f64(delta.im, bits)),
+        }
+    }
+
+    /// Return the offset of this point from `other`, rounded to `f64`.
+    fn offset_from(&self, other: &DeepPoint) -> Complex<f64> {
+        Complex {
+            re: self.re.sub(&other.re).to_f64(),
+            im: self.im.sub(&other.im).to_f64(),
+        }
+    }
+}
+
+/// Return how many fractional binary digits are needed to tell apart
+/// neighbouring pixels of size `pixel_size`, with a safety margin for the
+/// rounding errors the reference orbit accumulates.
+pub fn precision_for(pixel_size: f64) -> u32 {
+    let digits = (-pixel_size.log2()).ceil().max(0.0) as u32;
+    digits + 64
+}
+
+#[test]
+fn test_precision_for() {
+    assert_eq!(precision_for(1.0), 64);
+    assert_eq!(precision_for(0.25), 66);
+    // 1e-100 is about 2^-332
+    assert_eq!(precision_for(1e-100), 333 + 64);
+}
+
+/// A view whose center is known to arbitrary precision. Only the distance to
+/// the edges is stored as a float, since it never needs more than `f64`'s
+/// relative precision.
+#[derive(Debug, Clone)]
+pub struct DeepView {
+    pub center: DeepPoint,
+    pub half_width: f64,
+    pub half_height: f64,
+}
+
+impl DeepView {
+    /// Build a view centered on `center`, extending `radius` along the shorter
+    /// image axis like [`super::transform::view_from_center`].
+    pub fn new(center: DeepPoint, radius: f64, bounds: (usize, usize)) -> DeepView {
+        let aspect = bounds.0 as f64 / bounds.1 as f64;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (radius * aspect, radius)
+        } else {
+            (radius, radius / aspect)
+        };
+        DeepView {
+            center,
+            half_width,
+            half_height,
+        }
+    }
+
+    /// Scale the view by `factor` around `pointer`, matching what
+    /// [`super::transform::scale_point`] does to the corners.
+    pub fn zoom(&mut self, pointer: &DeepPoint, factor: f64) {
+        let bits = self.center.re.bits;
+        let factor_fixed = Fixed::from_f64(factor, bits);
+        let scale =
+            |center: &Fixed, pointer: &Fixed| pointer.add(&center.sub(pointer).mul(&factor_fixed));
+        self.center = DeepPoint {
+            re: scale(&self.center.re, &pointer.re),
+            im: scale(&self.center.im, &pointer.im),
+        };
+        self.half_width *= factor;
+        self.half_height *= factor;
+    }
+
+    /// Return the upper-left and lower-right corners, rounded to `f64`.
+    pub fn corners(&self) -> (Complex<f64>, Complex<f64>) {
+        let center = Complex {
+            re: self.center.re.to_f64(),
+            im: self.center.im.to_f64(),
+        };
+        let half = Complex {
+            re: self.half_width,
+            im: -self.half_height,
+        };
+        (center - half, center + half)
+    }
+
+    /// Return the offset of the middle of a pixel from the view center.
+    fn pixel_offset(&self, bounds: (usize, usize), pixel: (usize, usize)) -> Complex<f64> {
+        let (column, row) = (pixel.0 as f64 + 0.5, pixel.1 as f64 + 0.5);
+        Complex {
+            re: -self.half_width + column * 2.0 * self.half_width / bounds.0 as f64,
+            im: self.half_height - row * 2.0 * self.half_height / bounds.1 as f64,
+        }
+    }
+}
+
+/// Iterate `z = z^2 + c` for the reference point at full precision, returning
+/// every value of `z` rounded to `f64`, up to and including the one that
+/// escapes.
+fn reference_orbit(c: &DeepPoint, limit: usize, escape_radius: f64) -> Vec<Complex<f64>> {
+    let bits = c.re.bits;
+    let two = Fixed {
+        value: BigInt::one() << (bits as usize + 1),
+        bits,
+    };
+    let mut orbit = Vec::with_capacity(limit);
+    let (mut re, mut im) = (Fixed::zero(bits), Fixed::zero(bits));
+    for _ in 0..limit {
+        let z = Complex {
+            re: re.to_f64(),
+            im: im.to_f64(),
+        };
+        orbit.push(z);
+        // Values past the radius (or ones that blew up) can't be continued
+        if z.norm_sqr() > escape_radius.powi(2) || re.value.bits() > bits as u64 + 8 {
+            break;
+        }
+        let re_squared = re.mul(&re);
+        let im_squared = im.mul(&im);
+        im = two.mul(&re).mul(&im).add(&c.im);
+        re = re_squared.sub(&im_squared).add(&c.re);
+    }
+    orbit
+}
+
+/// Outcome of iterating a single pixel against a reference orbit
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    /// The pixel was iterated as accurately as `escape_details` would
+    Done(EscapeResult),
+    /// The delta lost its precision and needs a different reference
+    Glitch,
+}
+
+/// Iterate the difference `dz` between a pixel's orbit and the reference
+/// orbit, where `dc` is the difference between their `c` values:
+/// `dz = 2 Z dz + dz^2 + dc`.
+fn perturb(orbit: &[Complex<f64>], dc: Complex<f64>, limit: usize, escape_radius: f64) -> Outcome {
+    let mut dz = Complex { re: 0.0, im: 0.0 };
+    let mut z = dz;
+    // The derivative and trap distance only need the full z, so plain floats do
+    let mut derivative = dz;
+    let mut min_trap_distance = f64::INFINITY;
+    let mut magnitude_sum = 0.0;
+    for i in 0..limit {
+        // The reference escaped before this pixel did
+        let Some(&reference) = orbit.get(i) else {
+            return Outcome::Glitch;
+        };
+        z = reference + dz;
+        let magnitude = z.norm_sqr();
+        if magnitude > escape_radius.powi(2) {
+            return Outcome::Done(EscapeResult {
+                iterations: Some(i),
+                final_z: z,
+                final_dz: derivative,
+                min_trap_distance,
+                ..EscapeResult::default()
+            });
+        }
+        if magnitude < GLITCH_TOLERANCE * reference.norm_sqr() {
+            return Outcome::Glitch;
+        }
+        derivative = 2.0 * z * derivative + 1.0;
+        dz = 2.0 * reference * dz + dz * dz + dc;
+        if let Some(&next) = orbit.get(i + 1) {
+            let magnitude = (next + dz).norm();
+            min_trap_distance = min_trap_distance.min(magnitude);
+            magnitude_sum += magnitude;
+        }
+    }
+    // Members report the last value that was checked against the radius
+    Outcome::Done(EscapeResult {
+        iterations: None,
+        final_z: z,
+        final_dz: derivative,
+        min_trap_distance,
+        mean_magnitude: magnitude_sum / limit as f64,
+        ..EscapeResult::default()
+    })
+}
+
+/// Return whether perturbation supports the formula selected by `params`.
+pub fn supports(params: &FractalParams) -> bool {
+    matches!(params.fractal_type, super::FractalType::Mandelbrot)
+        && params.power == 2
+        && params.conjugate.is_none()
+        && params.complex_power.is_none()
+        && params.morph.is_none()
+        && params.initial_z.is_none()
+}
+
+/// The main reference orbit of the last frame rendered, kept so that the
+/// frames of an animation can share it
+#[derive(Debug, Clone)]
+pub struct OrbitCache {
+    /// Largest fraction of the pixels that may glitch against a reused orbit
+    /// before it is thrown away and a new one computed
+    max_glitches: f64,
+    /// The reference point, the iteration limit and escape radius the orbit
+    /// was computed with, and the orbit
+    entry: Option<(DeepPoint, usize, f64, Vec<Complex<f64>>)>,
+    /// Number of frames that reused the orbit of an earlier frame
+    pub reused: usize,
+}
+
+impl OrbitCache {
+    /// Create an empty cache whose orbits are reused while no more than
+    /// `max_glitches` of the pixels, as a fraction, glitch against them.
+    pub fn new(max_glitches: f64) -> OrbitCache {
+        OrbitCache {
+            max_glitches,
+            entry: None,
+            reused: 0,
+        }
+    }
+
+    /// Return the offset of the cached reference from the center of `view`
+    /// and its orbit, if the reference is still inside the view and its
+    /// orbit runs at least `limit` iterations with the same escape radius.
+    fn reusable(
+        &self,
+        view: &DeepView,
+        limit: usize,
+        escape_radius: f64,
+    ) -> Option<(Complex<f64>, &[Complex<f64>])> {
+        let (reference, orbit_limit, orbit_radius, orbit) = self.entry.as_ref()?;
+        if *orbit_limit < limit
+            || *orbit_radius != escape_radius
+            || reference.re.bits != view.center.re.bits
+        {
+            return None;
+        }
+        let offset = reference.offset_from(&view.center);
+        let inside = offset.re.abs() <= view.half_width && offset.im.abs() <= view.half_height;
+        inside.then_some((offset, &orbit[..]))
+    }
+}
+
+/// Render `view` into `pixels` using perturbation theory: one reference orbit
+/// is computed at high precision and every pixel is iterated as a small `f64`
+/// offset from it. Pixels that glitch are retried against a new reference
+/// chosen among them.
+pub fn render<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    view: &DeepView,
+    params: &FractalParams,
+    colors: &ColorOptions,
+) {
+    render_cached(
+        pixels,
+        bounds,
+        view,
+        params,
+        colors,
+        &mut OrbitCache::new(0.0),
+    );
+}
+
+/// Render like [`render`], starting from the reference orbit left in
+/// `cache` by an earlier frame when its reference point is still in view.
+///
+/// During a zoom the reference stays in view for many frames, and its
+/// orbit, the costly high-precision part of the render, is the same. If
+/// more of the pixels than the cache allows glitch against it, the orbit is
+/// recomputed from the view center as if there were no cache. The main
+/// orbit the frame ends up with is kept in `cache` for the next frame.
+pub fn render_cached<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    view: &DeepView,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    cache: &mut OrbitCache,
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    assert!(supports(params));
+    let limit = params.max_iterations;
+
+    let mut outcomes = vec![Outcome::Glitch; pixels.len()];
+    let mut pending: Vec<usize> = (0..pixels.len()).collect();
+    // Start from the cached reference, or else the view center
+    let mut reference_offset = Complex { re: 0.0, im: 0.0 };
+    let mut cached = cache
+        .reusable(view, limit, params.escape_radius)
+        .map(|(offset, orbit)| (offset, orbit.to_vec()));
+
+    let mut references = 0;
+    while references < MAX_REFERENCES {
+        let reusing = cached.is_some();
+        let orbit = match cached.take() {
+            Some((offset, orbit)) => {
+                reference_offset = offset;
+                orbit
+            }
+            None => {
+                let reference = view.center.offset(reference_offset);
+                let orbit = reference_orbit(&reference, limit, params.escape_radius);
+                if references == 0 {
+                    cache.entry = Some((reference, limit, params.escape_radius, orbit.clone()));
+                }
+                orbit
+            }
+        };
+        let results: Vec<Outcome> = pending
+            .par_iter()
+            .map(|&index| {
+                let pixel = (index % bounds.0, index / bounds.0);
+                let dc = view.pixel_offset(bounds, pixel) - reference_offset;
+                perturb(&orbit, dc, limit, params.escape_radius)
+            })
+            .collect();
+        for (&index, &outcome) in pending.iter().zip(&results) {
+            outcomes[index] = outcome;
+        }
+
+        pending.retain(|&index| outcomes[index] == Outcome::Glitch);
+        if reusing {
+            if pending.len() as f64 > cache.max_glitches * pixels.len() as f64 {
+                // The old orbit no longer suits the view, so start afresh
+                pending = (0..pixels.len()).collect();
+                reference_offset = Complex { re: 0.0, im: 0.0 };
+                continue;
+            }
+            cache.reused += 1;
+        }
+        references += 1;
+        // Pick the next reference from the middle of the glitched pixels
+        let Some(&next) = pending.get(pending.len() / 2) else {
+            break;
+        };
+        reference_offset = view.pixel_offset(bounds, (next % bounds.0, next / bounds.0));
+    }
+
+    for (pixel, outcome) in pixels.iter_mut().zip(outcomes) {
+        // Any glitches left over are shown as members
+        *pixel = match outcome {
+            Outcome::Done(result) => colors.shade_result(&result, limit),
+            Outcome::Glitch => colors.shade(None, limit),
+        };
+    }
+}
+
+#[test]
+fn test_perturbation_matches_direct() {
+    use super::transform::pixel_to_point;
+
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let bounds = (64, 48);
+    let bits = precision_for(1e-3);
+    let center = DeepPoint::parse("-0.75,0.1", bits).unwrap();
+    let view = DeepView::new(center, 0.5, bounds);
+
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    render(&mut pixels, bounds, &view, &params, &colors);
+
+    let upper_left = Complex {
+        re: -0.75 - view.half_width,
+        im: 0.1 + view.half_height,
+    };
+    let lower_right = Complex {
+        re: -0.75 + view.half_width,
+        im: 0.1 - view.half_height,
+    };
+    let mut matching = 0;
+    for row in 0..bounds.1 {
+        for column in 0..bounds.0 {
+            let point = pixel_to_point(bounds, (column, row), upper_left, lower_right);
+            let escape = super::escape_time(
+                point,
+                params.max_iterations,
+                2,
+                params.escape_radius,
+                params.fractal_type,
+                None,
+            );
+            let expected: u8 = colors.shade(escape, params.max_iterations);
+            if pixels[row * bounds.0 + column] == expected {
+                matching += 1;
+            }
+        }
+    }
+    // Rounding differs slightly right at escape boundaries
+    assert!(
+        matching * 100 >= pixels.len() * 99,
+        "only {} of {} pixels match",
+        matching,
+        pixels.len()
+    );
+}
+
+#[test]
+fn test_perturbation_deep_zoom() {
+    // c = i is a Misiurewicz point, so the set keeps its detail at any depth;
+    // f64 can't even tell 1 from 1 + 1e-30, so a direct render is flat
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let bounds = (16, 16);
+    let radius = 1e-30;
+    let bits = precision_for(2.0 * radius / bounds.0 as f64);
+    let view = DeepView::new(DeepPoint::parse("0,1", bits).unwrap(), radius, bounds);
+
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    render(&mut pixels, bounds, &view, &params, &colors);
+
+    let first = pixels[0];
+    assert!(
+        pixels.iter().any(|&p| p != first),
+        "the deep view rendered as a single flat color"
+    );
+}
+
+#[test]
+fn test_deep_view_zoom() {
+    let bits = 128;
+    let mut view = DeepView::new(DeepPoint::parse("1,1", bits).unwrap(), 2.0, (100, 50));
+    assert_eq!((view.half_width, view.half_height), (4.0, 2.0));
+    view.zoom(&DeepPoint::parse("0,0", bits).unwrap(), 0.5);
+    assert_eq!(view.center.re.to_f64(), 0.5);
+    assert_eq!(view.center.im.to_f64(), 0.5);
+    assert_eq!((view.half_width, view.half_height), (2.0, 1.0));
+}
+
+#[test]
+fn test_orbit_cache() {
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let bounds = (32, 24);
+    let bits = precision_for(1e-6);
+    let pointer = DeepPoint::parse("-0.7435,0.1314", bits).unwrap();
+    let mut view = DeepView::new(pointer.clone(), 1e-3, bounds);
+    let mut cache = OrbitCache::new(0.5);
+
+    let mut first = vec![0u8; bounds.0 * bounds.1];
+    render_cached(&mut first, bounds, &view, &params, &colors, &mut cache);
+    assert_eq!(cache.reused, 0);
+
+    // Zooming about the reference keeps it in view, so its orbit is reused
+    // and the frame comes out as if rendered afresh
+    view.zoom(&pointer, 0.8);
+    let mut reused = vec![0u8; bounds.0 * bounds.1];
+    render_cached(&mut reused, bounds, &view, &params, &colors, &mut cache);
+    assert_eq!(cache.reused, 1);
+    let mut fresh = vec![0u8; bounds.0 * bounds.1];
+    render(&mut fresh, bounds, &view, &params, &colors);
+    let matching = reused.iter().zip(&fresh).filter(|(a, b)| a == b).count();
+    assert!(
+        matching * 100 >= fresh.len() * 99,
+        "only {} match",
+        matching
+    );
+
+    // A view the reference has left, or a higher limit, needs a new orbit
+    let far = DeepView::new(DeepPoint::parse("-0.74,0.13", bits).unwrap(), 1e-3, bounds);
+    render_cached(&mut reused, bounds, &far, &params, &colors, &mut cache);
+    let deeper = FractalParams {
+        max_iterations: 1000,
+        ..params
+    };
+    render_cached(&mut reused, bounds, &far, &deeper, &colors, &mut cache);
+    assert_eq!(cache.reused, 1);
+}
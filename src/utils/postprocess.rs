@@ -0,0 +1,571 @@
+use super::Channel;
+use std::str::FromStr;
+
+/// Apply gamma correction to a buffer of pixel values in place.
+///
+/// Each value is mapped as `max * (value / max)^(1 / gamma)`, where `max` is
+/// the brightest value of the channel type, so a `gamma`
+/// above 1.0 brightens the dark tones and a value below 1.0 darkens them.
+/// A `gamma` of exactly 1.0 leaves the buffer untouched.
+pub fn apply_gamma<P: Channel>(pixels: &mut [P], gamma: f64) {
+    assert!(gamma > 0.0);
+    if gamma == 1.0 {
+        return;
+    }
+
+    // Every channel value goes through the same curve, so precompute it once
+    let max = P::MAX as f64;
+    let table: Vec<u32> = (0..=P::MAX)
+        .map(|v| (max * (v as f64 / max).powf(1.0 / gamma)).round() as u32)
+        .collect();
+
+    for pixel in pixels.iter_mut() {
+        *pixel = pixel.with_level(table[pixel.level() as usize]);
+    }
+}
+
+#[test]
+fn test_apply_gamma() {
+    let original: Vec<u8> = vec![0, 64, 128, 255];
+
+    let mut pixels = original.clone();
+    apply_gamma(&mut pixels, 1.0);
+    assert_eq!(pixels, original);
+
+    let mut pixels = original.clone();
+    apply_gamma(&mut pixels, 2.0);
+    assert_eq!(pixels, vec![0, 128, 181, 255]);
+
+    let mut pixels = original.clone();
+    apply_gamma(&mut pixels, 0.5);
+    assert_eq!(pixels, vec![0, 16, 64, 255]);
+
+    let mut pixels: Vec<u16> = vec![0, 16384, 65535];
+    apply_gamma(&mut pixels, 0.5);
+    assert_eq!(pixels, vec![0, 4096, 65535]);
+
+    // Transparency is left alone
+    let mut pixels = vec![super::GrayAlpha::interior(64)];
+    apply_gamma(&mut pixels, 2.0);
+    assert_eq!(pixels[0].gray, 128);
+    assert_eq!(pixels[0].alpha, 0);
+}
+
+/// Sharpen a buffer of pixel values of dimensions `bounds` in place with an
+/// unsharp mask.
+///
+/// Each level moves away from the average of its neighbourhood, a Gaussian
+/// blur of one pixel, by `amount` times its difference from it, so edges
+/// gain contrast and flat areas stay flat. Alpha is left alone. The mask
+/// also amplifies noise, such as the grain of a Buddhabrot sampled from
+/// too few points. An `amount` of 0.0 leaves the buffer untouched.
+pub fn sharpen<P: Channel>(pixels: &mut [P], bounds: (usize, usize), amount: f64) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    if amount == 0.0 {
+        return;
+    }
+    let max = P::MAX as f32;
+    let mut plane: Vec<f32> = pixels.iter().map(|p| p.level() as f32 / max).collect();
+    unsharp_mask(&mut plane, bounds, amount as f32);
+    for (pixel, value) in pixels.iter_mut().zip(plane) {
+        *pixel = pixel.with_level((value * max).round() as u32);
+    }
+}
+
+/// Sharpen an RGB image of dimensions `bounds` in place like [`sharpen`],
+/// each color channel on its own.
+pub fn sharpen_rgb(pixels: &mut [[u8; 3]], bounds: (usize, usize), amount: f64) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    if amount == 0.0 {
+        return;
+    }
+    for channel in 0..3 {
+        let mut plane: Vec<f32> = pixels.iter().map(|p| p[channel] as f32 / 255.0).collect();
+        unsharp_mask(&mut plane, bounds, amount as f32);
+        for (pixel, value) in pixels.iter_mut().zip(plane) {
+            pixel[channel] = (value * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Apply an unsharp mask to a plane of values in `0.0..=1.0`, clamping the
+/// results to the same range.
+fn unsharp_mask(plane: &mut [f32], bounds: (usize, usize), amount: f32) {
+    let blurred = gaussian(plane, bounds, 1.0);
+    for (value, blurred) in plane.iter_mut().zip(blurred) {
+        *value = (*value + amount * (*value - blurred)).clamp(0.0, 1.0);
+    }
+}
+
+/// Return a plane of values of dimensions `bounds` blurred with a Gaussian
+/// of standard deviation `sigma` pixels.
+fn gaussian(plane: &[f32], bounds: (usize, usize), sigma: f32) -> Vec<f32> {
+    let image = image::ImageBuffer::<image::Luma<f32>, _>::from_raw(
+        bounds.0 as u32,
+        bounds.1 as u32,
+        plane.to_vec(),
+    )
+    .unwrap();
+    image::imageops::blur(&image, sigma).into_raw()
+}
+
+/// Blur a buffer of pixel values of dimensions `bounds` in place with a
+/// Gaussian of standard deviation `sigma` pixels, leaving alpha alone.
+pub fn blur<P: Channel>(pixels: &mut [P], bounds: (usize, usize), sigma: f64) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    assert!(sigma > 0.0);
+    let max = P::MAX as f32;
+    let plane: Vec<f32> = pixels.iter().map(|p| p.level() as f32 / max).collect();
+    for (pixel, value) in pixels
+        .iter_mut()
+        .zip(gaussian(&plane, bounds, sigma as f32))
+    {
+        *pixel = pixel.with_level((value.clamp(0.0, 1.0) * max).round() as u32);
+    }
+}
+
+/// Stretch the gray levels of a buffer in place away from middle gray by
+/// `factor`, clamping them to the channel range, so a `factor` above 1.0
+/// raises the contrast and one below lowers it. Alpha is left alone.
+pub fn contrast<P: Channel>(pixels: &mut [P], factor: f64) {
+    assert!(factor >= 0.0);
+    if factor == 1.0 {
+        return;
+    }
+    let middle = P::MAX as f64 / 2.0;
+    for pixel in pixels.iter_mut() {
+        let level = middle + factor * (pixel.level() as f64 - middle);
+        *pixel = pixel.with_level(level.round().clamp(0.0, P::MAX as f64) as u32);
+    }
+}
+
+#[test]
+fn test_blur_and_contrast() {
+    // A single bright pixel spreads into its neighbours
+    let mut pixels = vec![0u8; 25];
+    pixels[12] = 255;
+    blur(&mut pixels, (5, 5), 1.0);
+    assert!(pixels[12] < 255 && pixels[11] > 0 && pixels[7] > 0);
+    assert_eq!(pixels[11], pixels[13]);
+
+    let mut pixels: Vec<u8> = vec![0, 100, 128, 200, 255];
+    contrast(&mut pixels, 2.0);
+    assert_eq!(pixels, vec![0, 73, 129, 255, 255]);
+    let mut pixels: Vec<u16> = vec![0, 65535];
+    contrast(&mut pixels, 0.5);
+    assert_eq!(pixels, vec![16384, 49151]);
+}
+
+/// One step of a [`Pipeline`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostOp {
+    /// Gamma correction, see [`apply_gamma`]
+    Gamma(f64),
+    /// Contrast about middle gray, see [`contrast`]
+    Contrast(f64),
+    /// An unsharp mask of this amount, see [`sharpen`]
+    Sharpen(f64),
+    /// A Gaussian blur of this many pixels, see [`blur`]
+    Blur(f64),
+}
+
+/// An ordered list of post-processing steps applied to a frame one after
+/// another, parsed from comma-separated `name=value` pairs such as
+/// `gamma=2.2,contrast=1.1,sharpen=0.5`.
+///
+/// The names are `gamma` and `blur`, which take a positive value, and
+/// `contrast` and `sharpen`, which also take zero. A step may appear more
+/// than once.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pipeline(pub Vec<PostOp>);
+
+impl Pipeline {
+    /// Apply every step in turn to a buffer of dimensions `bounds`.
+    pub fn apply<P: Channel>(&self, pixels: &mut [P], bounds: (usize, usize)) {
+        for op in &self.0 {
+            match *op {
+                PostOp::Gamma(gamma) => apply_gamma(pixels, gamma),
+                PostOp::Contrast(factor) => contrast(pixels, factor),
+                PostOp::Sharpen(amount) => sharpen(pixels, bounds, amount),
+                PostOp::Blur(sigma) => blur(pixels, bounds, sigma),
+            }
+        }
+    }
+}
+
+impl FromStr for Pipeline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|step| {
+                let step = step.trim();
+                let (name, value) = step.split_once('=').ok_or_else(|| {
+                    format!("`{}` must be a name and a value, like gamma=2.2", step)
+                })?;
+                let value: f64 = match value.trim().parse() {
+                    Ok(value) if f64::is_finite(value) => value,
+                    _ => return Err(format!("invalid value in `{}`", step)),
+                };
+                let (op, in_range) = match name.trim() {
+                    "gamma" => (PostOp::Gamma(value), value > 0.0),
+                    "blur" => (PostOp::Blur(value), value > 0.0),
+                    "contrast" => (PostOp::Contrast(value), value >= 0.0),
+                    "sharpen" => (PostOp::Sharpen(value), value >= 0.0),
+                    name => {
+                        return Err(format!(
+                            "unknown step `{}`, expected gamma, contrast, sharpen or blur",
+                            name
+                        ));
+                    }
+                };
+                if !in_range {
+                    return Err(format!("`{}` is out of range", step));
+                }
+                Ok(op)
+            })
+            .collect::<Result<_, _>>()
+            .map(Pipeline)
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    let pipeline: Pipeline = "gamma=2, contrast=1.5,sharpen=0,blur=0.5".parse().unwrap();
+    assert_eq!(
+        pipeline.0,
+        [
+            PostOp::Gamma(2.0),
+            PostOp::Contrast(1.5),
+            PostOp::Sharpen(0.0),
+            PostOp::Blur(0.5)
+        ]
+    );
+    for (s, error) in [
+        (
+            "gamma",
+            "`gamma` must be a name and a value, like gamma=2.2",
+        ),
+        ("gamma=2,", "` must be a name and a value, like gamma=2.2"),
+        ("gamma=x", "invalid value in `gamma=x`"),
+        ("blur=inf", "invalid value in `blur=inf`"),
+        ("gamma=0", "`gamma=0` is out of range"),
+        ("contrast=-1", "`contrast=-1` is out of range"),
+        (
+            "glow=1",
+            "unknown step `glow`, expected gamma, contrast, sharpen or blur",
+        ),
+    ] {
+        assert!(
+            s.parse::<Pipeline>().unwrap_err().ends_with(error),
+            "{:?}",
+            s
+        );
+    }
+
+    // Steps run in order, so they do not commute
+    let original: Vec<u8> = vec![0, 64, 128, 255];
+    let mut pixels = original.clone();
+    "gamma=2,contrast=2"
+        .parse::<Pipeline>()
+        .unwrap()
+        .apply(&mut pixels, (4, 1));
+    let mut expected = original.clone();
+    apply_gamma(&mut expected, 2.0);
+    contrast(&mut expected, 2.0);
+    assert_eq!(pixels, expected);
+    let mut reversed = original.clone();
+    "contrast=2,gamma=2"
+        .parse::<Pipeline>()
+        .unwrap()
+        .apply(&mut reversed, (4, 1));
+    assert_ne!(reversed, pixels);
+}
+
+#[test]
+fn test_sharpen() {
+    // A soft step between two grays becomes steeper on both sides
+    let row = [100u8, 100, 100, 120, 140, 160, 160, 160];
+    let mut pixels: Vec<u8> = row.repeat(4);
+    sharpen(&mut pixels, (8, 4), 1.0);
+    assert!(pixels[2] < 100 && pixels[5] > 160, "{:?}", &pixels[..8]);
+    // Flat areas and an amount of zero are left alone
+    let mut pixels = vec![90u16; 16];
+    sharpen(&mut pixels, (4, 4), 2.0);
+    assert_eq!(pixels, vec![90; 16]);
+    let mut pixels: Vec<u8> = row.repeat(4);
+    sharpen(&mut pixels, (8, 4), 0.0);
+    assert_eq!(pixels, row.repeat(4));
+
+    // Each color channel is sharpened on its own
+    let mut pixels: Vec<[u8; 3]> = row.iter().map(|&v| [v, 50, 255 - v]).collect();
+    sharpen_rgb(&mut pixels, (8, 1), 1.0);
+    assert!(pixels[2][0] < 100 && pixels[2][2] > 155);
+    assert!(pixels.iter().all(|p| p[1] == 50));
+}
+
+/// Blend `other` into `pixels` in place, giving it `weight` between 0.0
+/// (keep `pixels`) and 1.0 (replace them).
+///
+/// Only gray levels are mixed; the alpha of `pixels` is kept.
+pub fn crossfade<P: Channel>(pixels: &mut [P], other: &[P], weight: f64) {
+    assert!(pixels.len() == other.len());
+    let weight = weight.clamp(0.0, 1.0);
+    for (pixel, other) in pixels.iter_mut().zip(other) {
+        let level = pixel.level() as f64 * (1.0 - weight) + other.level() as f64 * weight;
+        *pixel = pixel.with_level(level.round() as u32);
+    }
+}
+
+#[test]
+fn test_crossfade() {
+    let mut pixels: Vec<u8> = vec![0, 100, 255];
+    crossfade(&mut pixels, &[255, 100, 0], 0.25);
+    assert_eq!(pixels, vec![64, 100, 191]);
+
+    let mut pixels: Vec<u16> = vec![0, 65535];
+    crossfade(&mut pixels, &[65535, 0], 1.0);
+    assert_eq!(pixels, vec![65535, 0]);
+}
+
+/// Add the gray level of every pixel of `pixels` to its entry of `sums`.
+pub fn accumulate<P: Channel>(sums: &mut [u32], pixels: &[P]) {
+    assert!(sums.len() == pixels.len());
+    for (sum, pixel) in sums.iter_mut().zip(pixels) {
+        *sum += pixel.level();
+    }
+}
+
+/// Set the gray levels of `pixels` to the average of `n` levels summed in
+/// `sums` by [`accumulate`], keeping the alpha of `pixels`.
+pub fn average<P: Channel>(pixels: &mut [P], sums: &[u32], n: u32) {
+    assert!(pixels.len() == sums.len() && n > 0);
+    for (pixel, &sum) in pixels.iter_mut().zip(sums) {
+        *pixel = pixel.with_level((sum + n / 2) / n);
+    }
+}
+
+#[test]
+fn test_average() {
+    let mut sums = vec![0; 3];
+    accumulate(&mut sums, &[0u8, 100, 255]);
+    accumulate(&mut sums, &[255u8, 101, 255]);
+    assert_eq!(sums, vec![255, 201, 510]);
+    let mut pixels = vec![0u8; 3];
+    average(&mut pixels, &sums, 2);
+    assert_eq!(pixels, vec![128, 101, 255]);
+}
+
+/// Filter used to reduce a supersampled buffer to the output size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Downscale {
+    /// Average each block of samples with equal weights
+    Box,
+    /// Weigh samples by a tent that falls off linearly with distance
+    Bilinear,
+    /// Weigh samples by a three-lobed windowed sinc, which keeps the
+    /// boundary of the set sharpest
+    Lanczos,
+    /// Weigh samples by a Gaussian with a standard deviation of half an
+    /// output pixel, which smooths edges without any ringing
+    Gaussian,
+    /// Weigh samples by the Mitchell-Netravali cubic with B = C = 1/3, sharper
+    /// than the Gaussian with hardly any ringing
+    Mitchell,
+}
+
+/// Return the Gaussian weight of a sample `x` output pixels from the center
+/// of the pixel, cut off at three standard deviations.
+fn gaussian_weight(x: f32) -> f32 {
+    const SIGMA: f32 = 0.5;
+    if x.abs() < 3.0 * SIGMA {
+        (-x * x / (2.0 * SIGMA * SIGMA)).exp()
+    } else {
+        0.0
+    }
+}
+
+/// Return the Mitchell-Netravali weight, with B = C = 1/3, of a sample `x`
+/// output pixels from the center of the pixel.
+fn mitchell_weight(x: f32) -> f32 {
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+    let x = x.abs();
+    let weight = if x < 1.0 {
+        (12.0 - 9.0 * B - 6.0 * C) * x.powi(3)
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B)
+    } else if x < 2.0 {
+        (-B - 6.0 * C) * x.powi(3)
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C)
+    } else {
+        0.0
+    };
+    weight / 6.0
+}
+
+/// Reduce `large`, rendered at `factor` times the dimensions `bounds` in
+/// each direction, into `pixels` with `filter`.
+///
+/// Gray levels and, if the channel type has one, alpha are filtered
+/// separately. A `factor` of 1 copies the buffer unchanged.
+pub fn downscale<P: Channel>(
+    large: &[P],
+    factor: usize,
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    filter: Downscale,
+) {
+    assert!(factor > 0);
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    assert!(large.len() == pixels.len() * factor * factor);
+    if factor == 1 {
+        pixels.copy_from_slice(large);
+        return;
+    }
+    let (width, height) = (bounds.0 * factor, bounds.1 * factor);
+    let max = P::MAX as f32;
+    let finish = |level: f32, alpha: f32| {
+        P::from_level((level * max).round().clamp(0.0, max) as u32)
+            .with_alpha((alpha * 255.0).round().clamp(0.0, 255.0) as u8)
+    };
+
+    match filter {
+        Downscale::Box => {
+            let area = (factor * factor) as f32;
+            for (index, pixel) in pixels.iter_mut().enumerate() {
+                let (column, row) = (index % bounds.0, index / bounds.0);
+                let (mut level, mut alpha) = (0.0, 0.0);
+                for y in row * factor..(row + 1) * factor {
+                    for sample in &large[y * width + column * factor..][..factor] {
+                        level += sample.level() as f32 / max;
+                        alpha += sample.alpha() as f32 / 255.0;
+                    }
+                }
+                *pixel = finish(level / area, alpha / area);
+            }
+        }
+        Downscale::Bilinear | Downscale::Lanczos => {
+            let raw: Vec<f32> = large
+                .iter()
+                .flat_map(|p| [p.level() as f32 / max, p.alpha() as f32 / 255.0])
+                .collect();
+            let image = image::ImageBuffer::<image::LumaA<f32>, _>::from_raw(
+                width as u32,
+                height as u32,
+                raw,
+            )
+            .unwrap();
+            let filter = match filter {
+                Downscale::Bilinear => image::imageops::FilterType::Triangle,
+                _ => image::imageops::FilterType::Lanczos3,
+            };
+            let small = image::imageops::resize(&image, bounds.0 as u32, bounds.1 as u32, filter);
+            for (pixel, sample) in pixels.iter_mut().zip(small.pixels()) {
+                let [level, alpha] = sample.0;
+                *pixel = finish(level, alpha);
+            }
+        }
+        Downscale::Gaussian | Downscale::Mitchell => {
+            let (kernel, radius): (fn(f32) -> f32, f32) = match filter {
+                Downscale::Gaussian => (gaussian_weight, 1.5),
+                _ => (mitchell_weight, 2.0),
+            };
+            // The weight of each sample, from the offset of its center from
+            // the center of the pixel, which is the same for every pixel
+            let reach = ((radius - 0.5) * factor as f32).ceil() as isize;
+            let taps: Vec<(isize, f32)> = (-reach..factor as isize + reach)
+                .map(|t| (t, kernel((t as f32 + 0.5) / factor as f32 - 0.5)))
+                .filter(|&(_, weight)| weight != 0.0)
+                .collect();
+            // Past the edges of the image the weights of the samples left
+            // are scaled up to sum to one
+            let filter_at = |sample: &dyn Fn(usize) -> [f32; 2], len: usize, output: usize| {
+                let (mut sum, mut total) = ([0.0f32; 2], 0.0f32);
+                for &(t, weight) in &taps {
+                    let s = (output * factor) as isize + t;
+                    if s >= 0 && (s as usize) < len {
+                        let [level, alpha] = sample(s as usize);
+                        sum[0] += weight * level;
+                        sum[1] += weight * alpha;
+                        total += weight;
+                    }
+                }
+                [sum[0] / total, sum[1] / total]
+            };
+            let value = |p: &P| [p.level() as f32 / max, p.alpha() as f32 / 255.0];
+
+            // The kernel is separable, so filter the rows and then the columns
+            let mut rows = vec![[0.0f32; 2]; bounds.0 * height];
+            for (index, value_at) in rows.iter_mut().enumerate() {
+                let (column, y) = (index % bounds.0, index / bounds.0);
+                *value_at = filter_at(&|s| value(&large[y * width + s]), width, column);
+            }
+            for (index, pixel) in pixels.iter_mut().enumerate() {
+                let (column, row) = (index % bounds.0, index / bounds.0);
+                let [level, alpha] = filter_at(&|s| rows[s * bounds.0 + column], height, row);
+                *pixel = finish(level, alpha);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_downscale() {
+    // Four blocks of 2x2 samples, each reduced to one pixel
+    let large: Vec<u8> = vec![
+        0, 0, 200, 200, //
+        0, 0, 200, 200, //
+        100, 100, 255, 255, //
+        100, 100, 255, 255,
+    ];
+    let mut pixels = vec![0u8; 4];
+    downscale(&large, 2, &mut pixels, (2, 2), Downscale::Box);
+    assert_eq!(pixels, vec![0, 200, 100, 255]);
+    // The wider filters blend in neighbouring blocks but keep their order
+    for filter in [
+        Downscale::Bilinear,
+        Downscale::Lanczos,
+        Downscale::Gaussian,
+        Downscale::Mitchell,
+    ] {
+        downscale(&large, 2, &mut pixels, (2, 2), filter);
+        assert!(
+            pixels[0] < pixels[2] && pixels[2] < pixels[1] && pixels[1] < pixels[3],
+            "{:?} gave {:?}",
+            filter,
+            pixels
+        );
+    }
+
+    // The kernels weigh the samples nearest the center most and keep flat
+    // areas flat
+    assert!((mitchell_weight(0.0) - 8.0 / 9.0).abs() < 1e-6 && mitchell_weight(2.0) == 0.0);
+    assert!(mitchell_weight(1.5) < 0.0 && gaussian_weight(0.5) < gaussian_weight(0.0));
+    let flat = vec![77u8; 36];
+    for filter in [Downscale::Gaussian, Downscale::Mitchell] {
+        let mut pixels = vec![0u8; 4];
+        downscale(&flat, 3, &mut pixels, (2, 2), filter);
+        assert_eq!(pixels, vec![77; 4], "{:?}", filter);
+    }
+
+    // Box averaging mixes the gray and the alpha of each block
+    let mut large = vec![super::GrayAlpha::from_level(255); 4];
+    large[0] = super::GrayAlpha::interior(0);
+    let mut pixels = vec![super::GrayAlpha::default()];
+    downscale(&large, 2, &mut pixels, (1, 1), Downscale::Box);
+    assert_eq!(
+        pixels[0],
+        super::GrayAlpha {
+            gray: 191,
+            alpha: 191
+        }
+    );
+
+    // A factor of one leaves the buffer alone
+    let mut pixels = vec![0u16; 3];
+    downscale(&[1, 2, 3], 1, &mut pixels, (3, 1), Downscale::Lanczos);
+    assert_eq!(pixels, vec![1, 2, 3]);
+}
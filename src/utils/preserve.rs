@@ -1,125 +1,1866 @@
-use image::{ImageBuffer, Luma};
+use super::Channel;
+use super::error::FractalError;
+use super::palette::Palette;
 /// Write the buffer `pixels`, whose dimensions are given by `bounds`, to the
-/// file named `filename`.
-pub fn write_image(
+/// file named `filename`, creating its folder if needed.
+///
+/// See [`write_image_to`] for the format.
+pub fn write_image<P: Channel>(
+    filename: &str,
+    pixels: &[P],
+    bounds: (usize, usize),
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+) -> Result<(), FractalError> {
+    check_profile_channels(icc_profile, true)?;
+    write_image_to(
+        create_file(filename)?,
+        pixels,
+        bounds,
+        dpi,
+        icc_profile,
+        text,
+    )
+}
+
+/// Encode the buffer `pixels`, whose dimensions are given by `bounds`, as a
+/// PNG image into `sink`, such as a file or standard output.
+///
+/// The image is saved as 8-bit or 16-bit grayscale depending on the type of
+/// the values in `pixels`, with an alpha channel if they carry one. If `dpi`
+/// is given it is recorded in the file, so that print software sizes the
+/// image correctly, and an `icc_profile`, which must be a gray one, is
+/// embedded in it. Each `(keyword, text)` pair of `text` is stored in a tEXt
+/// chunk.
+pub fn write_image_to<P: Channel>(
+    sink: impl std::io::Write,
+    pixels: &[P],
+    bounds: (usize, usize),
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+) -> Result<(), FractalError> {
+    let wide = P::MAX > u8::MAX as u32;
+    let color = if P::HAS_ALPHA {
+        png::ColorType::GrayscaleAlpha
+    } else {
+        png::ColorType::Grayscale
+    };
+    let depth = if wide {
+        png::BitDepth::Sixteen
+    } else {
+        png::BitDepth::Eight
+    };
+    write_png(
+        sink,
+        bounds,
+        color,
+        depth,
+        None,
+        dpi,
+        icc_profile,
+        text,
+        |y, raw| {
+            for &pixel in &pixels[y * bounds.0..(y + 1) * bounds.0] {
+                let level = pixel.level();
+                if wide {
+                    raw.extend_from_slice(&(level as u16).to_be_bytes());
+                } else {
+                    raw.push(level as u8);
+                }
+                if P::HAS_ALPHA {
+                    raw.push(pixel.alpha());
+                }
+            }
+        },
+    )
+}
+
+/// Write the 8-bit gray levels in `pixels` to the file named `filename`,
+/// replacing each level by its color in `palette`, see
+/// [`write_palette_image_to`].
+pub fn write_palette_image<P: Channel>(
     filename: &str,
-    pixels: &[u8],
+    pixels: &[P],
+    bounds: (usize, usize),
+    palette: &Palette,
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+) -> Result<(), FractalError> {
+    check_profile_channels(icc_profile, false)?;
+    write_palette_image_to(
+        create_file(filename)?,
+        pixels,
+        bounds,
+        palette,
+        dpi,
+        icc_profile,
+        text,
+    )
+}
+
+/// Encode the 8-bit gray levels in `pixels` as a PNG image into `sink`,
+/// replacing each level by its color in `palette`.
+///
+/// The image is saved as RGBA if the values in `pixels` carry an alpha
+/// channel, and as RGB otherwise. `dpi`, `icc_profile`, an RGB one, and
+/// `text` are recorded in the file like [`write_image_to`] does.
+pub fn write_palette_image_to<P: Channel>(
+    sink: impl std::io::Write,
+    pixels: &[P],
     bounds: (usize, usize),
-) -> Result<(), std::io::Error> {
-    // Make sure the folder is created
+    palette: &Palette,
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+) -> Result<(), FractalError> {
+    assert!(P::MAX == u8::MAX as u32);
+    let color = if P::HAS_ALPHA {
+        png::ColorType::Rgba
+    } else {
+        png::ColorType::Rgb
+    };
+    write_png(
+        sink,
+        bounds,
+        color,
+        png::BitDepth::Eight,
+        None,
+        dpi,
+        icc_profile,
+        text,
+        |y, raw| {
+            for p in &pixels[y * bounds.0..(y + 1) * bounds.0] {
+                raw.extend_from_slice(&palette.color(p.level() as u8));
+                if P::HAS_ALPHA {
+                    raw.push(p.alpha());
+                }
+            }
+        },
+    )
+}
+
+/// Write the gray levels in `pixels` to the file named `filename` as an
+/// indexed PNG whose palette is `palette`, see [`write_indexed_image_to`].
+pub fn write_indexed_image<P: Channel>(
+    filename: &str,
+    pixels: &[P],
+    bounds: (usize, usize),
+    palette: &Palette,
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+) -> Result<(), FractalError> {
+    check_profile_channels(icc_profile, false)?;
+    write_indexed_image_to(
+        create_file(filename)?,
+        pixels,
+        bounds,
+        palette,
+        dpi,
+        icc_profile,
+        text,
+    )
+}
+
+/// Encode the gray levels in `pixels` into `sink` as an indexed PNG whose
+/// palette is `palette`, so that each pixel is stored as the one-byte index
+/// of its color, like the frames of a GIF.
+///
+/// Levels deeper than 8 bits are reduced to 8 first and then matched to the
+/// first palette entry of their color. If the values in `pixels` carry an
+/// alpha channel, the last palette entry is given up to transparency, as in
+/// a transparent GIF: the transparent interior and any pixel less than half
+/// opaque take it, and every other pixel the closest of the remaining
+/// colors. `dpi`, `icc_profile` and `text` are recorded in the file like
+/// [`write_image_to`] does.
+pub fn write_indexed_image_to<P: Channel>(
+    sink: impl std::io::Write,
+    pixels: &[P],
+    bounds: (usize, usize),
+    palette: &Palette,
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+) -> Result<(), FractalError> {
+    let mut indices = PaletteIndex::new(palette, P::HAS_ALPHA);
+    write_png(
+        sink,
+        bounds,
+        png::ColorType::Indexed,
+        png::BitDepth::Eight,
+        Some((palette, P::HAS_ALPHA)),
+        dpi,
+        icc_profile,
+        text,
+        |y, raw| {
+            for p in &pixels[y * bounds.0..(y + 1) * bounds.0] {
+                if P::HAS_ALPHA && p.alpha() < 128 {
+                    raw.push(TRANSPARENT_INDEX);
+                } else {
+                    let level = (p.level() as u64 * u8::MAX as u64 / P::MAX as u64) as u8;
+                    raw.push(indices.get(palette.color(level)));
+                }
+            }
+        },
+    )
+}
+
+#[test]
+fn test_write_indexed_image() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("frame.png");
+    let filename = path.display().to_string();
+    let read = |path: &std::path::Path| {
+        let decoder = png::Decoder::new(std::fs::File::open(path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        let info = reader.info();
+        assert_eq!(info.color_type, png::ColorType::Indexed);
+        let plte = info.palette.as_ref().unwrap().to_vec();
+        (data, plte, info.trns.as_ref().map(|t| t.to_vec()))
+    };
+
+    // Gray levels are their own indices, and decode to the palette colors
+    let gradient = super::palette::Gradient::parse("0 0 0 255\n1 255 0 0").unwrap();
+    let palette = Palette::from_gradient(&gradient);
+    write_indexed_image(
+        &filename,
+        &[0u8, 9, 200, 255],
+        (2, 2),
+        &palette,
+        None,
+        None,
+        &[],
+    )
+    .unwrap();
+    let (data, plte, trns) = read(&path);
+    assert_eq!(data, [0, 9, 200, 255]);
+    assert_eq!(plte, palette.0.as_flattened());
+    assert_eq!(trns, None);
+    let rgb = image::open(&path).unwrap().to_rgb8().into_raw();
+    let mut palette_path = dir.path().join("palette.png");
+    write_palette_image(
+        &palette_path.display().to_string(),
+        &[0u8, 9, 200, 255],
+        (2, 2),
+        &palette,
+        None,
+        None,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        rgb,
+        image::open(&palette_path).unwrap().to_rgb8().into_raw()
+    );
+
+    // Repeated colors share the index of their first entry, and deeper
+    // levels are reduced to 8 bits
+    write_indexed_image(
+        &filename,
+        &[0u16, 257 * 9, 65535, 7],
+        (2, 2),
+        &Palette::grayscale(),
+        None,
+        None,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(read(&path).0, [0, 9, 255, 0]);
+
+    // The last entry is kept for the transparent interior
+    let pixels = [
+        super::GrayAlpha { gray: 10, alpha: 0 },
+        super::GrayAlpha {
+            gray: 255,
+            alpha: 255,
+        },
+        super::GrayAlpha {
+            gray: 254,
+            alpha: 200,
+        },
+        super::GrayAlpha {
+            gray: 3,
+            alpha: 100,
+        },
+    ];
+    palette_path.set_file_name("alpha.png");
+    let filename = palette_path.display().to_string();
+    write_indexed_image(
+        &filename,
+        &pixels,
+        (2, 2),
+        &Palette::grayscale(),
+        None,
+        None,
+        &[],
+    )
+    .unwrap();
+    let (data, _, trns) = read(&palette_path);
+    assert_eq!(data, [255, 254, 254, 255]);
+    let trns = trns.unwrap();
+    assert_eq!(trns.len(), 256);
+    assert!(trns[..255].iter().all(|&a| a == 255) && trns[255] == 0);
+}
+
+/// Write the RGB colors in `pixels` to the file named `filename`, recording
+/// `dpi`, `icc_profile` and `text` in the file like [`write_image`] does.
+pub fn write_rgb_image(
+    filename: &str,
+    pixels: &[[u8; 3]],
+    bounds: (usize, usize),
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+) -> Result<(), FractalError> {
+    check_profile_channels(icc_profile, false)?;
+    write_png(
+        create_file(filename)?,
+        bounds,
+        png::ColorType::Rgb,
+        png::BitDepth::Eight,
+        None,
+        dpi,
+        icc_profile,
+        text,
+        |y, raw| raw.extend_from_slice(pixels[y * bounds.0..(y + 1) * bounds.0].as_flattened()),
+    )
+}
+
+/// Write `values`, one float per pixel of an image of dimensions `bounds`,
+/// to `filename` as a 32-bit float OpenEXR image, which must end in `.exr`.
+///
+/// Floats are only encoded in color images, so each value is written to the
+/// red, green and blue channels alike.
+pub fn write_float_image(
+    filename: &str,
+    values: &[f32],
+    bounds: (usize, usize),
+) -> Result<(), FractalError> {
+    assert!(values.len() == bounds.0 * bounds.1);
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str());
+    if !extension.is_some_and(|e| e.eq_ignore_ascii_case("exr")) {
+        return Err(FractalError::Parse(format!(
+            "{} is not an .exr file",
+            filename
+        )));
+    }
+    let (width, height) = (bounds.0 as u32, bounds.1 as u32);
+    let image = image::Rgb32FImage::from_fn(width, height, |x, y| {
+        image::Rgb([values[y as usize * bounds.0 + x as usize]; 3])
+    });
+    image.save(filename)?;
+    Ok(())
+}
+
+#[test]
+fn test_write_float_image() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let values = [-1.0, 0.0, 2.5, 17.125, 100.0, 1e-3];
+    let path = dir.path().join("values.exr").display().to_string();
+    write_float_image(&path, &values, (3, 2)).unwrap();
+    let read = image::open(&path).unwrap().to_rgb32f();
+    assert_eq!(read.dimensions(), (3, 2));
+    let red: Vec<f32> = read.pixels().map(|p| p.0[0]).collect();
+    assert_eq!(red, values);
+    let path = dir.path().join("values.tiff").display().to_string();
+    assert!(matches!(
+        write_float_image(&path, &values, (3, 2)),
+        Err(FractalError::Parse(_))
+    ));
+}
+
+/// Check that `profile` holds an ICC profile that a PNG file can embed: a
+/// whole profile, by the length and signature in its header, of the gray or
+/// RGB color space, since PNG images have no other channels to describe.
+pub fn check_icc_profile(profile: &[u8]) -> Result<(), String> {
+    if profile.len() < 128 || &profile[36..40] != b"acsp" {
+        return Err("not an ICC profile".to_string());
+    }
+    let size = u32::from_be_bytes(profile[..4].try_into().unwrap()) as usize;
+    if size != profile.len() {
+        return Err(format!(
+            "the ICC profile header gives {} bytes, but there are {}",
+            size,
+            profile.len()
+        ));
+    }
+    match &profile[16..20] {
+        b"GRAY" | b"RGB " => Ok(()),
+        space => Err(format!(
+            "PNG files only embed gray or RGB profiles, not {} ones",
+            String::from_utf8_lossy(space).trim()
+        )),
+    }
+}
+
+/// Check that `icc_profile`, if any, describes the channels of a `gray` or
+/// color image, before any file is created for the image.
+fn check_profile_channels(icc_profile: Option<&[u8]>, gray: bool) -> Result<(), FractalError> {
+    let Some(profile) = icc_profile else {
+        return Ok(());
+    };
+    let expected: &[u8] = if gray { b"GRAY" } else { b"RGB " };
+    if &profile[16..20] != expected {
+        return Err(FractalError::Parse(format!(
+            "the ICC profile is for {} images, but this image is saved in {}",
+            String::from_utf8_lossy(&profile[16..20]).trim(),
+            if gray { "grayscale" } else { "color" }
+        )));
+    }
+    Ok(())
+}
+
+/// Create the file named `filename` for writing, with the folders leading to
+/// it.
+pub fn create_file(filename: &str) -> Result<std::io::BufWriter<std::fs::File>, FractalError> {
     let path = std::path::Path::new(filename);
     if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to create directory: {}", e),
-            )
-        })?;
-    }
-    // Create an image buffer from the pixel data
-    if let Some(img) =
-        ImageBuffer::<Luma<u8>, _>::from_raw(bounds.0 as u32, bounds.1 as u32, pixels.to_vec())
-    {
-        // Save the image, converting any errors to std::io::Error
-        img.save(filename)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        std::fs::create_dir_all(parent)?;
     }
+    Ok(std::io::BufWriter::new(std::fs::File::create(path)?))
+}
 
-    Ok(())
+/// Encode a PNG image into `sink`.
+///
+/// The image is streamed to the encoder one row at a time: `fill_row(y, raw)`
+/// appends the samples of row `y` to the empty `raw`, so no copy of the whole
+/// image is ever held in memory.
+///
+/// Indexed images store `palette` in the PLTE chunk, and if its flag is set
+/// make the last entry transparent in the tRNS chunk. A resolution of `dpi`
+/// dots per inch is stored in the pHYs chunk, which counts pixels per meter,
+/// `icc_profile` in the iCCP chunk and `text` in tEXt chunks ahead of the
+/// pixels. A profile must describe the color space of the image, gray for
+/// grayscale images and RGB for color ones.
+#[allow(clippy::too_many_arguments)]
+fn write_png(
+    sink: impl std::io::Write,
+    bounds: (usize, usize),
+    color: png::ColorType,
+    depth: png::BitDepth,
+    palette: Option<(&Palette, bool)>,
+    dpi: Option<f64>,
+    icc_profile: Option<&[u8]>,
+    text: &[(String, String)],
+    mut fill_row: impl FnMut(usize, &mut Vec<u8>),
+) -> Result<(), FractalError> {
+    let mut info = png::Info::with_size(bounds.0 as u32, bounds.1 as u32);
+    check_profile_channels(
+        icc_profile,
+        matches!(
+            color,
+            png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha
+        ),
+    )?;
+    info.icc_profile = icc_profile.map(Into::into);
+    let mut encoder = png::Encoder::with_info(sink, info)?;
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+    if let Some((palette, transparent)) = palette {
+        encoder.set_palette(palette.0.as_flattened().to_vec());
+        if transparent {
+            let mut alpha = vec![u8::MAX; palette.0.len()];
+            alpha[TRANSPARENT_INDEX as usize] = 0;
+            encoder.set_trns(alpha);
+        }
+    }
+    if let Some(dpi) = dpi {
+        let per_meter = (dpi / 0.0254).round() as u32;
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: per_meter,
+            yppu: per_meter,
+            unit: png::Unit::Meter,
+        }));
+    }
+    for (keyword, value) in text {
+        encoder.add_text_chunk(keyword.clone(), value.clone())?;
+    }
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+    let mut raw = Vec::new();
+    for y in 0..bounds.1 {
+        raw.clear();
+        fill_row(y, &mut raw);
+        std::io::Write::write_all(&mut stream, &raw)?;
+    }
+    stream.finish()?;
+    Ok(writer.finish()?)
+}
+
+#[test]
+fn test_write_image_dpi() {
+    let dir = tempfile::tempdir().unwrap();
+    let filename = dir.path().join("frame.png").display().to_string();
+    write_image(
+        &filename,
+        &[0u16, 1000, 65535, 7],
+        (2, 2),
+        Some(300.0),
+        None,
+        &[],
+    )
+    .unwrap();
+
+    let decoder = png::Decoder::new(std::fs::File::open(&filename).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let dims = reader.info().pixel_dims.unwrap();
+    assert_eq!(
+        (dims.xppu, dims.yppu, dims.unit),
+        (11811, 11811, png::Unit::Meter)
+    );
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    assert_eq!(data, [0, 0, 0x03, 0xe8, 0xff, 0xff, 0, 7]);
+
+    // Without a resolution the chunk is left out
+    write_image(&filename, &[0u8; 4], (2, 2), None, None, &[]).unwrap();
+    let decoder = png::Decoder::new(std::fs::File::open(&filename).unwrap());
+    assert!(decoder.read_info().unwrap().info().pixel_dims.is_none());
+
+    // Any sink takes the same bytes as the file
+    let mut bytes = Vec::new();
+    write_image_to(&mut bytes, &[0u8; 4], (2, 2), None, None, &[]).unwrap();
+    assert_eq!(bytes, std::fs::read(&filename).unwrap());
+}
+
+#[test]
+fn test_write_icc_profile() {
+    // A profile is only its header here, with no tags
+    let profile = |space: &[u8; 4]| {
+        let mut bytes = vec![0u8; 128];
+        bytes[..4].copy_from_slice(&128u32.to_be_bytes());
+        bytes[16..20].copy_from_slice(space);
+        bytes[36..40].copy_from_slice(b"acsp");
+        bytes
+    };
+    let (rgb, gray) = (profile(b"RGB "), profile(b"GRAY"));
+    assert_eq!(check_icc_profile(&rgb), Ok(()));
+    assert!(check_icc_profile(&rgb[..100]).is_err());
+    assert!(check_icc_profile(&[rgb.clone(), vec![0; 4]].concat()).is_err());
+    assert_eq!(
+        check_icc_profile(&profile(b"CMYK")),
+        Err("PNG files only embed gray or RGB profiles, not CMYK ones".to_string())
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let filename = dir.path().join("frame.png").display().to_string();
+    let palette = Palette::grayscale();
+    write_palette_image(
+        &filename,
+        &[0u8; 4],
+        (2, 2),
+        &palette,
+        None,
+        Some(&rgb),
+        &[],
+    )
+    .unwrap();
+    let decoder = png::Decoder::new(std::fs::File::open(&filename).unwrap());
+    let reader = decoder.read_info().unwrap();
+    assert_eq!(reader.info().icc_profile.as_deref(), Some(&rgb[..]));
+
+    // The profile must match the channels of the image
+    write_image(&filename, &[0u8; 4], (2, 2), None, Some(&gray), &[]).unwrap();
+    assert!(matches!(
+        write_image(&filename, &[0u8; 4], (2, 2), None, Some(&rgb), &[]),
+        Err(FractalError::Parse(_))
+    ));
+    // and is checked before the file is created
+    let colored = dir.path().join("colored.png");
+    assert!(matches!(
+        write_rgb_image(
+            &colored.display().to_string(),
+            &[[0u8; 3]; 4],
+            (2, 2),
+            None,
+            Some(&gray),
+            &[]
+        ),
+        Err(FractalError::Parse(_))
+    ));
+    assert!(!colored.exists());
+}
+
+/// Read the keywords and texts of the tEXt, zTXt and iTXt chunks ahead of the
+/// pixels of the PNG file `path`, in that order.
+pub fn read_text_chunks(path: &std::path::Path) -> Result<Vec<(String, String)>, std::io::Error> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!("Failed to open {}: {}", path.display(), e),
+        )
+    })?;
+    let decoder = png::Decoder::new(std::io::BufReader::new(file));
+    let reader = decoder.read_info().map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Failed to read {}: {}", path.display(), e),
+        )
+    })?;
+    let info = reader.info();
+    let mut chunks: Vec<(String, String)> = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+        .collect();
+    for chunk in &info.compressed_latin1_text {
+        let text = chunk.get_text().map_err(std::io::Error::other)?;
+        chunks.push((chunk.keyword.clone(), text));
+    }
+    for chunk in &info.utf8_text {
+        let text = chunk.get_text().map_err(std::io::Error::other)?;
+        chunks.push((chunk.keyword.clone(), text));
+    }
+    Ok(chunks)
+}
+
+#[test]
+fn test_text_chunks() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("frame.png");
+    let text = [
+        ("Fractal type".to_string(), "julia".to_string()),
+        ("Comment".to_string(), "two\nlines".to_string()),
+    ];
+    let filename = path.display().to_string();
+    write_palette_image(
+        &filename,
+        &[9u8; 4],
+        (2, 2),
+        &Palette::grayscale(),
+        None,
+        None,
+        &text,
+    )
+    .unwrap();
+    assert_eq!(read_text_chunks(&path).unwrap(), text);
+
+    // The pixels are unchanged by the chunks
+    let image = image::open(&path).unwrap().to_rgb8();
+    assert_eq!(image.into_raw(), [9; 12]);
+
+    write_image(&filename, &[0u8; 4], (2, 2), None, None, &[]).unwrap();
+    assert!(read_text_chunks(&path).unwrap().is_empty());
+    assert!(read_text_chunks(&dir.path().join("missing.png")).is_err());
+}
+
+/// How the files of an image sequence are named: a path with one frame
+/// number in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequencePattern {
+    /// Everything before the frame number
+    pub prefix: String,
+    /// Digits the frame number is zero-padded to, or `None` for as many as
+    /// the largest frame number has
+    pub width: Option<usize>,
+    /// Everything after the frame number
+    pub suffix: String,
+}
+
+impl SequencePattern {
+    /// Parse a pattern like `stills/%05d.png`, whose one `%0Nd` stands for
+    /// the frame number padded with zeros to `N` digits. A plain `%d` pads
+    /// it to as many digits as the largest frame number has, and `%%` is a
+    /// literal `%`. The number must be in the file name, not a folder.
+    pub fn parse(s: &str) -> Option<SequencePattern> {
+        let mut parts = vec![String::new()];
+        let mut width = None;
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                parts.last_mut().unwrap().push(c);
+                continue;
+            }
+            if chars.next_if_eq(&'%').is_some() {
+                parts.last_mut().unwrap().push('%');
+                continue;
+            }
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                digits.push(d);
+            }
+            chars.next_if_eq(&'d')?;
+            width = match digits.as_str() {
+                "" => None,
+                d if d.starts_with('0') && d.len() > 1 => Some(d[1..].parse().ok()?),
+                _ => return None,
+            };
+            parts.push(String::new());
+        }
+        let [prefix, suffix] = <[String; 2]>::try_from(parts).ok()?;
+        if suffix.contains(['/', '\\']) {
+            return None;
+        }
+        Some(SequencePattern {
+            prefix,
+            width,
+            suffix,
+        })
+    }
+
+    /// Return the file name of frame `number` of `n_frames`.
+    pub fn name(&self, number: usize, n_frames: usize) -> String {
+        let width = self.width.unwrap_or_else(|| n_frames.to_string().len());
+        format!("{}{:0width$}{}", self.prefix, number, self.suffix)
+    }
+}
+
+#[test]
+fn test_sequence_pattern() {
+    let pattern = SequencePattern::parse("stills/%05d.png").unwrap();
+    assert_eq!(pattern.name(42, 100), "stills/00042.png");
+    let pattern = SequencePattern::parse("shot-%d.png").unwrap();
+    assert_eq!(pattern.name(7, 100), "shot-007.png");
+    assert_eq!(pattern.name(7, 12), "shot-07.png");
+
+    // The padding grows with the frame count, so the names still sort
+    let names: Vec<String> = (1..=1500).map(|i| pattern.name(i, 1500)).collect();
+    assert_eq!(names[0], "shot-0001.png");
+    assert_eq!(names[999], "shot-1000.png");
+    assert_eq!(names[1499], "shot-1500.png");
+    assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(
+        SequencePattern::parse("100%%-%02d").unwrap().name(3, 10),
+        "100%-03"
+    );
+    for s in [
+        "frame.png",
+        "%d-%d.png",
+        "%5d.png",
+        "%x.png",
+        "%0d.png",
+        "%d/frame.png",
+    ] {
+        assert_eq!(SequencePattern::parse(s), None, "{:?}", s);
+    }
+}
+
+/// Palette index used for transparent pixels when frames have an alpha channel
+const TRANSPARENT_INDEX: u8 = u8::MAX;
+
+/// Maps frame colors back to the index of the palette entry they came from
+#[derive(Clone)]
+struct PaletteIndex<'a> {
+    palette: &'a Palette,
+    /// Number of leading palette entries that hold colors
+    usable: usize,
+    indices: std::collections::HashMap<[u8; 3], u8>,
+}
+
+impl<'a> PaletteIndex<'a> {
+    fn new(palette: &'a Palette, transparent: bool) -> PaletteIndex<'a> {
+        let usable = if transparent {
+            TRANSPARENT_INDEX as usize
+        } else {
+            palette.0.len()
+        };
+        let mut indices = std::collections::HashMap::new();
+        for (index, color) in palette.0[..usable].iter().enumerate() {
+            indices.entry(*color).or_insert(index as u8);
+        }
+        PaletteIndex {
+            palette,
+            usable,
+            indices,
+        }
+    }
+
+    /// Return the index of `color`, or of the closest palette entry to it.
+    fn get(&mut self, color: [u8; 3]) -> u8 {
+        let (palette, usable) = (self.palette, self.usable);
+        *self.indices.entry(color).or_insert_with(|| {
+            let distance = |entry: &[u8; 3]| -> u32 {
+                (0..3)
+                    .map(|i| (entry[i] as i32 - color[i] as i32).pow(2) as u32)
+                    .sum()
+            };
+            (0..usable)
+                .min_by_key(|&index| distance(&palette.0[index]))
+                .unwrap_or(0) as u8
+        })
+    }
+}
+
+#[test]
+fn test_palette_index() {
+    let palette = Palette::grayscale();
+    let mut indices = PaletteIndex::new(&palette, false);
+    assert_eq!(indices.get([7, 7, 7]), 7);
+    assert_eq!(indices.get([255, 255, 255]), 255);
+    // Colors outside the palette go to the closest gray
+    assert_eq!(indices.get([10, 20, 30]), 20);
+
+    // The transparent entry is never used for colors
+    let mut indices = PaletteIndex::new(&palette, true);
+    assert_eq!(indices.get([255, 255, 255]), 254);
+}
+
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+/// How a GIF animation plays back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Playback {
+    /// Repeat the frames forever
+    #[default]
+    Loop,
+    /// Play the frames through a single time
+    Once,
+    /// Play the frames forward, then backward, forever
+    PingPong,
+}
+
+impl Playback {
+    /// Return the order in which to show `n` frames.
+    ///
+    /// Ping-pong skips the first and last frames on the way back, since they
+    /// are already shown at each turn.
+    pub fn frame_order(self, n: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..n).collect();
+        if self == Playback::PingPong && n > 2 {
+            order.extend((1..n - 1).rev());
+        }
+        order
+    }
+
+    /// Return how often the GIF plays, given the number of times looping
+    /// playback runs through, 0 for forever.
+    fn repeat(self, loop_count: u16) -> Repeat {
+        match (self, loop_count) {
+            (Playback::Once, _) => Repeat::Finite(1),
+            (_, 0) => Repeat::Infinite,
+            (_, n) => Repeat::Finite(n),
+        }
+    }
+}
+
+#[test]
+fn test_playback_frame_order() {
+    assert_eq!(Playback::Loop.frame_order(4), vec![0, 1, 2, 3]);
+    assert_eq!(Playback::Once.frame_order(4), vec![0, 1, 2, 3]);
+    assert_eq!(Playback::PingPong.frame_order(4), vec![0, 1, 2, 3, 2, 1]);
+    assert_eq!(Playback::PingPong.frame_order(2), vec![0, 1]);
+    assert_eq!(Playback::PingPong.frame_order(1), vec![0]);
+}
+
+/// Encodes GIF frames one at a time as they are produced
+///
+/// Frames are written out immediately, except that ping-pong playback keeps
+/// the palette indices of every frame, one byte per pixel, to replay them
+/// backwards when the writer is finished.
+pub struct GifWriter<'a> {
+    encoder: Encoder<Box<dyn std::io::Write + Send + Sync>>,
+    bounds: (usize, usize),
+    delay: u16,
+    transparent: bool,
+    indices: PaletteIndex<'a>,
+    playback: Playback,
+    /// Number of frames added so far
+    frames: usize,
+    /// Indexed frames kept for the way back of ping-pong playback
+    history: Vec<Vec<u8>>,
+    /// Whether frames are cropped to the pixels that changed
+    optimize: bool,
+    /// The indexed pixels on screen after the last written frame, when
+    /// optimizing
+    canvas: Option<Vec<u8>>,
+    /// Number of GIF frames each added frame after the first is spread over
+    interpolate: usize,
+    /// The colors of the last added frame, when interpolating
+    previous: Option<Vec<[u8; 4]>>,
+    /// Effort of quantizing each frame to its own palette, see
+    /// [`GifWriter::set_quality`]
+    quality: Option<u8>,
+    /// Quantized frames kept for the way back of ping-pong playback
+    quantized: Vec<Frame<'static>>,
+}
+
+impl<'a> GifWriter<'a> {
+    /// Create the GIF file `output` for frames of dimensions `bounds`, colored
+    /// with `palette`, see [`GifWriter::to_writer`].
+    pub fn create(
+        output: &str,
+        bounds: (usize, usize),
+        delay: u16,
+        palette: &'a Palette,
+        playback: Playback,
+        loop_count: u16,
+        transparent: bool,
+    ) -> Result<GifWriter<'a>, FractalError> {
+        let file = std::fs::File::create(output)?;
+        GifWriter::to_writer(
+            file,
+            bounds,
+            delay,
+            palette,
+            playback,
+            loop_count,
+            transparent,
+        )
+    }
+
+    /// Start a GIF in `sink`, such as a file or standard output, for frames
+    /// of dimensions `bounds`, colored with `palette`.
+    ///
+    /// Looping and ping-pong playback run through `loop_count` times, or
+    /// forever if it is 0. If `transparent` is set, pixels with an alpha below
+    /// one half become transparent and the last palette entry is given up for
+    /// them.
+    pub fn to_writer(
+        sink: impl std::io::Write + Send + Sync + 'static,
+        bounds: (usize, usize),
+        delay: u16,
+        palette: &'a Palette,
+        playback: Playback,
+        loop_count: u16,
+        transparent: bool,
+    ) -> Result<GifWriter<'a>, FractalError> {
+        let too_large = |_| {
+            FractalError::Parse(format!(
+                "{}x{} frames are too large for a GIF",
+                bounds.0, bounds.1
+            ))
+        };
+        let (width, height) = (
+            u16::try_from(bounds.0).map_err(too_large)?,
+            u16::try_from(bounds.1).map_err(too_large)?,
+        );
+
+        // Create the GIF encoder with the frames' 256 colors
+        let colors: Vec<u8> = palette.0.iter().flatten().copied().collect();
+        let sink: Box<dyn std::io::Write + Send + Sync> = Box::new(sink);
+        let mut encoder = Encoder::new(sink, width, height, &colors)?;
+        encoder.set_repeat(playback.repeat(loop_count))?;
+
+        Ok(GifWriter {
+            encoder,
+            bounds,
+            delay,
+            transparent,
+            indices: PaletteIndex::new(palette, transparent),
+            playback,
+            frames: 0,
+            history: Vec::new(),
+            optimize: false,
+            canvas: None,
+            interpolate: 1,
+            previous: None,
+            quality: None,
+            quantized: Vec::new(),
+        })
+    }
+
+    /// Write each frame after the first as only the rectangle of pixels that
+    /// differ from the frame before it, which shrinks animations with large
+    /// static regions.
+    ///
+    /// Earlier frames are kept on screen under the smaller ones. This has no
+    /// effect on transparent GIFs, whose frames must clear the screen to let
+    /// transparent pixels show through.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.optimize = optimize && !self.transparent;
+    }
+
+    /// Write `factor - 1` frames before each frame added after the first,
+    /// cross-dissolving from the frame before it to it in evenly spaced
+    /// steps.
+    ///
+    /// Frames are blended in the colors they are shown in, with their alpha.
+    /// This is much cheaper than rendering the frames in between, but frames
+    /// that differ a lot, as in a fast zoom, blur into each other instead of
+    /// moving.
+    pub fn set_interpolation(&mut self, factor: usize) {
+        self.interpolate = factor.max(1);
+    }
+
+    /// Give each frame its own palette of the 256 colors NeuQuant picks from
+    /// it instead of mapping its colors to the closest entries of the
+    /// writer's palette, with `quality` from 1, which samples few pixels and
+    /// is fastest, to 10, which learns from every pixel; `None` keeps the
+    /// writer's palette.
+    ///
+    /// This suits frames with more colors than the palette holds, like the
+    /// blends of interpolated frames, while frames in the palette's own
+    /// colors only lose by it. Quantized frames are never cropped by
+    /// [`set_optimize`](GifWriter::set_optimize), since the same index means
+    /// a different color in every frame.
+    pub fn set_quality(&mut self, quality: Option<u8>) {
+        self.quality = quality.map(|q| q.clamp(1, 10));
+    }
+
+    /// Add a frame straight from a rendered pixel buffer.
+    ///
+    /// Gray levels are colored with the writer's palette; channels with more
+    /// than 8 bits are scaled down first.
+    pub fn add_frame<P: Channel>(&mut self, pixels: &[P]) -> Result<(), FractalError> {
+        self.check_size(pixels.len(), "Frame buffer")?;
+        let palette = self.indices.palette;
+        if self.interpolate > 1 || self.quality.is_some() {
+            let colors = pixels
+                .iter()
+                .map(|p| {
+                    let level = (p.level() as u64 * u8::MAX as u64 / P::MAX as u64) as u8;
+                    let [r, g, b] = palette.color(level);
+                    [r, g, b, p.alpha()]
+                })
+                .collect();
+            return self.add_colors(colors);
+        }
+        let buffer = pixels
+            .iter()
+            .map(|p| {
+                if self.transparent && p.alpha() < 128 {
+                    return TRANSPARENT_INDEX;
+                }
+                let level = (p.level() as u64 * u8::MAX as u64 / P::MAX as u64) as u8;
+                self.indices.get(palette.color(level))
+            })
+            .collect();
+        self.add_indexed(buffer)
+    }
+
+    /// Add an RGBA image, mapping its colors to the closest palette entries.
+    pub fn add_image(&mut self, img: &image::RgbaImage) -> Result<(), FractalError> {
+        self.check_size((img.width() * img.height()) as usize, "Image")?;
+        if self.interpolate > 1 || self.quality.is_some() {
+            return self.add_colors(img.pixels().map(|p| p.0).collect());
+        }
+        let buffer = self.index_image(img);
+        self.add_indexed(buffer)
+    }
+
+    /// Add a frame of RGBA colors, after the frames blended from the last one
+    /// into it.
+    fn add_colors(&mut self, colors: Vec<[u8; 4]>) -> Result<(), FractalError> {
+        if let Some(previous) = self.previous.take() {
+            let steps = self.interpolate as u32;
+            for step in 1..steps {
+                let blended: Vec<[u8; 4]> = previous
+                    .iter()
+                    .zip(&colors)
+                    .map(|(from, to)| {
+                        std::array::from_fn(|c| {
+                            let sum = from[c] as u32 * (steps - step) + to[c] as u32 * step;
+                            ((sum + steps / 2) / steps) as u8
+                        })
+                    })
+                    .collect();
+                self.add_rgba(blended)?;
+            }
+        }
+        if self.interpolate > 1 {
+            self.add_rgba(colors.clone())?;
+            self.previous = Some(colors);
+            Ok(())
+        } else {
+            self.add_rgba(colors)
+        }
+    }
+
+    /// Add a frame of RGBA colors, quantized or mapped to the palette.
+    fn add_rgba(&mut self, colors: Vec<[u8; 4]>) -> Result<(), FractalError> {
+        if self.quality.is_some() {
+            let frame = self.quantize(colors.into_flattened());
+            return self.add_quantized(frame);
+        }
+        let buffer = self.index_colors(&colors);
+        self.add_indexed(buffer)
+    }
+
+    /// Quantize the RGBA bytes of a whole frame to a palette of its own.
+    ///
+    /// This only reads the writer, so several frames can be quantized at
+    /// once.
+    fn quantize(&self, mut rgba: Vec<u8>) -> Frame<'static> {
+        // NeuQuant makes fully transparent pixels transparent
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel[3] = if self.transparent && pixel[3] < 128 {
+                0
+            } else {
+                u8::MAX
+            };
+        }
+        // Its sample factor runs from 1, every pixel, to 30
+        let speed = 1 + (10 - self.quality.unwrap_or(10) as i32) * 29 / 9;
+        let (width, height) = (self.bounds.0 as u16, self.bounds.1 as u16);
+        let mut frame = Frame::from_rgba_speed(width, height, &mut rgba, speed);
+        frame.delay = self.delay;
+        frame.dispose = if self.transparent {
+            DisposalMethod::Background
+        } else {
+            DisposalMethod::Keep
+        };
+        frame
+    }
+
+    fn add_quantized(&mut self, frame: Frame<'static>) -> Result<(), FractalError> {
+        self.encoder.write_frame(&frame)?;
+        self.frames += 1;
+        if self.playback == Playback::PingPong {
+            self.quantized.push(frame);
+        }
+        Ok(())
+    }
+
+    /// Map RGBA colors to the closest palette entries.
+    fn index_colors(&mut self, colors: &[[u8; 4]]) -> Vec<u8> {
+        colors
+            .iter()
+            .map(|&[r, g, b, alpha]| {
+                if self.transparent && alpha < 128 {
+                    TRANSPARENT_INDEX
+                } else {
+                    self.indices.get([r, g, b])
+                }
+            })
+            .collect()
+    }
+
+    /// Map the colors of an RGBA image to the closest palette entries.
+    ///
+    /// This only reads the writer, so several frames can be indexed at once.
+    fn index_image(&self, img: &image::RgbaImage) -> Vec<u8> {
+        let mut indices = self.indices.clone();
+        img.pixels()
+            .map(|p| match p.0 {
+                [_, _, _, alpha] if self.transparent && alpha < 128 => TRANSPARENT_INDEX,
+                [r, g, b, _] => indices.get([r, g, b]),
+            })
+            .collect()
+    }
+
+    fn check_size(&self, len: usize, what: &str) -> Result<(), FractalError> {
+        if len == self.bounds.0 * self.bounds.1 {
+            return Ok(());
+        }
+        Err(FractalError::Parse(format!(
+            "{} of {} pixels does not match the GIF size {}x{}",
+            what, len, self.bounds.0, self.bounds.1
+        )))
+    }
+
+    fn add_indexed(&mut self, buffer: Vec<u8>) -> Result<(), FractalError> {
+        self.write_indexed(&buffer)?;
+        self.frames += 1;
+        if self.playback == Playback::PingPong {
+            self.history.push(buffer);
+        }
+        Ok(())
+    }
+
+    fn write_indexed(&mut self, buffer: &[u8]) -> Result<(), FractalError> {
+        let (left, top, width, height) = match &self.canvas {
+            Some(canvas) if self.optimize => changed_rect(canvas, buffer, self.bounds),
+            _ => (0, 0, self.bounds.0, self.bounds.1),
+        };
+        let pixels = if (width, height) == self.bounds {
+            std::borrow::Cow::Borrowed(buffer)
+        } else {
+            let rows = buffer.chunks(self.bounds.0).skip(top).take(height);
+            rows.flat_map(|row| &row[left..left + width])
+                .copied()
+                .collect()
+        };
+        let frame = Frame {
+            left: left as u16,
+            top: top as u16,
+            width: width as u16,
+            height: height as u16,
+            delay: self.delay,
+            buffer: pixels,
+            // Clear transparent areas so earlier frames don't show through
+            transparent: self.transparent.then_some(TRANSPARENT_INDEX),
+            dispose: if self.transparent {
+                DisposalMethod::Background
+            } else {
+                DisposalMethod::Keep
+            },
+            ..Frame::default()
+        };
+        self.encoder.write_frame(&frame)?;
+        if self.optimize {
+            let canvas = self.canvas.get_or_insert_default();
+            canvas.clear();
+            canvas.extend_from_slice(buffer);
+        }
+        Ok(())
+    }
+
+    /// Write any frames still owed to the playback order and close the file.
+    pub fn finish(mut self) -> Result<(), FractalError> {
+        let history = std::mem::take(&mut self.history);
+        for i in self
+            .playback
+            .frame_order(self.frames)
+            .into_iter()
+            .skip(self.frames)
+        {
+            match self.quantized.get(i) {
+                Some(frame) => self.encoder.write_frame(frame)?,
+                None => self.write_indexed(&history[i])?,
+            }
+        }
+        // Write the trailer and flush, so a pipe gets everything now
+        std::io::Write::flush(&mut self.encoder.into_inner()?)?;
+        Ok(())
+    }
+}
+
+/// Return the smallest rectangle, as left, top, width and height, that holds
+/// every pixel where the frames `from` and `to` of dimensions `bounds`
+/// differ.
+///
+/// Identical frames give the single pixel in the upper-left corner, since a
+/// GIF frame cannot be empty.
+fn changed_rect(from: &[u8], to: &[u8], bounds: (usize, usize)) -> (usize, usize, usize, usize) {
+    let (mut left, mut top, mut right, mut bottom) = (bounds.0, bounds.1, 0, 0);
+    for (y, (a, b)) in from.chunks(bounds.0).zip(to.chunks(bounds.0)).enumerate() {
+        let Some(first) = a.iter().zip(b).position(|(a, b)| a != b) else {
+            continue;
+        };
+        let last = a.iter().zip(b).rposition(|(a, b)| a != b).unwrap_or(first);
+        left = left.min(first);
+        right = right.max(last + 1);
+        top = top.min(y);
+        bottom = y + 1;
+    }
+    if right == 0 {
+        return (0, 0, 1, 1);
+    }
+    (left, top, right - left, bottom - top)
+}
+
+#[test]
+fn test_changed_rect() {
+    let from = [0u8; 12];
+    let mut to = from;
+    assert_eq!(changed_rect(&from, &to, (4, 3)), (0, 0, 1, 1));
+    to[5] = 1;
+    assert_eq!(changed_rect(&from, &to, (4, 3)), (1, 1, 1, 1));
+    to[11] = 1;
+    to[8] = 1;
+    assert_eq!(changed_rect(&from, &to, (4, 3)), (0, 1, 4, 2));
 }
 
-use gif::{Encoder, Frame, Repeat};
 /// Create a GIF from a series of PNG images.
 ///
 /// # Arguments
 /// * `frames` - A vector of file paths to the PNG images to include in the GIF
 /// * `output` - The file path for the output GIF
 /// * `delay` - The delay between frames in hundredths of a second (e.g., 10 = 0.1 seconds)
+/// * `palette` - The colors the frames were saved with; pixels of other colors
+///   are mapped to the closest one
+/// * `playback` - Whether the animation loops, plays once or ping-pongs
+/// * `loop_count` - How many times a looping or ping-pong animation plays,
+///   0 for forever
+/// * `optimize` - Whether to crop frames to the pixels that changed, see
+///   [`GifWriter::set_optimize`]
+/// * `interpolate` - Number of GIF frames each frame after the first is
+///   spread over, see [`GifWriter::set_interpolation`]
+/// * `quality` - Effort of quantizing each frame to a palette of its own, or
+///   `None` to keep `palette`, see [`GifWriter::set_quality`]
 ///
 /// # Returns
 /// * `Ok(())` if the GIF was created successfully
-/// * `Err(std::io::Error)` if there was an error creating or writing the GIF
+/// * `Err(FractalError)` if there was an error creating or writing the GIF
 ///
 /// # Example
-/// ```
+/// ```no_run
+/// use mandelbrot::utils::palette::Palette;
+/// use mandelbrot::utils::preserve::{Playback, make_gif};
+///
 /// let frames = vec!["frame1.png".to_string(), "frame2.png".to_string()];
-/// make_gif(frames, "animation.gif", 10)?;
+/// make_gif(frames, "animation.gif", 10, &Palette::grayscale(), Playback::Loop, 0, false, 1, None)?;
+/// # Ok::<(), mandelbrot::utils::error::FractalError>(())
 /// ```
-pub fn make_gif(frames: Vec<String>, output: &str, delay: u16) -> Result<(), std::io::Error> {
+#[allow(clippy::too_many_arguments)]
+pub fn make_gif(
+    frames: Vec<String>,
+    output: &str,
+    delay: u16,
+    palette: &Palette,
+    playback: Playback,
+    loop_count: u16,
+    optimize: bool,
+    interpolate: usize,
+    quality: Option<u8>,
+) -> Result<(), FractalError> {
+    make_gif_in_batches(
+        frames,
+        output,
+        delay,
+        palette,
+        playback,
+        loop_count,
+        optimize,
+        interpolate,
+        quality,
+        1,
+    )
+}
+
+/// Create a GIF from a series of PNG images like [`make_gif`], decoding and
+/// indexing as many frames at a time as rayon has threads.
+///
+/// The frames are still written one after another and in order, since the
+/// encoder compresses each frame into the same stream. Up to one batch of
+/// decoded frames is held in memory at once.
+#[allow(clippy::too_many_arguments)]
+pub fn make_gif_parallel(
+    frames: Vec<String>,
+    output: &str,
+    delay: u16,
+    palette: &Palette,
+    playback: Playback,
+    loop_count: u16,
+    optimize: bool,
+    interpolate: usize,
+    quality: Option<u8>,
+) -> Result<(), FractalError> {
+    let batch = rayon::current_num_threads();
+    make_gif_in_batches(
+        frames,
+        output,
+        delay,
+        palette,
+        playback,
+        loop_count,
+        optimize,
+        interpolate,
+        quality,
+        batch,
+    )
+}
+
+/// Decode PNG frames `batch` at a time in parallel and write them to `output`.
+#[allow(clippy::too_many_arguments)]
+fn make_gif_in_batches(
+    frames: Vec<String>,
+    output: &str,
+    delay: u16,
+    palette: &Palette,
+    playback: Playback,
+    loop_count: u16,
+    optimize: bool,
+    interpolate: usize,
+    quality: Option<u8>,
+    batch: usize,
+) -> Result<(), FractalError> {
+    use rayon::prelude::*;
+
     // Check if we have any frames
     if frames.is_empty() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "No frames provided for GIF creation",
+        return Err(FractalError::Parse(
+            "No frames provided for GIF creation".to_string(),
         ));
     }
 
-    // Create output file and encoder
-    let file = std::fs::File::create(output)?;
-
     // Open the first image to get dimensions
-    let first_img = image::open(&frames[0])
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-    let (width, height) = (first_img.width(), first_img.height());
-
-    // Create a grayscale palette with 256 shades
-    let mut palette = Vec::with_capacity(768); // 256 colors * 3 channels
-    for i in 0..256 {
-        palette.push(i as u8); // R
-        palette.push(i as u8); // G
-        palette.push(i as u8); // B
-    }
-
-    // Create the GIF encoder with our grayscale palette
-    let mut encoder = Encoder::new(file, width as u16, height as u16, &palette)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-    // Configure the GIF settings
-    encoder
-        .set_repeat(Repeat::Infinite)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-    // Process each frame
-    for (i, frame_path) in frames.iter().enumerate() {
-        // Load the image
-        let img = image::open(frame_path).map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Failed to open frame {}: {}", frame_path, e),
-            )
-        })?;
-
-        // Convert to luma8 (grayscale)
-        let img = img.to_luma8();
-
-        // Check dimensions match the first frame
-        if i > 0 && (img.width() != width || img.height() != height) {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "Frame dimensions mismatch: {} has different size than the first frame",
-                    frame_path
-                ),
+    let first_img = image::open(&frames[0])?;
+    let bounds = (first_img.width() as usize, first_img.height() as usize);
+    // Frames with an alpha channel give up the last palette entry for transparency
+    let transparent = first_img.color().has_alpha();
+    let mut writer = GifWriter::create(
+        output,
+        bounds,
+        delay,
+        palette,
+        playback,
+        loop_count,
+        transparent,
+    )?;
+    writer.set_optimize(optimize);
+    writer.set_interpolation(interpolate);
+    writer.set_quality(quality);
+
+    for chunk in frames.chunks(batch.max(1)) {
+        // Decode the batch concurrently, keeping the frame order
+        let decoded: Vec<Result<image::RgbaImage, FractalError>> = chunk
+            .par_iter()
+            .map(|frame_path| {
+                // Load the image
+                let img = image::open(frame_path).map_err(|e| match e {
+                    image::ImageError::IoError(e) => FractalError::Io(e),
+                    e => FractalError::Image(format!("Failed to open frame {}: {}", frame_path, e)),
+                })?;
+
+                let img = img.to_rgba8();
+
+                // Check dimensions match the first frame
+                if (img.width() as usize, img.height() as usize) != bounds {
+                    return Err(FractalError::Parse(format!(
+                        "Frame dimensions mismatch: {} has different size than the first frame",
+                        frame_path
+                    )));
+                }
+
+                Ok(img)
+            })
+            .collect();
+
+        // Frames blended with the one before them are indexed in order
+        if interpolate > 1 {
+            for img in decoded {
+                writer.add_image(&img?)?;
+            }
+            continue;
+        }
+        if quality.is_some() {
+            let quantized: Vec<Result<Frame<'static>, FractalError>> = decoded
+                .into_par_iter()
+                .map(|img| img.map(|img| writer.quantize(img.into_raw())))
+                .collect();
+            for frame in quantized {
+                writer.add_quantized(frame?)?;
+            }
+            continue;
+        }
+        let indexed: Vec<Result<Vec<u8>, FractalError>> = decoded
+            .into_par_iter()
+            .map(|img| img.map(|img| writer.index_image(&img)))
+            .collect();
+        for buffer in indexed {
+            writer.add_indexed(buffer?)?;
+        }
+    }
+
+    writer.finish()
+}
+
+#[test]
+fn test_make_gif_parallel() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let frames: Vec<String> = (0..5u8)
+        .map(|i| {
+            let name = dir.path().join(format!("{}.png", i)).display().to_string();
+            write_image(&name, &[i * 50; 6], (3, 2), None, None, &[]).unwrap();
+            name
+        })
+        .collect();
+    let sequential = dir.path().join("sequential.gif").display().to_string();
+    let parallel = dir.path().join("parallel.gif").display().to_string();
+    let palette = Palette::grayscale();
+    make_gif(
+        frames.clone(),
+        &sequential,
+        5,
+        &palette,
+        Playback::Loop,
+        0,
+        false,
+        1,
+        None,
+    )
+    .unwrap();
+    let parallel_gif = |quality| {
+        let (frames, palette) = (frames.clone(), &palette);
+        make_gif_parallel(
+            frames,
+            &parallel,
+            5,
+            palette,
+            Playback::Loop,
+            0,
+            false,
+            1,
+            quality,
+        )
+        .unwrap();
+    };
+    parallel_gif(None);
+    // Frames are written in the same order either way
+    assert_eq!(
+        std::fs::read(&sequential).unwrap(),
+        std::fs::read(&parallel).unwrap()
+    );
+
+    // And quantized the same way
+    make_gif(
+        frames.clone(),
+        &sequential,
+        5,
+        &palette,
+        Playback::Loop,
+        0,
+        false,
+        1,
+        Some(3),
+    )
+    .unwrap();
+    parallel_gif(Some(3));
+    assert_eq!(
+        std::fs::read(&sequential).unwrap(),
+        std::fs::read(&parallel).unwrap()
+    );
+}
+
+/// Create a GIF from rendered pixel buffers, without saving the frames as
+/// images first.
+///
+/// Each item of `frames` is a buffer and its dimensions, which must be the
+/// same for every frame. Buffers whose channel has an alpha value make the
+/// GIF transparent where the alpha is below one half. See [`make_gif`] for
+/// the other arguments.
+pub fn make_gif_from_buffers<'b, P, I>(
+    frames: I,
+    output: &str,
+    delay: u16,
+    palette: &Palette,
+    playback: Playback,
+    loop_count: u16,
+) -> Result<(), FractalError>
+where
+    P: Channel + 'b,
+    I: IntoIterator<Item = (&'b [P], (usize, usize))>,
+{
+    let mut frames = frames.into_iter();
+    let Some((first, bounds)) = frames.next() else {
+        return Err(FractalError::Parse(
+            "No frames provided for GIF creation".to_string(),
+        ));
+    };
+    let mut writer = GifWriter::create(
+        output,
+        bounds,
+        delay,
+        palette,
+        playback,
+        loop_count,
+        P::HAS_ALPHA,
+    )?;
+    writer.add_frame(first)?;
+    for (pixels, frame_bounds) in frames {
+        if frame_bounds != bounds {
+            return Err(FractalError::Parse(
+                "Frame dimensions mismatch: a buffer has a different size than the first frame"
+                    .to_string(),
             ));
         }
+        writer.add_frame(pixels)?;
+    }
+    writer.finish()
+}
 
-        // Get the raw pixel data - this contains grayscale values
-        let buffer = img.into_raw();
+#[test]
+fn test_make_gif_from_buffers() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let output = dir.path().join("frames.gif").display().to_string();
+    let frames: Vec<Vec<u8>> = (0..3).map(|i| vec![i * 100; 6]).collect();
+    make_gif_from_buffers(
+        frames.iter().map(|f| (&f[..], (3, 2))),
+        &output,
+        5,
+        &Palette::grayscale(),
+        Playback::PingPong,
+        3,
+    )
+    .unwrap();
 
-        // Create a GIF frame
-        let mut frame = Frame::default();
-        frame.width = width as u16;
-        frame.height = height as u16;
-        frame.delay = delay;
+    // Ping-pong plays 0, 1, 2 and then 1 on the way back, three times over
+    let decoder = gif::DecodeOptions::new();
+    let mut decoder = decoder
+        .read_info(std::fs::File::open(&output).unwrap())
+        .unwrap();
+    let mut levels = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!((frame.width, frame.height), (3, 2));
+        levels.push(frame.buffer[0]);
+    }
+    assert_eq!(levels, vec![0, 100, 200, 100]);
+    assert_eq!(decoder.repeat(), Repeat::Finite(3));
+
+    // Buffers must all have the same size
+    let result = make_gif_from_buffers(
+        [(&frames[0][..], (3, 2)), (&frames[1][..2], (1, 2))],
+        &output,
+        5,
+        &Palette::grayscale(),
+        Playback::Loop,
+        0,
+    );
+    assert!(result.is_err());
+}
 
-        // The buffer already contains the palette indices (grayscale values 0-255)
-        frame.buffer = std::borrow::Cow::Owned(buffer);
+/// Frames waiting for the encoder thread of a [`GifPipeline`] before
+/// [`GifPipeline::send`] blocks
+const PIPELINE_DEPTH: usize = 2;
 
-        // Write the frame to the GIF
-        encoder
-            .write_frame(&frame)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+/// A GIF encoded on a thread of its own from frames sent to it, so that
+/// encoding one frame overlaps rendering the next.
+///
+/// Frames are passed as 8-bit gray levels with their alpha; sending blocks
+/// while the encoder is more than a couple of frames behind, which bounds
+/// the memory held.
+pub struct GifPipeline {
+    sender: Option<std::sync::mpsc::SyncSender<Vec<super::GrayAlpha>>>,
+    thread: Option<std::thread::JoinHandle<Result<(), FractalError>>>,
+}
+
+impl GifPipeline {
+    /// Start the encoder thread, which colors frames with `palette` and
+    /// writes them with the writer `create` makes from it.
+    pub fn spawn<F>(palette: Palette, create: F) -> GifPipeline
+    where
+        F: FnOnce(&Palette) -> Result<GifWriter<'_>, FractalError> + Send + 'static,
+    {
+        let (sender, receiver) = std::sync::mpsc::sync_channel::<Vec<_>>(PIPELINE_DEPTH);
+        let thread = std::thread::spawn(move || {
+            let mut writer = create(&palette)?;
+            for frame in receiver {
+                writer.add_frame::<super::GrayAlpha>(&frame)?;
+            }
+            writer.finish()
+        });
+        GifPipeline {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
     }
 
-    Ok(())
+    /// Queue `pixels` as the next frame of the GIF.
+    ///
+    /// If the encoder has stopped, returns the error it stopped with.
+    pub fn send<P: Channel>(&mut self, pixels: &[P]) -> Result<(), FractalError> {
+        let frame = pixels
+            .iter()
+            .map(|p| super::GrayAlpha {
+                gray: (p.level() as u64 * u8::MAX as u64 / P::MAX as u64) as u8,
+                alpha: p.alpha(),
+            })
+            .collect();
+        let sent = self.sender.as_ref().is_some_and(|s| s.send(frame).is_ok());
+        if sent {
+            return Ok(());
+        }
+        self.sender = None;
+        self.join().and(Err(FractalError::Gif(
+            "the GIF encoder stopped".to_string(),
+        )))
+    }
+
+    /// Wait for the encoder to write the frames sent and close the file.
+    pub fn finish(mut self) -> Result<(), FractalError> {
+        self.sender = None;
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<(), FractalError> {
+        match self.thread.take().map(std::thread::JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_gif_pipeline() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let output = dir.path().join("pipelined.gif").display().to_string();
+    let path = output.clone();
+    let mut pipeline = GifPipeline::spawn(Palette::grayscale(), move |palette| {
+        GifWriter::create(&path, (3, 2), 5, palette, Playback::Loop, 0, false)
+    });
+    for level in [0u16, 32896, 65535] {
+        pipeline.send(&[level; 6]).unwrap();
+    }
+    pipeline.finish().unwrap();
+
+    // The frames arrive in order, scaled to 8 bits
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(std::fs::File::open(&output).unwrap())
+        .unwrap();
+    let mut levels = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        levels.push(frame.buffer[0]);
+    }
+    assert_eq!(levels, [0, 128, 255]);
+
+    // A writer that cannot be created fails the frames sent to it
+    let missing = dir
+        .path()
+        .join("missing/pipelined.gif")
+        .display()
+        .to_string();
+    let mut pipeline = GifPipeline::spawn(Palette::grayscale(), move |palette| {
+        GifWriter::create(&missing, (3, 2), 5, palette, Playback::Loop, 0, false)
+    });
+    let mut result = Ok(());
+    for _ in 0..=PIPELINE_DEPTH + 1 {
+        result = result.and_then(|_| pipeline.send(&[0u8; 6]));
+    }
+    assert!(matches!(result, Err(FractalError::Io(_))), "{:?}", result);
+    assert!(pipeline.finish().is_ok());
+}
+
+#[test]
+fn test_gif_optimize() {
+    use image::AnimationDecoder;
+    let dir = tempfile::TempDir::new().unwrap();
+    let palette = Palette::grayscale();
+    let bounds = (6, 4);
+    let first = vec![10u8; 24];
+    let mut second = first.clone();
+    second[6 + 2] = 200;
+    second[12 + 3] = 200;
+    let frames = [first, second.clone(), second, vec![50; 24]];
+
+    let write = |name: &str, optimize: bool| {
+        let output = dir.path().join(name).display().to_string();
+        let mut writer =
+            GifWriter::create(&output, bounds, 5, &palette, Playback::Loop, 0, false).unwrap();
+        writer.set_optimize(optimize);
+        for frame in &frames {
+            writer.add_frame(frame).unwrap();
+        }
+        writer.finish().unwrap();
+        output
+    };
+    let optimized = write("optimized.gif", true);
+    let plain = write("plain.gif", false);
+
+    // Only the changed rectangles are stored, and the animation still loops
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(std::fs::File::open(&optimized).unwrap())
+        .unwrap();
+    let mut rects = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!(frame.dispose, DisposalMethod::Keep);
+        rects.push((frame.left, frame.top, frame.width, frame.height));
+    }
+    assert_eq!(
+        rects,
+        [(0, 0, 6, 4), (2, 1, 2, 2), (0, 0, 1, 1), (0, 0, 6, 4)]
+    );
+    assert_eq!(decoder.repeat(), Repeat::Infinite);
+
+    // Played back, both GIFs show the same frames
+    let play = |path: &str| -> Vec<Vec<u8>> {
+        let file = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+        let decoder = image::codecs::gif::GifDecoder::new(file).unwrap();
+        decoder
+            .into_frames()
+            .map(|frame| frame.unwrap().into_buffer().into_raw())
+            .collect()
+    };
+    assert_eq!(play(&optimized), play(&plain));
+
+    // Transparent GIFs keep whole frames
+    let output = dir.path().join("transparent.gif").display().to_string();
+    let mut writer =
+        GifWriter::create(&output, bounds, 5, &palette, Playback::Loop, 0, true).unwrap();
+    writer.set_optimize(true);
+    assert!(!writer.optimize);
+}
+
+#[test]
+fn test_gif_interpolation() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let palette = Palette::grayscale();
+    let output = dir.path().join("interpolated.gif").display().to_string();
+    let mut writer =
+        GifWriter::create(&output, (3, 2), 5, &palette, Playback::Loop, 0, false).unwrap();
+    writer.set_interpolation(4);
+    for level in [0u8, 200, 100] {
+        writer.add_frame(&[level; 6]).unwrap();
+    }
+    writer.finish().unwrap();
+
+    // Three frames over four steps each own three blended frames between them
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(std::fs::File::open(&output).unwrap())
+        .unwrap();
+    let mut levels = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        levels.push(frame.buffer[0]);
+    }
+    assert_eq!(levels, [0, 50, 100, 150, 200, 175, 150, 125, 100]);
+
+    // Saved frames are blended the same way
+    let frames: Vec<String> = [0u8, 200]
+        .iter()
+        .map(|&level| {
+            let name = dir.path().join(format!("{}.png", level));
+            let name = name.display().to_string();
+            write_image(&name, &[level; 6], (3, 2), None, None, &[]).unwrap();
+            name
+        })
+        .collect();
+    make_gif_parallel(
+        frames,
+        &output,
+        5,
+        &palette,
+        Playback::Loop,
+        0,
+        false,
+        2,
+        None,
+    )
+    .unwrap();
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(std::fs::File::open(&output).unwrap())
+        .unwrap();
+    let mut levels = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        levels.push(frame.buffer[0]);
+    }
+    assert_eq!(levels, [0, 100, 200]);
+}
+
+#[test]
+fn test_gif_quality() {
+    use image::AnimationDecoder;
+    let dir = tempfile::TempDir::new().unwrap();
+    // A frame of many more colors than the gray palette has
+    let bounds = (64, 16);
+    let colors: Vec<[u8; 4]> = (0..bounds.0 * bounds.1)
+        .map(|i| [(i % 64 * 4) as u8, (i / 64 * 16) as u8, 128, 255])
+        .collect();
+    let image = image::RgbaImage::from_raw(
+        bounds.0 as u32,
+        bounds.1 as u32,
+        colors.as_flattened().to_vec(),
+    )
+    .unwrap();
+    let palette = Palette::grayscale();
+    let write = |name: &str, quality| {
+        let output = dir.path().join(name).display().to_string();
+        let mut writer =
+            GifWriter::create(&output, bounds, 5, &palette, Playback::PingPong, 0, false).unwrap();
+        writer.set_quality(quality);
+        writer.add_image(&image).unwrap();
+        writer.add_image(&image).unwrap();
+        writer.finish().unwrap();
+        let file = std::io::BufReader::new(std::fs::File::open(&output).unwrap());
+        let decoder = image::codecs::gif::GifDecoder::new(file).unwrap();
+        let frames: Vec<Vec<u8>> = decoder
+            .into_frames()
+            .map(|frame| frame.unwrap().into_buffer().into_raw())
+            .collect();
+        frames
+    };
+    let error = |frame: &[u8]| -> u64 {
+        frame
+            .iter()
+            .zip(colors.as_flattened())
+            .map(|(&a, &b)| (a as i64 - b as i64).pow(2) as u64)
+            .sum()
+    };
+
+    // Grays lose the hues, which quantized frames keep better the harder
+    // they try
+    let gray = write("gray.gif", None);
+    let fast = write("fast.gif", Some(1));
+    let best = write("best.gif", Some(10));
+    assert_eq!((gray.len(), fast.len(), best.len()), (2, 2, 2));
+    assert!(error(&fast[0]) < error(&gray[0]) / 4);
+    assert!(error(&best[0]) <= error(&fast[0]));
+    // Ping-pong playback replays the quantized frames
+    assert_eq!(best[0], best[1]);
 }
@@ -0,0 +1,260 @@
+use super::Channel;
+use super::transform::point_to_pixel;
+use num::Complex;
+use std::str::FromStr;
+
+/// Radius of the circle external rays are started from, far enough out that
+/// `z_n(c)` is close to the Böttcher coordinate raised to its power
+pub const RAY_ESCAPE_RADIUS: f64 = 65536.0;
+
+/// Newton steps taken between one depth of a ray and the next
+const SHARPNESS: u32 = 4;
+
+/// Newton iterations allowed to reach each point of a ray
+const MAX_NEWTON_STEPS: usize = 64;
+
+/// The angle of an external ray as an exact fraction of a whole turn, so
+/// that doubling it, which the ray does at every depth, loses nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Angle {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl Angle {
+    /// Return the angle `numerator / denominator` of a turn, wrapped into
+    /// `0..1`, or `None` for a zero denominator.
+    pub fn new(numerator: u64, denominator: u64) -> Option<Angle> {
+        (denominator > 0).then(|| Angle {
+            numerator: numerator % denominator,
+            denominator,
+        })
+    }
+
+    /// Return the angle in turns.
+    pub fn turns(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Return twice the angle, wrapped into `0..1`.
+    pub fn doubled(self) -> Angle {
+        let numerator = 2 * self.numerator as u128 % self.denominator as u128;
+        Angle {
+            numerator: numerator as u64,
+            ..self
+        }
+    }
+}
+
+impl FromStr for Angle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .split_once('/')
+            .and_then(|(p, q)| Angle::new(p.trim().parse().ok()?, q.trim().parse().ok()?))
+            .ok_or_else(|| format!("`{}` is not an angle (expected P/Q of a turn, Q > 0)", s))
+    }
+}
+
+#[test]
+fn test_parse_angle() {
+    let angle: Angle = "1/3".parse().unwrap();
+    assert_eq!(angle.doubled(), Angle::new(2, 3).unwrap());
+    assert_eq!(angle.doubled().doubled(), angle);
+    assert_eq!(" 5 / 4 ".parse(), Ok(Angle::new(1, 4).unwrap()));
+    assert_eq!(Angle::new(1, 4).unwrap().turns(), 0.25);
+    // A denominator past the range of doubling in u64 still wraps exactly
+    let large = Angle::new(u64::MAX - 1, u64::MAX).unwrap();
+    assert_eq!(large.doubled(), Angle::new(u64::MAX - 2, u64::MAX).unwrap());
+    for s in ["1/0", "0.25", "-1/3", "1/x", ""] {
+        assert!(s.parse::<Angle>().is_err(), "{:?}", s);
+    }
+}
+
+/// Trace the external ray of the Mandelbrot set at `angle` inward from
+/// [`RAY_ESCAPE_RADIUS`], returning its points from the outside in.
+///
+/// A point of the ray at depth `n` solves `z_n(c) = r e^(2πi 2^(n-1) θ)`,
+/// where the radius `r` falls from the escape radius towards its square root
+/// over a few Newton steps from the point before, after which the depth goes
+/// up and the angle doubles. The ray stops after `max_depth` depths, when a
+/// whole depth moves it less than `precision`, as it does close to where it
+/// lands on the boundary, or if Newton's method fails to converge.
+pub fn trace(angle: Angle, max_depth: usize, precision: f64) -> Vec<Complex<f64>> {
+    let mut target_angle = angle;
+    let mut c = Complex::from_polar(RAY_ESCAPE_RADIUS, std::f64::consts::TAU * angle.turns());
+    let mut points = vec![c];
+    for depth in 1..=max_depth {
+        let start = c;
+        for k in 0..SHARPNESS {
+            let exponent = 0.5f64.powf((k as f64 + 0.5) / SHARPNESS as f64);
+            let target = Complex::from_polar(
+                RAY_ESCAPE_RADIUS.powf(exponent),
+                std::f64::consts::TAU * target_angle.turns(),
+            );
+            match newton(c, target, depth) {
+                Some(next) => c = next,
+                None => return points,
+            }
+            points.push(c);
+        }
+        if (c - start).norm() < precision {
+            break;
+        }
+        target_angle = target_angle.doubled();
+    }
+    points
+}
+
+/// Solve `z_n(c) = target` with Newton's method from `c`, returning `None`
+/// if it fails to converge.
+fn newton(mut c: Complex<f64>, target: Complex<f64>, n: usize) -> Option<Complex<f64>> {
+    for _ in 0..MAX_NEWTON_STEPS {
+        // z_n and its derivative with respect to c
+        let (mut z, mut dc) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+        for _ in 0..n {
+            dc = 2.0 * z * dc + 1.0;
+            z = z * z + c;
+        }
+        let next = c - (z - target) / dc;
+        if !next.re.is_finite() || !next.im.is_finite() {
+            return None;
+        }
+        let step = (next - c).norm();
+        c = next;
+        if step <= 1e-14 * c.norm().max(1e-300) {
+            return Some(c);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_trace() {
+    // The ray at angle 1/2 runs along the negative real axis and lands on -2
+    let ray = trace(Angle::new(1, 2).unwrap(), 200, 1e-9);
+    assert!(ray.iter().all(|c| c.im.abs() < 1e-9 && c.re < -1.99));
+    assert!((ray.last().unwrap() - Complex::new(-2.0, 0.0)).norm() < 1e-3);
+    // The points of a ray escape ever more slowly on the way in
+    let ray = trace(Angle::new(1, 3).unwrap(), 60, 1e-6);
+    let potentials: Vec<f64> = ray
+        .iter()
+        .map(|&c| {
+            let mut z = Complex::new(0.0, 0.0);
+            let mut n = 0;
+            while z.norm() < 1e6 && n < 1000 {
+                z = z * z + c;
+                n += 1;
+            }
+            z.norm().ln() / 2f64.powi(n)
+        })
+        .collect();
+    assert!(
+        potentials.windows(2).all(|w| w[1] < w[0]),
+        "{:?}",
+        potentials
+    );
+    // The ray at 1/4 stops on the tip it lands on well before the limit
+    let ray = trace(Angle::new(1, 4).unwrap(), 200, 1e-9);
+    assert!(ray.len() < 200);
+    let tip = Complex::new(-0.228155493653962, 1.115142508039937);
+    assert!((ray.last().unwrap() - tip).norm() < 1e-6);
+}
+
+/// Draw the polylines through the points of `rays` onto the `pixels` buffer,
+/// whose dimensions are given by `bounds` and whose corners map to
+/// `upper_left` and `lower_right`.
+///
+/// Pixels under a ray are inverted, once even where rays cross, so the rays
+/// stay visible on both dark and bright regions; parts outside the image
+/// are left out.
+pub fn draw<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    rays: &[Vec<Complex<f64>>],
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    let pixel_size = Complex {
+        re: (lower_right.re - upper_left.re) / bounds.0 as f64,
+        im: (upper_left.im - lower_right.im) / bounds.1 as f64,
+    };
+    let (low, high) = (
+        Complex::new(
+            upper_left.re.min(lower_right.re),
+            upper_left.im.min(lower_right.im),
+        ),
+        Complex::new(
+            upper_left.re.max(lower_right.re),
+            upper_left.im.max(lower_right.im),
+        ),
+    );
+    let mut covered = vec![false; pixels.len()];
+    for ray in rays {
+        for segment in ray.windows(2) {
+            // Clip the segment to the view, then sample it once per pixel
+            // along its longer axis
+            let delta = segment[1] - segment[0];
+            let (mut t0, mut t1) = (0.0f64, 1.0f64);
+            for (start, step, low, high) in [
+                (segment[0].re, delta.re, low.re, high.re),
+                (segment[0].im, delta.im, low.im, high.im),
+            ] {
+                if step == 0.0 {
+                    if start < low || start > high {
+                        t1 = -1.0;
+                    }
+                    continue;
+                }
+                let (a, b) = ((low - start) / step, (high - start) / step);
+                t0 = t0.max(a.min(b));
+                t1 = t1.min(a.max(b));
+            }
+            if t0 > t1 {
+                continue;
+            }
+            let steps = ((t1 - t0)
+                * (delta.re / pixel_size.re)
+                    .abs()
+                    .max((delta.im / pixel_size.im).abs()))
+            .ceil() as usize;
+            for i in 0..=steps {
+                let t = t0 + (t1 - t0) * i as f64 / steps.max(1) as f64;
+                let point = segment[0] + delta * t;
+                if let Some((column, row)) = point_to_pixel(bounds, point, upper_left, lower_right)
+                {
+                    covered[row * bounds.0 + column] = true;
+                }
+            }
+        }
+    }
+    for (pixel, _) in pixels
+        .iter_mut()
+        .zip(covered)
+        .filter(|(_, covered)| *covered)
+    {
+        *pixel = pixel.with_level(P::MAX - pixel.level());
+    }
+}
+
+#[test]
+fn test_draw_rays() {
+    let bounds = (20, 10);
+    let mut pixels = vec![0u8; bounds.0 * bounds.1];
+    let upper_left = Complex { re: -2.0, im: 1.0 };
+    let lower_right = Complex { re: 2.0, im: -1.0 };
+    // A ray crossing the middle row from far outside, drawn twice
+    let ray = vec![Complex::new(-100.0, 0.05), Complex::new(0.0, 0.05)];
+    draw(
+        &mut pixels,
+        bounds,
+        upper_left,
+        lower_right,
+        &[ray.clone(), ray],
+    );
+    assert!((0..=10).all(|column| pixels[4 * bounds.0 + column] == 255));
+    assert!((11..20).all(|column| pixels[4 * bounds.0 + column] == 0));
+    assert_eq!(pixels.iter().filter(|&&p| p == 255).count(), 11);
+}
@@ -0,0 +1,543 @@
+use super::{
+    ColorOptions, DEFAULT_JULIA_CONSTANT, DEFAULT_PHOENIX_CONSTANT, EscapeCondition, FractalParams,
+    FractalType, SMOOTHING_RADIUS, transform::SamplePattern,
+};
+use num::Complex;
+
+/// Shading language a fragment shader is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    /// GLSL ES 3.00, for WebGL 2 and OpenGL ES 3
+    Glsl,
+    /// WGSL, for WebGPU
+    Wgsl,
+}
+
+/// A value the shader is built around, declared once at the top
+enum Constant {
+    Int(i64),
+    Float(f64),
+    Point(Complex<f64>),
+}
+
+/// Write `x` as a float literal both languages accept, with a point or an
+/// exponent so that it is not read as an integer.
+fn float(x: f64) -> String {
+    format!("{:?}", x)
+}
+
+/// The parts of a shader that do not depend on the language, written with
+/// the `vec2(...)` and `vec3(...)` constructors of GLSL
+struct Parts {
+    comment: Vec<String>,
+    constants: Vec<(&'static str, Constant)>,
+    /// Expression of the starting `z`, given the sampled `point`
+    start: String,
+    /// Expression of the next `z`, given `z`, `previous` and `c`
+    formula: String,
+    /// Condition on `z` that stops an orbit; NaNs compare false, so orbits
+    /// that overflowed count as escaped like on the CPU
+    escaped: String,
+    /// Whether the orbit is followed on past the escape radius
+    smooth: bool,
+    /// Statements that turn the escape count `n` into the 8-bit level
+    shade: Vec<String>,
+    palette: Option<super::palette::Palette>,
+}
+
+/// Return the source of a fragment shader that renders the view from
+/// `upper_left` to `lower_right` like [`render`](super::render) with these
+/// parameters and colors, or why it cannot.
+///
+/// The shader fills its whole viewport with the view, one sample at each
+/// pixel center, and takes the size of the viewport in pixels as a uniform:
+/// `u_resolution` in GLSL and binding 0 of group 0 in WGSL. Colors are
+/// written as the 8-bit levels of the palette, for a target that is not
+/// sRGB-encoded.
+///
+/// The Mandelbrot, Julia, Burning Ship, Tricorn, sin, cos, phoenix and
+/// whole-power multibrot formulas are carried over, with escape counts or
+/// smooth escape times, contours, band periods, count ranges, palette
+/// shifts, color scales and palettes. GPUs iterate in single precision, so
+/// views narrower than about 1e-5 break up into blocks that the CPU renders
+/// smoothly.
+pub fn emit(
+    language: Language,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+) -> Result<String, String> {
+    let parts = parts(params, colors, upper_left, lower_right)?;
+    Ok(match language {
+        Language::Glsl => glsl(&parts),
+        Language::Wgsl => wgsl(&parts)
+            .replace("vec2(", "vec2f(")
+            .replace("vec3(", "vec3f(")
+            .replace("vec4(", "vec4f("),
+    })
+}
+
+fn parts(
+    params: &FractalParams,
+    colors: &ColorOptions,
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+) -> Result<Parts, String> {
+    let unsupported = [
+        (params.conjugate.is_some(), "--conjugate"),
+        (
+            params.complex_power.is_some(),
+            "complex or fractional powers",
+        ),
+        (params.morph.is_some(), "--morph-to"),
+        (params.rotation.is_some(), "rotated views"),
+        (
+            params.sample_pattern != SamplePattern::Rect,
+            "polar sampling",
+        ),
+        (colors.decomposition.is_some(), "--decomposition"),
+        (colors.stripe_density.is_some(), "--stripe-density"),
+        (colors.interior_coloring.is_some(), "interior coloring"),
+        (colors.potential.is_some(), "--potential"),
+        (colors.root_coloring, "--root-coloring"),
+    ];
+    if let Some((_, name)) = unsupported.iter().find(|(used, _)| *used) {
+        return Err(format!("shaders do not support {}", name));
+    }
+    let fractal_type = params.fractal_type;
+    let uses_power = matches!(
+        fractal_type,
+        FractalType::Mandelbrot
+            | FractalType::Multibrot
+            | FractalType::Julia
+            | FractalType::Tricorn
+            | FractalType::Phoenix
+    );
+    if uses_power && params.power < 1 {
+        return Err(format!(
+            "shaders only support powers of 1 or more, not {}",
+            params.power
+        ));
+    }
+
+    let formula = match fractal_type {
+        FractalType::Mandelbrot | FractalType::Multibrot => "c_pow(z) + c".to_string(),
+        FractalType::Julia => {
+            let k = params.julia_constant.unwrap_or(DEFAULT_JULIA_CONSTANT);
+            format!("c_pow(z) + vec2({}, {})", float(k.re), float(k.im))
+        }
+        FractalType::BurningShip => "c_mul(abs(z), abs(z)) + c".to_string(),
+        FractalType::Tricorn => "c_pow(vec2(z.x, -z.y)) + c".to_string(),
+        FractalType::Sin => "c_sin(z) + c".to_string(),
+        FractalType::Cos => "c_cos(z) + c".to_string(),
+        FractalType::Phoenix => {
+            let p = params.julia_constant.unwrap_or(DEFAULT_PHOENIX_CONSTANT);
+            format!(
+                "c_pow(z) + vec2({}, 0.0) + {} * previous",
+                float(p.re),
+                float(p.im)
+            )
+        }
+        FractalType::Nova
+        | FractalType::Collatz
+        | FractalType::Lyapunov
+        | FractalType::Magnet1
+        | FractalType::Magnet2 => {
+            return Err(format!(
+                "shaders do not support the {} fractal type",
+                fractal_type.name().replace('_', "-")
+            ));
+        }
+    };
+    let start = match (params.initial_z, fractal_type) {
+        (Some(start), _) => format!("vec2({}, {})", float(start.re), float(start.im)),
+        (None, FractalType::Julia | FractalType::Phoenix) => "point".to_string(),
+        (None, _) => "vec2(0.0, 0.0)".to_string(),
+    };
+    let escaped = match params.escape_condition() {
+        EscapeCondition::Norm => "!(dot(z, z) <= ESCAPE_RADIUS * ESCAPE_RADIUS)",
+        EscapeCondition::Imag => "!(abs(z.y) <= ESCAPE_RADIUS)",
+        EscapeCondition::Real => "!(abs(z.x) <= ESCAPE_RADIUS)",
+    }
+    .to_string();
+
+    // The same steps as ColorOptions::shade_result, for 8-bit levels
+    let limit = params.max_iterations;
+    let mut shade = Vec::new();
+    match colors.smooth {
+        Some(power) => {
+            shade.push(format!(
+                "level = n + steps - log(ratio) / {};",
+                float(power.max(2.0).ln())
+            ));
+            shade.push("if (!(abs(level) < 1e30)) { level = n; }".to_string());
+            shade.push("level = max(level, 0.0);".to_string());
+            shade.push(match colors.band_period {
+                Some(period) => format!(
+                    "level = (1.0 - abs(2.0 * fract(level / {}) - 1.0)) * 255.0;",
+                    float(period)
+                ),
+                None => format!("level = level * 255.0 / {};", float(limit as f64)),
+            });
+            shade.push("level = floor(clamp(level, 0.0, 255.0) + 0.5);".to_string());
+        }
+        None => shade.push(match colors.contour {
+            Some(period) => {
+                let period = float(period as f64);
+                format!(
+                    "level = floor((n - {p} * floor(n / {p})) * 255.0 / {p});",
+                    p = period
+                )
+            }
+            None => {
+                let (low, high) = colors.count_range.unwrap_or((0, limit));
+                format!(
+                    "level = floor((clamp(n, {}, {}) - {}) * 255.0 / {});",
+                    float(low as f64),
+                    float(high as f64),
+                    float(low as f64),
+                    float((high - low).max(1) as f64)
+                )
+            }
+        }),
+    }
+    if !colors.invert {
+        shade.push("level = 255.0 - level;".to_string());
+    }
+    if let Some(scale) = colors.color_scale {
+        shade.push(format!("level = floor(level * {} + 0.5);", float(scale)));
+    }
+    let offset = (colors.palette_shift.rem_euclid(1.0) * 256.0).round();
+    if offset != 0.0 {
+        shade.push(format!("level = level + {};", float(offset)));
+    }
+    if colors.color_scale.is_some() || offset != 0.0 {
+        shade.push("level = level - 256.0 * floor(level / 256.0);".to_string());
+    }
+
+    let name = fractal_type.name().replace('_', "-");
+    let mut comment = vec![format!("The {} fractal", name)];
+    if uses_power {
+        comment[0] += &format!(" of power {}", params.power);
+    }
+    comment.push(format!(
+        "View from {},{} in the upper left to {},{} in the lower right",
+        upper_left.re, upper_left.im, lower_right.re, lower_right.im
+    ));
+    comment.push(format!(
+        "Generated by mandelbrot {}",
+        env!("CARGO_PKG_VERSION")
+    ));
+
+    // Corner sampling moves each sample half a pixel up and to the left
+    let corner = if params.corner_sampling { 0.5 } else { 0.0 };
+    Ok(Parts {
+        comment,
+        constants: vec![
+            ("UPPER_LEFT", Constant::Point(upper_left)),
+            ("LOWER_RIGHT", Constant::Point(lower_right)),
+            ("CORNER", Constant::Float(corner)),
+            ("POWER", Constant::Int(params.power as i64)),
+            ("MAX_ITERATIONS", Constant::Int(limit as i64)),
+            ("ESCAPE_RADIUS", Constant::Float(params.escape_radius)),
+            ("SMOOTHING_RADIUS", Constant::Float(SMOOTHING_RADIUS)),
+            ("MAX_SMOOTHING_STEPS", Constant::Float(64.0)),
+            ("INTERIOR", Constant::Float(colors.interior as f64)),
+        ],
+        start,
+        formula,
+        escaped,
+        smooth: colors.smooth.is_some(),
+        shade,
+        palette: colors.palette,
+    })
+}
+
+/// Return the palette as 256 `vec3` colors, one per line.
+fn palette_entries(palette: &super::palette::Palette) -> String {
+    palette
+        .0
+        .iter()
+        .map(|[r, g, b]| {
+            format!(
+                "    vec3({:.6}, {:.6}, {:.6}),\n",
+                *r as f64 / 255.0,
+                *g as f64 / 255.0,
+                *b as f64 / 255.0
+            )
+        })
+        .collect()
+}
+
+fn glsl(parts: &Parts) -> String {
+    let mut out = String::from("#version 300 es\n");
+    for line in &parts.comment {
+        out += &format!("// {}\n", line);
+    }
+    out += "precision highp float;\n\nuniform vec2 u_resolution;\nout vec4 frag_color;\n\n";
+    for (name, value) in &parts.constants {
+        out += &match value {
+            Constant::Int(n) => format!("const int {} = {};\n", name, n),
+            Constant::Float(x) => format!("const float {} = {};\n", name, float(*x)),
+            Constant::Point(c) => format!(
+                "const vec2 {} = vec2({}, {});\n",
+                name,
+                float(c.re),
+                float(c.im)
+            ),
+        };
+    }
+    if let Some(palette) = &parts.palette {
+        // GLSL allows no comma after the last argument
+        out += &format!(
+            "const vec3 PALETTE[256] = vec3[256](\n{}\n);\n",
+            palette_entries(palette).trim_end_matches(",\n")
+        );
+    }
+    out += &format!(
+        r#"
+vec2 c_mul(vec2 a, vec2 b) {{
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}}
+
+vec2 c_pow(vec2 z) {{
+    vec2 w = z;
+    for (int k = 1; k < POWER; k++) {{
+        w = c_mul(w, z);
+    }}
+    return w;
+}}
+
+vec2 c_sin(vec2 z) {{
+    return vec2(sin(z.x) * cosh(z.y), cos(z.x) * sinh(z.y));
+}}
+
+vec2 c_cos(vec2 z) {{
+    return vec2(cos(z.x) * cosh(z.y), -sin(z.x) * sinh(z.y));
+}}
+
+vec2 advance(vec2 z, vec2 previous, vec2 c) {{
+    return {formula};
+}}
+
+bool escaped(vec2 z) {{
+    return {escaped};
+}}
+
+void main() {{
+    // gl_FragCoord counts up from the bottom of the viewport
+    vec2 t = (gl_FragCoord.xy + vec2(-CORNER, CORNER)) / u_resolution;
+    vec2 point = vec2(mix(UPPER_LEFT.x, LOWER_RIGHT.x, t.x), mix(LOWER_RIGHT.y, UPPER_LEFT.y, t.y));
+    vec2 c = point;
+    vec2 z = {start};
+    vec2 previous = vec2(0.0, 0.0);
+    int count = -1;
+    for (int i = 0; i < MAX_ITERATIONS; i++) {{
+        if (escaped(z)) {{
+            count = i;
+            break;
+        }}
+        vec2 next = advance(z, previous, c);
+        previous = z;
+        z = next;
+    }}
+    float level = INTERIOR;
+    if (count >= 0) {{
+        float n = float(count);
+"#,
+        formula = parts.formula,
+        escaped = parts.escaped,
+        start = parts.start,
+    );
+    if parts.smooth {
+        out += r#"        vec2 beyond = z;
+        vec2 before = previous;
+        float steps = 0.0;
+        while (dot(beyond, beyond) <= SMOOTHING_RADIUS * SMOOTHING_RADIUS && steps < MAX_SMOOTHING_STEPS) {
+            vec2 next = advance(beyond, before, c);
+            before = beyond;
+            beyond = next;
+            steps += 1.0;
+        }
+        float ratio = log(length(beyond)) / log(ESCAPE_RADIUS);
+"#;
+    }
+    for statement in &parts.shade {
+        out += &format!("        {}\n", statement);
+    }
+    out += "    }\n";
+    out += match parts.palette {
+        Some(_) => "    frag_color = vec4(PALETTE[int(level)], 1.0);\n}\n",
+        None => "    frag_color = vec4(vec3(level / 255.0), 1.0);\n}\n",
+    };
+    out
+}
+
+fn wgsl(parts: &Parts) -> String {
+    let mut out = String::new();
+    for line in &parts.comment {
+        out += &format!("// {}\n", line);
+    }
+    out += "\n@group(0) @binding(0) var<uniform> resolution: vec2<f32>;\n\n";
+    for (name, value) in &parts.constants {
+        out += &match value {
+            Constant::Int(n) => format!("const {}: i32 = {};\n", name, n),
+            Constant::Float(x) => format!("const {}: f32 = {};\n", name, float(*x)),
+            Constant::Point(c) => {
+                format!("const {} = vec2({}, {});\n", name, float(c.re), float(c.im))
+            }
+        };
+    }
+    // Only arrays in memory can be indexed by a level computed at run time
+    if let Some(palette) = &parts.palette {
+        out += &format!(
+            "var<private> PALETTE: array<vec3<f32>, 256> = array<vec3<f32>, 256>(\n{});\n",
+            palette_entries(palette)
+        );
+    }
+    out += &format!(
+        r#"
+fn c_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {{
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}}
+
+fn c_pow(z: vec2<f32>) -> vec2<f32> {{
+    var w = z;
+    for (var k = 1; k < POWER; k++) {{
+        w = c_mul(w, z);
+    }}
+    return w;
+}}
+
+fn c_sin(z: vec2<f32>) -> vec2<f32> {{
+    return vec2(sin(z.x) * cosh(z.y), cos(z.x) * sinh(z.y));
+}}
+
+fn c_cos(z: vec2<f32>) -> vec2<f32> {{
+    return vec2(cos(z.x) * cosh(z.y), -sin(z.x) * sinh(z.y));
+}}
+
+fn advance(z: vec2<f32>, previous: vec2<f32>, c: vec2<f32>) -> vec2<f32> {{
+    return {formula};
+}}
+
+fn escaped(z: vec2<f32>) -> bool {{
+    return {escaped};
+}}
+
+@fragment
+fn main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {{
+    // The position counts down from the top of the viewport
+    let t = (position.xy - CORNER) / resolution;
+    let point = vec2(mix(UPPER_LEFT.x, LOWER_RIGHT.x, t.x), mix(UPPER_LEFT.y, LOWER_RIGHT.y, t.y));
+    let c = point;
+    var z = {start};
+    var previous = vec2(0.0, 0.0);
+    var count = -1;
+    for (var i = 0; i < MAX_ITERATIONS; i++) {{
+        if (escaped(z)) {{
+            count = i;
+            break;
+        }}
+        let next = advance(z, previous, c);
+        previous = z;
+        z = next;
+    }}
+    var level = INTERIOR;
+    if (count >= 0) {{
+        let n = f32(count);
+"#,
+        formula = parts.formula,
+        escaped = parts.escaped,
+        start = parts.start,
+    );
+    if parts.smooth {
+        out += r#"        var beyond = z;
+        var before = previous;
+        var steps = 0.0;
+        while (dot(beyond, beyond) <= SMOOTHING_RADIUS * SMOOTHING_RADIUS && steps < MAX_SMOOTHING_STEPS) {
+            let next = advance(beyond, before, c);
+            before = beyond;
+            beyond = next;
+            steps += 1.0;
+        }
+        let ratio = log(length(beyond)) / log(ESCAPE_RADIUS);
+"#;
+    }
+    for statement in &parts.shade {
+        out += &format!("        {}\n", statement);
+    }
+    out += "    }\n";
+    out += match parts.palette {
+        Some(_) => "    return vec4(PALETTE[i32(level)], 1.0);\n}\n",
+        None => "    return vec4(vec3(level / 255.0), 1.0);\n}\n",
+    };
+    out
+}
+
+#[test]
+fn test_emit_shader() {
+    let upper_left = Complex { re: -2.0, im: 1.2 };
+    let lower_right = Complex { re: 1.0, im: -1.2 };
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let glsl = emit(Language::Glsl, &params, &colors, upper_left, lower_right).unwrap();
+    assert!(glsl.starts_with("#version 300 es\n// The mandelbrot fractal of power 2\n"));
+    for line in [
+        "const vec2 UPPER_LEFT = vec2(-2.0, 1.2);",
+        "const int MAX_ITERATIONS = 255;",
+        "const float ESCAPE_RADIUS = 2.0;",
+        "    return c_pow(z) + c;",
+        "    vec2 z = vec2(0.0, 0.0);",
+        "        level = floor((clamp(n, 0.0, 255.0) - 0.0) * 255.0 / 255.0);",
+        "        level = 255.0 - level;",
+        "    frag_color = vec4(vec3(level / 255.0), 1.0);",
+    ] {
+        assert!(glsl.lines().any(|l| l == line), "{}", line);
+    }
+    assert!(!glsl.contains("beyond") && !glsl.contains("PALETTE"));
+
+    // WGSL is written with its own constructors, and a smooth palette
+    // follows the orbit past the escape radius
+    let params = FractalParams {
+        fractal_type: FractalType::Julia,
+        julia_constant: Some(Complex::new(-0.8, 0.156)),
+        ..params
+    };
+    let colors = ColorOptions {
+        smooth: Some(2.0),
+        palette_shift: 0.25,
+        palette: Some(super::palette::Palette::grayscale()),
+        ..colors
+    };
+    let wgsl = emit(Language::Wgsl, &params, &colors, upper_left, lower_right).unwrap();
+    for line in [
+        "const UPPER_LEFT = vec2f(-2.0, 1.2);",
+        "const POWER: i32 = 2;",
+        "    return c_pow(z) + vec2f(-0.8, 0.156);",
+        "    var z = point;",
+        "        let ratio = log(length(beyond)) / log(ESCAPE_RADIUS);",
+        "        level = level + 64.0;",
+        "    vec3f(1.000000, 1.000000, 1.000000),",
+        "    return vec4f(PALETTE[i32(level)], 1.0);",
+    ] {
+        assert!(wgsl.lines().any(|l| l == line), "{}", line);
+    }
+    assert!(!wgsl.contains("vec2(") && !wgsl.contains("vec3("));
+
+    // Formulas and colorings without a GPU version are refused
+    let nova = FractalParams {
+        fractal_type: FractalType::Nova,
+        ..params
+    };
+    assert_eq!(
+        emit(Language::Glsl, &nova, &colors, upper_left, lower_right),
+        Err("shaders do not support the nova fractal type".to_string())
+    );
+    let stripes = ColorOptions {
+        stripe_density: Some(5.0),
+        ..colors
+    };
+    assert!(emit(Language::Wgsl, &params, &stripes, upper_left, lower_right).is_err());
+}
@@ -0,0 +1,246 @@
+use super::concat::{Segment, frame_number};
+use std::path::{Path, PathBuf};
+
+/// The frames one worker of a split render is given, counting from 1 like
+/// `--frame-range`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Share {
+    /// Number of the worker, counting from 1
+    pub worker: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The shares of an animation split between several machines, which render
+/// them with the same options and `--frame-range` to be merged afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub n_frames: usize,
+    pub shares: Vec<Share>,
+}
+
+impl Manifest {
+    /// Split `n_frames` frames between `workers` workers in runs of
+    /// consecutive frames, the first ones taking one frame more than the
+    /// rest when they do not divide evenly.
+    ///
+    /// With the same options, a frame depends only on its number:
+    /// `--frame-range` refuses the options that would make it depend on the
+    /// clock or on the other frames a worker renders, such as `--time-budget`,
+    /// `--stop-when-uniform`, `--global-normalize` and `--random-view`
+    /// without a seed. So the same split renders the same animation however
+    /// the workers are scheduled.
+    pub fn new(n_frames: usize, workers: usize) -> Result<Manifest, String> {
+        if workers == 0 || workers > n_frames {
+            return Err(format!(
+                "cannot split {} frames between {} workers",
+                n_frames, workers
+            ));
+        }
+        let (size, extra) = (n_frames / workers, n_frames % workers);
+        let mut start = 1;
+        let shares = (1..=workers)
+            .map(|worker| {
+                let end = start + size + (worker <= extra) as usize - 1;
+                let share = Share { worker, start, end };
+                start = end + 1;
+                share
+            })
+            .collect();
+        Ok(Manifest { n_frames, shares })
+    }
+
+    /// Serialize the manifest as a JSON object, one worker per line with the
+    /// options that select its frames.
+    pub fn to_json(&self) -> String {
+        let workers: Vec<String> = self
+            .shares
+            .iter()
+            .map(|share| {
+                format!(
+                    "    {{\"worker\": {}, \"start\": {}, \"end\": {}, \
+                     \"args\": \"--n-frames {} --frame-range {} {}\"}}",
+                    share.worker, share.start, share.end, self.n_frames, share.start, share.end
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"n_frames\": {},\n  \"workers\": [\n{}\n  ]\n}}\n",
+            self.n_frames,
+            workers.join(",\n")
+        )
+    }
+
+    /// Parse a manifest written by [`Manifest::to_json`], whose shares must
+    /// cover every frame once, in order.
+    ///
+    /// Only the layout of that method is understood, not arbitrary JSON.
+    pub fn from_json(text: &str) -> Result<Manifest, String> {
+        let (head, workers) = text
+            .split_once("\"workers\"")
+            .ok_or("missing \"workers\"")?;
+        let n_frames = number(head, "n_frames")?;
+        let shares = workers
+            .split('{')
+            .skip(1)
+            .map(|object| {
+                Ok(Share {
+                    worker: number(object, "worker")?,
+                    start: number(object, "start")?,
+                    end: number(object, "end")?,
+                })
+            })
+            .collect::<Result<Vec<Share>, String>>()?;
+        let mut next = 1;
+        for share in &shares {
+            if share.start != next || share.end < share.start {
+                return Err(format!(
+                    "worker {} should start at frame {}, not {}",
+                    share.worker, next, share.start
+                ));
+            }
+            next = share.end + 1;
+        }
+        if next != n_frames + 1 || shares.is_empty() {
+            return Err(format!(
+                "the workers cover {} of the {} frames",
+                next - 1,
+                n_frames
+            ));
+        }
+        Ok(Manifest { n_frames, shares })
+    }
+
+    /// Read the manifest file `path`, see [`Manifest::from_json`].
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Manifest::from_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Return the frames of the render, in order, from the PNG files of the
+    /// workers' `folders`, which may hold any of the shares each.
+    ///
+    /// Files are matched to frames by the number at the end of their names,
+    /// as `--frame-range` keeps them; every frame must be found exactly once.
+    pub fn collect_frames(&self, folders: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+        let mut frames: Vec<Option<PathBuf>> = vec![None; self.n_frames];
+        for folder in folders {
+            let files = match Segment::open(folder) {
+                Ok(Segment::Frames { files, .. }) => files,
+                Ok(Segment::Gif(_)) => {
+                    return Err(format!("{} is not a folder", folder.display()));
+                }
+                Err(e) => return Err(format!("cannot read {}: {}", folder.display(), e)),
+            };
+            for file in files {
+                let number = frame_number(&file).1.map(|n| n as usize);
+                let Some(slot) = number
+                    .filter(|&n| n >= 1)
+                    .and_then(|n| frames.get_mut(n - 1))
+                else {
+                    return Err(format!(
+                        "{} is not numbered as a frame from 1 to {}",
+                        file.display(),
+                        self.n_frames
+                    ));
+                };
+                if let Some(other) = slot {
+                    return Err(format!(
+                        "{} and {} are the same frame",
+                        other.display(),
+                        file.display()
+                    ));
+                }
+                *slot = Some(file);
+            }
+        }
+        for share in &self.shares {
+            let missing = (share.start..=share.end)
+                .filter(|&n| frames[n - 1].is_none())
+                .count();
+            if missing > 0 {
+                return Err(format!(
+                    "{} of the frames {} to {} of worker {} are missing",
+                    missing, share.start, share.end, share.worker
+                ));
+            }
+        }
+        Ok(frames.into_iter().flatten().collect())
+    }
+}
+
+/// Parse the whole number `key` of the JSON object `text`.
+fn number(text: &str, key: &str) -> Result<usize, String> {
+    let quoted = format!("\"{}\"", key);
+    let start = text.find(&quoted).ok_or(format!("missing \"{}\"", key))? + quoted.len();
+    let value = text[start..]
+        .trim_start()
+        .strip_prefix(':')
+        .ok_or(format!("expected `:` after \"{}\"", key))?
+        .trim_start();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value[..end]
+        .parse()
+        .map_err(|_| format!("invalid count for \"{}\"", key))
+}
+
+#[test]
+fn test_manifest() {
+    let manifest = Manifest::new(10, 3).unwrap();
+    let ranges: Vec<_> = manifest.shares.iter().map(|s| (s.start, s.end)).collect();
+    assert_eq!(ranges, [(1, 4), (5, 7), (8, 10)]);
+    assert!(Manifest::new(2, 3).is_err() && Manifest::new(2, 0).is_err());
+
+    let json = manifest.to_json();
+    assert!(json.contains(
+        "{\"worker\": 2, \"start\": 5, \"end\": 7, \"args\": \"--n-frames 10 --frame-range 5 7\"}"
+    ));
+    assert_eq!(Manifest::from_json(&json), Ok(manifest.clone()));
+    // Shares that leave out or repeat frames are refused
+    for (from, to) in [
+        ("\"end\": 7", "\"end\": 6"),
+        ("\"n_frames\": 10", "\"n_frames\": 11"),
+    ] {
+        assert!(
+            Manifest::from_json(&json.replace(from, to)).is_err(),
+            "{}",
+            to
+        );
+    }
+
+    // Frames are collected from any worker's folder, in number order
+    let dir = tempfile::tempdir().unwrap();
+    let folders = [dir.path().join("a"), dir.path().join("b")];
+    for (folder, numbers) in folders.iter().zip([[8, 9, 10, 1, 2], [3, 4, 5, 6, 7]]) {
+        std::fs::create_dir(folder).unwrap();
+        for n in numbers {
+            let name = folder.join(format!("mandelbrot-{:02}.png", n));
+            super::preserve::write_image(
+                &name.display().to_string(),
+                &[0u8],
+                (1, 1),
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        }
+    }
+    let frames = manifest.collect_frames(&folders).unwrap();
+    let numbers: Vec<_> = frames.iter().map(|f| frame_number(f).1.unwrap()).collect();
+    assert_eq!(numbers, (1..=10).collect::<Vec<u64>>());
+
+    assert_eq!(
+        manifest.collect_frames(&folders[..1]),
+        Err("2 of the frames 1 to 4 of worker 1 are missing".to_string())
+    );
+    assert!(
+        manifest
+            .collect_frames(&[folders[0].clone(), folders[0].clone()])
+            .unwrap_err()
+            .ends_with("are the same frame")
+    );
+}
@@ -0,0 +1,179 @@
+use super::{Channel, ColorOptions, FractalParams, render_parallel};
+use num::Complex;
+
+/// An axis of the complex plane that a view can be mirrored across
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mirror {
+    /// Reflect the top half across the real axis to fill the bottom half
+    Horizontal,
+    /// Reflect the left half across the imaginary axis to fill the right half
+    Vertical,
+}
+
+impl Mirror {
+    /// Return whether the view between `upper_left` and `lower_right` is
+    /// centered on the axis, so that its two halves are reflections of each
+    /// other.
+    pub fn is_symmetric(self, upper_left: Complex<f64>, lower_right: Complex<f64>) -> bool {
+        let (near, far) = match self {
+            Mirror::Horizontal => (upper_left.im, lower_right.im),
+            Mirror::Vertical => (upper_left.re, lower_right.re),
+        };
+        (near + far).abs() <= 1e-9 * (near - far).abs()
+    }
+
+    /// Return the dimensions and lower-right corner of the half of the image
+    /// that is rendered, which keeps the middle row or column of an odd size.
+    fn half(
+        self,
+        bounds: (usize, usize),
+        upper_left: Complex<f64>,
+        lower_right: Complex<f64>,
+    ) -> ((usize, usize), Complex<f64>) {
+        match self {
+            Mirror::Horizontal => {
+                let rows = bounds.1.div_ceil(2);
+                let im = upper_left.im
+                    + (lower_right.im - upper_left.im) * rows as f64 / bounds.1 as f64;
+                (
+                    (bounds.0, rows),
+                    Complex {
+                        re: lower_right.re,
+                        im,
+                    },
+                )
+            }
+            Mirror::Vertical => {
+                let columns = bounds.0.div_ceil(2);
+                let re = upper_left.re
+                    + (lower_right.re - upper_left.re) * columns as f64 / bounds.0 as f64;
+                (
+                    (columns, bounds.1),
+                    Complex {
+                        re,
+                        im: lower_right.im,
+                    },
+                )
+            }
+        }
+    }
+}
+
+#[test]
+fn test_is_symmetric() {
+    let upper_left = Complex { re: -2.0, im: 1.5 };
+    let lower_right = Complex { re: 1.0, im: -1.5 };
+    assert!(Mirror::Horizontal.is_symmetric(upper_left, lower_right));
+    assert!(!Mirror::Vertical.is_symmetric(upper_left, lower_right));
+    let lower_right = Complex { re: 2.0, im: -1.0 };
+    assert!(!Mirror::Horizontal.is_symmetric(upper_left, lower_right));
+    assert!(Mirror::Vertical.is_symmetric(upper_left, lower_right));
+}
+
+/// Render like [`render_parallel`], computing only half of the image and
+/// copying its reflection across `mirror` into the other half.
+///
+/// The result is only faithful when the fractal is symmetric about the axis
+/// and the view is centered on it; see [`Mirror::is_symmetric`].
+pub fn render<P: Channel>(
+    pixels: &mut [P],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    colors: &ColorOptions,
+    mirror: Mirror,
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    let (half_bounds, half_lower_right) = mirror.half(bounds, upper_left, lower_right);
+    let (width, height) = bounds;
+    match mirror {
+        Mirror::Horizontal => {
+            // The top rows are contiguous, so render straight into them
+            let (top, _) = pixels.split_at_mut(width * half_bounds.1);
+            render_parallel(
+                top,
+                half_bounds,
+                upper_left,
+                half_lower_right,
+                params,
+                colors,
+            );
+            for row in half_bounds.1..height {
+                let source = (height - 1 - row) * width;
+                pixels.copy_within(source..source + width, row * width);
+            }
+        }
+        Mirror::Vertical => {
+            let mut half = vec![P::default(); half_bounds.0 * half_bounds.1];
+            render_parallel(
+                &mut half,
+                half_bounds,
+                upper_left,
+                half_lower_right,
+                params,
+                colors,
+            );
+            for (row, half_row) in pixels.chunks_mut(width).zip(half.chunks(half_bounds.0)) {
+                row[..half_bounds.0].copy_from_slice(half_row);
+                for column in half_bounds.0..width {
+                    row[column] = half_row[width - 1 - column];
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_render_mirrored() {
+    let params = FractalParams::default();
+    let colors = ColorOptions::default();
+    let bounds = (40, 31);
+    let upper_left = Complex { re: -2.0, im: 1.2 };
+    let lower_right = Complex { re: 0.6, im: -1.2 };
+
+    let mut full = vec![0u8; bounds.0 * bounds.1];
+    render_parallel(&mut full, bounds, upper_left, lower_right, &params, &colors);
+    let mut mirrored = vec![0u8; bounds.0 * bounds.1];
+    render(
+        &mut mirrored,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+        Mirror::Horizontal,
+    );
+
+    // The two halves are exact reflections, including across the middle row
+    let rows: Vec<&[u8]> = mirrored.chunks(bounds.0).collect();
+    assert!((0..bounds.1).all(|row| rows[row] == rows[bounds.1 - 1 - row]));
+    // Up to rounding in the sample points, the full render agrees
+    let differing = full.iter().zip(&mirrored).filter(|(a, b)| a != b).count();
+    assert!(differing * 100 < full.len(), "{} pixels differ", differing);
+
+    // A Julia set with a real constant is symmetric about both axes
+    let params = FractalParams {
+        fractal_type: super::FractalType::Julia,
+        julia_constant: Some(Complex { re: -0.8, im: 0.0 }),
+        ..params
+    };
+    let (upper_left, lower_right) = (Complex { re: -1.6, im: 1.2 }, Complex { re: 1.6, im: -1.2 });
+    render_parallel(&mut full, bounds, upper_left, lower_right, &params, &colors);
+    render(
+        &mut mirrored,
+        bounds,
+        upper_left,
+        lower_right,
+        &params,
+        &colors,
+        Mirror::Vertical,
+    );
+    assert!(
+        mirrored
+            .chunks(bounds.0)
+            .all(|row| row.iter().eq(row.iter().rev()))
+    );
+    let differing = full.iter().zip(&mirrored).filter(|(a, b)| a != b).count();
+    assert!(differing * 100 < full.len(), "{} pixels differ", differing);
+}
@@ -0,0 +1,369 @@
+use super::random::Rng;
+use super::{FractalParams, transform};
+use num::Complex;
+use rayon::prelude::*;
+
+/// Width in pixels of the preliminary render used to look for detail
+const PROBE_WIDTH: usize = 160;
+
+/// Find the most detailed point of the view between `upper_left` and
+/// `lower_right`, for an image whose dimensions are given by `bounds`.
+///
+/// The view is rendered at low resolution and the point returned is the one
+/// where the escape count changes most sharply between neighbouring pixels,
+/// which is where the boundary of the set is busiest. Ties go to the point
+/// closest to the center of the view.
+pub fn find(
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+) -> Complex<f64> {
+    // Keep the probe's pixels square, like the final frames
+    let width = PROBE_WIDTH.min(bounds.0).max(3);
+    let height = (width * bounds.1 / bounds.0).max(3);
+    let probe = (width, height);
+    let limit = params.max_iterations;
+
+    // Members count as the iteration limit, so the edge of the set stands out
+    let counts: Vec<usize> = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let point = transform::pixel_to_point(
+                probe,
+                (index % width, index / width),
+                upper_left,
+                lower_right,
+            );
+            params.escape_time(point).unwrap_or(limit)
+        })
+        .collect();
+    let count = |column: usize, row: usize| counts[row * width + column] as i64;
+
+    let center = (width as f64 / 2.0, height as f64 / 2.0);
+    let mut best = (0, f64::INFINITY, center.0 as usize, center.1 as usize);
+    for row in 1..height - 1 {
+        for column in 1..width - 1 {
+            let gradient = (count(column + 1, row) - count(column - 1, row)).abs()
+                + (count(column, row + 1) - count(column, row - 1)).abs();
+            let distance = (column as f64 - center.0).hypot(row as f64 - center.1);
+            if gradient > best.0 || (gradient == best.0 && distance < best.1) {
+                best = (gradient, distance, column, row);
+            }
+        }
+    }
+
+    // Aim at the middle of the chosen probe pixel, where it was sampled
+    transform::pixel_to_point(probe, (best.2, best.3), upper_left, lower_right)
+}
+
+#[test]
+fn test_find_boundary() {
+    let params = FractalParams::default();
+    let upper_left = Complex { re: -2.0, im: 1.5 };
+    let lower_right = Complex { re: 1.0, im: -1.5 };
+    let target = find((400, 400), upper_left, lower_right, &params);
+
+    // The target lies on the edge of the set, so within about one probe
+    // pixel of it there are both members and escaping points
+    let escapes: Vec<bool> = (-2..=2)
+        .flat_map(|i| (-2..=2).map(move |j| (i, j)))
+        .map(|(i, j)| {
+            let point = target + Complex::new(i as f64 * 0.01, j as f64 * 0.01);
+            super::escape_time(point, 255, 2, 2.0, params.fractal_type, None).is_some()
+        })
+        .collect();
+    assert!(
+        escapes.contains(&true) && escapes.contains(&false),
+        "target {} is not on the boundary",
+        target
+    );
+}
+
+#[test]
+fn test_find_uniform_view() {
+    // Far outside the set every point escapes at once, so the center wins
+    let params = FractalParams::default();
+    let upper_left = Complex { re: 10.0, im: 11.0 };
+    let lower_right = Complex { re: 12.0, im: 9.0 };
+    let target = find((100, 100), upper_left, lower_right, &params);
+    assert!((target - Complex { re: 11.0, im: 10.0 }).norm() < 0.05);
+}
+
+/// Points sampled across the view in the first round of [`random`]
+const RANDOM_CANDIDATES: usize = 256;
+
+/// Points sampled around the best point so far in each later round
+const REFINE_CANDIDATES: usize = 64;
+
+/// Rounds that narrow the search around the best point after the first
+const REFINE_ROUNDS: usize = 4;
+
+/// Pick a random point near the boundary of the set in the view between
+/// `upper_left` and `lower_right`, the same one for the same `seed`.
+///
+/// Points are sampled uniformly across the view and the escaping one that
+/// took the most iterations wins: members are deep inside the set and quick
+/// escapes far outside it, while slow escapes sit at its edge. The search
+/// then repeats in ever smaller squares around the winner, so the point
+/// stays close to the boundary for a deep zoom. Returns `None` if no sampled
+/// point escapes.
+pub fn random(
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    seed: u64,
+) -> Option<Complex<f64>> {
+    let mut rng = Rng::new(seed);
+    let mut best: Option<(usize, Complex<f64>)> = None;
+    let consider = |point: Complex<f64>, best: &mut Option<(usize, Complex<f64>)>| {
+        if let Some(count) = params.escape_time(point)
+            && best.is_none_or(|(most, _)| count > most)
+        {
+            *best = Some((count, point));
+        }
+    };
+
+    for _ in 0..RANDOM_CANDIDATES {
+        let point = Complex {
+            re: rng.range(upper_left.re, lower_right.re),
+            im: rng.range(lower_right.im, upper_left.im),
+        };
+        consider(point, &mut best);
+    }
+    let mut radius = (lower_right.re - upper_left.re).abs() / 16.0;
+    for _ in 0..REFINE_ROUNDS {
+        let (_, center) = best?;
+        for _ in 0..REFINE_CANDIDATES {
+            let point = center
+                + Complex {
+                    re: rng.range(-radius, radius),
+                    im: rng.range(-radius, radius),
+                };
+            consider(point, &mut best);
+        }
+        radius /= 8.0;
+    }
+    best.map(|(_, point)| point)
+}
+
+#[test]
+fn test_random_boundary_point() {
+    let params = FractalParams::default();
+    let upper_left = Complex { re: -2.0, im: 1.5 };
+    let lower_right = Complex { re: 1.0, im: -1.5 };
+    let point = random(upper_left, lower_right, &params, 7).unwrap();
+    assert_eq!(random(upper_left, lower_right, &params, 7), Some(point));
+    assert_ne!(random(upper_left, lower_right, &params, 8), Some(point));
+
+    // The point escapes, but only after many iterations, so it is close to
+    // the edge of the set
+    let count = params.escape_time(point).unwrap();
+    assert!(count > 50, "{} escapes after {} iterations", point, count);
+
+    // Far outside the set every point escapes at once, and the first wins
+    let far = random(
+        Complex { re: 10.0, im: 11.0 },
+        Complex { re: 12.0, im: 9.0 },
+        &params,
+        7,
+    )
+    .unwrap();
+    assert!((10.0..12.0).contains(&far.re) && (9.0..11.0).contains(&far.im));
+
+    // Inside the main cardioid nothing escapes
+    let inside = random(
+        Complex { re: -0.2, im: 0.1 },
+        Complex { re: 0.0, im: -0.1 },
+        &params,
+        7,
+    );
+    assert_eq!(inside, None);
+}
+
+/// Width in points of the grid [`minibrot`] looks for atom domains on
+const MINIBROT_GRID: usize = 64;
+
+/// Newton steps allowed to reach a nucleus
+const NUCLEUS_STEPS: usize = 64;
+
+/// A nucleus of the quadratic Mandelbrot set: the center of a hyperbolic
+/// component, where the critical orbit returns to zero after `period` steps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nucleus {
+    pub center: Complex<f64>,
+    pub period: usize,
+    /// Rough size of the component, in the units of the plane
+    pub size: f64,
+}
+
+/// Find the minibrot of the lowest period whose nucleus lies in the view
+/// between `upper_left` and `lower_right` of the power 2 Mandelbrot set, or
+/// `None` if there is none up to `limit` iterations.
+///
+/// Every point of a grid over the view is iterated to find its atom domain:
+/// the step at which its orbit comes closest to zero, which is the period
+/// of a nucleus nearby. From the closest point of each period, in rising
+/// order, Newton's method solves `z_p(c) = 0` for the nucleus. One that
+/// lands in the view, has exactly that period and whose component is
+/// shaped like a cardioid rather than a disc is a minibrot, a small copy of
+/// the whole set. The main cardioid of period 1 is a copy too, so a view
+/// of the whole set finds its center.
+pub fn minibrot(
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    limit: usize,
+) -> Option<Nucleus> {
+    let width = MINIBROT_GRID;
+    let height = ((width as f64 * (upper_left.im - lower_right.im)
+        / (lower_right.re - upper_left.re))
+        .abs()
+        .round() as usize)
+        .clamp(1, 4 * width);
+    let grid = (width, height);
+
+    // The atom domain of each point, with how close its orbit came to zero
+    let domains: Vec<(usize, f64, Complex<f64>)> = (0..width * height)
+        .into_par_iter()
+        .filter_map(|index| {
+            let c = transform::pixel_to_point(
+                grid,
+                (index % width, index / width),
+                upper_left,
+                lower_right,
+            );
+            let mut z = Complex::new(0.0, 0.0);
+            let mut closest = (0, f64::INFINITY);
+            for n in 1..=limit {
+                z = z * z + c;
+                let norm = z.norm_sqr();
+                if norm > 4.0 {
+                    break;
+                }
+                if norm < closest.1 {
+                    closest = (n, norm);
+                }
+            }
+            (closest.0 > 0).then_some((closest.0, closest.1, c))
+        })
+        .collect();
+
+    let mut periods: Vec<usize> = domains.iter().map(|&(period, _, _)| period).collect();
+    periods.sort_unstable();
+    periods.dedup();
+    let (low, high) = (
+        Complex::new(
+            upper_left.re.min(lower_right.re),
+            upper_left.im.min(lower_right.im),
+        ),
+        Complex::new(
+            upper_left.re.max(lower_right.re),
+            upper_left.im.max(lower_right.im),
+        ),
+    );
+    periods.into_iter().find_map(|period| {
+        let &(_, _, start) = domains
+            .iter()
+            .filter(|&&(p, _, _)| p == period)
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+        let center = nucleus(start, period)?;
+        let inside =
+            (low.re..=high.re).contains(&center.re) && (low.im..=high.im).contains(&center.im);
+        (inside && exact_period(center, period) && is_cardioid(center, period)).then(|| Nucleus {
+            center,
+            period,
+            size: size(center, period),
+        })
+    })
+}
+
+/// Solve `z_period(c) = 0` with Newton's method from `c`, returning `None`
+/// if it does not converge.
+fn nucleus(mut c: Complex<f64>, period: usize) -> Option<Complex<f64>> {
+    for _ in 0..NUCLEUS_STEPS {
+        // z_p and its derivative with respect to c
+        let (mut z, mut dc) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+        for _ in 0..period {
+            dc = 2.0 * z * dc + 1.0;
+            z = z * z + c;
+        }
+        let next = c - z / dc;
+        if !next.re.is_finite() || !next.im.is_finite() {
+            return None;
+        }
+        let step = (next - c).norm();
+        c = next;
+        if step <= 1e-15 * c.norm().max(1e-300) {
+            return Some(c);
+        }
+    }
+    None
+}
+
+/// Return whether the critical orbit of `c` first comes back to zero after
+/// `period` steps, and not after a divisor of it.
+fn exact_period(c: Complex<f64>, period: usize) -> bool {
+    let mut z = Complex::new(0.0, 0.0);
+    let mut closest = f64::INFINITY;
+    for _ in 1..period {
+        z = z * z + c;
+        closest = closest.min(z.norm());
+    }
+    z = z * z + c;
+    z.norm() < 1e-6 * closest
+}
+
+/// Return whether the component of the nucleus `c` is a cardioid rather
+/// than a disc, from the shape estimate of its derivatives.
+fn is_cardioid(c: Complex<f64>, period: usize) -> bool {
+    let mut z = c;
+    let (mut dc, mut dz) = (Complex::new(1.0, 0.0), Complex::new(1.0, 0.0));
+    let (mut dcdc, mut dcdz) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+    for _ in 1..period {
+        dcdc = 2.0 * (z * dcdc + dc * dc);
+        dcdz = 2.0 * (z * dcdz + dc * dz);
+        dc = 2.0 * z * dc + 1.0;
+        dz = 2.0 * z * dz;
+        z = z * z + c;
+    }
+    // Near 0 for cardioids and near 1 for discs
+    let shape = -(dcdc / (2.0 * dc) + dcdz / dz) / (dc * dz);
+    shape.norm() < (shape - 1.0).norm()
+}
+
+/// Estimate the size of the component of the nucleus `c`.
+fn size(c: Complex<f64>, period: usize) -> f64 {
+    let mut z = Complex::new(0.0, 0.0);
+    let (mut b, mut l) = (Complex::new(1.0, 0.0), Complex::new(1.0, 0.0));
+    for _ in 1..period {
+        z = z * z + c;
+        l = 2.0 * z * l;
+        b += 1.0 / l;
+    }
+    1.0 / (b * l * l).norm()
+}
+
+#[test]
+fn test_minibrot() {
+    // The whole set finds its own main cardioid, of size about 1
+    let found = minibrot(Complex::new(-2.5, 1.5), Complex::new(1.0, -1.5), 255).unwrap();
+    assert_eq!(found.period, 1);
+    assert!(found.center.norm() < 1e-12);
+
+    // Around the period 3 minibrot on the needle, passing over the discs of
+    // the bulbs beside it
+    let found = minibrot(Complex::new(-1.8, 0.05), Complex::new(-1.7, -0.05), 1000).unwrap();
+    assert_eq!(found.period, 3);
+    assert!((found.center - Complex::new(-1.754877666246693, 0.0)).norm() < 1e-12);
+    assert!(found.size > 0.005 && found.size < 0.05, "{}", found.size);
+    assert!(is_cardioid(found.center, 3));
+    assert!(!is_cardioid(Complex::new(-1.0, 0.0), 2));
+    assert!(exact_period(Complex::new(-1.0, 0.0), 2));
+    assert!(!exact_period(Complex::new(-1.0, 0.0), 4));
+
+    // Far outside the set there is nothing to find
+    assert_eq!(
+        minibrot(Complex::new(10.0, 11.0), Complex::new(12.0, 9.0), 255),
+        None
+    );
+}
@@ -0,0 +1,111 @@
+use super::{EscapeResult, FractalParams, escape_orbit};
+use image::RgbImage;
+use num::Complex;
+use rayon::prelude::*;
+
+/// Look up the color of an escaping point in `texture`, or return `None` for
+/// members.
+///
+/// The horizontal texture coordinate is the argument of the final `z`, once
+/// around the origin across the width of the texture, and the vertical one
+/// the fractional part of the smooth escape time for a formula of degree
+/// `power`, so that every band of equal escape count shows the whole texture
+/// stretched to fit it, whatever the texture's size.
+pub fn sample(texture: &RgbImage, result: &EscapeResult, power: f64) -> Option<[u8; 3]> {
+    let count = result.iterations?;
+    let u = (result.final_z.arg() / std::f64::consts::TAU).rem_euclid(1.0);
+    let v = result.smooth_count(count, power).fract();
+    let (width, height) = texture.dimensions();
+    assert!(width > 0 && height > 0);
+    // Rounding can land on 1.0 itself, which wraps to the first texel
+    let x = (u * width as f64) as u32 % width;
+    let y = (v * height as f64) as u32 % height;
+    Some(texture.get_pixel(x, y).0)
+}
+
+/// Render the view between `upper_left` and `lower_right` into `pixels`,
+/// coloring escaping points from `texture` with [`sample`] and painting
+/// members with `interior`.
+///
+/// Rows are rendered in parallel on the current rayon thread pool.
+pub fn render(
+    pixels: &mut [[u8; 3]],
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+    params: &FractalParams,
+    texture: &RgbImage,
+    interior: [u8; 3],
+) {
+    assert!(pixels.len() == bounds.0 * bounds.1);
+    let power = params
+        .complex_power
+        .map_or(params.power as f64, |power| power.re);
+    pixels
+        .par_chunks_mut(bounds.0)
+        .enumerate()
+        .for_each(|(row, row_pixels)| {
+            for (column, pixel) in row_pixels.iter_mut().enumerate() {
+                let point = params.sample_point(bounds, (column, row), upper_left, lower_right);
+                let result = escape_orbit(point, params, None);
+                *pixel = sample(texture, &result, power).unwrap_or(interior);
+            }
+        });
+}
+
+#[test]
+fn test_sample_texture() {
+    // Four texels: red and green along the top, blue and white below
+    let texture = RgbImage::from_fn(2, 2, |x, y| match (x, y) {
+        (0, 0) => image::Rgb([255, 0, 0]),
+        (1, 0) => image::Rgb([0, 255, 0]),
+        (0, 1) => image::Rgb([0, 0, 255]),
+        _ => image::Rgb([255, 255, 255]),
+    });
+    let escaped = |re: f64, im: f64, smooth: f64| EscapeResult {
+        iterations: Some(5),
+        final_z: Complex { re, im },
+        // No smoothing steps and a ratio of 2^(5 - smooth) give `smooth`
+        bailout_ratio: 2f64.powf(5.0 - smooth),
+        ..EscapeResult::default()
+    };
+    assert_eq!(
+        sample(&texture, &escaped(3.0, 0.1, 4.25), 2.0),
+        Some([255, 0, 0])
+    );
+    assert_eq!(
+        sample(&texture, &escaped(-3.0, -0.1, 4.25), 2.0),
+        Some([0, 255, 0])
+    );
+    assert_eq!(
+        sample(&texture, &escaped(-0.1, 3.0, 4.75), 2.0),
+        Some([0, 0, 255])
+    );
+    // Just below the positive real axis the argument wraps to the far side
+    assert_eq!(
+        sample(&texture, &escaped(3.0, -0.1, 4.75), 2.0),
+        Some([255, 255, 255])
+    );
+    assert_eq!(sample(&texture, &EscapeResult::default(), 2.0), None);
+
+    // Members take the interior color, and escaping points texture colors
+    let params = FractalParams::default();
+    let bounds = (8, 4);
+    let mut pixels = vec![[1, 2, 3]; bounds.0 * bounds.1];
+    render(
+        &mut pixels,
+        bounds,
+        Complex { re: -2.0, im: 1.0 },
+        Complex { re: 1.0, im: -1.0 },
+        &params,
+        &texture,
+        [9, 9, 9],
+    );
+    assert!(pixels.contains(&[9, 9, 9]));
+    assert!(!pixels.contains(&[1, 2, 3]));
+    assert!(
+        pixels
+            .iter()
+            .all(|p| *p == [9, 9, 9] || texture.pixels().any(|t| t.0 == *p))
+    );
+}
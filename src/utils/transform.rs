@@ -16,10 +16,11 @@ pub fn pixel_to_point(
         lower_right.re - upper_left.re,
         upper_left.im - lower_right.im,
     );
+    // Sample the middle of the pixel, so the samples sit symmetrically inside the view
     Complex {
-        re: upper_left.re + pixel.0 as f64 * width / bounds.0 as f64,
-        im: upper_left.im - pixel.1 as f64 * height / bounds.1 as f64, // Why subtraction here? pixel.1 increases as we go down,
-                                                                       // but the imaginary component increases as we go up.
+        re: upper_left.re + (pixel.0 as f64 + 0.5) * width / bounds.0 as f64,
+        im: upper_left.im - (pixel.1 as f64 + 0.5) * height / bounds.1 as f64, // Why subtraction here? pixel.1 increases as we go down,
+                                                                               // but the imaginary component increases as we go up.
     }
 }
 #[test]
@@ -31,11 +32,245 @@ fn test_pixel_to_point() {
             Complex { re: -1.0, im: 1.0 },
             Complex { re: 1.0, im: -1.0 }
         ),
+        Complex {
+            re: -0.49,
+            im: 1.0 - 175.5 / 100.0
+        }
+    );
+}
+
+/// Return the point at the upper-left corner of `pixel`, with the same
+/// arguments as [`pixel_to_point`].
+///
+/// The corner of the pixel one past the last column and row is the
+/// `lower_right` corner of the view.
+pub fn pixel_corner_to_point(
+    bounds: (usize, usize),
+    pixel: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+) -> Complex<f64> {
+    let (width, height) = (
+        lower_right.re - upper_left.re,
+        upper_left.im - lower_right.im,
+    );
+    Complex {
+        re: upper_left.re + pixel.0 as f64 * width / bounds.0 as f64,
+        im: upper_left.im - pixel.1 as f64 * height / bounds.1 as f64,
+    }
+}
+
+#[test]
+fn test_pixel_corner_to_point() {
+    let upper_left = Complex { re: -1.0, im: 1.0 };
+    let lower_right = Complex { re: 1.0, im: -1.0 };
+    assert_eq!(
+        pixel_corner_to_point((100, 200), (25, 175), upper_left, lower_right),
         Complex {
             re: -0.5,
             im: -0.75
         }
     );
+    assert_eq!(
+        pixel_corner_to_point((100, 200), (100, 200), upper_left, lower_right),
+        lower_right
+    );
+}
+
+/// Return the `(upper_left, lower_right)` corners of the part of the view
+/// covered by the pixels from `rect.0` to `rect.1`, two opposite corners of
+/// a selected rectangle given as (column, row) pairs, with the other
+/// arguments as for [`pixel_to_point`].
+///
+/// Both corner pixels lie inside the rectangle, so its corners are the outer
+/// corners of its pixels, from [`pixel_corner_to_point`], and selecting the
+/// whole image gives back the view. The pixels may be given in either order.
+pub fn rect_to_bounds(
+    bounds: (usize, usize),
+    rect: ((usize, usize), (usize, usize)),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+) -> (Complex<f64>, Complex<f64>) {
+    let ((x0, y0), (x1, y1)) = rect;
+    let (left, right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+    assert!(right < bounds.0 && bottom < bounds.1);
+    (
+        pixel_corner_to_point(bounds, (left, top), upper_left, lower_right),
+        pixel_corner_to_point(bounds, (right + 1, bottom + 1), upper_left, lower_right),
+    )
+}
+
+#[test]
+fn test_rect_to_bounds() {
+    let upper_left = Complex { re: -1.0, im: 1.0 };
+    let lower_right = Complex { re: 1.0, im: -1.0 };
+    assert_eq!(
+        rect_to_bounds((100, 200), ((25, 100), (74, 174)), upper_left, lower_right),
+        (
+            Complex { re: -0.5, im: 0.0 },
+            Complex { re: 0.5, im: -0.75 }
+        )
+    );
+    // The opposite corners in the other order select the same rectangle
+    assert_eq!(
+        rect_to_bounds((100, 200), ((74, 100), (25, 174)), upper_left, lower_right),
+        rect_to_bounds((100, 200), ((25, 174), (74, 100)), upper_left, lower_right)
+    );
+    // One pixel covers the area its center samples, and the whole image the view
+    let (a, b) = rect_to_bounds((100, 200), ((25, 175), (25, 175)), upper_left, lower_right);
+    let center = pixel_to_point((100, 200), (25, 175), upper_left, lower_right);
+    assert!(((a + b) / 2.0 - center).norm() < 1e-12);
+    assert_eq!(
+        rect_to_bounds((100, 200), ((0, 0), (99, 199)), upper_left, lower_right),
+        (upper_left, lower_right)
+    );
+}
+
+/// Return the `(upper_left, lower_right)` corners of a view centered on
+/// `center` for an image whose dimensions are given by `bounds`.
+///
+/// `radius` is the distance from the center to the edge of the view along the
+/// shorter image axis; the longer axis is stretched to keep pixels square.
+pub fn view_from_center(
+    bounds: (usize, usize),
+    center: Complex<f64>,
+    radius: f64,
+) -> (Complex<f64>, Complex<f64>) {
+    let aspect = bounds.0 as f64 / bounds.1 as f64;
+    let (half_width, half_height) = if aspect >= 1.0 {
+        (radius * aspect, radius)
+    } else {
+        (radius, radius / aspect)
+    };
+    (
+        Complex {
+            re: center.re - half_width,
+            im: center.im + half_height,
+        },
+        Complex {
+            re: center.re + half_width,
+            im: center.im - half_height,
+        },
+    )
+}
+
+#[test]
+fn test_view_from_center() {
+    let center = Complex { re: -0.5, im: 0.25 };
+    assert_eq!(
+        view_from_center((100, 100), center, 1.0),
+        (
+            Complex { re: -1.5, im: 1.25 },
+            Complex { re: 0.5, im: -0.75 }
+        )
+    );
+    assert_eq!(
+        view_from_center((200, 100), center, 1.0),
+        (
+            Complex { re: -2.5, im: 1.25 },
+            Complex { re: 1.5, im: -0.75 }
+        )
+    );
+    assert_eq!(
+        view_from_center((100, 200), center, 1.0),
+        (
+            Complex { re: -1.5, im: 2.25 },
+            Complex { re: 0.5, im: -1.75 }
+        )
+    );
+}
+
+/// Return the corners of the smallest view with the shape of an image of
+/// `bounds` pixels that contains the view from `upper_left` to `lower_right`
+/// and has the same center, so its pixels stay square.
+pub fn fit_view(
+    bounds: (usize, usize),
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+) -> (Complex<f64>, Complex<f64>) {
+    let aspect = bounds.0 as f64 / bounds.1 as f64;
+    let (half_width, half_height) = (
+        (lower_right.re - upper_left.re).abs() / 2.0,
+        (upper_left.im - lower_right.im).abs() / 2.0,
+    );
+    let radius = if aspect >= 1.0 {
+        half_height.max(half_width / aspect)
+    } else {
+        half_width.max(half_height * aspect)
+    };
+    view_from_center(bounds, (upper_left + lower_right) / 2.0, radius)
+}
+
+#[test]
+fn test_fit_view() {
+    let (upper_left, lower_right) = (Complex { re: -2.5, im: 1.0 }, Complex { re: 1.5, im: -1.0 });
+    // A wide view in a square image gains height, a tall image more still
+    assert_eq!(
+        fit_view((100, 100), upper_left, lower_right),
+        (Complex { re: -2.5, im: 2.0 }, Complex { re: 1.5, im: -2.0 })
+    );
+    assert_eq!(
+        fit_view((100, 200), upper_left, lower_right),
+        (Complex { re: -2.5, im: 4.0 }, Complex { re: 1.5, im: -4.0 })
+    );
+    // A wider image than the view gains width
+    assert_eq!(
+        fit_view((400, 100), upper_left, lower_right),
+        (Complex { re: -4.5, im: 1.0 }, Complex { re: 3.5, im: -1.0 })
+    );
+    assert_eq!(
+        fit_view((200, 100), upper_left, lower_right),
+        (upper_left, lower_right)
+    );
+}
+
+/// Return the corners of the rectangle spanned by `a` and `b`, ordered so the
+/// first is the upper-left one (smaller real part, larger imaginary part),
+/// along with whether they had to be reordered.
+///
+/// Fails if the rectangle has no width or no height.
+pub fn order_corners(
+    a: Complex<f64>,
+    b: Complex<f64>,
+) -> Result<(Complex<f64>, Complex<f64>, bool), String> {
+    if a.re == b.re || a.im == b.im {
+        return Err(format!(
+            "the corners {},{} and {},{} span an empty view; they need different real and imaginary parts",
+            a.re, a.im, b.re, b.im
+        ));
+    }
+    let upper_left = Complex {
+        re: a.re.min(b.re),
+        im: a.im.max(b.im),
+    };
+    let lower_right = Complex {
+        re: a.re.max(b.re),
+        im: a.im.min(b.im),
+    };
+    Ok((upper_left, lower_right, upper_left != a))
+}
+
+#[test]
+fn test_order_corners() {
+    let upper_left = Complex { re: -2.0, im: 1.5 };
+    let lower_right = Complex { re: 1.0, im: -1.5 };
+    assert_eq!(
+        order_corners(upper_left, lower_right),
+        Ok((upper_left, lower_right, false))
+    );
+    assert_eq!(
+        order_corners(lower_right, upper_left),
+        Ok((upper_left, lower_right, true))
+    );
+    // Only the imaginary parts are swapped
+    let flipped = (Complex::new(-2.0, -1.5), Complex::new(1.0, 1.5));
+    assert_eq!(
+        order_corners(flipped.0, flipped.1),
+        Ok((upper_left, lower_right, true))
+    );
+    assert!(order_corners(upper_left, Complex::new(-2.0, 0.0)).is_err());
+    assert!(order_corners(upper_left, Complex::new(0.0, 1.5)).is_err());
 }
 
 /// Scale a point around a center point by a given factor.
@@ -84,3 +319,382 @@ fn test_scale_point() {
     assert_eq!(scaled.re, 0.0);
     assert_eq!(scaled.im, 0.0);
 }
+
+/// How the pixel grid is laid over the complex plane
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SamplePattern {
+    /// Columns run along the real axis and rows along the imaginary axis
+    #[default]
+    Rect,
+    /// Columns run once around `center` and rows in towards it, each row's
+    /// radius a fixed fraction of the one above. The grid covers a rectangle
+    /// of the log-polar plane, whose real part is the angle and imaginary part
+    /// the logarithm of the radius, so zooming about `center` only moves the
+    /// rows up
+    LogPolar { center: Complex<f64> },
+}
+
+impl SamplePattern {
+    /// Map a point of the grid to the complex plane.
+    pub fn map(self, point: Complex<f64>) -> Complex<f64> {
+        match self {
+            SamplePattern::Rect => point,
+            SamplePattern::LogPolar { center } => {
+                center
+                    + Complex {
+                        re: point.im,
+                        im: point.re,
+                    }
+                    .exp()
+            }
+        }
+    }
+
+    /// Return the corners of the grid of dimensions `bounds` that shows the
+    /// view between `upper_left` and `lower_right`.
+    ///
+    /// For the log-polar pattern the top row is the circle whose radius is
+    /// half the view's shorter side, and the columns span one turn starting
+    /// from the negative real axis. The radius shrinks from row to row just
+    /// as much as the angle grows from column to column, so features keep
+    /// their shape.
+    pub fn grid(
+        self,
+        bounds: (usize, usize),
+        upper_left: Complex<f64>,
+        lower_right: Complex<f64>,
+    ) -> (Complex<f64>, Complex<f64>) {
+        match self {
+            SamplePattern::Rect => (upper_left, lower_right),
+            SamplePattern::LogPolar { .. } => {
+                let radius = (lower_right.re - upper_left.re)
+                    .abs()
+                    .min((upper_left.im - lower_right.im).abs())
+                    / 2.0;
+                let top = radius.ln();
+                let bottom = top - std::f64::consts::TAU * bounds.1 as f64 / bounds.0.max(1) as f64;
+                let pi = std::f64::consts::PI;
+                (Complex { re: -pi, im: top }, Complex { re: pi, im: bottom })
+            }
+        }
+    }
+}
+
+#[test]
+fn test_log_polar_grid() {
+    let center = Complex { re: -0.75, im: 0.1 };
+    let pattern = SamplePattern::LogPolar { center };
+    let upper_left = center + Complex { re: -2.0, im: 1.0 };
+    let lower_right = center + Complex { re: 2.0, im: -1.0 };
+    let (grid_upper_left, grid_lower_right) = pattern.grid((100, 50), upper_left, lower_right);
+
+    // The top left of the grid lies on the circle of radius 1, left of the center
+    let corner = pattern.map(grid_upper_left);
+    assert!((corner - (center + Complex { re: -1.0, im: 0.0 })).norm() < 1e-12);
+    // Half as many rows as columns shrink the radius by half a turn's worth
+    let bottom = pattern.map(Complex {
+        re: 0.0,
+        im: grid_lower_right.im,
+    });
+    let expected = (-std::f64::consts::PI).exp();
+    assert!(
+        (bottom
+            - (center
+                + Complex {
+                    re: expected,
+                    im: 0.0
+                }))
+        .norm()
+            < 1e-12
+    );
+
+    // Zooming in about the center moves the grid down without changing it
+    let zoomed = pattern.grid(
+        (100, 50),
+        scale_point(upper_left, center, 0.5),
+        scale_point(lower_right, center, 0.5),
+    );
+    assert_eq!(zoomed.0.re, grid_upper_left.re);
+    assert!((grid_upper_left.im - zoomed.0.im - 2f64.ln()).abs() < 1e-12);
+    assert_eq!(
+        SamplePattern::Rect.grid((100, 50), upper_left, lower_right),
+        (upper_left, lower_right)
+    );
+}
+
+/// A rotation of the complex plane about a fixed point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation {
+    /// The point that stays in place
+    pub center: Complex<f64>,
+    /// Counterclockwise angle in radians
+    pub angle: f64,
+}
+
+impl Rotation {
+    /// Rotate `point` about the center.
+    pub fn apply(&self, point: Complex<f64>) -> Complex<f64> {
+        self.center + (point - self.center) * Complex::from_polar(1.0, self.angle)
+    }
+}
+
+#[test]
+fn test_rotation() {
+    let rotation = Rotation {
+        center: Complex { re: 1.0, im: 1.0 },
+        angle: std::f64::consts::FRAC_PI_2,
+    };
+    let rotated = rotation.apply(Complex { re: 2.0, im: 1.0 });
+    assert!((rotated - Complex { re: 1.0, im: 2.0 }).norm() < 1e-15);
+    assert_eq!(rotation.apply(rotation.center), rotation.center);
+}
+
+/// Given a point on the complex plane, return the (column, row) of the pixel
+/// containing it, or `None` if the point lies outside the image.
+///
+/// This is the inverse of `pixel_to_point` and `pixel_corner_to_point`.
+pub fn point_to_pixel(
+    bounds: (usize, usize),
+    point: Complex<f64>,
+    upper_left: Complex<f64>,
+    lower_right: Complex<f64>,
+) -> Option<(usize, usize)> {
+    let (width, height) = (
+        lower_right.re - upper_left.re,
+        upper_left.im - lower_right.im,
+    );
+    let column = (point.re - upper_left.re) / width * bounds.0 as f64;
+    let row = (upper_left.im - point.im) / height * bounds.1 as f64;
+    if column < 0.0 || row < 0.0 || column >= bounds.0 as f64 || row >= bounds.1 as f64 {
+        return None;
+    }
+    Some((column as usize, row as usize))
+}
+
+#[test]
+fn test_point_to_pixel() {
+    let upper_left = Complex { re: -1.0, im: 1.0 };
+    let lower_right = Complex { re: 1.0, im: -1.0 };
+    assert_eq!(
+        point_to_pixel(
+            (100, 200),
+            Complex {
+                re: -0.5,
+                im: -0.75
+            },
+            upper_left,
+            lower_right
+        ),
+        Some((25, 175))
+    );
+    assert_eq!(
+        point_to_pixel(
+            (100, 200),
+            Complex { re: 1.0, im: 0.0 },
+            upper_left,
+            lower_right
+        ),
+        None
+    );
+    assert_eq!(
+        point_to_pixel(
+            (100, 200),
+            Complex { re: 0.0, im: 1.5 },
+            upper_left,
+            lower_right
+        ),
+        None
+    );
+}
+
+/// Return the per-frame scale factor that zooms in by `total_zoom` over an
+/// animation of `n_frames` frames.
+///
+/// The view is scaled between consecutive frames, so there are `n_frames - 1`
+/// steps and the factor is `(1 / total_zoom)^(1 / (n_frames - 1))`. The last
+/// frame then shows exactly `total_zoom` times the detail of the first. A
+/// `total_zoom` below 1.0 zooms out instead.
+pub fn scale_factor_for_zoom(total_zoom: f64, n_frames: usize) -> f64 {
+    assert!(total_zoom > 0.0);
+    if n_frames < 2 {
+        return 1.0;
+    }
+    total_zoom.recip().powf(1.0 / (n_frames - 1) as f64)
+}
+
+#[test]
+fn test_scale_factor_for_zoom() {
+    let factor = scale_factor_for_zoom(1000.0, 4);
+    assert!((factor - 0.1).abs() < 1e-12);
+
+    let factor = scale_factor_for_zoom(1e6, 100);
+    assert!((factor.powi(99) - 1e-6).abs() < 1e-15);
+
+    assert_eq!(scale_factor_for_zoom(2.0, 1), 1.0);
+    assert!((scale_factor_for_zoom(0.5, 2) - 2.0).abs() < 1e-12);
+}
+
+/// Return the dimensions of a preview of frames of `bounds` pixels, each
+/// multiplied by `scale` and rounded to an even number of at least 2.
+///
+/// Rounding both sides the same way keeps the aspect ratio to within a pixel
+/// or two, and even dimensions suit video encoders as well as GIFs.
+pub fn preview_bounds(bounds: (usize, usize), scale: f64) -> (usize, usize) {
+    let side = |n: usize| ((n as f64 * scale / 2.0).round() as usize).max(1) * 2;
+    (side(bounds.0), side(bounds.1))
+}
+
+#[test]
+fn test_preview_bounds() {
+    assert_eq!(preview_bounds((1024, 768), 0.25), (256, 192));
+    assert_eq!(preview_bounds((1000, 750), 0.1), (100, 76));
+    assert_eq!(preview_bounds((1920, 1080), 1.0 / 3.0), (640, 360));
+    assert_eq!(preview_bounds((10, 5), 0.01), (2, 2));
+}
+
+/// Return the per-frame scale factor of a zoom that loops seamlessly,
+/// magnifying `loop_zoom` times over the `n_frames` frames of one loop.
+///
+/// Unlike [`scale_factor_for_zoom`], the zoom spans `n_frames` steps rather
+/// than `n_frames - 1`: the step after the last frame would land exactly
+/// `loop_zoom` times deeper than the first frame, so that frame is left out
+/// and playback cuts straight back to the start. The cut is invisible when
+/// the fractal around the zoom pointer looks the same at both scales, i.e.
+/// when `loop_zoom` is a self-similarity factor of the point.
+pub fn loop_scale_factor(loop_zoom: f64, n_frames: usize) -> f64 {
+    assert!(loop_zoom > 0.0);
+    if n_frames == 0 {
+        return 1.0;
+    }
+    loop_zoom.recip().powf(1.0 / n_frames as f64)
+}
+
+/// Return how much of the view zoomed out by one whole loop to blend into
+/// frame `i` of a looping zoom, if any.
+///
+/// The last `crossfade` frames fade linearly towards the matching frames of
+/// the previous loop, so that the frame after the last, had it been
+/// rendered, would be the first frame exactly. This hides the seam when the
+/// fractal is only roughly self-similar at the loop's zoom factor.
+pub fn loop_crossfade_weight(i: usize, n_frames: usize, crossfade: usize) -> Option<f64> {
+    let start = n_frames.checked_sub(crossfade)?;
+    if crossfade == 0 || i < start {
+        return None;
+    }
+    Some((i - start + 1) as f64 / (crossfade + 1) as f64)
+}
+
+#[test]
+fn test_loop_scale_factor() {
+    let factor = loop_scale_factor(2.0, 10);
+    assert!((factor.powi(10) - 0.5).abs() < 1e-12);
+    assert_eq!(loop_scale_factor(4.0, 0), 1.0);
+
+    assert_eq!(loop_crossfade_weight(5, 10, 0), None);
+    assert_eq!(loop_crossfade_weight(6, 10, 3), None);
+    assert_eq!(loop_crossfade_weight(7, 10, 3), Some(0.25));
+    assert_eq!(loop_crossfade_weight(9, 10, 3), Some(0.75));
+}
+
+/// A rectangle within the view, given as fractions of its width and height
+/// measured from the upper-left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crop {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl Crop {
+    /// Parse `X0,Y0,X1,Y1` for an image whose dimensions are given by `bounds`.
+    ///
+    /// Whole numbers are pixel coordinates, while values written with a
+    /// decimal point are fractions of the image, so `0.25,0.25,0.75,0.75` and
+    /// `256,256,768,768` pick the same middle quarter of a 1024x1024 frame.
+    pub fn parse(s: &str, bounds: (usize, usize)) -> Result<Crop, String> {
+        let fields: Vec<&str> = s.split(',').map(str::trim).collect();
+        let [x0, y0, x1, y1] = fields[..] else {
+            return Err(format!("`{}` should have the form X0,Y0,X1,Y1", s));
+        };
+        let normalized = fields.iter().any(|field| field.contains('.'));
+        let value = |field: &str, size: usize| -> Result<f64, String> {
+            let v: f64 = field
+                .parse()
+                .map_err(|_| format!("`{}` is not a number", field))?;
+            Ok(if normalized { v } else { v / size as f64 })
+        };
+        let crop = Crop {
+            left: value(x0, bounds.0)?,
+            top: value(y0, bounds.1)?,
+            right: value(x1, bounds.0)?,
+            bottom: value(y1, bounds.1)?,
+        };
+        let inside = |v: f64| (0.0..=1.0).contains(&v);
+        if ![crop.left, crop.top, crop.right, crop.bottom]
+            .into_iter()
+            .all(inside)
+        {
+            return Err(format!("crop `{}` reaches outside the image", s));
+        }
+        if crop.left >= crop.right || crop.top >= crop.bottom {
+            return Err(format!(
+                "crop `{}` must have X0 < X1 and Y0 < Y1 to cover any area",
+                s
+            ));
+        }
+        Ok(crop)
+    }
+
+    /// Return the corners of this part of the view between `upper_left` and
+    /// `lower_right`.
+    pub fn apply(
+        &self,
+        upper_left: Complex<f64>,
+        lower_right: Complex<f64>,
+    ) -> (Complex<f64>, Complex<f64>) {
+        let (width, height) = (
+            lower_right.re - upper_left.re,
+            upper_left.im - lower_right.im,
+        );
+        let point = |x: f64, y: f64| Complex {
+            re: upper_left.re + x * width,
+            im: upper_left.im - y * height,
+        };
+        (point(self.left, self.top), point(self.right, self.bottom))
+    }
+
+    /// Return the width divided by the height of the cropped area in pixels of
+    /// an image with the given `bounds`.
+    pub fn aspect(&self, bounds: (usize, usize)) -> f64 {
+        ((self.right - self.left) * bounds.0 as f64) / ((self.bottom - self.top) * bounds.1 as f64)
+    }
+}
+
+#[test]
+fn test_crop() {
+    let bounds = (200, 100);
+    let pixels = Crop::parse("50,25,150,75", bounds).unwrap();
+    let fractions = Crop::parse("0.25, 0.25, 0.75, 0.75", bounds).unwrap();
+    assert_eq!(pixels, fractions);
+    assert_eq!(pixels.aspect(bounds), 2.0);
+
+    let upper_left = Complex { re: -2.0, im: 1.0 };
+    let lower_right = Complex { re: 2.0, im: -1.0 };
+    assert_eq!(
+        pixels.apply(upper_left, lower_right),
+        (Complex { re: -1.0, im: 0.5 }, Complex { re: 1.0, im: -0.5 })
+    );
+    // The cropped corners match the pixels they were given as
+    let (cropped_upper_left, _) = pixels.apply(upper_left, lower_right);
+    assert_eq!(
+        cropped_upper_left,
+        pixel_corner_to_point(bounds, (50, 25), upper_left, lower_right)
+    );
+
+    assert!(Crop::parse("0,0,10", bounds).is_err());
+    assert!(Crop::parse("0,0,300,50", bounds).is_err());
+    assert!(Crop::parse("0.5,0,0.5,1", bounds).is_err());
+    assert!(Crop::parse("60,0,40,50", bounds).is_err());
+    assert!(Crop::parse("a,0,1,1", bounds).is_err());
+}
@@ -0,0 +1,116 @@
+use super::{ColorOptions, FractalParams, FractalType, render_parallel};
+use num::Complex;
+use std::path::Path;
+
+/// Dimensions of every reference image
+pub const BOUNDS: (usize, usize) = (96, 96);
+
+/// Render the canonical view of `fractal_type` that its reference image
+/// holds.
+///
+/// Every case shows the opening view of the program, from -2+2i to 2-2i,
+/// with the default parameters and escape radius of the type and plain
+/// grayscale shading, rendered the way frames are: in parallel rows, with the
+/// lane-parallel loop where it applies.
+pub fn render_case(fractal_type: FractalType) -> Vec<u8> {
+    let params = FractalParams {
+        fractal_type,
+        escape_radius: fractal_type.default_escape_radius(),
+        ..FractalParams::default()
+    };
+    let mut pixels = vec![0; BOUNDS.0 * BOUNDS.1];
+    render_parallel(
+        &mut pixels,
+        BOUNDS,
+        Complex { re: -2.0, im: 2.0 },
+        Complex { re: 2.0, im: -2.0 },
+        &params,
+        &ColorOptions::default(),
+    );
+    pixels
+}
+
+/// Return the path of the reference image of `fractal_type` in `folder`.
+pub fn reference_path(folder: &Path, fractal_type: FractalType) -> std::path::PathBuf {
+    folder.join(format!("{}.png", fractal_type.name()))
+}
+
+/// Read the gray levels of the reference image `path`, which must have the
+/// dimensions [`BOUNDS`].
+pub fn load_reference(path: &Path) -> Result<Vec<u8>, std::io::Error> {
+    let image = image::open(path)
+        .map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to read {}: {}", path.display(), e),
+            )
+        })?
+        .to_luma8();
+    if image.dimensions() != (BOUNDS.0 as u32, BOUNDS.1 as u32) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "{} is {}x{}, but reference images are {}x{}",
+                path.display(),
+                image.width(),
+                image.height(),
+                BOUNDS.0,
+                BOUNDS.1
+            ),
+        ));
+    }
+    Ok(image.into_raw())
+}
+
+/// How far a render is from its reference image
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Difference {
+    /// Number of pixels whose levels differ
+    pub pixels: usize,
+    /// Largest difference between two levels
+    pub max: u8,
+    /// Difference between levels averaged over every pixel
+    pub mean: f64,
+}
+
+/// Compare the gray levels of `actual` with those of `expected`, pixel by
+/// pixel.
+pub fn compare(expected: &[u8], actual: &[u8]) -> Difference {
+    assert!(expected.len() == actual.len());
+    let mut difference = Difference::default();
+    let mut total = 0u64;
+    for (&a, &b) in expected.iter().zip(actual) {
+        let error = a.abs_diff(b);
+        if error > 0 {
+            difference.pixels += 1;
+            difference.max = difference.max.max(error);
+            total += error as u64;
+        }
+    }
+    difference.mean = total as f64 / expected.len().max(1) as f64;
+    difference
+}
+
+#[test]
+fn test_compare() {
+    assert_eq!(compare(&[1, 2, 3, 4], &[1, 2, 3, 4]), Difference::default());
+    let difference = compare(&[0, 10, 200, 50], &[0, 13, 190, 50]);
+    assert_eq!((difference.pixels, difference.max), (2, 10));
+    assert_eq!(difference.mean, 3.25);
+}
+
+#[test]
+fn test_references_match() {
+    let folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/references");
+    for fractal_type in FractalType::all() {
+        let expected = load_reference(&reference_path(&folder, fractal_type)).unwrap();
+        let difference = compare(&expected, &render_case(fractal_type));
+        assert_eq!(
+            difference.pixels,
+            0,
+            "{} differs from its reference: {:?}",
+            fractal_type.name(),
+            difference
+        );
+    }
+}
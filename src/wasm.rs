@@ -0,0 +1,93 @@
+use crate::utils::palette::Palette;
+use crate::utils::{self, ColorOptions, FractalParams, GrayAlpha};
+use num::Complex;
+use wasm_bindgen::prelude::*;
+
+pub use crate::utils::RenderConfig;
+
+/// Render one frame and return it as row-major RGBA bytes, ready to be drawn
+/// on a canvas. Gray levels are colored with the palette in `config.colors`,
+/// if there is one.
+///
+/// This does no I/O and renders on the calling thread, since browsers don't
+/// let wasm spawn threads without extra setup.
+pub fn render_to_rgba(config: &RenderConfig) -> Vec<u8> {
+    let (width, height) = config.bounds;
+    let mut pixels = vec![GrayAlpha::default(); width * height];
+    utils::render(
+        &mut pixels,
+        config.bounds,
+        config.upper_left,
+        config.lower_right,
+        &config.params,
+        &config.colors,
+    );
+    let palette = config.colors.palette.unwrap_or_else(Palette::grayscale);
+    pixels
+        .iter()
+        .flat_map(|p| {
+            let alpha = if config.transparent_interior {
+                p.alpha
+            } else {
+                u8::MAX
+            };
+            let [r, g, b] = palette.color(p.gray);
+            [r, g, b, alpha]
+        })
+        .collect()
+}
+
+#[test]
+fn test_render_to_rgba() {
+    let mut config = RenderConfig {
+        bounds: (8, 6),
+        upper_left: Complex { re: -2.0, im: 1.5 },
+        lower_right: Complex { re: 1.0, im: -1.5 },
+        params: FractalParams::default(),
+        colors: ColorOptions::default(),
+        transparent_interior: false,
+    };
+    let bytes = render_to_rgba(&config);
+    assert_eq!(bytes.len(), 8 * 6 * 4);
+    assert!(
+        bytes
+            .chunks(4)
+            .all(|p| p[0] == p[1] && p[1] == p[2] && p[3] == 255)
+    );
+
+    // The origin sits inside the set, so some pixels turn transparent
+    config.transparent_interior = true;
+    let bytes = render_to_rgba(&config);
+    assert!(bytes.chunks(4).any(|p| p[3] == 0));
+}
+
+/// JavaScript entry point: render the Mandelbrot set in the view of the given
+/// `radius` around `center_re + center_im i` into RGBA bytes.
+#[wasm_bindgen(js_name = renderToRgba)]
+pub fn render_view_to_rgba(
+    width: u32,
+    height: u32,
+    center_re: f64,
+    center_im: f64,
+    radius: f64,
+    max_iterations: u32,
+) -> Vec<u8> {
+    let bounds = (width as usize, height as usize);
+    let center = Complex {
+        re: center_re,
+        im: center_im,
+    };
+    let (upper_left, lower_right) = utils::transform::view_from_center(bounds, center, radius);
+    let params = FractalParams {
+        max_iterations: max_iterations.max(1) as usize,
+        ..FractalParams::default()
+    };
+    render_to_rgba(&RenderConfig {
+        bounds,
+        upper_left,
+        lower_right,
+        params,
+        colors: ColorOptions::default(),
+        transparent_interior: false,
+    })
+}
@@ -0,0 +1,42 @@
+use std::process::Command;
+
+#[test]
+fn test_batch_failing_job() {
+    // A job that fails between two good ones only ends itself, with a
+    // one-line error even when backtraces are asked for
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("jobs.json");
+    std::fs::write(
+        &file,
+        r#"[{"name": "first"}, {"name": "broken", "pixels": "0x0"}, {"name": "last"}]"#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mandelbrot"))
+        .arg("batch")
+        .arg(&file)
+        .arg("--output-folder")
+        .arg(dir.path().join("results"))
+        .args([
+            "--threads",
+            "1",
+            "--",
+            "--pixels=16x12",
+            "--n-frames=1",
+            "--no-gif",
+        ])
+        .env("RUST_BACKTRACE", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stdout.contains("2 of 3 jobs rendered"), "{}", stdout);
+    assert!(stderr.contains("Error: job broken failed"), "{}", stderr);
+    assert!(stderr.contains("Error: failed jobs: broken"), "{}", stderr);
+    assert!(!stderr.contains("backtrace"), "{}", stderr);
+    for name in ["first", "last"] {
+        let frames = dir.path().join("results").join(name).join("frames");
+        assert_eq!(std::fs::read_dir(&frames).unwrap().count(), 1, "{}", name);
+    }
+}
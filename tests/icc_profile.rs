@@ -0,0 +1,58 @@
+use std::process::Command;
+
+/// Write an ICC profile of the color space `space` to `path`, only its
+/// header and no tags, which is all that is checked before embedding it
+fn write_profile(path: &std::path::Path, space: &[u8; 4]) {
+    let mut bytes = vec![0u8; 128];
+    bytes[..4].copy_from_slice(&128u32.to_be_bytes());
+    bytes[16..20].copy_from_slice(space);
+    bytes[36..40].copy_from_slice(b"acsp");
+    std::fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn test_domain_coloring_profile() {
+    // Domain colorings are saved in color, so they take RGB profiles only,
+    // and a gray one is refused before any file is written
+    let dir = tempfile::tempdir().unwrap();
+    for (space, succeeds) in [(b"RGB ", true), (b"GRAY", false)] {
+        let profile = dir.path().join("profile.icc");
+        write_profile(&profile, space);
+        let output_folder = dir.path().join(String::from_utf8_lossy(space).trim());
+        let output = Command::new(env!("CARGO_BIN_EXE_mandelbrot"))
+            .args(["--domain-coloring", "--pixels", "16x12", "--icc-profile"])
+            .arg(&profile)
+            .arg("--output-folder")
+            .arg(&output_folder)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.success(), succeeds, "{}", stderr);
+
+        let files: Vec<_> = std::fs::read_dir(&output_folder)
+            .map(|entries| {
+                entries
+                    .map(|entry| entry.unwrap().path())
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if succeeds {
+            assert_eq!(files.len(), 1, "{:?}", files);
+            let decoder = png::Decoder::new(std::fs::File::open(&files[0]).unwrap());
+            let reader = decoder.read_info().unwrap();
+            assert_eq!(reader.info().color_type, png::ColorType::Rgb);
+            assert_eq!(
+                reader.info().icc_profile.as_deref(),
+                Some(&std::fs::read(&profile).unwrap()[..])
+            );
+        } else {
+            assert!(
+                stderr.contains("--icc-profile is a gray profile"),
+                "{}",
+                stderr
+            );
+            assert!(files.is_empty(), "{:?}", files);
+        }
+    }
+}
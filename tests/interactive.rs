@@ -0,0 +1,59 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Run `--interactive` with `args`, typing `input`, and return its standard
+/// output and error.
+fn explore(args: &[&str], input: &str) -> (String, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mandelbrot"))
+        .current_dir(dir.path())
+        .arg("--interactive")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn test_interactive_switch() {
+    // A switch goes through the checks of the command line and keeps the view
+    let (stdout, stderr) = explore(&[], "+\ntype nova\n+\nq\n");
+    assert!(stderr.is_empty(), "{}", stderr);
+    assert!(
+        stdout.ends_with("\n--fractal-type nova --view-center=\"0,0\" --view-radius 0.5\n"),
+        "{}",
+        stdout
+    );
+
+    // A switch the command line would refuse leaves the formula as it was
+    let (stdout, stderr) = explore(&["--initial-z=0.1,0"], "type julia\nq\n");
+    assert!(
+        stderr.contains("Error: --initial-z needs a formula whose orbits start at a fixed point"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stdout.contains("Staying with --fractal-type mandelbrot"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.ends_with("\n--fractal-type mandelbrot --view-center=\"0,0\" --view-radius 2\n"),
+        "{}",
+        stdout
+    );
+}